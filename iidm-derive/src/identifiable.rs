@@ -4,9 +4,15 @@ use syn::{Data, DeriveInput, Fields, Type, TypePath};
 
 pub fn impl_identifiable_trait(ast: DeriveInput) -> TokenStream {
     let name = &ast.ident;
+    let nested = nested_fields(&ast.data);
 
     // Générer l'implémentation pour tous les champs
-    let register_impl = generate_register_impl(&ast.data);
+    let register_impl = generate_register_impl(&nested);
+    let link_impl = generate_link_impl(&nested);
+    let detached_impl = generate_detached_impl(&nested);
+    let check_available_impl = generate_check_available_impl(&nested);
+    let spawn_impl = generate_spawn_impl(&nested);
+    let attach_children_impl = generate_attach_children_impl(&nested);
 
     let expanded = quote! {
         impl Identifiable for #name {
@@ -15,12 +21,12 @@ pub fn impl_identifiable_trait(ast: DeriveInput) -> TokenStream {
             }
 
             fn register(&self, world: &mut bevy_ecs::world::World, schedule: &mut bevy_ecs::schedule::Schedule) {
-                // Register self first
+                // Register self first, nested identifiables live in their own entities
                 {
                     let mut event_writer = world.resource_mut::<bevy_ecs::event::Events<crate::plugins::RegisterEvent<Self>>>();
                     event_writer.send(RegisterEvent {
                         id: self.id(),
-                        component: self.clone(),
+                        component: crate::extensions::Hierarchy::detached(self),
                     });
                 }
 
@@ -28,72 +34,309 @@ pub fn impl_identifiable_trait(ast: DeriveInput) -> TokenStream {
                 #register_impl
 
                 schedule.run(world);
+
+                // Finally link the spawned children to their parent
+                #link_impl
+            }
+        }
+
+        impl crate::extensions::Hierarchy for #name {
+            fn detached(&self) -> Self {
+                #[allow(unused_mut)]
+                let mut detached = self.clone();
+                #detached_impl
+                detached
+            }
+
+            #[allow(unused_variables)]
+            fn attach_children(&mut self, world: &bevy_ecs::world::World, entity: bevy_ecs::entity::Entity) {
+                #attach_children_impl
+            }
+
+            fn check_available(&self, registry: &crate::AssetRegistry) -> Result<(), crate::RegistryError> {
+                registry.check_available::<Self>(&self.id())?;
+                #check_available_impl
+                Ok(())
+            }
+
+            fn spawn(&self, world: &mut bevy_ecs::world::World) {
+                let id = self.id();
+                world.resource_scope(|world, mut registry: bevy_ecs::world::Mut<crate::AssetRegistry>| {
+                    let mut commands = world.commands();
+                    // The ids are checked by `check_available` beforehand
+                    let _ = registry.add_component(&mut commands, id, crate::extensions::Hierarchy::detached(self));
+                });
+                world.flush();
+
+                #spawn_impl
+                #link_impl
             }
         }
     };
 
-    expanded.into()
-}
-
-pub fn generate_register_impl(data: &Data) -> TokenStream {
-    match data {
-        Data::Struct(data_struct) => {
-            match &data_struct.fields {
-                Fields::Named(fields) => {
-                    let field_registers = fields.named.iter().map(|field| {
-                        let field_name = &field.ident;
-                        let field_type = &field.ty;
-
-                        // Check if field type implements Identifiable
-                        if is_identifiable_type(field_type) {
-                            // Handle Vec<T> where T: Identifiable
-                            if let Type::Path(TypePath { path, .. }) = field_type {
-                                if path
-                                    .segments
-                                    .last()
-                                    .map(|s| s.ident == "Vec")
-                                    .unwrap_or(false)
-                                {
-                                    return quote! {
-                                        for item in &self.#field_name {
-                                            item.register(world, schedule);
-                                        }
-                                    };
-                                }
-                            }
-
-                            // Handle single Identifiable field
-                            quote! {
-                                self.#field_name.register(world, schedule);
-                            }
-                        } else {
-                            quote! {}
-                        }
-                    });
+    expanded
+}
 
-                    quote! {
-                        #(#field_registers)*
-                    }
-                }
-                _ => quote! {},
+/// How an identifiable is nested in its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nesting {
+    Single,
+    Optional,
+    List,
+    OptionalList,
+    /// Switches of an optional voltage level topology
+    TopologySwitches,
+}
+
+struct NestedField<'a> {
+    name: &'a syn::Ident,
+    nesting: Nesting,
+    /// Identifiable type of the nested elements
+    ty: Type,
+}
+
+impl NestedField<'_> {
+    /// Field named by the relations of the nested elements
+    fn relation(&self) -> String {
+        match self.nesting {
+            Nesting::TopologySwitches => format!("{}.switches", self.name),
+            _ => self.name.to_string(),
+        }
+    }
+}
+
+fn nested_fields(data: &Data) -> Vec<NestedField<'_>> {
+    let Data::Struct(data_struct) = data else {
+        return Vec::new();
+    };
+    let Fields::Named(fields) = &data_struct.fields else {
+        return Vec::new();
+    };
+
+    fields
+        .named
+        .iter()
+        .filter_map(|field| {
+            let name = field.ident.as_ref()?;
+            if is_topology_type(&field.ty) {
+                return Some(NestedField {
+                    name,
+                    nesting: Nesting::TopologySwitches,
+                    ty: syn::parse_quote!(Switch),
+                });
+            }
+            if !is_identifiable_type(&field.ty) {
+                return None;
+            }
+            let (nesting, ty) = match generic_argument(&field.ty, "Option") {
+                Some(inner) => match generic_argument(inner, "Vec") {
+                    Some(item) => (Nesting::OptionalList, item),
+                    None => (Nesting::Optional, inner),
+                },
+                None => match generic_argument(&field.ty, "Vec") {
+                    Some(item) => (Nesting::List, item),
+                    None => (Nesting::Single, &field.ty),
+                },
+            };
+            Some(NestedField {
+                name,
+                nesting,
+                ty: ty.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Iterates over the nested elements of a field as references
+fn nested_items(field: &NestedField) -> TokenStream {
+    let name = field.name;
+    match field.nesting {
+        Nesting::Single => quote! { std::iter::once(&self.#name) },
+        Nesting::Optional => quote! { self.#name.iter() },
+        Nesting::List => quote! { self.#name.iter() },
+        Nesting::OptionalList => quote! { self.#name.iter().flatten() },
+        Nesting::TopologySwitches => quote! {
+            self.#name.iter().flat_map(|topology| topology.switches.iter())
+        },
+    }
+}
+
+fn generate_register_impl(nested: &[NestedField]) -> TokenStream {
+    let field_registers = nested.iter().map(|field| {
+        let items = nested_items(field);
+        quote! {
+            for item in #items {
+                item.register(world, schedule);
             }
         }
-        _ => quote! {},
+    });
+
+    quote! {
+        #(#field_registers)*
     }
 }
 
-fn is_identifiable_type(ty: &Type) -> bool {
+fn generate_check_available_impl(nested: &[NestedField]) -> TokenStream {
+    let field_checks = nested.iter().map(|field| {
+        let items = nested_items(field);
+        quote! {
+            for item in #items {
+                crate::extensions::Hierarchy::check_available(item, registry)?;
+            }
+        }
+    });
+
+    quote! {
+        #(#field_checks)*
+    }
+}
+
+fn generate_spawn_impl(nested: &[NestedField]) -> TokenStream {
+    let field_spawns = nested.iter().map(|field| {
+        let items = nested_items(field);
+        quote! {
+            for item in #items {
+                crate::extensions::Hierarchy::spawn(item, world);
+            }
+        }
+    });
+
+    quote! {
+        #(#field_spawns)*
+    }
+}
+
+fn generate_link_impl(nested: &[NestedField]) -> TokenStream {
+    if nested.is_empty() {
+        return quote! {};
+    }
+
+    let field_links = nested.iter().map(|field| {
+        let items = nested_items(field);
+        let field_name = field.relation();
+        let ty = &field.ty;
+        let attach = match field.nesting {
+            Nesting::Single => quote! { crate::relations::attach_required_child },
+            _ => quote! { crate::relations::attach_child },
+        };
+        quote! {
+            for item in #items {
+                #attach::<Self, #ty>(world, &parent, &item.id(), #field_name);
+            }
+        }
+    });
+
+    quote! {
+        let parent = self.id();
+        crate::relations::init_children::<Self>(world, &parent);
+        #(#field_links)*
+    }
+}
+
+fn generate_detached_impl(nested: &[NestedField]) -> TokenStream {
+    let field_clears = nested.iter().map(|field| {
+        let name = field.name;
+        match field.nesting {
+            // Required elements are left blank, the parent is assembled from its `Required`
+            // children
+            Nesting::Single => quote! { detached.#name = Default::default(); },
+            Nesting::Optional => quote! { detached.#name = None; },
+            Nesting::List => quote! { detached.#name = Vec::new(); },
+            Nesting::OptionalList => quote! {
+                detached.#name = detached.#name.as_ref().map(|_| Vec::new());
+            },
+            // The topology itself stays with the voltage level
+            Nesting::TopologySwitches => quote! {
+                if let Some(topology) = detached.#name.as_mut() {
+                    topology.switches = Vec::new();
+                }
+            },
+        }
+    });
+
+    quote! {
+        #(#field_clears)*
+    }
+}
+
+fn generate_attach_children_impl(nested: &[NestedField]) -> TokenStream {
+    let field_attaches = nested.iter().map(|field| {
+        let name = field.name;
+        let ty = &field.ty;
+        let field_name = field.relation();
+        let children = quote! {
+            crate::relations::children::<#ty>(world, entity, #field_name)
+        };
+        match field.nesting {
+            // Required children cannot be deleted, a registered parent always has them
+            Nesting::Single => quote! {
+                if let Some(item) = #children.into_iter().next() {
+                    self.#name = item;
+                }
+            },
+            Nesting::Optional => quote! {
+                self.#name = #children.into_iter().next();
+            },
+            Nesting::List => quote! {
+                self.#name = #children;
+            },
+            Nesting::OptionalList => quote! {
+                let items = #children;
+                if self.#name.is_some() || !items.is_empty() {
+                    self.#name = Some(items);
+                }
+            },
+            Nesting::TopologySwitches => quote! {
+                if let Some(topology) = self.#name.as_mut() {
+                    topology.switches = #children;
+                }
+            },
+        }
+    });
+
+    quote! {
+        #(#field_attaches)*
+    }
+}
+
+/// Returns the type parameter of `wrapper<T>` when `ty` is that wrapper
+pub(crate) fn generic_argument<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(TypePath { path, .. }) = ty else {
+        return None;
+    };
+    let last_segment = path.segments.last()?;
+    if last_segment.ident != wrapper {
+        return None;
+    }
+    match &last_segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(syn::GenericArgument::Type(inner_type)) => Some(inner_type),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether a field holds an optional voltage level topology, whose switches are identifiables
+fn is_topology_type(ty: &Type) -> bool {
+    generic_argument(ty, "Option").is_some_and(|inner| {
+        let Type::Path(TypePath { path, .. }) = inner else {
+            return false;
+        };
+        path.segments.last().is_some_and(|segment| {
+            segment.ident == "BusBreakerTopology" || segment.ident == "NodeBreakerTopology"
+        })
+    })
+}
+
+pub(crate) fn is_identifiable_type(ty: &Type) -> bool {
     if let Type::Path(type_path) = ty {
         let segments = &type_path.path.segments;
         if let Some(last_segment) = segments.last() {
-            // Si c'est un Vec, regarder le type à l'intérieur
-            if last_segment.ident == "Vec" {
-                if let syn::PathArguments::AngleBracketed(args) = &last_segment.arguments {
-                    if let Some(syn::GenericArgument::Type(inner_type)) = args.args.first() {
-                        return is_identifiable_type(inner_type);
-                    }
-                }
-                return false;
+            // Si c'est un Vec ou une Option, regarder le type à l'intérieur
+            if last_segment.ident == "Vec" || last_segment.ident == "Option" {
+                return generic_argument(ty, &last_segment.ident.to_string())
+                    .is_some_and(is_identifiable_type);
             }
 
             // Vérifier si le type lui-même est identifiable
@@ -166,6 +409,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_is_identifiable_type_options() {
+        let type_option: Type = parse_quote!(Option<Vec<Generator>>);
+        assert!(
+            is_identifiable_type(&type_option),
+            "Option<Vec<Generator>> devrait être identifiable"
+        );
+
+        let type_option: Type = parse_quote!(Option<BusBreakerTopology>);
+        assert!(
+            !is_identifiable_type(&type_option),
+            "Option<BusBreakerTopology> ne devrait pas être identifiable"
+        );
+    }
+
     #[test]
     fn test_is_identifiable_type_all_types() {
         // Test individuellement chaque type
@@ -219,4 +477,35 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_nested_fields_nesting() {
+        let ast: DeriveInput = parse_quote! {
+            struct Parent {
+                id: String,
+                lines: Vec<Line>,
+                generators: Option<Vec<Generator>>,
+                station: Option<HvdcConverterStation>,
+                dangling_line: DanglingLine,
+                topology: Option<BusBreakerTopology>,
+                limits: Option<CurrentLimits>,
+            }
+        };
+
+        let nested: Vec<(String, Nesting)> = nested_fields(&ast.data)
+            .iter()
+            .map(|field| (field.name.to_string(), field.nesting))
+            .collect();
+
+        assert_eq!(
+            nested,
+            vec![
+                ("lines".to_string(), Nesting::List),
+                ("generators".to_string(), Nesting::OptionalList),
+                ("station".to_string(), Nesting::Optional),
+                ("dangling_line".to_string(), Nesting::Single),
+                ("topology".to_string(), Nesting::TopologySwitches),
+            ]
+        );
+    }
 }
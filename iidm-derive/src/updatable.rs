@@ -1,7 +1,9 @@
 use convert_case::{Case, Casing};
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{Data, DeriveInput, Fields, LitStr};
+use syn::{Data, DeriveInput, Fields, LitStr, Type, TypePath};
+
+use crate::identifiable::{generic_argument, is_identifiable_type};
 
 // Utility to extract field name from serde rename attribute
 fn extract_serde_rename(attrs: &[syn::Attribute], default_name: &str) -> String {
@@ -85,24 +87,57 @@ pub fn impl_updatable_trait(ast: DeriveInput) -> TokenStream {
     // Extract fields
     let fields = extract_named_fields(&ast);
 
-    // Filter to exclude 'id' field
-    let filtered_fields = fields
+    // The id is never updated, and nested identifiables are updated through their own entity
+    let updated_fields: Vec<_> = fields
         .iter()
-        .filter(|f| f.ident.as_ref().map_or(true, |id| id != "id"));
+        .filter(|f| f.ident.as_ref().is_none_or(|id| id != "id"))
+        .filter(|f| !is_identifiable_type(&f.ty))
+        .collect();
 
     // Generate field definitions for the Updater
-    let field_defs = filtered_fields.clone().map(|f| {
+    let field_defs = updated_fields.iter().map(|f| {
         let name = &f.ident;
         let ty = &f.ty;
         let attrs = &f.attrs;
-        quote! {
-            #(#attrs)*
-            pub #name: Option<#ty>
+        match update_kind(ty) {
+            UpdateKind::Replace => {
+                // An explicit null clears an optional field instead of leaving it untouched
+                let clear = generic_argument(ty, "Option").map(|_| {
+                    quote! { #[serde(deserialize_with = "crate::libs::json::double_option")] }
+                });
+                quote! {
+                    #(#attrs)*
+                    #clear
+                    pub #name: Option<#ty>
+                }
+            }
+            UpdateKind::Nested(inner) => {
+                let updater = updater_type(inner);
+                quote! {
+                    #(#attrs)*
+                    pub #name: Option<#updater>
+                }
+            }
+            UpdateKind::OptionalNested(inner) => {
+                let updater = updater_type(inner);
+                quote! {
+                    #(#attrs)*
+                    #[serde(deserialize_with = "crate::libs::json::present")]
+                    pub #name: Option<crate::OptionalUpdate<#inner, #updater>>
+                }
+            }
+            UpdateKind::List(inner) => {
+                let updater = updater_type(inner);
+                quote! {
+                    #(#attrs)*
+                    pub #name: Option<crate::ListUpdate<#inner, #updater>>
+                }
+            }
         }
     });
 
     // Generate implementation for fields_json
-    let fields_json_impl = filtered_fields.clone().map(|f| {
+    let fields_json_impl = updated_fields.iter().map(|f| {
         let field_name = f.ident.as_ref().unwrap().to_string();
         let rename = extract_serde_rename(&f.attrs, &field_name);
 
@@ -112,17 +147,37 @@ pub fn impl_updatable_trait(ast: DeriveInput) -> TokenStream {
     });
 
     // Generate implementation for update
-    let update_impl = filtered_fields.map(|f| {
+    let update_impl = updated_fields.iter().map(|f| {
         let name = &f.ident;
-        quote! {
-            if let Some(value) = updates.#name {
-                self.#name = value;
-            }
+        let key = extract_serde_rename(&f.attrs, &name.as_ref().unwrap().to_string());
+        match update_kind(&f.ty) {
+            UpdateKind::Replace => quote! {
+                if let Some(value) = updates.#name {
+                    self.#name = value;
+                }
+            },
+            UpdateKind::Nested(_) => quote! {
+                if let Some(update) = updates.#name {
+                    crate::extensions::Updatable::update(&mut self.#name, update)
+                        .map_err(|error| error.nested(#key))?;
+                }
+            },
+            UpdateKind::OptionalNested(_) | UpdateKind::List(_) => quote! {
+                if let Some(update) = updates.#name {
+                    update
+                        .apply(&mut self.#name)
+                        .map_err(|error| error.nested(#key))?;
+                }
+            },
         }
     });
 
+    let patchable_impl = impl_patchable_trait(&ast);
+
     // Generate complete implementation
     quote! {
+        #patchable_impl
+
         // Include error enum
         #error_type
 
@@ -147,9 +202,10 @@ pub fn impl_updatable_trait(ast: DeriveInput) -> TokenStream {
         }
 
         impl crate::extensions::UpdatableExt for #name {
-            fn update_ext(&mut self, updater: crate::updatable::Updaters) {
-                if let crate::updatable::Updaters::#update_name(updates) = updater {
-                    self.update(updates);
+            fn update_ext(&mut self, updater: crate::updatable::Updaters) -> Result<(), crate::PatchError> {
+                match updater {
+                    crate::updatable::Updaters::#update_name(updates) => self.update(updates),
+                    _ => Ok(()),
                 }
             }
         }
@@ -157,9 +213,145 @@ pub fn impl_updatable_trait(ast: DeriveInput) -> TokenStream {
         impl crate::extensions::Updatable for #name {
             type Updater = #update_name;
 
-            fn update(&mut self, updates: Self::Updater) {
+            fn update(&mut self, updates: Self::Updater) -> Result<(), crate::PatchError> {
                 #(#update_impl)*
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How a field is modified by the generated updater
+enum UpdateKind<'a> {
+    /// Replaced as a whole
+    Replace,
+    /// Structure updated field by field
+    Nested(&'a Type),
+    /// Optional structure, cleared, set or updated
+    OptionalNested(&'a Type),
+    /// List of structures, replaced or edited element by element
+    List(&'a Type),
+}
+
+fn update_kind(ty: &Type) -> UpdateKind<'_> {
+    if let Some(inner) = generic_argument(ty, "Option").filter(|inner| is_updatable_type(inner)) {
+        return UpdateKind::OptionalNested(inner);
+    }
+    if let Some(inner) = generic_argument(ty, "Vec").filter(|inner| is_updatable_type(inner)) {
+        return UpdateKind::List(inner);
+    }
+    if is_updatable_type(ty) {
+        return UpdateKind::Nested(ty);
+    }
+    UpdateKind::Replace
+}
+
+/// Structures deriving `Updatable` which are not nested identifiables
+fn is_updatable_type(ty: &Type) -> bool {
+    let Type::Path(TypePath { path, .. }) = ty else {
+        return false;
+    };
+    path.segments.last().is_some_and(|segment| {
+        matches!(
+            segment.ident.to_string().as_str(),
+            "ReactiveCapabilityCurve"
+                | "ReactiveCapabilityCurvePoint"
+                | "MinMaxReactiveLimits"
+                | "ExponentialLoadModel"
+                | "ZipLoadModel"
+                | "BusBreakerTopology"
+                | "NodeBreakerTopology"
+                | "Node"
+                | "InternalConnection"
+                | "Bus"
+                | "RatioTapChanger"
+                | "PhaseTapChanger"
+                | "TapStep"
+                | "PhaseTapStep"
+                | "CurrentLimits"
+                | "TemporaryLimit"
+                | "TerminalRef"
+        )
+    })
+}
+
+/// Generated updater of an updatable structure
+fn updater_type(ty: &Type) -> TokenStream {
+    let Type::Path(TypePath { path, .. }) = ty else {
+        return quote! { #ty };
+    };
+    let name = &path.segments.last().unwrap().ident;
+    let updater = syn::Ident::new(&format!("{}Updater", name), name.span());
+    quote! { #updater }
+}
+
+/// Merge patch matching the serialized field names, the id only accepting its current value
+pub fn impl_patchable_trait(ast: &DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let fields = extract_named_fields(ast);
+
+    let field_arms = fields.iter().map(|f| {
+        let field = f.ident.as_ref().unwrap();
+        let key = extract_serde_rename(&f.attrs, &field.to_string());
+        let path = format!("/{}", key);
+
+        if field == "id" {
+            quote! {
+                #key => {
+                    let id = serde_json::to_value(&self.#field)
+                        .map_err(crate::PatchError::invalid_value)?;
+                    if *value != id {
+                        return Err(crate::PatchError::ReadOnly(#path.to_string()));
+                    }
+                }
+            }
+        } else if is_identifiable_type(&f.ty) {
+            quote! {
+                #key => return Err(crate::PatchError::NestedIdentifiable(#path.to_string())),
             }
+        } else {
+            quote! {
+                #key => crate::extensions::Patchable::merge_patch(&mut self.#field, value)
+                    .map_err(|error| error.nested(#key))?,
+            }
+        }
+    });
+
+    // Elements of a list are found by id, or by name for the limits
+    let key_field = ["id", "name"].into_iter().find_map(|candidate| {
+        fields
+            .iter()
+            .filter_map(|f| f.ident.as_ref())
+            .find(|field| *field == candidate)
+    });
+    let patch_key_impl = key_field.map(|field| {
+        quote! {
+            fn patch_key(&self) -> Option<String> {
+                Some(self.#field.to_string())
+            }
+        }
+    });
+
+    quote! {
+        impl crate::extensions::Patchable for #name {
+            fn merge_patch(&mut self, patch: &serde_json::Value) -> Result<(), crate::PatchError> {
+                let fields = match patch {
+                    serde_json::Value::Object(fields) => fields,
+                    serde_json::Value::Null => {
+                        return Err(crate::PatchError::NotNullable(String::new()))
+                    }
+                    _ => return Err(crate::PatchError::invalid_value("expected an object")),
+                };
+                for (key, value) in fields {
+                    match key.as_str() {
+                        #(#field_arms)*
+                        _ => return Err(crate::PatchError::UnknownField(format!("/{}", key))),
+                    }
+                }
+                Ok(())
+            }
+
+            #patch_key_impl
         }
     }
 }
@@ -297,4 +489,64 @@ mod tests {
         assert!(generated.to_string().contains("type Updater = UserUpdater"));
         assert!(generated.to_string().contains("type Err = UserError"));
     }
+
+    #[test]
+    fn test_impl_patchable_trait() {
+        let input: DeriveInput = parse_quote! {
+            struct Line {
+                id: String,
+                #[serde(rename = "currentLimits1")]
+                current_limits1: Option<CurrentLimits>,
+                generators: Vec<Generator>,
+            }
+        };
+
+        let generated = impl_patchable_trait(&input).to_string();
+
+        assert!(generated.contains("impl crate :: extensions :: Patchable for Line"));
+        assert!(generated.contains("crate :: PatchError :: ReadOnly (\"/id\" . to_string ())"));
+        assert!(generated.contains("\"currentLimits1\" => crate :: extensions :: Patchable :: merge_patch (& mut self . current_limits1 , value)"));
+        assert!(generated.contains(
+            "crate :: PatchError :: NestedIdentifiable (\"/generators\" . to_string ())"
+        ));
+        assert!(generated.contains("Some (self . id . to_string ())"));
+    }
+
+    #[test]
+    fn test_optional_fields_can_be_cleared() {
+        let input: DeriveInput = parse_quote! {
+            struct Line {
+                id: String,
+                r: f64,
+                #[serde(rename = "currentLimits1")]
+                current_limits1: Option<CurrentLimits>,
+            }
+        };
+
+        let generated = impl_updatable_trait(input).to_string();
+
+        assert!(generated.contains("pub r : Option < f64 >"));
+        assert!(generated.contains(
+            "# [serde (deserialize_with = \"crate::libs::json::present\")] pub current_limits1 : Option < crate :: OptionalUpdate < CurrentLimits , CurrentLimitsUpdater >>"
+        ));
+        assert!(generated.contains("update . apply (& mut self . current_limits1) . map_err (| error | error . nested (\"currentLimits1\")) ?"));
+    }
+
+    #[test]
+    fn test_nested_fields_get_their_updaters() {
+        let input: DeriveInput = parse_quote! {
+            struct CurrentLimits {
+                permanent_limit: f64,
+                temporary_limits: Vec<TemporaryLimit>,
+                ratio_tap_changer: RatioTapChanger,
+            }
+        };
+
+        let generated = impl_updatable_trait(input).to_string();
+
+        assert!(generated.contains(
+            "pub temporary_limits : Option < crate :: ListUpdate < TemporaryLimit , TemporaryLimitUpdater >>"
+        ));
+        assert!(generated.contains("pub ratio_tap_changer : Option < RatioTapChangerUpdater >"));
+    }
 }
@@ -110,5 +110,5 @@ pub fn insert_iidm_ecs(mut world: &mut World, mut schedule: &mut Schedule) {
     init_updatable_components(&mut world, &mut schedule);
 
     // Init events
-    world.insert_resource(Events::<EntityNotFoundEvent>::default());
+    world.insert_resource(Events::<ComponentErrorEvent>::default());
 }
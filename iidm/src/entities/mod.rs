@@ -1,6 +1,10 @@
 pub mod identifiable;
+pub mod placement;
+pub mod references;
 pub mod updatable;
 
+pub use references::{missing_references, references_in};
+
 use crate::extensions::{Identifiable, Updatable};
 use crate::plugins::RegisterEvent;
 
@@ -27,7 +31,7 @@ pub struct Network {
     pub source_format: String,
 
     #[serde(rename = "minimumValidationLevel")]
-    pub minimum_validation_level: String,
+    pub minimum_validation_level: ValidationLevel,
 
     #[serde(default)]
     pub substations: Vec<Substation>,
@@ -63,6 +67,34 @@ pub struct Network {
     pub hvdc_lines: Vec<HvdcLine>,
 }
 
+/// Checks an element has to pass, each level adds its own to the lower ones
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    Component,
+    JsonSchema,
+    Display,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ValidationLevel {
+    /// Static description of the equipment
+    #[display("EQUIPMENT")]
+    Equipment,
+    /// Setpoints and positions needed to compute a steady state
+    #[default]
+    #[display("STEADY_STATE_HYPOTHESIS")]
+    SteadyStateHypothesis,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Component, Identifiable, Updatable, JsonSchema)]
 pub struct Substation {
     pub id: String,
@@ -469,7 +501,9 @@ pub enum StaticVarCompensatorRegulationMode {
     Off,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Component, Identifiable, Updatable, JsonSchema)]
+#[derive(
+    Debug, Clone, Default, Serialize, Deserialize, Component, Identifiable, Updatable, JsonSchema,
+)]
 pub struct DanglingLine {
     pub id: String,
     pub p0: f64,
@@ -515,7 +549,9 @@ pub struct HvdcLine {
     pub converter_station2: HvdcConverterStation,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Component, Identifiable, Updatable, JsonSchema)]
+#[derive(
+    Debug, Clone, Default, Serialize, Deserialize, Component, Identifiable, Updatable, JsonSchema,
+)]
 pub struct HvdcConverterStation {
     pub id: String,
     pub name: String,
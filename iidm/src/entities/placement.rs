@@ -0,0 +1,40 @@
+use super::*;
+use crate::relations::Placement;
+
+macro_rules! impl_placement {
+    ($component:ty => $parent:ty, $($field:literal),*) => {
+        impl Placement for $component {
+            type Parent = $parent;
+            const FIELDS: &'static [&'static str] = &[$($field),*];
+        }
+    };
+}
+
+impl_placement!(Network => Network,);
+impl_placement!(Bus => Network,);
+impl_placement!(TerminalRef => Network,);
+
+impl_placement!(Substation => Network, "substations");
+impl_placement!(Line => Network, "lines");
+impl_placement!(ThreeWindingsTransformer => Network, "three_windings_transformers");
+impl_placement!(Switch => Network, "switches");
+impl_placement!(ShuntCompensator => Network, "shunt_compensators");
+impl_placement!(StaticVarCompensator => Network, "static_var_compensators");
+impl_placement!(DanglingLine => Network, "dangling_lines");
+impl_placement!(TieLine => Network, "tie_lines");
+impl_placement!(HvdcLine => Network, "hvdc_lines");
+
+impl_placement!(VoltageLevel => Substation, "voltage_levels");
+impl_placement!(TwoWindingsTransformer => Substation, "two_windings_transformers");
+
+impl_placement!(Generator => VoltageLevel, "generators");
+impl_placement!(Load => VoltageLevel, "loads");
+impl_placement!(BusbarSection => VoltageLevel, "busbar_sections");
+
+/// A line always holds its two stations, created along with it, so a station created on its own
+/// finds no free side
+impl Placement for HvdcConverterStation {
+    type Parent = HvdcLine;
+    const FIELDS: &'static [&'static str] = &["converter_station1", "converter_station2"];
+    const SINGLE: bool = true;
+}
@@ -0,0 +1,391 @@
+use bevy_ecs::{entity::Entity, world::World};
+
+use super::*;
+use crate::extensions::{Reference, References};
+use crate::topology::BusView;
+use crate::AssetRegistry;
+
+fn voltage_level(id: &str) -> Option<Reference> {
+    (!id.is_empty()).then(|| Reference::VoltageLevel { id: id.to_string() })
+}
+
+/// Empty bus ids stand for disconnected terminals and are not references
+fn bus(voltage_level_id: &str, bus: &str) -> Option<Reference> {
+    (!bus.is_empty()).then(|| Reference::Bus {
+        voltage_level_id: (!voltage_level_id.is_empty()).then(|| voltage_level_id.to_string()),
+        bus: bus.to_string(),
+    })
+}
+
+/// Bus of a terminal and the bus it can be connected to, given once when they are the same
+fn terminal(voltage_level_id: &str, bus_id: &str, connectable_bus: &str) -> [Option<Reference>; 2] {
+    [
+        bus(voltage_level_id, bus_id),
+        bus(voltage_level_id, connectable_bus).filter(|_| connectable_bus != bus_id),
+    ]
+}
+
+macro_rules! no_references {
+    ($($component:ty),* $(,)?) => {
+        $(
+            impl References for $component {
+                fn references(&self) -> Vec<Reference> {
+                    Vec::new()
+                }
+            }
+        )*
+    };
+}
+
+/// Injections do not carry their voltage level, their buses are looked up in the voltage level
+/// holding them, see `references_in`
+macro_rules! injection_references {
+    ($($component:ty),* $(,)?) => {
+        $(
+            impl References for $component {
+                fn references(&self) -> Vec<Reference> {
+                    terminal("", &self.bus, &self.connectable_bus)
+                        .into_iter()
+                        .flatten()
+                        .collect()
+                }
+            }
+        )*
+    };
+}
+
+no_references!(Network, Substation, VoltageLevel, Bus, HvdcLine);
+
+// Nested structures are not resolved on their own
+no_references!(
+    ReactiveCapabilityCurve,
+    ReactiveCapabilityCurvePoint,
+    MinMaxReactiveLimits,
+    ExponentialLoadModel,
+    ZipLoadModel,
+    BusBreakerTopology,
+    NodeBreakerTopology,
+    Node,
+    InternalConnection,
+    RatioTapChanger,
+    PhaseTapChanger,
+    TapStep,
+    PhaseTapStep,
+    CurrentLimits,
+    TemporaryLimit
+);
+injection_references!(
+    Generator,
+    Load,
+    BusbarSection,
+    ShuntCompensator,
+    StaticVarCompensator,
+    DanglingLine
+);
+
+impl References for Line {
+    fn references(&self) -> Vec<Reference> {
+        [
+            voltage_level(&self.voltage_level_id1),
+            voltage_level(&self.voltage_level_id2),
+        ]
+        .into_iter()
+        .chain(terminal(
+            &self.voltage_level_id1,
+            &self.bus1,
+            &self.connectable_bus1,
+        ))
+        .chain(terminal(
+            &self.voltage_level_id2,
+            &self.bus2,
+            &self.connectable_bus2,
+        ))
+        .flatten()
+        .collect()
+    }
+}
+
+impl References for TwoWindingsTransformer {
+    fn references(&self) -> Vec<Reference> {
+        [
+            voltage_level(&self.voltage_level_id1),
+            voltage_level(&self.voltage_level_id2),
+        ]
+        .into_iter()
+        .chain(terminal(
+            &self.voltage_level_id1,
+            &self.bus1,
+            &self.connectable_bus1,
+        ))
+        .chain(terminal(
+            &self.voltage_level_id2,
+            &self.bus2,
+            &self.connectable_bus2,
+        ))
+        .flatten()
+        .collect()
+    }
+}
+
+impl References for ThreeWindingsTransformer {
+    fn references(&self) -> Vec<Reference> {
+        [
+            voltage_level(&self.voltage_level_id1),
+            voltage_level(&self.voltage_level_id2),
+            voltage_level(&self.voltage_level_id3),
+        ]
+        .into_iter()
+        .chain(terminal(
+            &self.voltage_level_id1,
+            &self.bus1,
+            &self.connectable_bus1,
+        ))
+        .chain(terminal(
+            &self.voltage_level_id2,
+            &self.bus2,
+            &self.connectable_bus2,
+        ))
+        .chain(terminal(
+            &self.voltage_level_id3,
+            &self.bus3,
+            &self.connectable_bus3,
+        ))
+        .flatten()
+        .collect()
+    }
+}
+
+impl References for Switch {
+    fn references(&self) -> Vec<Reference> {
+        [
+            voltage_level(&self.voltage_level_id),
+            bus(&self.voltage_level_id, &self.bus1),
+            bus(&self.voltage_level_id, &self.bus2),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+impl References for HvdcConverterStation {
+    fn references(&self) -> Vec<Reference> {
+        [voltage_level(&self.voltage_level_id)]
+            .into_iter()
+            .chain(terminal(
+                &self.voltage_level_id,
+                &self.bus,
+                &self.connectable_bus,
+            ))
+            .flatten()
+            .collect()
+    }
+}
+
+impl References for TieLine {
+    fn references(&self) -> Vec<Reference> {
+        let mut references = self.dangling_line1.references();
+        references.extend(self.dangling_line2.references());
+        references
+    }
+}
+
+impl References for TerminalRef {
+    fn references(&self) -> Vec<Reference> {
+        vec![Reference::Equipment {
+            id: self.id.clone(),
+        }]
+    }
+}
+
+impl std::fmt::Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reference::VoltageLevel { id } => write!(f, "voltage level '{}'", id),
+            Reference::Bus {
+                voltage_level_id: Some(voltage_level_id),
+                bus,
+            } => write!(f, "bus '{}' in voltage level '{}'", bus, voltage_level_id),
+            Reference::Bus {
+                voltage_level_id: None,
+                bus,
+            } => write!(f, "bus '{}'", bus),
+            Reference::Equipment { id } => write!(f, "equipment '{}'", id),
+        }
+    }
+}
+
+impl Reference {
+    /// Bus reference looked up in the voltage level holding the referring element, when it does
+    /// not name its voltage level
+    pub fn in_voltage_level(self, holder: &str) -> Self {
+        match self {
+            Reference::Bus {
+                voltage_level_id: None,
+                bus,
+            } => Reference::Bus {
+                voltage_level_id: Some(holder.to_string()),
+                bus,
+            },
+            reference => reference,
+        }
+    }
+
+    /// Checks the referenced element against the registered voltage levels and equipment
+    pub fn exists(&self, world: &World) -> bool {
+        let Some(registry) = world.get_resource::<AssetRegistry>() else {
+            return false;
+        };
+
+        self.resolves(
+            registry,
+            |entity| world.get::<VoltageLevel>(entity),
+            registry
+                .iter_typed::<VoltageLevel>()
+                .filter_map(|(_, entity)| world.get::<VoltageLevel>(entity)),
+        )
+    }
+
+    /// Checks the referenced element from the registry, with a lookup of the voltage level of an
+    /// entity and the list of every voltage level, for the systems which cannot borrow the world
+    pub fn resolves<'a>(
+        &self,
+        registry: &AssetRegistry,
+        voltage_level: impl Fn(Entity) -> Option<&'a VoltageLevel>,
+        mut voltage_levels: impl Iterator<Item = &'a VoltageLevel>,
+    ) -> bool {
+        match self {
+            Reference::VoltageLevel { id } => registry
+                .find_typed::<VoltageLevel>(id)
+                .and_then(voltage_level)
+                .is_some(),
+            Reference::Bus {
+                voltage_level_id: Some(voltage_level_id),
+                bus,
+            } => registry
+                .find_typed::<VoltageLevel>(voltage_level_id)
+                .and_then(voltage_level)
+                .is_some_and(|voltage_level| {
+                    BusView::compute(voltage_level, |switch| switch.open)
+                        .bus(bus)
+                        .is_some()
+                }),
+            Reference::Bus {
+                voltage_level_id: None,
+                bus,
+            } => {
+                let configured = |topology: &BusBreakerTopology| {
+                    topology.buses.iter().any(|candidate| &candidate.id == bus)
+                };
+                // Node numbers are only unique in their voltage level, so they resolve once the
+                // reference is put in the voltage level holding the element, see `references_in`
+                voltage_levels.any(|voltage_level| {
+                    voltage_level
+                        .bus_breaker_topology
+                        .as_ref()
+                        .is_some_and(configured)
+                })
+            }
+            Reference::Equipment { id } => registry.find(id).is_some(),
+        }
+    }
+}
+
+/// References of an element held by the voltage level `holder`, if any, its buses given without
+/// voltage level being looked up in it
+pub fn references_in<T: References>(element: &T, holder: Option<&str>) -> Vec<Reference> {
+    let references = element.references();
+    match holder {
+        Some(holder) => references
+            .into_iter()
+            .map(|reference| reference.in_voltage_level(holder))
+            .collect(),
+        None => references,
+    }
+}
+
+/// References of an element held by the voltage level `holder`, if any, which cannot be found
+/// in the world
+pub fn missing_references<T: References>(
+    world: &World,
+    element: &T,
+    holder: Option<&str>,
+) -> Vec<Reference> {
+    references_in(element, holder)
+        .into_iter()
+        .filter(|reference| !reference.exists(world))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_references_of_disconnected_side() {
+        let line: Line = serde_json::from_str(
+            r#"{"id": "L", "r": 1.0, "x": 10.0, "g1": 0.0, "b1": 0.0, "g2": 0.0, "b2": 0.0,
+                "voltageLevelId1": "VL1", "bus1": "B1", "connectableBus1": "B1",
+                "voltageLevelId2": "VL2", "bus2": "", "connectableBus2": "B2"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            line.references(),
+            vec![
+                Reference::VoltageLevel {
+                    id: "VL1".to_string()
+                },
+                Reference::VoltageLevel {
+                    id: "VL2".to_string()
+                },
+                Reference::Bus {
+                    voltage_level_id: Some("VL1".to_string()),
+                    bus: "B1".to_string()
+                },
+                // Only the bus it can be connected to is referenced
+                Reference::Bus {
+                    voltage_level_id: Some("VL2".to_string()),
+                    bus: "B2".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_injection_references_in_holder() {
+        let load: Load = serde_json::from_str(
+            r#"{"id": "LOAD", "loadType": "UNDEFINED", "p0": 1.0, "q0": 0.0,
+                "bus": "", "connectableBus": "B1"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            references_in(&load, Some("VL1")),
+            vec![Reference::Bus {
+                voltage_level_id: Some("VL1".to_string()),
+                bus: "B1".to_string()
+            }]
+        );
+        assert_eq!(
+            references_in(&load, None),
+            vec![Reference::Bus {
+                voltage_level_id: None,
+                bus: "B1".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_missing_references_without_registry() {
+        let world = World::new();
+        let switch: Switch = serde_json::from_str(
+            r#"{"id": "S", "kind": "BREAKER", "open": false, "retained": false,
+                "bus1": "B1", "bus2": "B2", "voltageLevelId": ""}"#,
+        )
+        .unwrap();
+
+        assert_eq!(missing_references(&world, &switch, None).len(), 2);
+        assert!(missing_references(&world, &Network::default(), None).is_empty());
+    }
+}
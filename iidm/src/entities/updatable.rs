@@ -38,6 +38,7 @@ pub enum Updatables {
     TemporaryLimit,
 }
 
+#[allow(clippy::large_enum_variant)]
 pub enum Updaters {
     NetworkUpdater(NetworkUpdater),
     LineUpdater(LineUpdater),
@@ -0,0 +1,145 @@
+//! Reassembles a `Network` document from the live components.
+//!
+//! The nesting of the registered network is rebuilt from the `ChildOf`/`Children` relations, so
+//! deleted elements are gone and updated ones hold their current values. Elements registered without a parent are attached to the network, substation or voltage level
+//! their references point to.
+
+use bevy_ecs::prelude::*;
+use thiserror::Error;
+
+use crate::entities::*;
+use crate::relations::{assemble, has_parent};
+use crate::{AssetRegistry, Hierarchy, Identifiable};
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("No network has been loaded")]
+    NoNetwork,
+}
+
+/// Exported network and the elements without parent which could not be attached to it
+#[derive(Debug, Clone)]
+pub struct NetworkExport {
+    pub network: Network,
+    /// Voltage levels have no substation id, and injections are only found through their bus
+    pub unplaced: Vec<String>,
+}
+
+struct Exporter<'w> {
+    world: &'w World,
+    registry: &'w AssetRegistry,
+}
+
+impl Exporter<'_> {
+    /// Assembled components of a type which have no parent, sorted by id
+    fn orphans<T: Component + Identifiable + Hierarchy + Clone>(&self) -> Vec<T> {
+        self.registry
+            .iter_typed::<T>()
+            .filter(|(_, entity)| !has_parent(self.world, *entity))
+            .filter_map(|(_, entity)| assemble::<T>(self.world, entity))
+            .collect()
+    }
+
+    /// Attaches the elements created after the upload, returns the ids left aside
+    fn attach_orphans(&self, network: &mut Network) -> Vec<String> {
+        network.substations.extend(self.orphans::<Substation>());
+        network.tie_lines.extend(self.orphans::<TieLine>());
+        network.hvdc_lines.extend(self.orphans::<HvdcLine>());
+        network.lines.extend(self.orphans::<Line>());
+        network
+            .three_windings_transformers
+            .extend(self.orphans::<ThreeWindingsTransformer>());
+        network.switches.extend(self.orphans::<Switch>());
+        network
+            .shunt_compensators
+            .extend(self.orphans::<ShuntCompensator>());
+        network
+            .static_var_compensators
+            .extend(self.orphans::<StaticVarCompensator>());
+        network
+            .dangling_lines
+            .extend(self.orphans::<DanglingLine>());
+
+        let mut unplaced = Vec::new();
+        for transformer in self.orphans::<TwoWindingsTransformer>() {
+            match substation_of(network, &transformer.voltage_level_id1) {
+                Some(substation) => substation.two_windings_transformers.push(transformer),
+                None => unplaced.push(transformer.id),
+            }
+        }
+        for generator in self.orphans::<Generator>() {
+            match voltage_level_of(network, &generator.bus) {
+                Some(voltage_level) => voltage_level
+                    .generators
+                    .get_or_insert_with(Vec::new)
+                    .push(generator),
+                None => unplaced.push(generator.id),
+            }
+        }
+        for load in self.orphans::<Load>() {
+            match voltage_level_of(network, &load.bus) {
+                Some(voltage_level) => voltage_level.loads.get_or_insert_with(Vec::new).push(load),
+                None => unplaced.push(load.id),
+            }
+        }
+        for section in self.orphans::<BusbarSection>() {
+            match voltage_level_of(network, &section.bus) {
+                Some(voltage_level) => voltage_level
+                    .busbar_sections
+                    .get_or_insert_with(Vec::new)
+                    .push(section),
+                None => unplaced.push(section.id),
+            }
+        }
+        unplaced.extend(self.orphans::<VoltageLevel>().into_iter().map(|vl| vl.id));
+        unplaced.extend(
+            self.orphans::<HvdcConverterStation>()
+                .into_iter()
+                .map(|station| station.id),
+        );
+        unplaced
+    }
+}
+
+fn substation_of<'n>(
+    network: &'n mut Network,
+    voltage_level_id: &str,
+) -> Option<&'n mut Substation> {
+    network.substations.iter_mut().find(|substation| {
+        substation
+            .voltage_levels
+            .iter()
+            .any(|voltage_level| voltage_level.id == voltage_level_id)
+    })
+}
+
+/// Voltage level holding a bus-breaker configured bus, whose ids are unique in the network
+fn voltage_level_of<'n>(network: &'n mut Network, bus: &str) -> Option<&'n mut VoltageLevel> {
+    network
+        .substations
+        .iter_mut()
+        .flat_map(|substation| substation.voltage_levels.iter_mut())
+        .find(|voltage_level| {
+            voltage_level
+                .bus_breaker_topology
+                .as_ref()
+                .is_some_and(|topology| topology.buses.iter().any(|candidate| candidate.id == bus))
+        })
+}
+
+/// Rebuilds the loaded network from the live components of the world
+pub fn export_network(world: &World) -> Result<NetworkExport, ExportError> {
+    let registry = world
+        .get_resource::<AssetRegistry>()
+        .ok_or(ExportError::NoNetwork)?;
+    let mut network = world
+        .iter_entities()
+        .find(|entity| entity.contains::<Network>())
+        .and_then(|entity| assemble::<Network>(world, entity.id()))
+        .ok_or(ExportError::NoNetwork)?;
+
+    let exporter = Exporter { world, registry };
+    let unplaced = exporter.attach_orphans(&mut network);
+
+    Ok(NetworkExport { network, unplaced })
+}
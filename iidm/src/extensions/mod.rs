@@ -1,7 +1,7 @@
 use enum_dispatch::enum_dispatch;
 pub use iidm_derive::{Identifiable, Updatable};
 
-use bevy_ecs::{schedule::Schedule, world::World};
+use bevy_ecs::{entity::Entity, schedule::Schedule, world::World};
 use serde::{Deserialize, Serialize};
 
 use crate::entities::identifiable::Identifiables;
@@ -14,15 +14,73 @@ pub trait Identifiable {
     fn register(&self, world: &mut World, schedule: &mut Schedule);
 }
 
+/// Nested identifiables are stored in their own entities, linked to their parent by
+/// `ChildOf`/`Children` relations, and put back in place when the parent is assembled
+pub trait Hierarchy: Sized {
+    /// Copy without the nested identifiables, the switches of the voltage level topologies
+    /// included. The single required ones (the dangling lines of a tie line, the converter
+    /// stations of an HVDC line) are left blank, `assemble` resolves them from the children
+    fn detached(&self) -> Self;
+    /// Fills the nested identifiables from the children of `entity`
+    fn attach_children(&mut self, world: &World, entity: Entity);
+    /// Checks that the ids of the element and of its nested identifiables are free
+    fn check_available(&self, registry: &crate::AssetRegistry) -> Result<(), crate::RegistryError>;
+    /// Registers the element and its nested identifiables straight into the world, without
+    /// going through the register events
+    fn spawn(&self, world: &mut World);
+}
+
 #[enum_dispatch]
 pub trait UpdatableExt {
-    fn update_ext(&mut self, updater: Updaters);
+    fn update_ext(&mut self, updater: Updaters) -> Result<(), crate::PatchError>;
 }
 
 pub trait Updatable: Sized + Serialize + for<'de> Deserialize<'de> {
     type Updater: Send + Sync;
 
-    fn update(&mut self, updates: Self::Updater);
+    /// Applies the given fields, nested structures and list elements being updated in place.
+    /// Fails when an element to update or remove is missing, the value being then partly updated
+    fn update(&mut self, updates: Self::Updater) -> Result<(), crate::PatchError>;
+}
+
+/// Partial modification from a JSON document, down to nested structures and list elements
+pub trait Patchable: Sized {
+    /// Applies a JSON Merge Patch (RFC 7396). Lists may also be patched element by element with
+    /// an object keyed by element id or index, where `null` removes the element.
+    fn merge_patch(&mut self, patch: &serde_json::Value) -> Result<(), crate::PatchError>;
+
+    /// Key of the element when held in a list, besides its index
+    fn patch_key(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Network elements pointed to by another element, which must exist for it to be valid
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Reference {
+    VoltageLevel {
+        id: String,
+    },
+    /// Configured bus id, or node number when the voltage level is given
+    Bus {
+        #[serde(rename = "voltageLevelId")]
+        voltage_level_id: Option<String>,
+        bus: String,
+    },
+    Equipment {
+        id: String,
+    },
+}
+
+pub trait References {
+    fn references(&self) -> Vec<Reference>;
+}
+
+/// Semantic checks of an element on its own, its references being checked apart
+pub trait Validate {
+    /// Every broken invariant of the element, whatever the level of the check
+    fn violations(&self) -> Vec<crate::Violation>;
 }
 
 pub trait JsonSchema: for<'de> Deserialize<'de> + Serialize {
@@ -0,0 +1,400 @@
+//! Edit journal of the network, to roll back the actions of a trainee.
+//!
+//! Every update or patch applied by the event handlers is recorded with the component before
+//! and after the change, so it can be undone and redone. Checkpoints keep a copy of the whole
+//! network, restoring one replaces every element of the world.
+
+use std::collections::BTreeMap;
+
+use bevy_ecs::{prelude::*, world::CommandQueue};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::entities::Network;
+use crate::{export_network, short_type_name, AssetRegistry, ExportError, Id, Identifiable};
+
+/// Number of changes kept by default, the oldest ones are forgotten first
+pub const DEFAULT_HISTORY_CAPACITY: usize = 1000;
+
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("Nothing to undo")]
+    NothingToUndo,
+
+    #[error("Nothing to redo")]
+    NothingToRedo,
+
+    #[error("Element '{0}' no longer exists")]
+    ElementNotFound(String),
+
+    #[error("Unknown checkpoint '{0}'")]
+    UnknownCheckpoint(String),
+
+    #[error("Elements cannot be placed in the network: {}", .0.join(", "))]
+    Unplaced(Vec<String>),
+
+    #[error("Failed to restore '{id}': {source}")]
+    Restore {
+        id: String,
+        source: serde_json::Error,
+    },
+
+    #[error("{0}")]
+    Export(#[from] ExportError),
+}
+
+/// Puts a recorded state back on the entity, false when the entity lost its component
+type RestoreFn = fn(&mut World, Entity, &Value) -> Result<bool, serde_json::Error>;
+type FindFn = fn(&AssetRegistry, &str) -> Option<Entity>;
+
+fn find_component<T: Component>(registry: &AssetRegistry, id: &str) -> Option<Entity> {
+    registry.find_typed::<T>(id)
+}
+
+fn restore_component<T: Component + DeserializeOwned>(
+    world: &mut World,
+    entity: Entity,
+    state: &Value,
+) -> Result<bool, serde_json::Error> {
+    let restored = T::deserialize(state)?;
+    match world.get_mut::<T>(entity) {
+        Some(mut component) => {
+            *component = restored;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Change applied to one element
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub sequence: u64,
+    pub id: String,
+    pub component_type: String,
+    /// Previous values of the modified fields
+    pub before: Value,
+    /// New values of the modified fields
+    pub after: Value,
+    #[serde(skip)]
+    previous: Value,
+    #[serde(skip)]
+    current: Value,
+    #[serde(skip)]
+    find: FindFn,
+    #[serde(skip)]
+    restore: RestoreFn,
+}
+
+impl HistoryEntry {
+    fn apply(&self, world: &mut World, state: &Value) -> Result<(), HistoryError> {
+        let entity = world
+            .get_resource::<AssetRegistry>()
+            .and_then(|registry| (self.find)(registry, &self.id))
+            .ok_or_else(|| HistoryError::ElementNotFound(self.id.clone()))?;
+        let restored =
+            (self.restore)(world, entity, state).map_err(|source| HistoryError::Restore {
+                id: self.id.clone(),
+                source,
+            })?;
+        if !restored {
+            return Err(HistoryError::ElementNotFound(self.id.clone()));
+        }
+        Ok(())
+    }
+
+    /// Serialized component once the change is applied
+    pub fn current(&self) -> &Value {
+        &self.current
+    }
+
+    /// Serialized component before the change
+    pub fn previous(&self) -> &Value {
+        &self.previous
+    }
+}
+
+/// Copy of the whole network, taken on demand
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub name: String,
+    /// Last change applied when the checkpoint was taken
+    pub sequence: u64,
+    #[serde(skip)]
+    network: Network,
+}
+
+/// Position in the journal to come back to when a group of changes is reverted
+#[derive(Debug, Clone)]
+pub struct HistoryMark {
+    sequence: u64,
+    undone: Vec<HistoryEntry>,
+}
+
+#[derive(Resource, Debug)]
+pub struct History {
+    done: Vec<HistoryEntry>,
+    undone: Vec<HistoryEntry>,
+    checkpoints: BTreeMap<String, Checkpoint>,
+    sequence: u64,
+    capacity: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl History {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            done: Vec::new(),
+            undone: Vec::new(),
+            checkpoints: BTreeMap::new(),
+            sequence: 0,
+            capacity,
+        }
+    }
+
+    /// Records a change of `id` made by a handler, nothing is kept when no field changed.
+    /// A new change cannot be redone over, it forgets the undone ones.
+    pub fn record<T>(&mut self, id: &str, previous: Value, component: &T)
+    where
+        T: Component + Serialize + DeserializeOwned,
+    {
+        let Ok(current) = serde_json::to_value(component) else {
+            return;
+        };
+        if current == previous {
+            return;
+        }
+
+        let (before, after) = changed_fields(&previous, &current);
+        self.sequence += 1;
+        self.done.push(HistoryEntry {
+            sequence: self.sequence,
+            id: id.to_string(),
+            component_type: short_type_name::<T>().to_string(),
+            before,
+            after,
+            previous,
+            current,
+            find: find_component::<T>,
+            restore: restore_component::<T>,
+        });
+        self.undone.clear();
+
+        if self.done.len() > self.capacity {
+            let overflow = self.done.len() - self.capacity;
+            self.done.drain(..overflow);
+        }
+    }
+
+    /// Applied changes, oldest first
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.done
+    }
+
+    /// Undone changes, the next one to redo last
+    pub fn undone(&self) -> &[HistoryEntry] {
+        &self.undone
+    }
+
+    pub fn checkpoints(&self) -> impl Iterator<Item = &Checkpoint> {
+        self.checkpoints.values()
+    }
+
+    /// Current position in the journal, see `rollback`
+    pub fn mark(&self) -> HistoryMark {
+        HistoryMark {
+            sequence: self.sequence,
+            undone: self.undone.clone(),
+        }
+    }
+
+    /// Forgets the changes recorded since `mark`, whose effect has been reverted by the caller,
+    /// and brings back the changes which could be redone then
+    pub fn rollback(&mut self, mark: HistoryMark) {
+        self.done.retain(|entry| entry.sequence <= mark.sequence);
+        self.undone = mark.undone;
+        self.sequence = mark.sequence;
+    }
+
+    /// Forgets every change and checkpoint, when another network is loaded
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+        self.checkpoints.clear();
+    }
+}
+
+/// Top-level fields which differ, with their values on both sides
+fn changed_fields(previous: &Value, current: &Value) -> (Value, Value) {
+    let (Value::Object(previous), Value::Object(current)) = (previous, current) else {
+        return (previous.clone(), current.clone());
+    };
+
+    let mut before = Map::new();
+    let mut after = Map::new();
+    for key in previous.keys().chain(current.keys()) {
+        let (old, new) = (previous.get(key), current.get(key));
+        if old != new && !before.contains_key(key) {
+            before.insert(key.clone(), old.cloned().unwrap_or(Value::Null));
+            after.insert(key.clone(), new.cloned().unwrap_or(Value::Null));
+        }
+    }
+    (Value::Object(before), Value::Object(after))
+}
+
+pub fn init_history(world: &mut World) {
+    world.init_resource::<History>();
+}
+
+/// Reverts the last applied change. When its element has been deleted since, the change is
+/// kept in the journal and an error is returned.
+pub fn undo(world: &mut World) -> Result<HistoryEntry, HistoryError> {
+    let entry = world
+        .get_resource_mut::<History>()
+        .and_then(|mut history| history.done.pop())
+        .ok_or(HistoryError::NothingToUndo)?;
+
+    let result = entry.apply(world, &entry.previous);
+    let mut history = world.resource_mut::<History>();
+    match result {
+        Ok(()) => {
+            history.undone.push(entry.clone());
+            Ok(entry)
+        }
+        Err(error) => {
+            history.done.push(entry);
+            Err(error)
+        }
+    }
+}
+
+/// Applies again the last undone change
+pub fn redo(world: &mut World) -> Result<HistoryEntry, HistoryError> {
+    let entry = world
+        .get_resource_mut::<History>()
+        .and_then(|mut history| history.undone.pop())
+        .ok_or(HistoryError::NothingToRedo)?;
+
+    let result = entry.apply(world, &entry.current);
+    let mut history = world.resource_mut::<History>();
+    match result {
+        Ok(()) => {
+            history.done.push(entry.clone());
+            Ok(entry)
+        }
+        Err(error) => {
+            history.undone.push(entry);
+            Err(error)
+        }
+    }
+}
+
+/// Keeps a copy of the current network under `name`, replacing a previous one of that name.
+/// Refused when some elements cannot be placed in the network, as restoring it would lose them.
+pub fn save_checkpoint(world: &mut World, name: &str) -> Result<Checkpoint, HistoryError> {
+    let export = export_network(world)?;
+    if !export.unplaced.is_empty() {
+        return Err(HistoryError::Unplaced(export.unplaced));
+    }
+    let mut history = world
+        .get_resource_mut::<History>()
+        .ok_or(HistoryError::Export(ExportError::NoNetwork))?;
+
+    let checkpoint = Checkpoint {
+        name: name.to_string(),
+        sequence: history.done.last().map_or(0, |entry| entry.sequence),
+        network: export.network,
+    };
+    history
+        .checkpoints
+        .insert(name.to_string(), checkpoint.clone());
+    Ok(checkpoint)
+}
+
+/// Replaces every element of the world by the network saved under `name`. The changes made
+/// before cannot be undone afterwards, the checkpoints are kept.
+pub fn restore_checkpoint(
+    world: &mut World,
+    schedule: &mut Schedule,
+    name: &str,
+) -> Result<Checkpoint, HistoryError> {
+    let checkpoint = world
+        .get_resource::<History>()
+        .and_then(|history| history.checkpoints.get(name))
+        .cloned()
+        .ok_or_else(|| HistoryError::UnknownCheckpoint(name.to_string()))?;
+
+    world.resource_scope(|world, mut registry: Mut<AssetRegistry>| {
+        let entities: Vec<Entity> = world
+            .query_filtered::<Entity, With<Id>>()
+            .iter(world)
+            .collect();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, world);
+        for entity in entities {
+            registry.remove_entity(&mut commands, entity);
+        }
+        queue.apply(world);
+    });
+
+    checkpoint.network.register(world, schedule);
+    schedule.run(world);
+
+    let mut history = world.resource_mut::<History>();
+    history.done.clear();
+    history.undone.clear();
+    Ok(checkpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_changed_fields() {
+        let (before, after) = changed_fields(
+            &json!({"id": "L", "r": 1.0, "x": 2.0, "limits": null}),
+            &json!({"id": "L", "r": 1.5, "x": 2.0, "limits": {"permanentLimit": 10.0}}),
+        );
+
+        assert_eq!(before, json!({"r": 1.0, "limits": null}));
+        assert_eq!(after, json!({"r": 1.5, "limits": {"permanentLimit": 10.0}}));
+    }
+
+    #[test]
+    fn test_record_keeps_capacity() {
+        let mut history = History::with_capacity(2);
+        let switch = |open: bool, retained: bool| crate::Switch {
+            id: "S".to_string(),
+            kind: crate::SwitchKind::Breaker,
+            open,
+            retained,
+            bus1: "B1".to_string(),
+            bus2: "B2".to_string(),
+            voltage_level_id: "VL".to_string(),
+        };
+        let state = |open, retained| serde_json::to_value(switch(open, retained)).unwrap();
+
+        history.record("S", state(false, false), &switch(false, false));
+        assert!(history.entries().is_empty());
+
+        history.record("S", state(false, false), &switch(true, false));
+        history.record("S", state(true, false), &switch(false, false));
+        history.record("S", state(false, false), &switch(false, true));
+
+        let sequences: Vec<u64> = history.entries().iter().map(|e| e.sequence).collect();
+        assert_eq!(sequences, vec![2, 3]);
+        assert_eq!(history.entries()[1].component_type, "Switch");
+        assert_eq!(history.entries()[1].before, json!({"retained": false}));
+        assert_eq!(history.entries()[1].after, json!({"retained": true}));
+    }
+}
@@ -1,11 +1,27 @@
 mod entities;
+mod export;
 mod extensions;
+mod history;
+mod loadflow;
+mod patch;
 mod plugins;
+mod relations;
 mod resources;
+mod scenario;
+mod topology;
+mod validation;
 
 pub use entities::*;
+pub use export::*;
 pub use extensions::*;
+pub use history::*;
+pub use loadflow::*;
+pub use patch::*;
 pub use plugins::*;
+pub use relations::*;
 pub use resources::*;
+pub use scenario::*;
+pub use topology::*;
+pub use validation::*;
 
 pub mod libs;
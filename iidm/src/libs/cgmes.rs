@@ -0,0 +1,1023 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read, Seek};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::entities::*;
+use crate::libs::xml::{read_document, Element, XmlError};
+
+const IIDM_VERSION: &str = "1.12";
+const SOURCE_FORMAT: &str = "CGMES";
+
+/// Classes read while building the network, the others are listed in the report
+const SUPPORTED_CLASSES: &[&str] = &[
+    "ACLineSegment",
+    "BaseVoltage",
+    "Bay",
+    "Breaker",
+    "BusbarSection",
+    "ConformLoad",
+    "ConnectivityNode",
+    "Disconnector",
+    "EnergyConsumer",
+    "GeneratingUnit",
+    "HydroGeneratingUnit",
+    "LoadBreakSwitch",
+    "NonConformLoad",
+    "NuclearGeneratingUnit",
+    "PowerTransformer",
+    "PowerTransformerEnd",
+    "RatioTapChanger",
+    "RegulatingControl",
+    "SolarGeneratingUnit",
+    "StationSupply",
+    "Substation",
+    "SynchronousMachine",
+    "TapChangerControl",
+    "Terminal",
+    "ThermalGeneratingUnit",
+    "TopologicalNode",
+    "VoltageLevel",
+    "WindGeneratingUnit",
+];
+
+#[derive(Debug, Error)]
+pub enum CgmesError {
+    #[error("{0}")]
+    Xml(#[from] XmlError),
+
+    #[error("Zip archive error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Unexpected root element '{0}', expected 'RDF'")]
+    UnexpectedRoot(String),
+
+    #[error("No CGMES profile found")]
+    NoProfile,
+
+    #[error("Missing property '{property}' on {class} '{id}'")]
+    MissingProperty {
+        class: String,
+        id: String,
+        property: String,
+    },
+
+    #[error("Invalid value '{value}' for property '{property}' on {class} '{id}'")]
+    InvalidValue {
+        class: String,
+        id: String,
+        property: String,
+        value: String,
+    },
+}
+
+/// What the import left out of the network
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CgmesReport {
+    /// Number of objects of each class without an IIDM counterpart
+    pub unsupported_classes: BTreeMap<String, usize>,
+    /// Objects of a supported class which could not be mapped, with the reason
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CgmesImport {
+    pub network: Network,
+    pub report: CgmesReport,
+}
+
+/// Reads a network from a single RDF/XML document holding all the profiles
+pub fn from_str(xml: &str) -> Result<CgmesImport, CgmesError> {
+    from_profiles([xml.as_bytes()])
+}
+
+/// Reads a network from its profiles (EQ, TP, SSH, SV...), in any order. The objects of every
+/// profile are merged by their ID.
+pub fn from_profiles<R, I>(profiles: I) -> Result<CgmesImport, CgmesError>
+where
+    R: BufRead,
+    I: IntoIterator<Item = R>,
+{
+    let mut model = Model::default();
+    for profile in profiles {
+        model.add_profile(&read_document(profile)?)?;
+    }
+    if model.profiles == 0 {
+        return Err(CgmesError::NoProfile);
+    }
+    model.into_network()
+}
+
+/// Reads a network from a zip archive of profiles, the entries other than `.xml` are ignored
+pub fn from_zip<R: Read + Seek>(archive: R) -> Result<CgmesImport, CgmesError> {
+    let mut archive = zip::ZipArchive::new(archive)?;
+    let mut profiles = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if !entry.is_file() || !entry.name().to_ascii_lowercase().ends_with(".xml") {
+            continue;
+        }
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        profiles.push(content);
+    }
+    from_profiles(
+        profiles
+            .iter()
+            .map(|content| BufReader::new(content.as_slice())),
+    )
+}
+
+/// Object of any profile, its properties keyed by their qualified name (`IdentifiedObject.name`)
+#[derive(Debug, Default)]
+struct CimObject {
+    class: String,
+    properties: HashMap<String, String>,
+}
+
+impl CimObject {
+    fn get(&self, property: &str) -> Option<&str> {
+        self.properties.get(property).map(String::as_str)
+    }
+
+    fn flag(&self, property: &str) -> Option<bool> {
+        self.get(property).map(|value| value == "true")
+    }
+
+    /// Last segment of an enumeration value, `generator` for `SynchronousMachineKind.generator`
+    fn enumeration(&self, property: &str) -> Option<&str> {
+        self.get(property)
+            .map(|value| value.rsplit('.').next().unwrap_or(value))
+    }
+}
+
+/// Terminal of an equipment, located on a node of a voltage level
+#[derive(Debug, Clone)]
+struct Connection {
+    voltage_level_id: String,
+    node: i32,
+    connected: bool,
+}
+
+impl Connection {
+    fn bus(&self) -> String {
+        if self.connected {
+            self.node.to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    fn connectable_bus(&self) -> String {
+        self.node.to_string()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Model {
+    profiles: usize,
+    header: Option<(String, CimObject)>,
+    objects: BTreeMap<String, CimObject>,
+}
+
+/// `#_abc`, `_abc` and `urn:uuid:abc` all stand for the object `_abc`
+fn normalize_id(id: &str) -> String {
+    let id = id.rsplit_once('#').map_or(id, |(_, fragment)| fragment);
+    match id.strip_prefix("urn:uuid:") {
+        Some(uuid) => format!("_{}", uuid),
+        None => id.to_string(),
+    }
+}
+
+fn is_equipment(header: &CimObject) -> bool {
+    header
+        .get("Model.profile")
+        .is_some_and(|profile| profile.contains("/Equipment"))
+}
+
+fn side(sequence: usize) -> Side {
+    match sequence {
+        2 => Side::Two,
+        3 => Side::Three,
+        _ => Side::One,
+    }
+}
+
+impl Model {
+    fn add_profile(&mut self, root: &Element) -> Result<(), CgmesError> {
+        if root.name != "RDF" {
+            return Err(CgmesError::UnexpectedRoot(root.name.clone()));
+        }
+        self.profiles += 1;
+
+        for element in &root.children {
+            let Some(id) = element.get("ID").or_else(|| element.get("about")) else {
+                continue;
+            };
+            let id = normalize_id(id);
+            let properties = element.children.iter().map(|property| {
+                let value = match property.get("resource") {
+                    Some(resource) => normalize_id(resource),
+                    None => property.text.clone(),
+                };
+                (property.name.clone(), value)
+            });
+
+            if element.name == "FullModel" {
+                let header = CimObject {
+                    class: element.name.clone(),
+                    properties: properties.collect(),
+                };
+                // The network is named after the first equipment profile, or the first one read
+                let named = match &self.header {
+                    Some((_, current)) => is_equipment(current) || !is_equipment(&header),
+                    None => false,
+                };
+                if !named {
+                    self.header = Some((id, header));
+                }
+                continue;
+            }
+            let object = self.objects.entry(id).or_default();
+            if object.class.is_empty() {
+                object.class = element.name.clone();
+            }
+            object.properties.extend(properties);
+        }
+        Ok(())
+    }
+
+    fn into_network(self) -> Result<CgmesImport, CgmesError> {
+        let mut report = CgmesReport::default();
+        for object in self.objects.values() {
+            if !SUPPORTED_CLASSES.contains(&object.class.as_str()) {
+                *report
+                    .unsupported_classes
+                    .entry(object.class.clone())
+                    .or_default() += 1;
+            }
+        }
+
+        let mut importer = Importer::new(&self.objects, report);
+        let (substations, lines) = importer.import()?;
+
+        let (id, case_date) = match &self.header {
+            Some((id, header)) => (id.clone(), case_date(id, header)?),
+            None => ("cgmes".to_string(), Utc::now().fixed_offset()),
+        };
+        let network = Network {
+            version: IIDM_VERSION.to_string(),
+            id,
+            case_date,
+            forecast_distance: 0,
+            source_format: SOURCE_FORMAT.to_string(),
+            minimum_validation_level: ValidationLevel::default(),
+            substations,
+            lines,
+            three_windings_transformers: Vec::new(),
+            switches: Vec::new(),
+            shunt_compensators: Vec::new(),
+            static_var_compensators: Vec::new(),
+            dangling_lines: Vec::new(),
+            tie_lines: Vec::new(),
+            hvdc_lines: Vec::new(),
+        };
+        Ok(CgmesImport {
+            network,
+            report: importer.report,
+        })
+    }
+}
+
+/// Scenario time of the model, or its creation time when missing
+fn case_date(id: &str, header: &CimObject) -> Result<DateTime<FixedOffset>, CgmesError> {
+    let Some((property, value)) = ["Model.scenarioTime", "Model.created"]
+        .into_iter()
+        .find_map(|property| Some((property, header.get(property)?)))
+    else {
+        return Ok(Utc::now().fixed_offset());
+    };
+    DateTime::parse_from_rfc3339(value).map_err(|_| CgmesError::InvalidValue {
+        class: header.class.clone(),
+        id: id.to_string(),
+        property: property.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Voltage level being filled with the equipment connected to its nodes
+struct VoltageLevelParts {
+    voltage_level: VoltageLevel,
+    substation_id: String,
+    topology: NodeBreakerTopology,
+    generators: Vec<Generator>,
+    loads: Vec<Load>,
+    busbar_sections: Vec<BusbarSection>,
+}
+
+struct Importer<'a> {
+    objects: &'a BTreeMap<String, CimObject>,
+    report: CgmesReport,
+    /// Terminals by ID, with their equipment and sequence number
+    terminals: HashMap<&'a str, (&'a str, usize, Option<Connection>)>,
+    /// Terminal IDs of each equipment, by sequence number
+    equipment_terminals: HashMap<&'a str, BTreeMap<usize, &'a str>>,
+    voltage_levels: BTreeMap<String, VoltageLevelParts>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(objects: &'a BTreeMap<String, CimObject>, report: CgmesReport) -> Self {
+        Self {
+            objects,
+            report,
+            terminals: HashMap::new(),
+            equipment_terminals: HashMap::new(),
+            voltage_levels: BTreeMap::new(),
+        }
+    }
+
+    fn of_class(&self, classes: &'a [&str]) -> impl Iterator<Item = (&'a str, &'a CimObject)> {
+        let objects: &'a BTreeMap<String, CimObject> = self.objects;
+        objects
+            .iter()
+            .filter(move |(_, object)| classes.contains(&object.class.as_str()))
+            .map(|(id, object)| (id.as_str(), object))
+    }
+
+    fn referenced(&self, object: &CimObject, property: &str) -> Option<(&'a str, &'a CimObject)> {
+        let (id, object) = self.objects.get_key_value(object.get(property)?)?;
+        Some((id.as_str(), object))
+    }
+
+    fn skip(&mut self, object: &CimObject, id: &str, reason: &str) {
+        self.report
+            .skipped
+            .push(format!("{} '{}': {}", object.class, id, reason));
+    }
+
+    fn import(&mut self) -> Result<(Vec<Substation>, Vec<Line>), CgmesError> {
+        self.read_voltage_levels()?;
+        self.read_terminals()?;
+        self.read_switches();
+        self.read_busbar_sections();
+        self.read_generators()?;
+        self.read_loads()?;
+        let lines = self.read_lines()?;
+        let transformers = self.read_transformers()?;
+        Ok((self.assemble_substations(transformers), lines))
+    }
+
+    fn read_voltage_levels(&mut self) -> Result<(), CgmesError> {
+        for (id, object) in self.of_class(&["VoltageLevel"]) {
+            let Some((substation_id, _)) = self.referenced(object, "VoltageLevel.Substation")
+            else {
+                self.skip(object, id, "not in a substation");
+                continue;
+            };
+            let nominal_v = match self.referenced(object, "VoltageLevel.BaseVoltage") {
+                Some((base_id, base)) => number(base_id, base, "BaseVoltage.nominalVoltage")?,
+                None => {
+                    self.skip(object, id, "no base voltage");
+                    continue;
+                }
+            };
+            let voltage_level = VoltageLevel {
+                id: id.to_string(),
+                nominal_v,
+                topology_kind: TopologyKind::NodeBreaker,
+                generators: None,
+                loads: None,
+                busbar_sections: None,
+                node_breaker_topology: None,
+                bus_breaker_topology: None,
+            };
+            self.voltage_levels.insert(
+                id.to_string(),
+                VoltageLevelParts {
+                    voltage_level,
+                    substation_id: substation_id.to_string(),
+                    topology: NodeBreakerTopology {
+                        nodes: Vec::new(),
+                        switches: Vec::new(),
+                        internal_connections: Vec::new(),
+                    },
+                    generators: Vec::new(),
+                    loads: Vec::new(),
+                    busbar_sections: Vec::new(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Voltage level holding a node, directly or through a bay
+    fn container_of(&self, node: &CimObject, property: &str) -> Option<&'a str> {
+        let (container_id, container) = self.referenced(node, property)?;
+        match container.class.as_str() {
+            "VoltageLevel" => Some(container_id),
+            "Bay" => self
+                .referenced(container, "Bay.VoltageLevel")
+                .map(|(id, _)| id),
+            _ => None,
+        }
+    }
+
+    /// Numbers the connectivity nodes of each voltage level, or its topological nodes for a
+    /// bus-branch model, then locates the terminals on them
+    fn read_terminals(&mut self) -> Result<(), CgmesError> {
+        let (node_class, terminal_property) = if self.of_class(&["ConnectivityNode"]).count() > 0 {
+            ("ConnectivityNode", "Terminal.ConnectivityNode")
+        } else {
+            ("TopologicalNode", "Terminal.TopologicalNode")
+        };
+        let container_property = format!("{}.ConnectivityNodeContainer", node_class);
+
+        let mut nodes: HashMap<&str, (&str, i32)> = HashMap::new();
+        for (id, object) in self.of_class(&["ConnectivityNode", "TopologicalNode"]) {
+            if object.class != node_class {
+                continue;
+            }
+            let container = self.container_of(object, &container_property);
+            let Some((container, parts)) = container
+                .and_then(|container| Some((container, self.voltage_levels.get_mut(container)?)))
+            else {
+                self.skip(object, id, "not in an imported voltage level");
+                continue;
+            };
+            let node = parts.topology.nodes.len() as i32;
+            parts.topology.nodes.push(Node { id: node });
+            nodes.insert(id, (container, node));
+        }
+
+        for (id, object) in self.of_class(&["Terminal"]) {
+            let Some(equipment) = object.get("Terminal.ConductingEquipment") else {
+                self.skip(object, id, "no conducting equipment");
+                continue;
+            };
+            let Some((equipment, _)) = self.objects.get_key_value(equipment) else {
+                continue;
+            };
+            let terminals = self.equipment_terminals.entry(equipment).or_default();
+            let sequence = match object.get("ACDCTerminal.sequenceNumber") {
+                Some(_) => number(id, object, "ACDCTerminal.sequenceNumber")?,
+                None => terminals.len() + 1,
+            };
+            terminals.insert(sequence, id);
+
+            let connection = object
+                .get(terminal_property)
+                .and_then(|node| nodes.get(node))
+                .map(|(voltage_level_id, node)| Connection {
+                    voltage_level_id: voltage_level_id.to_string(),
+                    node: *node,
+                    connected: object.flag("ACDCTerminal.connected").unwrap_or(true),
+                });
+            self.terminals.insert(id, (equipment, sequence, connection));
+        }
+        Ok(())
+    }
+
+    fn connection(&self, equipment: &str, sequence: usize) -> Option<Connection> {
+        let terminal = self.equipment_terminals.get(equipment)?.get(&sequence)?;
+        self.terminals.get(terminal)?.2.clone()
+    }
+
+    /// Connection of a one-terminal equipment, skipped when it is nowhere
+    fn single_connection(&mut self, id: &str, object: &CimObject) -> Option<Connection> {
+        let connection = self.connection(id, 1).filter(|connection| {
+            self.voltage_levels
+                .contains_key(&connection.voltage_level_id)
+        });
+        if connection.is_none() {
+            self.skip(
+                object,
+                id,
+                "terminal not on a node of an imported voltage level",
+            );
+        }
+        connection
+    }
+
+    /// Connections of both ends of a branch, skipped when either is nowhere
+    fn branch_connections(
+        &mut self,
+        id: &str,
+        object: &CimObject,
+        terminals: [Option<Connection>; 2],
+    ) -> Option<(Connection, Connection)> {
+        match terminals {
+            [Some(connection1), Some(connection2)] => Some((connection1, connection2)),
+            _ => {
+                self.skip(
+                    object,
+                    id,
+                    "both ends must be on nodes of imported voltage levels",
+                );
+                None
+            }
+        }
+    }
+
+    fn read_switches(&mut self) {
+        for (id, object) in self.of_class(&["Breaker", "Disconnector", "LoadBreakSwitch"]) {
+            let kind = match object.class.as_str() {
+                "Breaker" => SwitchKind::Breaker,
+                "Disconnector" => SwitchKind::Disconnector,
+                _ => SwitchKind::LoadBreakSwitch,
+            };
+            let terminals = [self.connection(id, 1), self.connection(id, 2)];
+            let Some((connection1, connection2)) = self.branch_connections(id, object, terminals)
+            else {
+                continue;
+            };
+            if connection1.voltage_level_id != connection2.voltage_level_id {
+                self.skip(object, id, "ends in different voltage levels");
+                continue;
+            }
+            let switch = Switch {
+                id: id.to_string(),
+                kind,
+                open: object
+                    .flag("Switch.open")
+                    .or_else(|| object.flag("Switch.normalOpen"))
+                    .unwrap_or(false),
+                retained: object.flag("Switch.retained").unwrap_or(false),
+                bus1: connection1.connectable_bus(),
+                bus2: connection2.connectable_bus(),
+                voltage_level_id: connection1.voltage_level_id.clone(),
+            };
+            if let Some(parts) = self.voltage_levels.get_mut(&connection1.voltage_level_id) {
+                parts.topology.switches.push(switch);
+            }
+        }
+    }
+
+    fn read_busbar_sections(&mut self) {
+        for (id, object) in self.of_class(&["BusbarSection"]) {
+            let Some(connection) = self.single_connection(id, object) else {
+                continue;
+            };
+            let section = BusbarSection {
+                id: id.to_string(),
+                name: object
+                    .get("IdentifiedObject.name")
+                    .unwrap_or_default()
+                    .to_string(),
+                bus: connection.bus(),
+                connectable_bus: connection.connectable_bus(),
+            };
+            if let Some(parts) = self.voltage_levels.get_mut(&connection.voltage_level_id) {
+                parts.busbar_sections.push(section);
+            }
+        }
+    }
+
+    /// Target of the regulating control of an equipment, when enabled
+    fn regulating_control(&self, object: &CimObject, property: &str) -> Option<&'a CimObject> {
+        let (_, control) = self.referenced(object, property)?;
+        control
+            .flag("RegulatingControl.enabled")
+            .unwrap_or(true)
+            .then_some(control)
+    }
+
+    fn read_generators(&mut self) -> Result<(), CgmesError> {
+        for (id, object) in self.of_class(&["SynchronousMachine"]) {
+            let Some(connection) = self.single_connection(id, object) else {
+                continue;
+            };
+            let unit = self.referenced(object, "RotatingMachine.GeneratingUnit");
+            let energy_source = match unit.map(|(_, unit)| unit.class.as_str()) {
+                Some("HydroGeneratingUnit") => EnergySource::Hydro,
+                Some("NuclearGeneratingUnit") => EnergySource::Nuclear,
+                Some("WindGeneratingUnit") => EnergySource::Wind,
+                Some("ThermalGeneratingUnit") => EnergySource::Thermal,
+                Some("SolarGeneratingUnit") => EnergySource::Solar,
+                _ => EnergySource::Other,
+            };
+            let unit_number = |property| match unit {
+                Some((unit_id, unit)) => optional_number(unit_id, unit, property),
+                None => Ok(None),
+            };
+
+            // CGMES counts the produced power as negative
+            let target_p = match optional_number::<f64>(id, object, "RotatingMachine.p")? {
+                Some(p) => -p,
+                None => unit_number("GeneratingUnit.initialP")?.unwrap_or(0.0),
+            };
+            let target_q = -optional_number(id, object, "RotatingMachine.q")?.unwrap_or(0.0);
+            let max_p = match unit_number("GeneratingUnit.maxOperatingP")? {
+                Some(max_p) => max_p,
+                None => {
+                    optional_number(id, object, "RotatingMachine.ratedS")?.unwrap_or(target_p.abs())
+                }
+            };
+
+            let control = self
+                .regulating_control(object, "RegulatingCondEq.RegulatingControl")
+                .filter(|control| {
+                    control
+                        .enumeration("RegulatingControl.mode")
+                        .unwrap_or("voltage")
+                        == "voltage"
+                });
+            let target_v = match control {
+                Some(control) => optional_number(id, control, "RegulatingControl.targetValue")?,
+                None => None,
+            };
+            let nominal_v = self.voltage_levels[&connection.voltage_level_id]
+                .voltage_level
+                .nominal_v;
+
+            let min_q = optional_number(id, object, "SynchronousMachine.minQ")?;
+            let max_q = optional_number(id, object, "SynchronousMachine.maxQ")?;
+            let generator = Generator {
+                id: id.to_string(),
+                energy_source,
+                min_p: unit_number("GeneratingUnit.minOperatingP")?.unwrap_or(0.0),
+                max_p,
+                voltage_regulator_on: target_v.is_some()
+                    && object
+                        .flag("RegulatingCondEq.controlEnabled")
+                        .unwrap_or(false),
+                target_p,
+                target_v: target_v.unwrap_or(nominal_v),
+                target_q,
+                bus: connection.bus(),
+                connectable_bus: connection.connectable_bus(),
+                reactive_capability_curve: None,
+                min_max_reactive_limits: min_q
+                    .zip(max_q)
+                    .map(|(min_q, max_q)| MinMaxReactiveLimits { min_q, max_q }),
+            };
+            if let Some(parts) = self.voltage_levels.get_mut(&connection.voltage_level_id) {
+                parts.generators.push(generator);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_loads(&mut self) -> Result<(), CgmesError> {
+        let classes = &[
+            "EnergyConsumer",
+            "ConformLoad",
+            "NonConformLoad",
+            "StationSupply",
+        ];
+        for (id, object) in self.of_class(classes) {
+            let Some(connection) = self.single_connection(id, object) else {
+                continue;
+            };
+            let load = Load {
+                id: id.to_string(),
+                load_type: match object.class.as_str() {
+                    "StationSupply" => LoadType::Auxiliary,
+                    _ => LoadType::Undefined,
+                },
+                p0: optional_number(id, object, "EnergyConsumer.p")?.unwrap_or(0.0),
+                q0: optional_number(id, object, "EnergyConsumer.q")?.unwrap_or(0.0),
+                bus: connection.bus(),
+                connectable_bus: connection.connectable_bus(),
+                exponential_model: None,
+                zip_model: None,
+            };
+            if let Some(parts) = self.voltage_levels.get_mut(&connection.voltage_level_id) {
+                parts.loads.push(load);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_lines(&mut self) -> Result<Vec<Line>, CgmesError> {
+        let mut lines = Vec::new();
+        for (id, object) in self.of_class(&["ACLineSegment"]) {
+            let terminals = [self.connection(id, 1), self.connection(id, 2)];
+            let Some((connection1, connection2)) = self.branch_connections(id, object, terminals)
+            else {
+                continue;
+            };
+            // The shunt admittance of the whole line is split between both ends
+            let b = optional_number(id, object, "ACLineSegment.bch")?.unwrap_or(0.0) / 2.0;
+            let g = optional_number(id, object, "ACLineSegment.gch")?.unwrap_or(0.0) / 2.0;
+            lines.push(Line {
+                id: id.to_string(),
+                r: number(id, object, "ACLineSegment.r")?,
+                x: number(id, object, "ACLineSegment.x")?,
+                b1: b,
+                b2: b,
+                g1: g,
+                g2: g,
+                voltage_level_id1: connection1.voltage_level_id.clone(),
+                bus1: connection1.bus(),
+                connectable_bus1: connection1.connectable_bus(),
+                voltage_level_id2: connection2.voltage_level_id.clone(),
+                bus2: connection2.bus(),
+                connectable_bus2: connection2.connectable_bus(),
+                current_limits1: None,
+                current_limits2: None,
+            });
+        }
+        Ok(lines)
+    }
+
+    /// Two windings transformers, with the substation they belong to
+    fn read_transformers(&mut self) -> Result<Vec<(String, TwoWindingsTransformer)>, CgmesError> {
+        let mut ends: HashMap<&str, BTreeMap<usize, (&str, &CimObject)>> = HashMap::new();
+        for (id, end) in self.of_class(&["PowerTransformerEnd"]) {
+            if let Some(transformer) = end.get("PowerTransformerEnd.PowerTransformer") {
+                let windings = ends.entry(transformer).or_default();
+                let end_number = match end.get("TransformerEnd.endNumber") {
+                    Some(_) => number(id, end, "TransformerEnd.endNumber")?,
+                    None => windings.len() + 1,
+                };
+                windings.insert(end_number, (id, end));
+            }
+        }
+
+        let mut transformers = Vec::new();
+        for (id, object) in self.of_class(&["PowerTransformer"]) {
+            let windings: Vec<_> = ends
+                .get(id)
+                .map(|windings| windings.values().copied().collect())
+                .unwrap_or_default();
+            let [(end1_id, end1), (end2_id, end2)] = windings[..] else {
+                let reason = format!(
+                    "{} windings, only two windings transformers are imported",
+                    windings.len()
+                );
+                self.skip(object, id, &reason);
+                continue;
+            };
+
+            let terminal = |end: &CimObject| {
+                let (_, sequence, connection) =
+                    self.terminals.get(end.get("TransformerEnd.Terminal")?)?;
+                Some((*sequence, connection.clone()?))
+            };
+            let terminals = [terminal(end1).map(|t| t.1), terminal(end2).map(|t| t.1)];
+            let Some((connection1, connection2)) = self.branch_connections(id, object, terminals)
+            else {
+                continue;
+            };
+            let Some(substation_id) = self
+                .voltage_levels
+                .get(&connection1.voltage_level_id)
+                .map(|parts| parts.substation_id.clone())
+            else {
+                continue;
+            };
+
+            let rated_u1 = number(end1_id, end1, "PowerTransformerEnd.ratedU")?;
+            let rated_u2 = number(end2_id, end2, "PowerTransformerEnd.ratedU")?;
+            // Impedances and admittances of both ends, brought to the side 2
+            let ratio = rated_u2 / rated_u1;
+            let value = |end_id, end, property| {
+                optional_number::<f64>(end_id, end, property).map(Option::unwrap_or_default)
+            };
+            let r = value(end1_id, end1, "PowerTransformerEnd.r")? * ratio * ratio
+                + value(end2_id, end2, "PowerTransformerEnd.r")?;
+            let x = value(end1_id, end1, "PowerTransformerEnd.x")? * ratio * ratio
+                + value(end2_id, end2, "PowerTransformerEnd.x")?;
+            let g = value(end1_id, end1, "PowerTransformerEnd.g")? / (ratio * ratio)
+                + value(end2_id, end2, "PowerTransformerEnd.g")?;
+            let b = value(end1_id, end1, "PowerTransformerEnd.b")? / (ratio * ratio)
+                + value(end2_id, end2, "PowerTransformerEnd.b")?;
+
+            let ratio_tap_changer = self.read_ratio_tap_changer(id, [end1_id, end2_id])?;
+            transformers.push((
+                substation_id,
+                TwoWindingsTransformer {
+                    id: id.to_string(),
+                    r,
+                    x,
+                    g,
+                    b,
+                    rated_u1,
+                    rated_u2,
+                    voltage_level_id1: connection1.voltage_level_id.clone(),
+                    bus1: connection1.bus(),
+                    connectable_bus1: connection1.connectable_bus(),
+                    voltage_level_id2: connection2.voltage_level_id.clone(),
+                    bus2: connection2.bus(),
+                    connectable_bus2: connection2.connectable_bus(),
+                    ratio_tap_changer,
+                    phase_tap_changer: None,
+                    current_limits1: None,
+                    current_limits2: None,
+                },
+            ));
+        }
+        Ok(transformers)
+    }
+
+    /// Linear ratio tap changer on either end of a transformer
+    fn read_ratio_tap_changer(
+        &self,
+        transformer_id: &str,
+        ends: [&str; 2],
+    ) -> Result<Option<RatioTapChanger>, CgmesError> {
+        let Some((position, id, object)) =
+            self.of_class(&["RatioTapChanger"])
+                .find_map(|(id, object)| {
+                    let end = object.get("RatioTapChanger.TransformerEnd")?;
+                    let position = ends.iter().position(|candidate| *candidate == end)?;
+                    Some((position, id, object))
+                })
+        else {
+            return Ok(None);
+        };
+
+        let low_step: i32 = number(id, object, "TapChanger.lowStep")?;
+        let high_step: i32 = number(id, object, "TapChanger.highStep")?;
+        let neutral_step: i32 = number(id, object, "TapChanger.neutralStep")?;
+        let increment =
+            optional_number(id, object, "RatioTapChanger.stepVoltageIncrement")?.unwrap_or(0.0);
+        // The current step may be given as a decimal in the SSH profile
+        let tap_position = match optional_number::<f64>(id, object, "TapChanger.step")? {
+            Some(step) => step.round() as i32,
+            None => optional_number(id, object, "TapChanger.normalStep")?.unwrap_or(neutral_step),
+        };
+
+        let steps = (low_step..=high_step)
+            .map(|step| {
+                let ratio = 1.0 + f64::from(step - neutral_step) * increment / 100.0;
+                TapStep {
+                    r: 0.0,
+                    x: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    // The voltage of the end holding the tap changer follows its ratio
+                    rho: if position == 0 { 1.0 / ratio } else { ratio },
+                }
+            })
+            .collect();
+
+        let control = self.regulating_control(object, "TapChanger.TapChangerControl");
+        let regulation_mode = match control.and_then(|c| c.enumeration("RegulatingControl.mode")) {
+            Some("reactivePower") => RatioRegulationMode::ReactivePower,
+            _ => RatioRegulationMode::Voltage,
+        };
+        let terminal_ref = control
+            .and_then(|control| {
+                self.terminals
+                    .get(control.get("RegulatingControl.Terminal")?)
+            })
+            .map(|(equipment, sequence, _)| TerminalRef {
+                id: equipment.to_string(),
+                side: side(*sequence),
+            })
+            .unwrap_or_else(|| TerminalRef {
+                id: transformer_id.to_string(),
+                side: side(position + 1),
+            });
+        let control_number = |property| match control {
+            Some(control) => optional_number(id, control, property),
+            None => Ok(None),
+        };
+
+        Ok(Some(RatioTapChanger {
+            regulating: control.is_some()
+                && object.flag("TapChanger.controlEnabled").unwrap_or(false),
+            low_tap_position: low_step,
+            tap_position,
+            target_deadband: control_number("RegulatingControl.targetDeadband")?.unwrap_or(0.0),
+            load_tap_changing_capabilities: object.flag("TapChanger.ltcFlag").unwrap_or(false),
+            regulation_mode,
+            regulation_value: control_number("RegulatingControl.targetValue")?.unwrap_or(0.0),
+            terminal_ref,
+            steps,
+        }))
+    }
+
+    fn assemble_substations(
+        &mut self,
+        transformers: Vec<(String, TwoWindingsTransformer)>,
+    ) -> Vec<Substation> {
+        let mut substations: BTreeMap<&str, Substation> = self
+            .of_class(&["Substation"])
+            .map(|(id, _)| {
+                let substation = Substation {
+                    id: id.to_string(),
+                    country: String::new(),
+                    tso: String::new(),
+                    geographical_tags: Vec::new(),
+                    voltage_levels: Vec::new(),
+                    two_windings_transformers: Vec::new(),
+                };
+                (id, substation)
+            })
+            .collect();
+
+        for parts in std::mem::take(&mut self.voltage_levels).into_values() {
+            let Some(substation) = substations.get_mut(parts.substation_id.as_str()) else {
+                self.report.skipped.push(format!(
+                    "VoltageLevel '{}': unknown substation '{}'",
+                    parts.voltage_level.id, parts.substation_id
+                ));
+                continue;
+            };
+            substation.voltage_levels.push(VoltageLevel {
+                generators: non_empty(parts.generators),
+                loads: non_empty(parts.loads),
+                busbar_sections: non_empty(parts.busbar_sections),
+                node_breaker_topology: Some(parts.topology),
+                ..parts.voltage_level
+            });
+        }
+        for (substation_id, transformer) in transformers {
+            if let Some(substation) = substations.get_mut(substation_id.as_str()) {
+                substation.two_windings_transformers.push(transformer);
+            }
+        }
+        substations.into_values().collect()
+    }
+}
+
+fn non_empty<T>(items: Vec<T>) -> Option<Vec<T>> {
+    (!items.is_empty()).then_some(items)
+}
+
+fn optional_number<T: std::str::FromStr>(
+    id: &str,
+    object: &CimObject,
+    property: &str,
+) -> Result<Option<T>, CgmesError> {
+    object
+        .get(property)
+        .map(|value| {
+            value.trim().parse().map_err(|_| CgmesError::InvalidValue {
+                class: object.class.clone(),
+                id: id.to_string(),
+                property: property.to_string(),
+                value: value.to_string(),
+            })
+        })
+        .transpose()
+}
+
+fn number<T: std::str::FromStr>(
+    id: &str,
+    object: &CimObject,
+    property: &str,
+) -> Result<T, CgmesError> {
+    optional_number(id, object, property)?.ok_or_else(|| CgmesError::MissingProperty {
+        class: object.class.clone(),
+        id: id.to_string(),
+        property: property.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_id() {
+        assert_eq!(normalize_id("_abc"), "_abc");
+        assert_eq!(normalize_id("#_abc"), "_abc");
+        assert_eq!(normalize_id("urn:uuid:abc"), "_abc");
+        assert_eq!(
+            normalize_id(
+                "http://iec.ch/TC57/2013/CIM-schema-cim16#SynchronousMachineKind.generator"
+            ),
+            "SynchronousMachineKind.generator"
+        );
+    }
+
+    #[test]
+    fn test_objects_merged_across_profiles() {
+        let equipment = r##"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:cim="http://iec.ch/TC57/2013/CIM-schema-cim16#">
+            <cim:RegulatingControl rdf:ID="_RC">
+                <cim:RegulatingControl.mode rdf:resource="http://iec.ch/TC57/2013/CIM-schema-cim16#RegulatingControlModeKind.voltage"/>
+            </cim:RegulatingControl>
+        </rdf:RDF>"##;
+        let hypothesis = r##"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:cim="http://iec.ch/TC57/2013/CIM-schema-cim16#">
+            <cim:RegulatingControl rdf:about="#_RC">
+                <cim:RegulatingControl.targetValue>21.5</cim:RegulatingControl.targetValue>
+            </cim:RegulatingControl>
+        </rdf:RDF>"##;
+
+        let mut model = Model::default();
+        for profile in [equipment, hypothesis] {
+            model
+                .add_profile(&read_document(profile.as_bytes()).unwrap())
+                .unwrap();
+        }
+        let control = &model.objects["_RC"];
+        assert_eq!(control.class, "RegulatingControl");
+        assert_eq!(
+            control.enumeration("RegulatingControl.mode"),
+            Some("voltage")
+        );
+        assert_eq!(
+            number::<f64>("_RC", control, "RegulatingControl.targetValue").unwrap(),
+            21.5
+        );
+    }
+}
@@ -46,6 +46,24 @@ where
     serde_json::from_value(value)
 }
 
+/// Keeps an explicit `null` apart from a missing field: `Some(None)` clears an optional value
+pub fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Reads a field given as `null` as a present value, to be told apart from a missing one
+pub fn present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,6 +186,24 @@ mod tests {
             .contains("Invalid JSON format"));
     }
 
+    #[derive(Debug, Default, Deserialize)]
+    #[serde(default)]
+    struct TestUpdater {
+        #[serde(deserialize_with = "double_option")]
+        nickname: Option<Option<String>>,
+    }
+
+    #[test]
+    fn test_double_option() {
+        let missing: TestUpdater = serde_json::from_str("{}").unwrap();
+        let cleared: TestUpdater = serde_json::from_str(r#"{"nickname": null}"#).unwrap();
+        let set: TestUpdater = serde_json::from_str(r#"{"nickname": "Al"}"#).unwrap();
+
+        assert_eq!(missing.nickname, None);
+        assert_eq!(cleared.nickname, Some(None));
+        assert_eq!(set.nickname, Some(Some("Al".to_string())));
+    }
+
     #[test]
     fn test_array_instead_of_object() {
         let json = r#"[{"name": "Alice", "age": 30}]"#;
@@ -0,0 +1,200 @@
+//! JSON Patch (RFC 6902) operations applied on a JSON document.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::PatchError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Applies the operations in order, the document may be partly modified on error
+pub fn apply_operations(
+    document: &mut Value,
+    operations: &[PatchOperation],
+) -> Result<(), PatchError> {
+    for operation in operations {
+        match operation {
+            PatchOperation::Add { path, value } => add(document, path, value.clone())?,
+            PatchOperation::Remove { path } => {
+                remove(document, path)?;
+            }
+            PatchOperation::Replace { path, value } => {
+                *pointer_mut(document, path)? = value.clone();
+            }
+            PatchOperation::Move { from, path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(PatchError::InvalidValue {
+                        path: path.clone(),
+                        message: "cannot move a value into itself".to_string(),
+                    });
+                }
+                let value = remove(document, from)?;
+                add(document, path, value)?;
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = pointer(document, from)?.clone();
+                add(document, path, value)?;
+            }
+            PatchOperation::Test { path, value } => {
+                if pointer(document, path)? != value {
+                    return Err(PatchError::TestFailed(path.clone()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Unescaped reference tokens of a JSON pointer
+fn tokens(path: &str) -> Result<Vec<String>, PatchError> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !path.starts_with('/') {
+        return Err(PatchError::InvalidValue {
+            path: path.to_string(),
+            message: "a JSON pointer starts with '/'".to_string(),
+        });
+    }
+    Ok(path[1..]
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn pointer<'v>(document: &'v Value, path: &str) -> Result<&'v Value, PatchError> {
+    document
+        .pointer(path)
+        .ok_or_else(|| PatchError::UnknownElement(path.to_string()))
+}
+
+fn pointer_mut<'v>(document: &'v mut Value, path: &str) -> Result<&'v mut Value, PatchError> {
+    document
+        .pointer_mut(path)
+        .ok_or_else(|| PatchError::UnknownElement(path.to_string()))
+}
+
+/// Parent container of the pointed value and the last token
+fn parent<'v>(document: &'v mut Value, path: &str) -> Result<(&'v mut Value, String), PatchError> {
+    let mut tokens = tokens(path)?;
+    let last = tokens
+        .pop()
+        .ok_or_else(|| PatchError::ReadOnly(String::new()))?;
+    let parent_path: String = tokens
+        .iter()
+        .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+        .collect();
+    Ok((pointer_mut(document, &parent_path)?, last))
+}
+
+fn array_index(token: &str, len: usize, path: &str) -> Result<usize, PatchError> {
+    token
+        .parse::<usize>()
+        .ok()
+        .filter(|index| *index < len)
+        .ok_or_else(|| PatchError::UnknownElement(path.to_string()))
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> Result<(), PatchError> {
+    if path.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+    let (parent, last) = parent(document, path)?;
+    match parent {
+        Value::Object(map) => {
+            map.insert(last, value);
+        }
+        Value::Array(array) if last == "-" => array.push(value),
+        Value::Array(array) => {
+            let index = array_index(&last, array.len() + 1, path)?;
+            array.insert(index, value);
+        }
+        _ => return Err(PatchError::UnknownElement(path.to_string())),
+    }
+    Ok(())
+}
+
+fn remove(document: &mut Value, path: &str) -> Result<Value, PatchError> {
+    let (parent, last) = parent(document, path)?;
+    match parent {
+        Value::Object(map) => map
+            .remove(&last)
+            .ok_or_else(|| PatchError::UnknownElement(path.to_string())),
+        Value::Array(array) => {
+            let index = array_index(&last, array.len(), path)?;
+            Ok(array.remove(index))
+        }
+        _ => Err(PatchError::UnknownElement(path.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn operations(value: Value) -> Vec<PatchOperation> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_operations() {
+        let mut document = json!({"a": {"b": 1}, "list": [1, 2], "c~d": 3});
+
+        apply_operations(
+            &mut document,
+            &operations(json!([
+                {"op": "add", "path": "/list/-", "value": 3},
+                {"op": "add", "path": "/list/0", "value": 0},
+                {"op": "remove", "path": "/list/1"},
+                {"op": "replace", "path": "/a/b", "value": 2},
+                {"op": "copy", "from": "/a", "path": "/e"},
+                {"op": "move", "from": "/c~0d", "path": "/f"},
+                {"op": "test", "path": "/e/b", "value": 2}
+            ])),
+        )
+        .unwrap();
+
+        assert_eq!(
+            document,
+            json!({"a": {"b": 2}, "list": [0, 2, 3], "e": {"b": 2}, "f": 3})
+        );
+    }
+
+    #[test]
+    fn test_failing_operations() {
+        let mut document = json!({"a": 1, "list": [1]});
+
+        assert_eq!(
+            apply_operations(
+                &mut document,
+                &operations(json!([{"op": "test", "path": "/a", "value": 2}]))
+            ),
+            Err(PatchError::TestFailed("/a".to_string()))
+        );
+        assert_eq!(
+            apply_operations(
+                &mut document,
+                &operations(json!([{"op": "remove", "path": "/list/3"}]))
+            ),
+            Err(PatchError::UnknownElement("/list/3".to_string()))
+        );
+        assert_eq!(
+            apply_operations(
+                &mut document,
+                &operations(json!([{"op": "replace", "path": "/b", "value": 1}]))
+            ),
+            Err(PatchError::UnknownElement("/b".to_string()))
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Incremental reading of network JSON documents: each element listed by the network, such as a
+//! substation or a line, is handed over as soon as it is parsed, so that a large document is
+//! never held in memory as a whole.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::{BufReader, Read};
+use std::marker::PhantomData;
+
+use bevy_ecs::{entity::Entity, schedule::Schedule, world::World};
+use serde::de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+use crate::entities::*;
+use crate::relations::{attach_child, remove_with_descendants};
+use crate::{short_type_name, AssetRegistry, Identifiable};
+
+type Attach = fn(&mut World, &str, &str, &'static str);
+
+macro_rules! network_elements {
+    ($($variant:ident: $key:literal => $field:ident),* $(,)?) => {
+        /// Element listed by a network, along with the elements nested in it
+        #[derive(Debug, Clone)]
+        #[allow(clippy::large_enum_variant)]
+        pub enum NetworkElement {
+            $($variant($variant),)*
+        }
+
+        $(
+            impl From<$variant> for NetworkElement {
+                fn from(element: $variant) -> Self {
+                    Self::$variant(element)
+                }
+            }
+        )*
+
+        impl NetworkElement {
+            pub fn id(&self) -> String {
+                match self {
+                    $(Self::$variant(element) => element.id(),)*
+                }
+            }
+
+            /// Type name, as used by the registries
+            pub fn type_name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant(_) => short_type_name::<$variant>(),)*
+                }
+            }
+
+            pub fn register(&self, world: &mut World, schedule: &mut Schedule) {
+                match self {
+                    $(Self::$variant(element) => element.register(world, schedule),)*
+                }
+            }
+
+            /// Entity of the element when already registered
+            fn find(&self, world: &World) -> Option<Entity> {
+                let registry = world.resource::<AssetRegistry>();
+                match self {
+                    $(Self::$variant(element) => registry.find_typed::<$variant>(&element.id),)*
+                }
+            }
+
+            /// Entity of the registered element, and how to link it to its network
+            fn registered(&self, world: &World) -> Option<(Entity, &'static str, Attach)> {
+                let registry = world.resource::<AssetRegistry>();
+                match self {
+                    $(Self::$variant(element) => registry
+                        .find_typed::<$variant>(&element.id)
+                        .map(|entity| {
+                            let attach: Attach = attach_child::<Network, $variant>;
+                            (entity, stringify!($field), attach)
+                        }),)*
+                }
+            }
+        }
+
+        impl<'de, F: FnMut(NetworkElement)> Visitor<'de> for NetworkVisitor<'_, F> {
+            type Value = Network;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a network object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Network, A::Error> {
+                let mut header = Map::new();
+                let mut lists = HashSet::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        $($key => {
+                            if !lists.insert($key) {
+                                return Err(A::Error::duplicate_field($key));
+                            }
+                            map.next_value_seed(Elements::<$variant, F> {
+                                on_element: &mut *self.on_element,
+                                marker: PhantomData,
+                            })?;
+                        })*
+                        _ => {
+                            let value = map.next_value::<Value>()?;
+                            if header.insert(key.clone(), value).is_some() {
+                                return Err(A::Error::custom(format!("duplicate field `{}`", key)));
+                            }
+                        }
+                    }
+                }
+                // The lists are left empty, their elements were handed over
+                Network::deserialize(Value::Object(header)).map_err(A::Error::custom)
+            }
+        }
+    };
+}
+
+network_elements!(
+    Substation: "substations" => substations,
+    Line: "lines" => lines,
+    ThreeWindingsTransformer: "threeWindingsTransformers" => three_windings_transformers,
+    Switch: "switches" => switches,
+    ShuntCompensator: "shuntCompensators" => shunt_compensators,
+    StaticVarCompensator: "staticVarCompensators" => static_var_compensators,
+    DanglingLine: "danglingLines" => dangling_lines,
+    TieLine: "tieLines" => tie_lines,
+    HvdcLine: "hvdcLines" => hvdc_lines,
+);
+
+struct NetworkVisitor<'f, F> {
+    on_element: &'f mut F,
+}
+
+/// Hands over the elements of a list one at a time
+struct Elements<'f, T, F> {
+    on_element: &'f mut F,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T, F> DeserializeSeed<'de> for Elements<'_, T, F>
+where
+    T: Deserialize<'de> + Into<NetworkElement>,
+    F: FnMut(NetworkElement),
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T, F> Visitor<'de> for Elements<'_, T, F>
+where
+    T: Deserialize<'de> + Into<NetworkElement>,
+    F: FnMut(NetworkElement),
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of elements")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(element) = seq.next_element::<T>()? {
+            (self.on_element)(element.into());
+        }
+        Ok(())
+    }
+}
+
+/// Reads a network JSON document, handing each listed element to `on_element` as soon as it is
+/// parsed. Returns the network without its lists.
+pub fn read_network<R, F>(reader: R, mut on_element: F) -> Result<Network, serde_json::Error>
+where
+    R: Read,
+    F: FnMut(NetworkElement),
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let network = (&mut deserializer).deserialize_map(NetworkVisitor {
+        on_element: &mut on_element,
+    })?;
+    deserializer.end()?;
+    Ok(network)
+}
+
+struct Registered {
+    id: String,
+    entity: Entity,
+    field: &'static str,
+    attach: Attach,
+    /// The entity was spawned by this registration, not reused from an element of the same ID
+    spawned: bool,
+}
+
+/// Registers the elements of a network as they are read, then the network itself once its
+/// header is known.
+///
+/// An element replaces the one of the same type and ID already in the world, which an aborted
+/// registration cannot bring back: a network replacing another one is better read into a world
+/// of its own.
+#[derive(Default)]
+pub struct Registration {
+    registered: Vec<Registered>,
+    counts: BTreeMap<&'static str, usize>,
+}
+
+impl Registration {
+    pub fn register(
+        &mut self,
+        world: &mut World,
+        schedule: &mut Schedule,
+        element: &NetworkElement,
+    ) {
+        let existing = element.find(world);
+        element.register(world, schedule);
+        *self.counts.entry(element.type_name()).or_default() += 1;
+        if let Some((entity, field, attach)) = element.registered(world) {
+            self.registered.push(Registered {
+                id: element.id(),
+                entity,
+                field,
+                attach,
+                spawned: existing != Some(entity),
+            });
+        }
+    }
+
+    /// Number of elements registered by type, nested ones excluded
+    pub fn counts(&self) -> &BTreeMap<&'static str, usize> {
+        &self.counts
+    }
+
+    /// Registers the network and links the elements to it, in the order they were read
+    pub fn finish(self, world: &mut World, schedule: &mut Schedule, network: &Network) {
+        network.register(world, schedule);
+        for registered in &self.registered {
+            (registered.attach)(world, &network.id, &registered.id, registered.field);
+        }
+    }
+
+    /// Removes the elements spawned by the registration, along with the elements nested in
+    /// them. The elements registered before under the same IDs are left in place.
+    pub fn abort(self, world: &mut World) {
+        let spawned = self.registered.iter().filter(|r| r.spawned);
+        remove_with_descendants(world, spawned.map(|r| r.entity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elements_handed_over_in_order() {
+        let json = r#"{
+            "version": "1.12",
+            "id": "sim1",
+            "lines": [],
+            "substations": [
+                {"id": "P1", "country": "FR", "tso": "RTE", "geographicalTags": [], "voltageLevels": [], "twoWindingsTransformers": []},
+                {"id": "P2", "country": "FR", "tso": "RTE", "geographicalTags": [], "voltageLevels": [], "twoWindingsTransformers": []}
+            ],
+            "caseDate": "2013-01-15T18:45:00.000+01:00",
+            "forecastDistance": 0,
+            "sourceFormat": "test",
+            "minimumValidationLevel": "EQUIPMENT"
+        }"#;
+        let mut ids = Vec::new();
+        let network = read_network(json.as_bytes(), |element| {
+            ids.push((element.type_name(), element.id()));
+        })
+        .unwrap();
+        assert_eq!(
+            ids,
+            [
+                ("Substation", "P1".to_string()),
+                ("Substation", "P2".to_string())
+            ]
+        );
+        assert_eq!(network.id, "sim1");
+        assert_eq!(network.minimum_validation_level, ValidationLevel::Equipment);
+        assert!(network.substations.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_documents() {
+        let read = |json: &str| read_network(json.as_bytes(), |_| {}).map(|_| ());
+        let error = read(r#"{"id": "sim1", "lines": [], "lines": []}"#).unwrap_err();
+        assert!(error.to_string().contains("duplicate field `lines`"));
+        let error = read(r#"{"id": "sim1", "id": "sim2"}"#).unwrap_err();
+        assert!(error.to_string().contains("duplicate field `id`"));
+        assert!(read(r#"{"substations": [{"id": 1}]}"#).is_err());
+        // Missing header fields
+        assert!(read(r#"{"lines": []}"#).is_err());
+        assert!(read("[]").is_err());
+        assert!(read(r#"{} {}"#).is_err());
+    }
+}
@@ -0,0 +1,1250 @@
+//! MATPOWER case files (`.m`, format version 2): the `mpc.bus`, `mpc.gen` and `mpc.branch`
+//! matrices, in per-unit of `mpc.baseMVA` and of the base voltage of each bus.
+//!
+//! Each bus becomes a bus-breaker voltage level `VL-{n}` holding the bus `BUS-{n}`, its load
+//! `LOAD-{n}` and its shunt susceptance `SHUNT-{n}`; buses joined by a transformer share a
+//! substation. Branches with a tap ratio, a phase shift or different base voltages at their ends
+//! are transformers `TWT-{from}-{to}`, the others lines `LINE-{from}-{to}`. Generators are named
+//! `GEN-{n}`. Bus shunt conductances and generator costs have no counterpart and are left out;
+//! the writer gives each generator a zero cost.
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use chrono::Utc;
+use thiserror::Error;
+
+use crate::entities::*;
+use crate::topology::{BusView, DisjointSet};
+
+const IIDM_VERSION: &str = "1.12";
+const SOURCE_FORMAT: &str = "MATPOWER";
+const DEFAULT_CASE_NAME: &str = "matpower";
+const CASE_VERSION: &str = "2";
+const DEFAULT_BASE_MVA: f64 = 100.0;
+/// Reactive limits written for the generators which have none
+const DEFAULT_REACTIVE_LIMIT: f64 = 9999.0;
+
+// Columns of the bus matrix
+const BUS_I: usize = 0;
+const BUS_TYPE: usize = 1;
+const PD: usize = 2;
+const QD: usize = 3;
+const BS: usize = 5;
+const BASE_KV: usize = 9;
+const BUS_COLUMNS: usize = 13;
+
+// Columns of the generator matrix
+const GEN_BUS: usize = 0;
+const PG: usize = 1;
+const QG: usize = 2;
+const QMAX: usize = 3;
+const QMIN: usize = 4;
+const VG: usize = 5;
+const GEN_STATUS: usize = 7;
+const PMAX: usize = 8;
+const PMIN: usize = 9;
+const GEN_COLUMNS: usize = 10;
+/// Columns of a generator row as written, the ones past `PMIN` being zero
+const GEN_WRITTEN_COLUMNS: usize = 21;
+
+// Columns of the branch matrix
+const F_BUS: usize = 0;
+const T_BUS: usize = 1;
+const BR_R: usize = 2;
+const BR_X: usize = 3;
+const BR_B: usize = 4;
+const RATE_A: usize = 5;
+const TAP: usize = 8;
+const SHIFT: usize = 9;
+const BR_STATUS: usize = 10;
+const BRANCH_COLUMNS: usize = 11;
+
+// Bus types
+const PQ: i64 = 1;
+const PV: i64 = 2;
+const REF: i64 = 3;
+const NONE: i64 = 4;
+
+#[derive(Debug, Error)]
+pub enum MatpowerError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Line {line}: {message}")]
+    Syntax { line: usize, message: String },
+
+    #[error("Line {line}: invalid number '{value}'")]
+    InvalidNumber { line: usize, value: String },
+
+    #[error("Unsupported MATPOWER case version '{0}', expected '2'")]
+    UnsupportedVersion(String),
+
+    #[error("Missing matrix mpc.{0}")]
+    MissingMatrix(&'static str),
+
+    #[error("Line {line}: mpc.{matrix} row has {found} columns, expected at least {expected}")]
+    MissingColumns {
+        line: usize,
+        matrix: &'static str,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("Line {line}: invalid bus {value}")]
+    InvalidBus { line: usize, value: f64 },
+
+    #[error("Line {line}: bus {bus} is defined twice")]
+    DuplicateBus { line: usize, bus: i64 },
+
+    #[error("Line {line}: unknown bus {bus}")]
+    UnknownBus { line: usize, bus: i64 },
+
+    #[error("Line {line}: bus {bus} has no base voltage")]
+    MissingBaseVoltage { line: usize, bus: i64 },
+
+    #[error("Bus '{bus}' of '{id}' is not found in its voltage level")]
+    UnknownTerminal { id: String, bus: String },
+}
+
+/// Reads a network from a MATPOWER case file
+pub fn from_str(text: &str) -> Result<Network, MatpowerError> {
+    from_reader(text.as_bytes())
+}
+
+/// Reads a network from any buffered MATPOWER source
+pub fn from_reader<R: BufRead>(input: R) -> Result<Network, MatpowerError> {
+    Importer::new(parse(input)?)?.import()
+}
+
+/// Writes a network as a MATPOWER case, one bus per bus of the bus view. Dangling lines, three
+/// windings transformers, static var compensators and HVDC lines are not written.
+pub fn to_string(network: &Network) -> Result<String, MatpowerError> {
+    let mut output = Vec::new();
+    to_writer(network, &mut output)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Writes a network as a MATPOWER case into any writer
+pub fn to_writer<W: Write>(network: &Network, output: W) -> Result<(), MatpowerError> {
+    Exporter::new(network)?.write(output)
+}
+
+/// Matrix row, with the line it starts on
+#[derive(Debug, Clone)]
+struct Row {
+    line: usize,
+    values: Vec<f64>,
+}
+
+impl Row {
+    fn integer(&self, column: usize) -> Result<i64, MatpowerError> {
+        let value = self.values[column];
+        if value.fract() != 0.0 || !value.is_finite() {
+            return Err(MatpowerError::InvalidBus {
+                line: self.line,
+                value,
+            });
+        }
+        Ok(value as i64)
+    }
+}
+
+/// Assignments of a case file, `function mpc = {name}` then `mpc.{field} = {value};`
+#[derive(Debug, Default)]
+struct Case {
+    name: Option<String>,
+    version: Option<String>,
+    base_mva: Option<f64>,
+    matrices: HashMap<String, Vec<Row>>,
+}
+
+impl Case {
+    fn matrix(&self, name: &'static str, columns: usize) -> Result<&[Row], MatpowerError> {
+        let rows = self
+            .matrices
+            .get(name)
+            .ok_or(MatpowerError::MissingMatrix(name))?;
+        if let Some(row) = rows.iter().find(|row| row.values.len() < columns) {
+            return Err(MatpowerError::MissingColumns {
+                line: row.line,
+                matrix: name,
+                expected: columns,
+                found: row.values.len(),
+            });
+        }
+        Ok(rows)
+    }
+}
+
+/// Drops a `%` comment, unless quoted
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '%' if !quoted => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Rows of a matrix, separated by semicolons or line breaks
+fn read_rows(line: usize, content: &str, rows: &mut Vec<Row>) -> Result<(), MatpowerError> {
+    for row in content.split(';') {
+        let values = row
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value.parse().map_err(|_| MatpowerError::InvalidNumber {
+                    line,
+                    value: value.to_string(),
+                })
+            })
+            .collect::<Result<Vec<f64>, _>>()?;
+        if !values.is_empty() {
+            rows.push(Row { line, values });
+        }
+    }
+    Ok(())
+}
+
+fn parse<R: BufRead>(input: R) -> Result<Case, MatpowerError> {
+    let mut case = Case::default();
+    // Matrix spanning several lines, with the line it starts on
+    let mut open: Option<(String, usize, Vec<Row>)> = None;
+    let mut in_cell = false;
+
+    for (index, line) in input.lines().enumerate() {
+        let number = index + 1;
+        let line = line?;
+        let line = strip_comment(&line).trim();
+
+        if in_cell {
+            in_cell = !line.contains('}');
+            continue;
+        }
+        if let Some((_, _, rows)) = open.as_mut() {
+            let end = line.find(']');
+            read_rows(number, &line[..end.unwrap_or(line.len())], rows)?;
+            if end.is_some() {
+                if let Some((field, _, rows)) = open.take() {
+                    case.matrices.insert(field, rows);
+                }
+            }
+            continue;
+        }
+
+        if let Some(signature) = line.strip_prefix("function") {
+            case.name = signature
+                .split_once('=')
+                .map(|(_, name)| name.trim().to_string())
+                .filter(|name| !name.is_empty());
+            continue;
+        }
+        // Other statements, such as the indexing of a field, are not part of the data
+        let Some((field, value)) = line
+            .strip_prefix("mpc.")
+            .and_then(|assignment| assignment.split_once('='))
+        else {
+            continue;
+        };
+        let field = field.trim();
+        if !field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        let value = value.trim();
+        if let Some(content) = value.strip_prefix('[') {
+            let mut rows = Vec::new();
+            match content.find(']') {
+                Some(end) => {
+                    read_rows(number, &content[..end], &mut rows)?;
+                    case.matrices.insert(field.to_string(), rows);
+                }
+                None => {
+                    read_rows(number, content, &mut rows)?;
+                    open = Some((field.to_string(), number, rows));
+                }
+            }
+        } else if value.starts_with('{') {
+            in_cell = !value.contains('}');
+        } else {
+            let value = value.trim_end_matches(';').trim().trim_matches('\'');
+            match field {
+                "version" => case.version = Some(value.to_string()),
+                "baseMVA" => {
+                    let base_mva = value.parse().map_err(|_| MatpowerError::InvalidNumber {
+                        line: number,
+                        value: value.to_string(),
+                    })?;
+                    case.base_mva = Some(base_mva);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some((field, line, _)) = open {
+        return Err(MatpowerError::Syntax {
+            line,
+            message: format!("matrix mpc.{} is not closed", field),
+        });
+    }
+    Ok(case)
+}
+
+fn impedance_base(base_kv: f64, base_mva: f64) -> f64 {
+    base_kv * base_kv / base_mva
+}
+
+/// Current in A of an apparent power in MVA
+fn current_limits(rate: f64, base_kv: f64) -> Option<CurrentLimits> {
+    (rate > 0.0).then(|| CurrentLimits {
+        permanent_limit: rate * 1000.0 / (3f64.sqrt() * base_kv),
+        temporary_limits: Vec::new(),
+    })
+}
+
+/// Gives the first element of a bus its base name and numbers the next ones
+#[derive(Default)]
+struct Ids {
+    counts: HashMap<String, usize>,
+}
+
+impl Ids {
+    fn unique(&mut self, base: String) -> String {
+        let count = self.counts.entry(base.clone()).or_default();
+        *count += 1;
+        match count {
+            1 => base,
+            count => format!("{}-{}", base, count),
+        }
+    }
+}
+
+struct CaseBus {
+    number: i64,
+    bus_type: i64,
+    active_load: f64,
+    reactive_load: f64,
+    shunt_susceptance: f64,
+    base_kv: f64,
+}
+
+impl CaseBus {
+    fn bus_id(&self) -> String {
+        format!("BUS-{}", self.number)
+    }
+
+    fn voltage_level_id(&self) -> String {
+        format!("VL-{}", self.number)
+    }
+
+    /// Connected bus of the elements of the bus, none for an isolated bus
+    fn connection(&self, in_service: bool) -> String {
+        if in_service && self.bus_type != NONE {
+            self.bus_id()
+        } else {
+            String::new()
+        }
+    }
+}
+
+struct Importer {
+    case: Case,
+    base_mva: f64,
+    buses: Vec<CaseBus>,
+    positions: HashMap<i64, usize>,
+    ids: Ids,
+}
+
+impl Importer {
+    fn new(case: Case) -> Result<Self, MatpowerError> {
+        if let Some(version) = case
+            .version
+            .as_ref()
+            .filter(|version| *version != CASE_VERSION)
+        {
+            return Err(MatpowerError::UnsupportedVersion(version.clone()));
+        }
+        let mut buses = Vec::new();
+        let mut positions = HashMap::new();
+        for row in case.matrix("bus", BUS_COLUMNS)? {
+            let number = row.integer(BUS_I)?;
+            if positions.insert(number, buses.len()).is_some() {
+                return Err(MatpowerError::DuplicateBus {
+                    line: row.line,
+                    bus: number,
+                });
+            }
+            let base_kv = row.values[BASE_KV];
+            if base_kv <= 0.0 {
+                return Err(MatpowerError::MissingBaseVoltage {
+                    line: row.line,
+                    bus: number,
+                });
+            }
+            buses.push(CaseBus {
+                number,
+                bus_type: row.integer(BUS_TYPE)?,
+                active_load: row.values[PD],
+                reactive_load: row.values[QD],
+                shunt_susceptance: row.values[BS],
+                base_kv,
+            });
+        }
+        Ok(Self {
+            base_mva: case.base_mva.unwrap_or(DEFAULT_BASE_MVA),
+            case,
+            buses,
+            positions,
+            ids: Ids::default(),
+        })
+    }
+
+    fn position(&self, row: &Row, column: usize) -> Result<usize, MatpowerError> {
+        let bus = row.integer(column)?;
+        self.positions
+            .get(&bus)
+            .copied()
+            .ok_or(MatpowerError::UnknownBus {
+                line: row.line,
+                bus,
+            })
+    }
+
+    fn is_transformer(&self, row: &Row, from: usize, to: usize) -> bool {
+        row.values[TAP] != 0.0
+            || row.values[SHIFT] != 0.0
+            || self.buses[from].base_kv != self.buses[to].base_kv
+    }
+
+    fn import(mut self) -> Result<Network, MatpowerError> {
+        let branches = self.case.matrix("branch", BRANCH_COLUMNS)?.to_vec();
+        let generators = self.case.matrix("gen", GEN_COLUMNS)?.to_vec();
+
+        // Buses joined by transformers share a substation
+        let mut groups = DisjointSet::new(self.buses.len());
+        let mut ends = Vec::with_capacity(branches.len());
+        for row in &branches {
+            let (from, to) = (self.position(row, F_BUS)?, self.position(row, T_BUS)?);
+            if self.is_transformer(row, from, to) {
+                groups.union(from, to);
+            }
+            ends.push((from, to));
+        }
+
+        let mut voltage_levels: Vec<VoltageLevel> = self
+            .buses
+            .iter()
+            .map(|bus| self.voltage_level(bus))
+            .collect();
+        for row in &generators {
+            let position = self.position(row, GEN_BUS)?;
+            let generator = self.generator(row, position);
+            voltage_levels[position]
+                .generators
+                .get_or_insert_with(Vec::new)
+                .push(generator);
+        }
+
+        let mut lines = Vec::new();
+        let mut transformers: Vec<Vec<TwoWindingsTransformer>> =
+            self.buses.iter().map(|_| Vec::new()).collect();
+        for (row, (from, to)) in branches.iter().zip(ends) {
+            if self.is_transformer(row, from, to) {
+                let transformer = self.transformer(row, from, to);
+                transformers[from].push(transformer);
+            } else {
+                lines.push(self.line(row, from, to));
+            }
+        }
+
+        let shunt_compensators = self
+            .buses
+            .iter()
+            .filter(|bus| bus.shunt_susceptance != 0.0)
+            .map(|bus| ShuntCompensator {
+                id: format!("SHUNT-{}", bus.number),
+                // Susceptance in MVar at 1 per-unit
+                b_per_section: bus.shunt_susceptance / (bus.base_kv * bus.base_kv),
+                maximum_section_count: 1,
+                section_count: 1,
+                bus: bus.connection(true),
+                connectable_bus: bus.bus_id(),
+            })
+            .collect();
+
+        let mut voltage_levels: Vec<Option<VoltageLevel>> =
+            voltage_levels.into_iter().map(Some).collect();
+        let substations = groups
+            .groups()
+            .into_iter()
+            .map(|members| Substation {
+                id: format!("SUB-{}", self.buses[members[0]].number),
+                country: String::new(),
+                tso: String::new(),
+                geographical_tags: Vec::new(),
+                voltage_levels: members
+                    .iter()
+                    .filter_map(|member| voltage_levels[*member].take())
+                    .collect(),
+                two_windings_transformers: members
+                    .iter()
+                    .flat_map(|member| std::mem::take(&mut transformers[*member]))
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Network {
+            version: IIDM_VERSION.to_string(),
+            id: self
+                .case
+                .name
+                .clone()
+                .unwrap_or_else(|| DEFAULT_CASE_NAME.to_string()),
+            case_date: Utc::now().fixed_offset(),
+            forecast_distance: 0,
+            source_format: SOURCE_FORMAT.to_string(),
+            minimum_validation_level: ValidationLevel::default(),
+            substations,
+            lines,
+            three_windings_transformers: Vec::new(),
+            switches: Vec::new(),
+            shunt_compensators,
+            static_var_compensators: Vec::new(),
+            dangling_lines: Vec::new(),
+            tie_lines: Vec::new(),
+            hvdc_lines: Vec::new(),
+        })
+    }
+
+    fn voltage_level(&self, bus: &CaseBus) -> VoltageLevel {
+        let loads = (bus.active_load != 0.0 || bus.reactive_load != 0.0).then(|| {
+            vec![Load {
+                id: format!("LOAD-{}", bus.number),
+                load_type: LoadType::Undefined,
+                p0: bus.active_load,
+                q0: bus.reactive_load,
+                bus: bus.connection(true),
+                connectable_bus: bus.bus_id(),
+                exponential_model: None,
+                zip_model: None,
+            }]
+        });
+        VoltageLevel {
+            id: bus.voltage_level_id(),
+            nominal_v: bus.base_kv,
+            topology_kind: TopologyKind::BusBreaker,
+            generators: None,
+            loads,
+            busbar_sections: None,
+            node_breaker_topology: None,
+            bus_breaker_topology: Some(BusBreakerTopology {
+                buses: vec![Bus { id: bus.bus_id() }],
+                switches: Vec::new(),
+            }),
+        }
+    }
+
+    fn generator(&mut self, row: &Row, position: usize) -> Generator {
+        let id = self
+            .ids
+            .unique(format!("GEN-{}", self.buses[position].number));
+        let bus = &self.buses[position];
+        let values = &row.values;
+        let in_service = values[GEN_STATUS] > 0.0;
+        Generator {
+            id,
+            energy_source: EnergySource::Other,
+            min_p: values[PMIN],
+            max_p: values[PMAX],
+            voltage_regulator_on: in_service && matches!(bus.bus_type, PV | REF),
+            target_p: values[PG],
+            target_v: values[VG] * bus.base_kv,
+            target_q: values[QG],
+            bus: bus.connection(in_service),
+            connectable_bus: bus.bus_id(),
+            reactive_capability_curve: None,
+            min_max_reactive_limits: Some(MinMaxReactiveLimits {
+                min_q: values[QMIN],
+                max_q: values[QMAX],
+            }),
+        }
+    }
+
+    fn line(&mut self, row: &Row, from: usize, to: usize) -> Line {
+        let values = &row.values;
+        let (from, to) = (&self.buses[from], &self.buses[to]);
+        let in_service = values[BR_STATUS] > 0.0;
+        let z_base = impedance_base(from.base_kv, self.base_mva);
+        Line {
+            id: self
+                .ids
+                .unique(format!("LINE-{}-{}", from.number, to.number)),
+            r: values[BR_R] * z_base,
+            x: values[BR_X] * z_base,
+            g1: 0.0,
+            g2: 0.0,
+            b1: values[BR_B] / 2.0 / z_base,
+            b2: values[BR_B] / 2.0 / z_base,
+            voltage_level_id1: from.voltage_level_id(),
+            bus1: from.connection(in_service),
+            connectable_bus1: from.bus_id(),
+            voltage_level_id2: to.voltage_level_id(),
+            bus2: to.connection(in_service),
+            connectable_bus2: to.bus_id(),
+            current_limits1: current_limits(values[RATE_A], from.base_kv),
+            current_limits2: current_limits(values[RATE_A], to.base_kv),
+        }
+    }
+
+    /// The tap ratio and the phase shift are on the side of the from bus, the impedance in the
+    /// base of the to bus. The charging susceptance, split between both sides in MATPOWER, is all
+    /// put on the side 1.
+    fn transformer(&mut self, row: &Row, from: usize, to: usize) -> TwoWindingsTransformer {
+        let values = &row.values;
+        let (from, to) = (&self.buses[from], &self.buses[to]);
+        let in_service = values[BR_STATUS] > 0.0;
+        let ratio = if values[TAP] == 0.0 { 1.0 } else { values[TAP] };
+        let z_base = impedance_base(to.base_kv, self.base_mva);
+        let id = self
+            .ids
+            .unique(format!("TWT-{}-{}", from.number, to.number));
+        let phase_tap_changer = (values[SHIFT] != 0.0).then(|| PhaseTapChanger {
+            regulating: false,
+            low_tap_position: 0,
+            tap_position: 0,
+            regulation_mode: PhaseRegulationMode::FixedTap,
+            regulation_value: 0.0,
+            target_deadband: 0.0,
+            terminal_ref: TerminalRef {
+                id: id.clone(),
+                side: Side::One,
+            },
+            steps: vec![PhaseTapStep {
+                r: 0.0,
+                x: 0.0,
+                g: 0.0,
+                b: 0.0,
+                rho: 1.0,
+                // MATPOWER divides the from voltage by the complex ratio
+                alpha: -values[SHIFT],
+            }],
+        });
+        TwoWindingsTransformer {
+            id,
+            r: values[BR_R] * z_base,
+            x: values[BR_X] * z_base,
+            g: 0.0,
+            b: values[BR_B] / z_base,
+            rated_u1: from.base_kv * ratio,
+            rated_u2: to.base_kv,
+            voltage_level_id1: from.voltage_level_id(),
+            bus1: from.connection(in_service),
+            connectable_bus1: from.bus_id(),
+            voltage_level_id2: to.voltage_level_id(),
+            bus2: to.connection(in_service),
+            connectable_bus2: to.bus_id(),
+            ratio_tap_changer: None,
+            phase_tap_changer,
+            current_limits1: current_limits(values[RATE_A], from.base_kv),
+            current_limits2: current_limits(values[RATE_A], to.base_kv),
+        }
+    }
+}
+
+/// Bus of the bus view being written, with the injections it holds
+struct ExportBus {
+    id: String,
+    base_kv: f64,
+    number: i64,
+    active_load: f64,
+    reactive_load: f64,
+    /// Shunt susceptance in MVar at 1 per-unit
+    shunt_susceptance: f64,
+    voltage: Option<f64>,
+    regulating_capacity: Option<f64>,
+    /// Whether any element is connected to the bus, the others are written as isolated
+    connected: bool,
+    /// Loads of an isolated bus, which MATPOWER keeps on the bus
+    disconnected_load: (f64, f64),
+}
+
+struct Exporter<'a> {
+    network: &'a Network,
+    buses: Vec<ExportBus>,
+    /// Position of the bus holding each configured bus or node, by voltage level
+    members: HashMap<(&'a str, &'a str), usize>,
+    /// Position of the bus holding each configured bus, whatever its voltage level
+    configured: HashMap<&'a str, usize>,
+    /// Generator rows, with the position of their bus
+    generators: Vec<(usize, &'a Generator)>,
+}
+
+/// Step a tap changer is on
+fn current_step<T>(steps: &[T], tap_position: i32, low_tap_position: i32) -> Option<&T> {
+    usize::try_from(tap_position - low_tap_position)
+        .ok()
+        .and_then(|index| steps.get(index))
+}
+
+/// Number rounded to 10 significant digits, to hide the errors of the unit conversions
+fn number(value: f64) -> String {
+    if value.is_infinite() {
+        return if value > 0.0 { "Inf" } else { "-Inf" }.to_string();
+    }
+    let rounded: f64 = format!("{:.9e}", value).parse().unwrap_or(value);
+    if rounded == 0.0 {
+        "0".to_string()
+    } else {
+        rounded.to_string()
+    }
+}
+
+/// MATLAB function name of the case
+fn case_name(id: &str) -> String {
+    let name: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("case_{}", name)
+    }
+}
+
+impl<'a> Exporter<'a> {
+    fn new(network: &'a Network) -> Result<Self, MatpowerError> {
+        let mut exporter = Self {
+            network,
+            buses: Vec::new(),
+            members: HashMap::new(),
+            configured: HashMap::new(),
+            generators: Vec::new(),
+        };
+        for voltage_level in network
+            .substations
+            .iter()
+            .flat_map(|substation| &substation.voltage_levels)
+        {
+            exporter.add_voltage_level(voltage_level)?;
+        }
+        for shunt in network.shunt_compensators.iter() {
+            if shunt.bus.is_empty() {
+                continue;
+            }
+            let position = exporter.configured_bus(&shunt.id, &shunt.bus)?;
+            let bus = &mut exporter.buses[position];
+            bus.connected = true;
+            let b = shunt.b_per_section * f64::from(shunt.section_count);
+            bus.shunt_susceptance += b * bus.base_kv * bus.base_kv;
+        }
+        exporter.mark_branch_ends();
+        exporter.number_buses();
+        Ok(exporter)
+    }
+
+    fn add_voltage_level(&mut self, voltage_level: &'a VoltageLevel) -> Result<(), MatpowerError> {
+        let view = BusView::compute(voltage_level, |switch| switch.open);
+        let first = self.buses.len();
+        for bus in &view.buses {
+            self.buses.push(ExportBus {
+                id: bus.id.clone(),
+                base_kv: voltage_level.nominal_v,
+                number: 0,
+                active_load: 0.0,
+                reactive_load: 0.0,
+                shunt_susceptance: 0.0,
+                voltage: None,
+                regulating_capacity: None,
+                connected: false,
+                disconnected_load: (0.0, 0.0),
+            });
+        }
+        let configured = voltage_level
+            .bus_breaker_topology
+            .iter()
+            .flat_map(|topology| &topology.buses)
+            .map(|bus| bus.id.as_str());
+        let nodes = voltage_level
+            .node_breaker_topology
+            .iter()
+            .flat_map(|topology| topology.switches.iter())
+            .flat_map(|switch| [switch.bus1.as_str(), switch.bus2.as_str()]);
+        for member in configured.clone().chain(nodes) {
+            if let Some(index) = view
+                .buses
+                .iter()
+                .position(|bus| bus.members.iter().any(|m| m == member))
+            {
+                self.members
+                    .insert((voltage_level.id.as_str(), member), first + index);
+            }
+        }
+        for member in configured {
+            if let Some(position) = self.members.get(&(voltage_level.id.as_str(), member)) {
+                self.configured.insert(member, *position);
+            }
+        }
+
+        let locate = |id: &str, bus: &str, members: &HashMap<(&str, &str), usize>| {
+            members
+                .get(&(voltage_level.id.as_str(), bus))
+                .copied()
+                .ok_or_else(|| MatpowerError::UnknownTerminal {
+                    id: id.to_string(),
+                    bus: bus.to_string(),
+                })
+        };
+        for load in voltage_level.loads.iter().flatten() {
+            if load.bus.is_empty() {
+                let position = locate(&load.id, &load.connectable_bus, &self.members)?;
+                let (p, q) = &mut self.buses[position].disconnected_load;
+                (*p, *q) = (*p + load.p0, *q + load.q0);
+                continue;
+            }
+            let position = locate(&load.id, &load.bus, &self.members)?;
+            let bus = &mut self.buses[position];
+            bus.active_load += load.p0;
+            bus.reactive_load += load.q0;
+            bus.connected = true;
+        }
+        for generator in voltage_level.generators.iter().flatten() {
+            let bus = if generator.bus.is_empty() {
+                &generator.connectable_bus
+            } else {
+                &generator.bus
+            };
+            let position = locate(&generator.id, bus, &self.members)?;
+            self.buses[position].connected |= !generator.bus.is_empty();
+            if generator.voltage_regulator_on && !generator.bus.is_empty() {
+                let bus = &mut self.buses[position];
+                bus.voltage
+                    .get_or_insert(generator.target_v / voltage_level.nominal_v);
+                *bus.regulating_capacity.get_or_insert(0.0) += generator.max_p;
+            }
+            self.generators.push((position, generator));
+        }
+        Ok(())
+    }
+
+    /// Bus of an element referencing a bus-breaker bus without its voltage level
+    fn configured_bus(&self, id: &str, bus: &str) -> Result<usize, MatpowerError> {
+        self.configured
+            .get(bus)
+            .copied()
+            .ok_or_else(|| MatpowerError::UnknownTerminal {
+                id: id.to_string(),
+                bus: bus.to_string(),
+            })
+    }
+
+    fn mark_branch_ends(&mut self) {
+        let network = self.network;
+        let transformers = network
+            .substations
+            .iter()
+            .flat_map(|substation| &substation.two_windings_transformers);
+        let mut ends: Vec<Option<usize>> = Vec::new();
+        for line in &network.lines {
+            ends.push(
+                self.members
+                    .get(&(line.voltage_level_id1.as_str(), line.bus1.as_str()))
+                    .copied(),
+            );
+            ends.push(
+                self.members
+                    .get(&(line.voltage_level_id2.as_str(), line.bus2.as_str()))
+                    .copied(),
+            );
+        }
+        for transformer in transformers {
+            ends.push(
+                self.members
+                    .get(&(
+                        transformer.voltage_level_id1.as_str(),
+                        transformer.bus1.as_str(),
+                    ))
+                    .copied(),
+            );
+            ends.push(
+                self.members
+                    .get(&(
+                        transformer.voltage_level_id2.as_str(),
+                        transformer.bus2.as_str(),
+                    ))
+                    .copied(),
+            );
+        }
+        for tie_line in &network.tie_lines {
+            ends.push(
+                self.configured
+                    .get(tie_line.dangling_line1.bus.as_str())
+                    .copied(),
+            );
+            ends.push(
+                self.configured
+                    .get(tie_line.dangling_line2.bus.as_str())
+                    .copied(),
+            );
+        }
+        for position in ends.into_iter().flatten() {
+            self.buses[position].connected = true;
+        }
+    }
+
+    /// Buses named `BUS-{n}` keep their number, the others follow the largest one
+    fn number_buses(&mut self) {
+        let mut used = std::collections::HashSet::new();
+        for bus in self.buses.iter_mut() {
+            let number = bus
+                .id
+                .strip_prefix("BUS-")
+                .and_then(|number| number.parse::<i64>().ok())
+                .filter(|number| *number > 0 && !used.contains(number));
+            if let Some(number) = number {
+                bus.number = number;
+                used.insert(number);
+            }
+        }
+        let mut next = used.iter().max().copied().unwrap_or(0);
+        for bus in self.buses.iter_mut().filter(|bus| bus.number == 0) {
+            next += 1;
+            bus.number = next;
+        }
+    }
+
+    /// Branch end, located by its connectable bus when disconnected
+    fn end(
+        &self,
+        id: &str,
+        voltage_level_id: &str,
+        bus: &str,
+        connectable_bus: &str,
+    ) -> Result<usize, MatpowerError> {
+        let bus = if bus.is_empty() { connectable_bus } else { bus };
+        self.members
+            .get(&(voltage_level_id, bus))
+            .copied()
+            .ok_or_else(|| MatpowerError::UnknownTerminal {
+                id: id.to_string(),
+                bus: bus.to_string(),
+            })
+    }
+
+    fn branch_rows(&self) -> Result<Vec<Vec<f64>>, MatpowerError> {
+        let base_mva = DEFAULT_BASE_MVA;
+        let rate = |limits: &Option<CurrentLimits>, base_kv: f64| {
+            limits.as_ref().map_or(0.0, |limits| {
+                limits.permanent_limit * 3f64.sqrt() * base_kv / 1000.0
+            })
+        };
+        let row = |from: usize,
+                   to: usize,
+                   (r, x, b): (f64, f64, f64),
+                   rate: f64,
+                   (tap, shift): (f64, f64),
+                   in_service: bool| {
+            vec![
+                self.buses[from].number as f64,
+                self.buses[to].number as f64,
+                r,
+                x,
+                b,
+                rate,
+                rate,
+                rate,
+                tap,
+                shift,
+                if in_service { 1.0 } else { 0.0 },
+                -360.0,
+                360.0,
+            ]
+        };
+
+        let mut rows = Vec::new();
+        for line in &self.network.lines {
+            let from = self.end(
+                &line.id,
+                &line.voltage_level_id1,
+                &line.bus1,
+                &line.connectable_bus1,
+            )?;
+            let to = self.end(
+                &line.id,
+                &line.voltage_level_id2,
+                &line.bus2,
+                &line.connectable_bus2,
+            )?;
+            let base_kv = self.buses[from].base_kv;
+            let z_base = impedance_base(base_kv, base_mva);
+            rows.push(row(
+                from,
+                to,
+                (
+                    line.r / z_base,
+                    line.x / z_base,
+                    (line.b1 + line.b2) * z_base,
+                ),
+                rate(&line.current_limits1, base_kv),
+                (0.0, 0.0),
+                !line.bus1.is_empty() && !line.bus2.is_empty(),
+            ));
+        }
+        for transformer in self
+            .network
+            .substations
+            .iter()
+            .flat_map(|substation| &substation.two_windings_transformers)
+        {
+            let id = &transformer.id;
+            let from = self.end(
+                id,
+                &transformer.voltage_level_id1,
+                &transformer.bus1,
+                &transformer.connectable_bus1,
+            )?;
+            let to = self.end(
+                id,
+                &transformer.voltage_level_id2,
+                &transformer.bus2,
+                &transformer.connectable_bus2,
+            )?;
+            let (base_kv1, base_kv2) = (self.buses[from].base_kv, self.buses[to].base_kv);
+            let mut rho = 1.0;
+            let mut alpha = 0.0;
+            if let Some(changer) = &transformer.ratio_tap_changer {
+                if let Some(step) = current_step(
+                    &changer.steps,
+                    changer.tap_position,
+                    changer.low_tap_position,
+                ) {
+                    rho *= step.rho;
+                }
+            }
+            if let Some(changer) = &transformer.phase_tap_changer {
+                if let Some(step) = current_step(
+                    &changer.steps,
+                    changer.tap_position,
+                    changer.low_tap_position,
+                ) {
+                    rho *= step.rho;
+                    alpha = step.alpha;
+                }
+            }
+            let z_base = impedance_base(base_kv2, base_mva);
+            let tap = transformer.rated_u1 * base_kv2 / (transformer.rated_u2 * base_kv1 * rho);
+            rows.push(row(
+                from,
+                to,
+                (
+                    transformer.r / z_base,
+                    transformer.x / z_base,
+                    transformer.b * z_base,
+                ),
+                rate(&transformer.current_limits1, base_kv1),
+                (tap, -alpha),
+                !transformer.bus1.is_empty() && !transformer.bus2.is_empty(),
+            ));
+        }
+        for tie_line in &self.network.tie_lines {
+            let (half1, half2) = (&tie_line.dangling_line1, &tie_line.dangling_line2);
+            let end = |half: &DanglingLine| {
+                let bus = if half.bus.is_empty() {
+                    &half.connectable_bus
+                } else {
+                    &half.bus
+                };
+                self.configured_bus(&tie_line.id, bus)
+            };
+            let (from, to) = (end(half1)?, end(half2)?);
+            let z_base = impedance_base(self.buses[from].base_kv, base_mva);
+            rows.push(row(
+                from,
+                to,
+                (
+                    (half1.r + half2.r) / z_base,
+                    (half1.x + half2.x) / z_base,
+                    (half1.b + half2.b) * z_base,
+                ),
+                0.0,
+                (0.0, 0.0),
+                !half1.bus.is_empty() && !half2.bus.is_empty(),
+            ));
+        }
+        Ok(rows)
+    }
+
+    /// The reference bus holds the largest regulating capacity, as for the load flow
+    fn bus_rows(&self) -> Vec<Vec<f64>> {
+        let reference = self
+            .buses
+            .iter()
+            .enumerate()
+            .filter_map(|(position, bus)| {
+                bus.regulating_capacity.map(|capacity| (position, capacity))
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(position, _)| position);
+        self.buses
+            .iter()
+            .enumerate()
+            .map(|(position, bus)| {
+                let bus_type = match (Some(position) == reference, bus.voltage) {
+                    _ if !bus.connected => NONE,
+                    (true, _) => REF,
+                    (false, Some(_)) => PV,
+                    (false, None) => PQ,
+                };
+                let (active_load, reactive_load) = if bus.connected {
+                    (bus.active_load, bus.reactive_load)
+                } else {
+                    bus.disconnected_load
+                };
+                vec![
+                    bus.number as f64,
+                    bus_type as f64,
+                    active_load,
+                    reactive_load,
+                    0.0,
+                    bus.shunt_susceptance,
+                    1.0,
+                    bus.voltage.unwrap_or(1.0),
+                    0.0,
+                    bus.base_kv,
+                    1.0,
+                    1.1,
+                    0.9,
+                ]
+            })
+            .collect()
+    }
+
+    fn generator_rows(&self) -> Vec<Vec<f64>> {
+        self.generators
+            .iter()
+            .map(|(position, generator)| {
+                let bus = &self.buses[*position];
+                let (min_q, max_q) = generator.min_max_reactive_limits.as_ref().map_or(
+                    (-DEFAULT_REACTIVE_LIMIT, DEFAULT_REACTIVE_LIMIT),
+                    |limits| (limits.min_q, limits.max_q),
+                );
+                let mut row = vec![
+                    bus.number as f64,
+                    generator.target_p,
+                    generator.target_q,
+                    max_q,
+                    min_q,
+                    generator.target_v / bus.base_kv,
+                    DEFAULT_BASE_MVA,
+                    if generator.bus.is_empty() { 0.0 } else { 1.0 },
+                    generator.max_p,
+                    generator.min_p,
+                ];
+                row.resize(GEN_WRITTEN_COLUMNS, 0.0);
+                row
+            })
+            .collect()
+    }
+
+    fn write<W: Write>(&self, mut output: W) -> Result<(), MatpowerError> {
+        let write_matrix =
+            |output: &mut W, name: &str, header: &str, rows: &[Vec<f64>]| -> std::io::Result<()> {
+                writeln!(output, "%\t{}", header)?;
+                writeln!(output, "mpc.{} = [", name)?;
+                for row in rows {
+                    let values: Vec<String> = row.iter().map(|value| number(*value)).collect();
+                    writeln!(output, "\t{};", values.join("\t"))?;
+                }
+                writeln!(output, "];")?;
+                writeln!(output)
+            };
+
+        writeln!(output, "function mpc = {}", case_name(&self.network.id))?;
+        writeln!(
+            output,
+            "%% MATPOWER case of the IIDM network {}",
+            self.network.id
+        )?;
+        writeln!(output)?;
+        writeln!(output, "mpc.version = '{}';", CASE_VERSION)?;
+        writeln!(output, "mpc.baseMVA = {};", number(DEFAULT_BASE_MVA))?;
+        writeln!(output)?;
+        writeln!(output, "%% bus data")?;
+        write_matrix(
+            &mut output,
+            "bus",
+            "bus_i\ttype\tPd\tQd\tGs\tBs\tarea\tVm\tVa\tbaseKV\tzone\tVmax\tVmin",
+            &self.bus_rows(),
+        )?;
+        writeln!(output, "%% generator data")?;
+        write_matrix(
+            &mut output,
+            "gen",
+            "bus\tPg\tQg\tQmax\tQmin\tVg\tmBase\tstatus\tPmax\tPmin\tPc1\tPc2\tQc1min\tQc1max\tQc2min\tQc2max\tramp_agc\tramp_10\tramp_30\tramp_q\tapf",
+            &self.generator_rows(),
+        )?;
+        writeln!(output, "%% branch data")?;
+        write_matrix(
+            &mut output,
+            "branch",
+            "fbus\ttbus\tr\tx\tb\trateA\trateB\trateC\tratio\tangle\tstatus\tangmin\tangmax",
+            &self.branch_rows()?,
+        )?;
+        writeln!(output, "%% generator cost data")?;
+        let costs: Vec<Vec<f64>> = self
+            .generators
+            .iter()
+            .map(|_| vec![2.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0])
+            .collect();
+        write_matrix(
+            &mut output,
+            "gencost",
+            "2\tstartup\tshutdown\tn\tc(n-1)\t...\tc0",
+            &costs,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assignments() {
+        let text = "function mpc = case2 % two buses\n\
+                    mpc.version = '2';\n\
+                    mpc.baseMVA = 50;\n\
+                    mpc.bus_name = {\n'A';\n'B';\n};\n\
+                    mpc.bus = [1 3 0 0; 2 1 10 5 % first row\n\t3, 1, 0, 0\n];\n\
+                    mpc.bus(:, 2) = 1;";
+        let case = parse(text.as_bytes()).unwrap();
+        assert_eq!(case.name.as_deref(), Some("case2"));
+        assert_eq!(case.version.as_deref(), Some("2"));
+        assert_eq!(case.base_mva, Some(50.0));
+        let rows = &case.matrices["bus"];
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1].values, [2.0, 1.0, 10.0, 5.0]);
+        assert_eq!((rows[2].line, rows[2].values[0]), (9, 3.0));
+        assert!(!case.matrices.contains_key("bus_name"));
+    }
+
+    #[test]
+    fn test_comments_outside_quotes() {
+        assert_eq!(strip_comment("mpc.x = 1; % note"), "mpc.x = 1; ");
+        assert_eq!(strip_comment("mpc.name = '50%';"), "mpc.name = '50%';");
+    }
+
+    #[test]
+    fn test_numbers_written() {
+        assert_eq!(number(0.1 + 0.2), "0.3");
+        assert_eq!(number(-0.0), "0");
+        assert_eq!(number(f64::NEG_INFINITY), "-Inf");
+        assert_eq!(case_name("sim-1"), "sim_1");
+        assert_eq!(case_name("9bus"), "case_9bus");
+    }
+}
@@ -1 +1,9 @@
+pub mod cgmes;
 pub mod json;
+pub mod json_patch;
+pub mod json_stream;
+pub mod matpower;
+pub mod psse;
+pub mod ucte;
+pub mod xiidm;
+pub mod xml;
@@ -0,0 +1,1246 @@
+//! PSS/E RAW case files, revisions 33 and 35: bus, load, fixed shunt, generator, branch,
+//! transformer and switched shunt records. The other records are skipped.
+//!
+//! Each bus becomes a bus-breaker voltage level `VL-{n}` holding the bus `BUS-{n}`; buses joined
+//! by a transformer share a substation. Elements are named after their buses and PSS/E
+//! identifier: `LOAD-{i}-{id}`, `GEN-{i}-{id}`, `SHUNT-{i}-{id}`, `SWITCHED-SHUNT-{i}`,
+//! `LINE-{i}-{j}-{ckt}`, `TWT-{i}-{j}-{ckt}` and `TWT-{i}-{j}-{k}-{ckt}`. Per-unit values are
+//! converted with the system base power and the base voltage of the buses.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use chrono::Utc;
+use thiserror::Error;
+
+use crate::entities::*;
+use crate::topology::DisjointSet;
+
+const IIDM_VERSION: &str = "1.12";
+const SOURCE_FORMAT: &str = "PSS/E";
+const DEFAULT_CASE_NAME: &str = "psse";
+const DEFAULT_BASE_MVA: f64 = 100.0;
+const DEFAULT_POWER_LIMIT: f64 = 9999.0;
+
+// Bus types
+const GENERATOR_BUS: i64 = 2;
+const SWING_BUS: i64 = 3;
+const ISOLATED_BUS: i64 = 4;
+
+#[derive(Debug, Error)]
+pub enum PsseError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Unsupported PSS/E revision {0}, expected 33 or 35")]
+    UnsupportedRevision(i64),
+
+    #[error("Line {line}: invalid {field} '{value}'")]
+    InvalidValue {
+        line: usize,
+        field: &'static str,
+        value: String,
+    },
+
+    #[error("Line {line}: missing {field}")]
+    MissingField { line: usize, field: &'static str },
+
+    #[error("Line {line}: transformer record is cut short")]
+    TruncatedTransformer { line: usize },
+
+    #[error("Line {line}: bus {bus} is defined twice")]
+    DuplicateBus { line: usize, bus: i64 },
+
+    #[error("Line {line}: unknown bus {bus}")]
+    UnknownBus { line: usize, bus: i64 },
+
+    #[error("Line {line}: bus {bus} has no base voltage")]
+    MissingBaseVoltage { line: usize, bus: i64 },
+}
+
+/// Reads a network from a PSS/E RAW case file
+pub fn from_str(text: &str) -> Result<Network, PsseError> {
+    from_reader(text.as_bytes())
+}
+
+/// Reads a network from any buffered PSS/E RAW source
+pub fn from_reader<R: BufRead>(input: R) -> Result<Network, PsseError> {
+    Importer::new(read_case(input)?)?.import()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    SystemWide,
+    Bus,
+    Load,
+    FixedShunt,
+    Generator,
+    Branch,
+    SwitchingDevice,
+    Transformer,
+    Area,
+    TwoTerminalDc,
+    VscDc,
+    ImpedanceCorrection,
+    MultiTerminalDc,
+    MultiSectionLine,
+    Zone,
+    InterAreaTransfer,
+    Owner,
+    Facts,
+    SwitchedShunt,
+    Gne,
+    InductionMachine,
+    Substation,
+}
+
+const SECTIONS_33: &[Section] = &[
+    Section::Bus,
+    Section::Load,
+    Section::FixedShunt,
+    Section::Generator,
+    Section::Branch,
+    Section::Transformer,
+    Section::Area,
+    Section::TwoTerminalDc,
+    Section::VscDc,
+    Section::ImpedanceCorrection,
+    Section::MultiTerminalDc,
+    Section::MultiSectionLine,
+    Section::Zone,
+    Section::InterAreaTransfer,
+    Section::Owner,
+    Section::Facts,
+    Section::SwitchedShunt,
+    Section::Gne,
+    Section::InductionMachine,
+];
+
+const SECTIONS_35: &[Section] = &[
+    Section::SystemWide,
+    Section::Bus,
+    Section::Load,
+    Section::FixedShunt,
+    Section::Generator,
+    Section::Branch,
+    Section::SwitchingDevice,
+    Section::Transformer,
+    Section::Area,
+    Section::TwoTerminalDc,
+    Section::VscDc,
+    Section::ImpedanceCorrection,
+    Section::MultiTerminalDc,
+    Section::MultiSectionLine,
+    Section::Zone,
+    Section::InterAreaTransfer,
+    Section::Owner,
+    Section::Facts,
+    Section::SwitchedShunt,
+    Section::Gne,
+    Section::InductionMachine,
+    Section::Substation,
+];
+
+/// Positions of the fields which moved between revisions
+struct Layout {
+    sections: &'static [Section],
+    generator_status: usize,
+    generator_max_p: usize,
+    generator_min_p: usize,
+    branch_rating: usize,
+    branch_admittances: usize,
+    branch_status: usize,
+    winding_control_mode: usize,
+    winding_controlled_bus: usize,
+    winding_ratio_limits: usize,
+    winding_target_limits: usize,
+    winding_tap_count: usize,
+    shunt_id: Option<usize>,
+    shunt_status: usize,
+    shunt_initial_b: usize,
+    /// First block of a switched shunt, and the number of fields of each block
+    shunt_blocks: (usize, usize),
+}
+
+const LAYOUT_33: Layout = Layout {
+    sections: SECTIONS_33,
+    generator_status: 14,
+    generator_max_p: 16,
+    generator_min_p: 17,
+    branch_rating: 6,
+    branch_admittances: 9,
+    branch_status: 13,
+    winding_control_mode: 6,
+    winding_controlled_bus: 7,
+    winding_ratio_limits: 8,
+    winding_target_limits: 10,
+    winding_tap_count: 12,
+    shunt_id: None,
+    shunt_status: 3,
+    shunt_initial_b: 9,
+    shunt_blocks: (10, 2),
+};
+
+/// Revision 35 adds a regulated node to generators, twelve ratings and a name to branches and
+/// windings, and an identifier and block statuses to switched shunts
+const LAYOUT_35: Layout = Layout {
+    sections: SECTIONS_35,
+    generator_status: 15,
+    generator_max_p: 17,
+    generator_min_p: 18,
+    branch_rating: 7,
+    branch_admittances: 19,
+    branch_status: 23,
+    winding_control_mode: 15,
+    winding_controlled_bus: 16,
+    winding_ratio_limits: 18,
+    winding_target_limits: 20,
+    winding_tap_count: 22,
+    shunt_id: Some(1),
+    shunt_status: 4,
+    shunt_initial_b: 11,
+    shunt_blocks: (12, 3),
+};
+
+/// Fields of a data line, blank fields taking their default value
+#[derive(Debug, Clone)]
+struct Record {
+    line: usize,
+    fields: Vec<String>,
+}
+
+/// Splits a data line on commas or blanks, up to a `/` comment. Quoted strings may hold both.
+fn split_fields(text: &str) -> Vec<String> {
+    #[derive(PartialEq)]
+    enum State {
+        /// Start of line or after a comma, where another comma gives a blank field
+        Separated,
+        /// After blanks ending a field, where a comma does not start a new one
+        Blank,
+        Field,
+    }
+
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut state = State::Separated;
+    let mut quote = None;
+    for c in text.chars() {
+        if let Some(delimiter) = quote {
+            if c == delimiter {
+                quote = None;
+            } else {
+                current.push(c);
+            }
+            continue;
+        }
+        match c {
+            '/' => break,
+            '\'' | '"' => {
+                quote = Some(c);
+                state = State::Field;
+            }
+            ',' => {
+                if state != State::Blank {
+                    fields.push(std::mem::take(&mut current).trim().to_string());
+                }
+                state = State::Separated;
+            }
+            c if c.is_whitespace() => {
+                if state == State::Field {
+                    fields.push(std::mem::take(&mut current).trim().to_string());
+                    state = State::Blank;
+                }
+            }
+            c => {
+                current.push(c);
+                state = State::Field;
+            }
+        }
+    }
+    if state == State::Field {
+        fields.push(current.trim().to_string());
+    }
+    fields
+}
+
+impl Record {
+    fn new(line: usize, text: &str) -> Self {
+        Self {
+            line,
+            fields: split_fields(text),
+        }
+    }
+
+    fn text(&self, index: usize) -> &str {
+        self.fields.get(index).map_or("", String::as_str)
+    }
+
+    /// Whether the record ends its section
+    fn is_terminator(&self) -> bool {
+        self.text(0) == "0"
+    }
+
+    fn parse<T: std::str::FromStr>(
+        &self,
+        index: usize,
+        field: &'static str,
+    ) -> Result<Option<T>, PsseError> {
+        let text = self.text(index);
+        if text.is_empty() {
+            return Ok(None);
+        }
+        text.parse().map(Some).map_err(|_| PsseError::InvalidValue {
+            line: self.line,
+            field,
+            value: text.to_string(),
+        })
+    }
+
+    fn number(&self, index: usize, field: &'static str, default: f64) -> Result<f64, PsseError> {
+        Ok(self.parse(index, field)?.unwrap_or(default))
+    }
+
+    fn integer(&self, index: usize, field: &'static str, default: i64) -> Result<i64, PsseError> {
+        Ok(self.parse(index, field)?.unwrap_or(default))
+    }
+
+    /// Bus number, whose sign only tells the metered end of a branch
+    fn bus(&self, index: usize, field: &'static str) -> Result<i64, PsseError> {
+        self.parse::<i64>(index, field)?
+            .map(i64::abs)
+            .ok_or(PsseError::MissingField {
+                line: self.line,
+                field,
+            })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Case {
+    title: String,
+    base_mva: f64,
+    revision: i64,
+    buses: Vec<Record>,
+    loads: Vec<Record>,
+    fixed_shunts: Vec<Record>,
+    generators: Vec<Record>,
+    branches: Vec<Record>,
+    /// Four records for two windings, five for three windings
+    transformers: Vec<Vec<Record>>,
+    switched_shunts: Vec<Record>,
+}
+
+fn read_case<R: BufRead>(input: R) -> Result<Case, PsseError> {
+    // Lines starting with `@!` are column headers written by PSS/E
+    let mut lines = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if !line.trim_start().starts_with("@!") {
+            lines.push((index + 1, line));
+        }
+    }
+    let mut lines = lines.into_iter();
+
+    let (number, identification) = lines.next().unwrap_or((1, String::new()));
+    let identification = Record::new(number, &identification);
+    let revision = identification.integer(2, "revision", 33)?;
+    let layout = match revision {
+        33 => &LAYOUT_33,
+        35 => &LAYOUT_35,
+        _ => return Err(PsseError::UnsupportedRevision(revision)),
+    };
+    let mut case = Case {
+        base_mva: identification.number(1, "system base", DEFAULT_BASE_MVA)?,
+        revision,
+        ..Default::default()
+    };
+    let titles: Vec<String> = lines.by_ref().take(2).map(|(_, line)| line).collect();
+    case.title = titles.first().map_or("", |title| title.trim()).to_string();
+
+    let mut sections = layout.sections.iter().copied();
+    let mut section = sections.next();
+    while let Some((number, line)) = lines.next() {
+        if line.trim() == "Q" {
+            break;
+        }
+        let record = Record::new(number, &line);
+        if record.fields.is_empty() {
+            continue;
+        }
+        // The system-wide data are optional, the bus data start with a bus number
+        if section == Some(Section::SystemWide)
+            && record.text(0).parse::<i64>().is_ok_and(|bus| bus > 0)
+        {
+            section = sections.next();
+        }
+        if record.is_terminator() {
+            section = sections.next();
+            continue;
+        }
+        match section {
+            Some(Section::Bus) => case.buses.push(record),
+            Some(Section::Load) => case.loads.push(record),
+            Some(Section::FixedShunt) => case.fixed_shunts.push(record),
+            Some(Section::Generator) => case.generators.push(record),
+            Some(Section::Branch) => case.branches.push(record),
+            Some(Section::Transformer) => {
+                let windings = if record.integer(2, "bus k", 0)? == 0 {
+                    3
+                } else {
+                    4
+                };
+                let mut records = vec![record];
+                for _ in 0..windings {
+                    let Some((number, line)) = lines.next() else {
+                        return Err(PsseError::TruncatedTransformer { line: number });
+                    };
+                    records.push(Record::new(number, &line));
+                }
+                case.transformers.push(records);
+            }
+            Some(Section::SwitchedShunt) => case.switched_shunts.push(record),
+            _ => {}
+        }
+    }
+    Ok(case)
+}
+
+/// Apparent power in MVA as a current in A
+fn current_limits(rating: f64, base_kv: f64) -> Option<CurrentLimits> {
+    (rating > 0.0).then(|| CurrentLimits {
+        permanent_limit: rating * 1000.0 / (3f64.sqrt() * base_kv),
+        temporary_limits: Vec::new(),
+    })
+}
+
+struct PsseBus {
+    number: i64,
+    bus_type: i64,
+    base_kv: f64,
+}
+
+impl PsseBus {
+    fn bus_id(&self) -> String {
+        format!("BUS-{}", self.number)
+    }
+
+    fn voltage_level_id(&self) -> String {
+        format!("VL-{}", self.number)
+    }
+
+    /// Connected bus of an element, none for an isolated bus
+    fn connection(&self, in_service: bool) -> String {
+        if in_service && self.bus_type != ISOLATED_BUS {
+            self.bus_id()
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// Winding data of a transformer record, the tap ratio in the unit given by `CW`
+struct Winding {
+    ratio: f64,
+    nominal_kv: f64,
+    angle: f64,
+    rating: f64,
+    control_mode: i64,
+    controlled_bus: i64,
+    ratio_limits: (f64, f64),
+    target_limits: (f64, f64),
+    tap_count: i64,
+}
+
+impl Winding {
+    fn read(record: &Record, layout: &Layout, full: bool) -> Result<Self, PsseError> {
+        let number = |index, field, default| record.number(index, field, default);
+        let mut winding = Self {
+            ratio: number(0, "winding ratio", 1.0)?,
+            nominal_kv: number(1, "nominal voltage", 0.0)?,
+            angle: 0.0,
+            rating: 0.0,
+            control_mode: 0,
+            controlled_bus: 0,
+            ratio_limits: (1.1, 0.9),
+            target_limits: (1.1, 0.9),
+            tap_count: 33,
+        };
+        // The last winding of a two windings transformer only gives its ratio
+        if full {
+            let (ratio, target, taps) = (
+                layout.winding_ratio_limits,
+                layout.winding_target_limits,
+                layout.winding_tap_count,
+            );
+            winding.angle = number(2, "winding angle", 0.0)?;
+            winding.rating = number(3, "winding rating", 0.0)?;
+            winding.control_mode =
+                record.integer(layout.winding_control_mode, "control mode", 0)?;
+            winding.controlled_bus =
+                record.integer(layout.winding_controlled_bus, "controlled bus", 0)?;
+            winding.ratio_limits = (
+                number(ratio, "upper ratio limit", 1.1)?,
+                number(ratio + 1, "lower ratio limit", 0.9)?,
+            );
+            winding.target_limits = (
+                number(target, "upper voltage limit", 1.1)?,
+                number(target + 1, "lower voltage limit", 0.9)?,
+            );
+            winding.tap_count = record.integer(taps, "tap positions", 33)?;
+        }
+        Ok(winding)
+    }
+
+    /// Rated voltage in kV
+    fn rated_u(&self, winding_code: i64, base_kv: f64) -> f64 {
+        match winding_code {
+            2 => self.ratio,
+            3 if self.nominal_kv > 0.0 => self.ratio * self.nominal_kv,
+            _ => self.ratio * base_kv,
+        }
+    }
+
+    /// Positions between the lower and upper limit, the current one at the given value
+    fn positions(&self, (upper, lower): (f64, f64), current: f64) -> Option<(Vec<f64>, i32)> {
+        if self.tap_count < 2 || upper <= lower {
+            return None;
+        }
+        let count = self.tap_count as usize;
+        let step = (upper - lower) / (count - 1) as f64;
+        let mut positions: Vec<f64> = (0..count).map(|k| lower + k as f64 * step).collect();
+        let tap = positions
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (*a - current).abs().total_cmp(&(*b - current).abs()))
+            .map_or(0, |(tap, _)| tap);
+        // The load flow uses the current ratio, whether on a position or not
+        positions[tap] = current;
+        Some((positions, tap as i32))
+    }
+
+    /// Ratio tap changer of a winding controlling a voltage or a reactive flow. The steps divide
+    /// the ratio by the winding ratio of each position.
+    fn ratio_tap_changer(
+        &self,
+        terminal_ref: TerminalRef,
+        controlled_kv: f64,
+    ) -> Option<RatioTapChanger> {
+        let regulation_mode = match self.control_mode.abs() {
+            1 => RatioRegulationMode::Voltage,
+            2 => RatioRegulationMode::ReactivePower,
+            _ => return None,
+        };
+        let (positions, tap_position) = self.positions(self.ratio_limits, self.ratio)?;
+        let (upper, lower) = self.target_limits;
+        // Voltage limits are in per-unit of the controlled bus
+        let scale = match regulation_mode {
+            RatioRegulationMode::Voltage => controlled_kv,
+            RatioRegulationMode::ReactivePower => 1.0,
+        };
+        Some(RatioTapChanger {
+            regulating: self.control_mode > 0,
+            low_tap_position: 0,
+            tap_position,
+            target_deadband: (upper - lower) * scale,
+            load_tap_changing_capabilities: true,
+            regulation_mode,
+            regulation_value: (upper + lower) / 2.0 * scale,
+            terminal_ref,
+            steps: positions
+                .iter()
+                .map(|ratio| TapStep {
+                    r: 0.0,
+                    x: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    rho: self.ratio / ratio,
+                })
+                .collect(),
+        })
+    }
+
+    /// Phase tap changer of a winding controlling an active flow, or a fixed phase shift.
+    /// PSS/E shifts the winding voltage by the opposite angle of the IIDM one.
+    fn phase_tap_changer(&self, terminal_ref: TerminalRef) -> Option<PhaseTapChanger> {
+        let step = |angle: f64| PhaseTapStep {
+            r: 0.0,
+            x: 0.0,
+            g: 0.0,
+            b: 0.0,
+            rho: 1.0,
+            alpha: -angle,
+        };
+        let controlled = self.control_mode.abs() == 3;
+        let regulated = controlled
+            .then(|| self.positions(self.ratio_limits, self.angle))
+            .flatten();
+        let Some((positions, tap_position)) = regulated else {
+            return (self.angle != 0.0).then(|| PhaseTapChanger {
+                regulating: false,
+                low_tap_position: 0,
+                tap_position: 0,
+                regulation_mode: PhaseRegulationMode::FixedTap,
+                regulation_value: 0.0,
+                target_deadband: 0.0,
+                terminal_ref,
+                steps: vec![step(self.angle)],
+            });
+        };
+        let (upper, lower) = self.target_limits;
+        Some(PhaseTapChanger {
+            regulating: self.control_mode > 0,
+            low_tap_position: 0,
+            tap_position,
+            regulation_mode: if self.control_mode > 0 {
+                PhaseRegulationMode::ActivePowerControl
+            } else {
+                PhaseRegulationMode::FixedTap
+            },
+            regulation_value: (upper + lower) / 2.0,
+            target_deadband: upper - lower,
+            terminal_ref,
+            steps: positions.into_iter().map(step).collect(),
+        })
+    }
+}
+
+/// Series impedance in per-unit of the system base, from the unit given by `CZ`
+fn system_impedance(
+    impedance_code: i64,
+    (r, x): (f64, f64),
+    winding_base: f64,
+    system_base: f64,
+) -> (f64, f64) {
+    let to_system = system_base / winding_base;
+    match impedance_code {
+        2 => (r * to_system, x * to_system),
+        // Load loss in W and impedance magnitude, on the winding base
+        3 => {
+            let r = r / 1e6 / winding_base;
+            let x = (x * x - r * r).max(0.0).sqrt();
+            (r * to_system, x * to_system)
+        }
+        _ => (r, x),
+    }
+}
+
+/// Magnetizing admittance in per-unit of the system base, from the unit given by `CM`
+fn system_magnetizing(
+    magnetizing_code: i64,
+    (g, b): (f64, f64),
+    winding_base: f64,
+    system_base: f64,
+) -> (f64, f64) {
+    match magnetizing_code {
+        // No-load loss in W and exciting current on the winding base
+        2 => {
+            let g = g / 1e6 / system_base;
+            let y = b * winding_base / system_base;
+            (g, -(y * y - g * g).max(0.0).sqrt())
+        }
+        _ => (g, b),
+    }
+}
+
+struct Importer {
+    case: Case,
+    layout: &'static Layout,
+    buses: Vec<PsseBus>,
+    positions: HashMap<i64, usize>,
+}
+
+impl Importer {
+    fn new(case: Case) -> Result<Self, PsseError> {
+        let layout = if case.revision == 35 {
+            &LAYOUT_35
+        } else {
+            &LAYOUT_33
+        };
+        let mut buses = Vec::new();
+        let mut positions = HashMap::new();
+        for record in &case.buses {
+            let number = record.bus(0, "bus number")?;
+            if positions.insert(number, buses.len()).is_some() {
+                return Err(PsseError::DuplicateBus {
+                    line: record.line,
+                    bus: number,
+                });
+            }
+            let base_kv = record.number(2, "base voltage", 0.0)?;
+            if base_kv <= 0.0 {
+                return Err(PsseError::MissingBaseVoltage {
+                    line: record.line,
+                    bus: number,
+                });
+            }
+            buses.push(PsseBus {
+                number,
+                bus_type: record.integer(3, "bus type", 1)?,
+                base_kv,
+            });
+        }
+        Ok(Self {
+            case,
+            layout,
+            buses,
+            positions,
+        })
+    }
+
+    fn position(&self, record: &Record, index: usize) -> Result<usize, PsseError> {
+        let bus = record.bus(index, "bus number")?;
+        self.positions
+            .get(&bus)
+            .copied()
+            .ok_or(PsseError::UnknownBus {
+                line: record.line,
+                bus,
+            })
+    }
+
+    fn import(self) -> Result<Network, PsseError> {
+        let mut voltage_levels: Vec<VoltageLevel> = self
+            .buses
+            .iter()
+            .map(|bus| VoltageLevel {
+                id: bus.voltage_level_id(),
+                nominal_v: bus.base_kv,
+                topology_kind: TopologyKind::BusBreaker,
+                generators: None,
+                loads: None,
+                busbar_sections: None,
+                node_breaker_topology: None,
+                bus_breaker_topology: Some(BusBreakerTopology {
+                    buses: vec![Bus { id: bus.bus_id() }],
+                    switches: Vec::new(),
+                }),
+            })
+            .collect();
+        for record in &self.case.loads {
+            let position = self.position(record, 0)?;
+            let load = self.load(record, &self.buses[position])?;
+            voltage_levels[position]
+                .loads
+                .get_or_insert_with(Vec::new)
+                .push(load);
+        }
+        for record in &self.case.generators {
+            let position = self.position(record, 0)?;
+            let generator = self.generator(record, &self.buses[position])?;
+            voltage_levels[position]
+                .generators
+                .get_or_insert_with(Vec::new)
+                .push(generator);
+        }
+
+        let mut shunt_compensators = Vec::new();
+        for record in &self.case.fixed_shunts {
+            let bus = &self.buses[self.position(record, 0)?];
+            shunt_compensators.push(ShuntCompensator {
+                id: format!("SHUNT-{}-{}", bus.number, record.text(1)),
+                // Susceptance in MVar at 1 per-unit, the conductance has no counterpart
+                b_per_section: record.number(4, "shunt susceptance", 0.0)?
+                    / (bus.base_kv * bus.base_kv),
+                maximum_section_count: 1,
+                section_count: 1,
+                bus: bus.connection(record.integer(2, "status", 1)? == 1),
+                connectable_bus: bus.bus_id(),
+            });
+        }
+        for record in &self.case.switched_shunts {
+            let bus = &self.buses[self.position(record, 0)?];
+            shunt_compensators.push(self.switched_shunt(record, bus)?);
+        }
+
+        let mut lines = Vec::new();
+        for record in &self.case.branches {
+            lines.push(self.line(record)?);
+        }
+
+        // Buses joined by transformers share a substation
+        let mut groups = DisjointSet::new(self.buses.len());
+        let mut two_windings_transformers: Vec<Vec<TwoWindingsTransformer>> =
+            self.buses.iter().map(|_| Vec::new()).collect();
+        let mut three_windings_transformers = Vec::new();
+        for records in &self.case.transformers {
+            let first = &records[0];
+            let (i, j) = (self.position(first, 0)?, self.position(first, 1)?);
+            groups.union(i, j);
+            if records.len() == 4 {
+                two_windings_transformers[i].push(self.two_windings_transformer(records, i, j)?);
+            } else {
+                let k = self.position(first, 2)?;
+                groups.union(i, k);
+                three_windings_transformers
+                    .push(self.three_windings_transformer(records, [i, j, k])?);
+            }
+        }
+
+        let mut voltage_levels: Vec<Option<VoltageLevel>> =
+            voltage_levels.into_iter().map(Some).collect();
+        let substations = groups
+            .groups()
+            .into_iter()
+            .map(|members| Substation {
+                id: format!("SUB-{}", self.buses[members[0]].number),
+                country: String::new(),
+                tso: String::new(),
+                geographical_tags: Vec::new(),
+                voltage_levels: members
+                    .iter()
+                    .filter_map(|member| voltage_levels[*member].take())
+                    .collect(),
+                two_windings_transformers: members
+                    .iter()
+                    .flat_map(|member| std::mem::take(&mut two_windings_transformers[*member]))
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Network {
+            version: IIDM_VERSION.to_string(),
+            id: if self.case.title.is_empty() {
+                DEFAULT_CASE_NAME.to_string()
+            } else {
+                self.case.title.clone()
+            },
+            case_date: Utc::now().fixed_offset(),
+            forecast_distance: 0,
+            source_format: SOURCE_FORMAT.to_string(),
+            minimum_validation_level: ValidationLevel::default(),
+            substations,
+            lines,
+            three_windings_transformers,
+            switches: Vec::new(),
+            shunt_compensators,
+            static_var_compensators: Vec::new(),
+            dangling_lines: Vec::new(),
+            tie_lines: Vec::new(),
+            hvdc_lines: Vec::new(),
+        })
+    }
+
+    /// Constant power, current and admittance parts, summed at 1 per-unit
+    fn load(&self, record: &Record, bus: &PsseBus) -> Result<Load, PsseError> {
+        let number = |index, field| record.number(index, field, 0.0);
+        let (p, q) = (number(5, "active load")?, number(6, "reactive load")?);
+        let (i_p, i_q) = (
+            number(7, "active current load")?,
+            number(8, "reactive current load")?,
+        );
+        let (z_p, z_q) = (
+            number(9, "active admittance load")?,
+            number(10, "reactive admittance load")?,
+        );
+        let (p0, q0) = (p + i_p + z_p, q + i_q + z_q);
+        let share = |part: f64, total: f64| if total == 0.0 { 0.0 } else { part / total };
+        let zip_model =
+            (i_p != 0.0 || i_q != 0.0 || z_p != 0.0 || z_q != 0.0).then(|| ZipLoadModel {
+                p0,
+                q0,
+                z_p: share(z_p, p0),
+                z_q: share(z_q, q0),
+                i_p: share(i_p, p0),
+                i_q: share(i_q, q0),
+                p_p: share(p, p0),
+                p_q: share(q, q0),
+            });
+        Ok(Load {
+            id: format!("LOAD-{}-{}", bus.number, record.text(1)),
+            load_type: LoadType::Undefined,
+            p0,
+            q0,
+            bus: bus.connection(record.integer(2, "status", 1)? == 1),
+            connectable_bus: bus.bus_id(),
+            exponential_model: None,
+            zip_model,
+        })
+    }
+
+    /// Generators regulate their own bus, even when PSS/E has them regulate a remote one
+    fn generator(&self, record: &Record, bus: &PsseBus) -> Result<Generator, PsseError> {
+        let layout = self.layout;
+        let in_service = record.integer(layout.generator_status, "status", 1)? == 1;
+        Ok(Generator {
+            id: format!("GEN-{}-{}", bus.number, record.text(1)),
+            energy_source: EnergySource::Other,
+            min_p: record.number(
+                layout.generator_min_p,
+                "minimum active power",
+                -DEFAULT_POWER_LIMIT,
+            )?,
+            max_p: record.number(
+                layout.generator_max_p,
+                "maximum active power",
+                DEFAULT_POWER_LIMIT,
+            )?,
+            voltage_regulator_on: in_service && matches!(bus.bus_type, GENERATOR_BUS | SWING_BUS),
+            target_p: record.number(2, "active power", 0.0)?,
+            target_v: record.number(6, "voltage setpoint", 1.0)? * bus.base_kv,
+            target_q: record.number(3, "reactive power", 0.0)?,
+            bus: bus.connection(in_service),
+            connectable_bus: bus.bus_id(),
+            reactive_capability_curve: None,
+            min_max_reactive_limits: Some(MinMaxReactiveLimits {
+                min_q: record.number(5, "minimum reactive power", -DEFAULT_POWER_LIMIT)?,
+                max_q: record.number(4, "maximum reactive power", DEFAULT_POWER_LIMIT)?,
+            }),
+        })
+    }
+
+    /// Blocks of equal susceptance become sections, other shunts a single section at their
+    /// current susceptance
+    fn switched_shunt(
+        &self,
+        record: &Record,
+        bus: &PsseBus,
+    ) -> Result<ShuntCompensator, PsseError> {
+        let layout = self.layout;
+        let id = match layout.shunt_id {
+            Some(index) => format!("SWITCHED-SHUNT-{}-{}", bus.number, record.text(index)),
+            None => format!("SWITCHED-SHUNT-{}", bus.number),
+        };
+        let b_base = bus.base_kv * bus.base_kv;
+        let initial_b = record.number(layout.shunt_initial_b, "initial susceptance", 0.0)?;
+        let (first, width) = layout.shunt_blocks;
+        let mut blocks = Vec::new();
+        for block in 0..8 {
+            let index = first + block * width;
+            // Revision 35 gives the status of each block before its size
+            let (count_index, status) = match width {
+                3 => (index + 1, record.integer(index, "block status", 1)?),
+                _ => (index, 1),
+            };
+            let count = record.integer(count_index, "block steps", 0)?;
+            let b = record.number(count_index + 1, "block susceptance", 0.0)?;
+            if count > 0 && status == 1 {
+                blocks.push((count, b));
+            }
+        }
+        let equal = blocks
+            .first()
+            .is_some_and(|(_, first)| *first != 0.0 && blocks.iter().all(|(_, b)| b == first));
+        let (b_per_section, maximum_section_count, section_count) = if equal {
+            let b = blocks[0].1;
+            let maximum: i64 = blocks.iter().map(|(count, _)| count).sum();
+            let sections = ((initial_b / b).round() as i64).clamp(0, maximum);
+            (b, maximum, sections)
+        } else {
+            (initial_b, 1, 1)
+        };
+        Ok(ShuntCompensator {
+            id,
+            b_per_section: b_per_section / b_base,
+            maximum_section_count: maximum_section_count as i32,
+            section_count: section_count as i32,
+            bus: bus.connection(record.integer(layout.shunt_status, "status", 1)? == 1),
+            connectable_bus: bus.bus_id(),
+        })
+    }
+
+    fn line(&self, record: &Record) -> Result<Line, PsseError> {
+        let layout = self.layout;
+        let (from, to) = (
+            &self.buses[self.position(record, 0)?],
+            &self.buses[self.position(record, 1)?],
+        );
+        let in_service = record.integer(layout.branch_status, "status", 1)? == 1;
+        let z_base = from.base_kv * from.base_kv / self.case.base_mva;
+        let number = |index, field| record.number(index, field, 0.0);
+        let admittances = layout.branch_admittances;
+        let b = number(5, "charging susceptance")?;
+        let rating = number(layout.branch_rating, "rating")?;
+        Ok(Line {
+            id: format!("LINE-{}-{}-{}", from.number, to.number, record.text(2)),
+            r: number(3, "resistance")? * z_base,
+            x: number(4, "reactance")? * z_base,
+            g1: number(admittances, "conductance i")? / z_base,
+            b1: (b / 2.0 + number(admittances + 1, "susceptance i")?) / z_base,
+            g2: number(admittances + 2, "conductance j")? / z_base,
+            b2: (b / 2.0 + number(admittances + 3, "susceptance j")?) / z_base,
+            voltage_level_id1: from.voltage_level_id(),
+            bus1: from.connection(in_service),
+            connectable_bus1: from.bus_id(),
+            voltage_level_id2: to.voltage_level_id(),
+            bus2: to.connection(in_service),
+            connectable_bus2: to.bus_id(),
+            current_limits1: current_limits(rating, from.base_kv),
+            current_limits2: current_limits(rating, to.base_kv),
+        })
+    }
+
+    /// Codes of the units of the winding ratios, impedances and magnetizing admittance
+    fn transformer_codes(&self, first: &Record) -> Result<(i64, i64, i64), PsseError> {
+        Ok((
+            first.integer(4, "winding data code", 1)?,
+            first.integer(5, "impedance data code", 1)?,
+            first.integer(6, "magnetizing data code", 1)?,
+        ))
+    }
+
+    /// Base voltage of the bus a tap changer controls, the given one when none
+    fn controlled_kv(&self, winding: &Winding, default: &PsseBus) -> f64 {
+        self.positions
+            .get(&winding.controlled_bus.abs())
+            .map_or(default.base_kv, |position| self.buses[*position].base_kv)
+    }
+
+    /// The ratio of the winding 1 is applied on the side 1, the impedance in the base of the
+    /// rated voltage of the winding 2 and the magnetizing admittance at the bus of the side 1
+    fn two_windings_transformer(
+        &self,
+        records: &[Record],
+        i: usize,
+        j: usize,
+    ) -> Result<TwoWindingsTransformer, PsseError> {
+        let (from, to) = (&self.buses[i], &self.buses[j]);
+        let first = &records[0];
+        let (winding_code, impedance_code, magnetizing_code) = self.transformer_codes(first)?;
+        let base_mva = self.case.base_mva;
+        let winding_base = records[1].number(2, "winding base", base_mva)?;
+        let winding1 = Winding::read(&records[2], self.layout, true)?;
+        let winding2 = Winding::read(&records[3], self.layout, false)?;
+        let in_service = first.integer(11, "status", 1)? == 1;
+
+        let rated_u1 = winding1.rated_u(winding_code, from.base_kv);
+        let rated_u2 = winding2.rated_u(winding_code, to.base_kv);
+        let (r, x) = system_impedance(
+            impedance_code,
+            (
+                records[1].number(0, "resistance", 0.0)?,
+                records[1].number(1, "reactance", 0.0)?,
+            ),
+            winding_base,
+            base_mva,
+        );
+        let (g, b) = system_magnetizing(
+            magnetizing_code,
+            (
+                first.number(7, "magnetizing conductance", 0.0)?,
+                first.number(8, "magnetizing susceptance", 0.0)?,
+            ),
+            winding_base,
+            base_mva,
+        );
+        let z_base = rated_u2 * rated_u2 / base_mva;
+        // Brought from the bus of the side 1 to the other side of the ratio
+        let y_base =
+            base_mva * rated_u1 * rated_u1 / (from.base_kv * from.base_kv * rated_u2 * rated_u2);
+
+        let id = format!("TWT-{}-{}-{}", from.number, to.number, first.text(3));
+        let controlled_side = if winding1.controlled_bus.abs() == from.number {
+            Side::One
+        } else {
+            Side::Two
+        };
+        let controlled_kv = self.controlled_kv(&winding1, to);
+        Ok(TwoWindingsTransformer {
+            r: r * z_base,
+            x: x * z_base,
+            g: g * y_base,
+            b: b * y_base,
+            rated_u1,
+            rated_u2,
+            voltage_level_id1: from.voltage_level_id(),
+            bus1: from.connection(in_service),
+            connectable_bus1: from.bus_id(),
+            voltage_level_id2: to.voltage_level_id(),
+            bus2: to.connection(in_service),
+            connectable_bus2: to.bus_id(),
+            ratio_tap_changer: winding1.ratio_tap_changer(
+                TerminalRef {
+                    id: id.clone(),
+                    side: controlled_side,
+                },
+                controlled_kv,
+            ),
+            phase_tap_changer: winding1.phase_tap_changer(TerminalRef {
+                id: id.clone(),
+                side: Side::One,
+            }),
+            current_limits1: current_limits(winding1.rating, from.base_kv),
+            current_limits2: current_limits(winding1.rating, to.base_kv),
+            id,
+        })
+    }
+
+    /// Windings meet at a star bus at the rated voltage of the winding 1. Only the windings 2
+    /// and 3 get a tap changer, and phase shifts are left out.
+    fn three_windings_transformer(
+        &self,
+        records: &[Record],
+        ends: [usize; 3],
+    ) -> Result<ThreeWindingsTransformer, PsseError> {
+        let buses = ends.map(|position| &self.buses[position]);
+        let first = &records[0];
+        let (winding_code, impedance_code, magnetizing_code) = self.transformer_codes(first)?;
+        let base_mva = self.case.base_mva;
+        let impedances = &records[1];
+        let mut pairs = [(0.0, 0.0); 3];
+        for (pair, index) in pairs.iter_mut().zip([0, 3, 6]) {
+            *pair = system_impedance(
+                impedance_code,
+                (
+                    impedances.number(index, "resistance", 0.0)?,
+                    impedances.number(index + 1, "reactance", 0.0)?,
+                ),
+                impedances.number(index + 2, "winding base", base_mva)?,
+                base_mva,
+            );
+        }
+        // Star impedances from the impedances between windings 1-2, 2-3 and 3-1
+        let [z12, z23, z31] = pairs;
+        let star = |a: (f64, f64), b: (f64, f64), c: (f64, f64)| {
+            ((a.0 + b.0 - c.0) / 2.0, (a.1 + b.1 - c.1) / 2.0)
+        };
+        let legs = [
+            star(z12, z31, z23),
+            star(z12, z23, z31),
+            star(z23, z31, z12),
+        ];
+
+        let windings = [
+            Winding::read(&records[2], self.layout, true)?,
+            Winding::read(&records[3], self.layout, true)?,
+            Winding::read(&records[4], self.layout, true)?,
+        ];
+        let rated_u: Vec<f64> = windings
+            .iter()
+            .zip(buses)
+            .map(|(winding, bus)| winding.rated_u(winding_code, bus.base_kv))
+            .collect();
+        let z_base = rated_u[0] * rated_u[0] / base_mva;
+        let (g, b) = system_magnetizing(
+            magnetizing_code,
+            (
+                first.number(7, "magnetizing conductance", 0.0)?,
+                first.number(8, "magnetizing susceptance", 0.0)?,
+            ),
+            impedances.number(2, "winding base", base_mva)?,
+            base_mva,
+        );
+        let y_base = base_mva / (buses[0].base_kv * buses[0].base_kv);
+
+        // 0 out, 1 in, 2, 3 and 4 with the winding 2, 3 or 1 out
+        let status = first.integer(11, "status", 1)?;
+        let in_service = [
+            !matches!(status, 0 | 4),
+            !matches!(status, 0 | 2),
+            !matches!(status, 0 | 3),
+        ];
+        let id = format!(
+            "TWT-{}-{}-{}-{}",
+            buses[0].number,
+            buses[1].number,
+            buses[2].number,
+            first.text(3)
+        );
+        let tap_changer = |winding: usize, side: Side| {
+            windings[winding].ratio_tap_changer(
+                TerminalRef {
+                    id: id.clone(),
+                    side,
+                },
+                self.controlled_kv(&windings[winding], buses[winding]),
+            )
+        };
+        Ok(ThreeWindingsTransformer {
+            r1: legs[0].0 * z_base,
+            x1: legs[0].1 * z_base,
+            g1: g * y_base,
+            b1: b * y_base,
+            rated_u1: rated_u[0],
+            r2: legs[1].0 * z_base,
+            x2: legs[1].1 * z_base,
+            g2: 0.0,
+            b2: 0.0,
+            rated_u2: rated_u[1],
+            r3: legs[2].0 * z_base,
+            x3: legs[2].1 * z_base,
+            g3: 0.0,
+            b3: 0.0,
+            rated_u3: rated_u[2],
+            voltage_level_id1: buses[0].voltage_level_id(),
+            voltage_level_id2: buses[1].voltage_level_id(),
+            voltage_level_id3: buses[2].voltage_level_id(),
+            bus1: buses[0].connection(in_service[0]),
+            bus2: buses[1].connection(in_service[1]),
+            bus3: buses[2].connection(in_service[2]),
+            connectable_bus1: buses[0].bus_id(),
+            connectable_bus2: buses[1].bus_id(),
+            connectable_bus3: buses[2].bus_id(),
+            ratio_tap_changer2: tap_changer(1, Side::Two),
+            ratio_tap_changer3: tap_changer(2, Side::Three),
+            current_limits1: current_limits(windings[0].rating, buses[0].base_kv),
+            current_limits2: current_limits(windings[1].rating, buses[1].base_kv),
+            current_limits3: current_limits(windings[2].rating, buses[2].base_kv),
+            id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_fields() {
+        assert_eq!(
+            split_fields("  101,'GEN 1 / A',  18.0,3 / comment, 4"),
+            ["101", "GEN 1 / A", "18.0", "3"]
+        );
+        // Blank fields between commas take their default value
+        assert_eq!(split_fields("1,,2, ,3"), ["1", "", "2", "", "3"]);
+        assert_eq!(split_fields("1 2\t\"ID\" ,3"), ["1", "2", "ID", "3"]);
+        assert!(split_fields("   ").is_empty());
+    }
+
+    #[test]
+    fn test_sections_of_each_revision() {
+        let case = "0, 100.0, 35\nTITLE\n\n\
+                    GENERAL, THRSHZ=0.0001\n\
+                    0 / END OF SYSTEM-WIDE DATA\n\
+                    @! I, 'NAME', BASKV\n\
+                    1, 'A', 230.0, 3\n0 / END OF BUS DATA\n\
+                    1, '1', 1, 1, 1, 10.0, 5.0\n0\n0\n0\n0\n0\n";
+        let transformer = format!("{case}1, 2, 0, '1', 1, 1, 1, 0, 0, 2, 'T', 1\n0.0, 0.1\n");
+        assert!(matches!(
+            read_case(transformer.as_bytes()),
+            Err(PsseError::TruncatedTransformer { line: 15 })
+        ));
+        let case = read_case(case.as_bytes()).unwrap();
+        assert_eq!((case.revision, case.title.as_str()), (35, "TITLE"));
+        assert_eq!((case.buses.len(), case.loads.len()), (1, 1));
+        assert_eq!(case.buses[0].line, 7);
+    }
+
+    #[test]
+    fn test_transformer_units() {
+        // Load loss of 200 kW and 10 % impedance on a 50 MVA winding
+        let (r, x) = system_impedance(3, (200e3, 0.1), 50.0, 100.0);
+        assert!((r - 0.008).abs() < 1e-12);
+        assert!((x - (0.01f64 - 0.004f64.powi(2)).sqrt() * 2.0).abs() < 1e-12);
+        assert_eq!(
+            system_impedance(2, (0.01, 0.1), 200.0, 100.0),
+            (0.005, 0.05)
+        );
+        assert_eq!(system_magnetizing(1, (0.1, -0.2), 50.0, 100.0), (0.1, -0.2));
+
+        let winding = Winding {
+            ratio: 1.02,
+            nominal_kv: 0.0,
+            angle: 0.0,
+            rating: 0.0,
+            control_mode: 1,
+            controlled_bus: 0,
+            ratio_limits: (1.1, 0.9),
+            target_limits: (1.1, 0.9),
+            tap_count: 5,
+        };
+        let (positions, tap) = winding.positions((1.1, 0.9), 1.02).unwrap();
+        assert_eq!(tap, 2);
+        assert_eq!(positions[2], 1.02);
+        assert_eq!(winding.rated_u(2, 230.0), 1.02);
+        assert!((winding.rated_u(1, 230.0) - 234.6).abs() < 1e-9);
+    }
+}
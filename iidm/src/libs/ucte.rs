@@ -0,0 +1,1393 @@
+//! UCTE-DEF exchange format: fixed-column records of nodes (`##N`), lines (`##L`), transformers
+//! (`##T`) and their regulations (`##R`).
+//!
+//! Each node becomes a bus named after its 8 characters code, in the voltage level named after the
+//! first 7 (country, location and voltage code) and the substation named after the first 6.
+//! Substations joined by a transformer are merged. X-nodes are the boundary points of the model:
+//! a line to an X-node becomes a `DanglingLine`, two lines meeting at the same X-node a `TieLine`.
+//! Elements are named `{node1} {node2} {order}`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use thiserror::Error;
+
+use crate::entities::*;
+
+const IIDM_VERSION: &str = "1.12";
+const SOURCE_FORMAT: &str = "UCTE";
+const NETWORK_ID: &str = "ucte";
+const DATE_FORMAT: &str = "%Y.%m.%d";
+/// Generation limits of a node which gives none
+const DEFAULT_POWER_LIMIT: f64 = 9999.0;
+
+#[derive(Debug, Error)]
+pub enum UcteError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Line {line}: invalid {field} '{value}'")]
+    InvalidValue {
+        line: usize,
+        field: &'static str,
+        value: String,
+    },
+
+    #[error("Line {line}: unknown node '{node}'")]
+    UnknownNode { line: usize, node: String },
+
+    #[error("Line {line}: no transformer '{id}' for this regulation")]
+    UnknownTransformer { line: usize, id: String },
+
+    #[error("Line {line}: coupler '{id}' joins two voltage levels")]
+    CouplerAcrossVoltageLevels { line: usize, id: String },
+
+    #[error("Bus '{0}' is not an 8 characters UCTE node code")]
+    InvalidNodeCode(String),
+
+    #[error("{field} of '{id}' does not fit its columns: {value}")]
+    ValueTooWide {
+        id: String,
+        field: &'static str,
+        value: f64,
+    },
+}
+
+/// Columns of a field, 1-based and inclusive as in the format description
+#[derive(Debug, Clone, Copy)]
+struct Column {
+    name: &'static str,
+    first: usize,
+    last: usize,
+}
+
+const fn column(name: &'static str, first: usize, last: usize) -> Column {
+    Column { name, first, last }
+}
+
+const NODE_CODE: Column = column("node code", 1, 8);
+const NODE_STATUS: Column = column("node status", 23, 23);
+const NODE_TYPE: Column = column("node type", 25, 25);
+const NODE_VOLTAGE: Column = column("voltage", 27, 32);
+const NODE_ACTIVE_LOAD: Column = column("active load", 34, 40);
+const NODE_REACTIVE_LOAD: Column = column("reactive load", 42, 48);
+const NODE_ACTIVE_GENERATION: Column = column("active generation", 50, 56);
+const NODE_REACTIVE_GENERATION: Column = column("reactive generation", 58, 64);
+const NODE_MIN_ACTIVE_GENERATION: Column = column("minimum active generation", 66, 72);
+const NODE_MAX_ACTIVE_GENERATION: Column = column("maximum active generation", 74, 80);
+const NODE_MIN_REACTIVE_GENERATION: Column = column("minimum reactive generation", 82, 88);
+const NODE_MAX_REACTIVE_GENERATION: Column = column("maximum reactive generation", 90, 96);
+const NODE_PLANT_TYPE: Column = column("power plant type", 128, 128);
+
+const ELEMENT_NODE1: Column = column("node 1", 1, 8);
+const ELEMENT_NODE2: Column = column("node 2", 10, 17);
+const ELEMENT_ORDER: Column = column("order code", 19, 19);
+const ELEMENT_STATUS: Column = column("status", 21, 21);
+
+const LINE_R: Column = column("resistance", 23, 28);
+const LINE_X: Column = column("reactance", 30, 35);
+const LINE_B: Column = column("susceptance", 37, 44);
+const LINE_CURRENT_LIMIT: Column = column("current limit", 46, 51);
+
+const TRANSFORMER_RATED_U1: Column = column("rated voltage 1", 23, 27);
+const TRANSFORMER_RATED_U2: Column = column("rated voltage 2", 29, 33);
+const TRANSFORMER_NOMINAL_POWER: Column = column("nominal power", 35, 39);
+const TRANSFORMER_R: Column = column("resistance", 41, 46);
+const TRANSFORMER_X: Column = column("reactance", 48, 53);
+const TRANSFORMER_B: Column = column("susceptance", 55, 62);
+const TRANSFORMER_G: Column = column("conductance", 64, 69);
+const TRANSFORMER_CURRENT_LIMIT: Column = column("current limit", 71, 76);
+
+const PHASE_STEP: Column = column("voltage step", 21, 25);
+const PHASE_TAPS: Column = column("number of taps", 27, 28);
+const PHASE_TAP: Column = column("tap position", 30, 32);
+const PHASE_VOLTAGE: Column = column("target voltage", 34, 38);
+const ANGLE_STEP: Column = column("angle step", 40, 44);
+const ANGLE_THETA: Column = column("angle", 46, 50);
+const ANGLE_TAPS: Column = column("number of angle taps", 52, 53);
+const ANGLE_TAP: Column = column("angle tap position", 55, 57);
+const ANGLE_POWER: Column = column("target active power", 59, 63);
+const ANGLE_TYPE: Column = column("regulation type", 65, 68);
+
+/// Reads a network from a UCTE-DEF document
+pub fn from_str(text: &str) -> Result<Network, UcteError> {
+    from_reader(text.as_bytes())
+}
+
+/// Reads a network from any buffered UCTE-DEF source
+pub fn from_reader<R: BufRead>(input: R) -> Result<Network, UcteError> {
+    let mut document = Document::default();
+    let mut section = Section::Other;
+    // Names are often encoded in Latin-1, only the codes and numbers need to be read
+    for (index, line) in input.split(b'\n').enumerate() {
+        let line = line?;
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches('\r');
+        let record = Record::new(index + 1, line);
+        if let Some(header) = line.strip_prefix("##") {
+            section = match header.trim_end() {
+                "N" => Section::Nodes,
+                "L" => Section::Lines,
+                "T" => Section::Transformers,
+                "R" => Section::Regulations,
+                country if country.starts_with('Z') && section == Section::Nodes => {
+                    document.country = country[1..].trim().to_string();
+                    Section::Nodes
+                }
+                comment if comment.starts_with('C') => {
+                    document.date = comment[1..]
+                        .trim()
+                        .split(' ')
+                        .next()
+                        .and_then(|date| NaiveDate::parse_from_str(date, DATE_FORMAT).ok());
+                    Section::Other
+                }
+                _ => Section::Other,
+            };
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        match section {
+            Section::Nodes => document.read_node(&record)?,
+            Section::Lines => document.lines.push(read_element(&record)?),
+            Section::Transformers => document.transformers.push(read_element(&record)?),
+            Section::Regulations => document.regulations.push(read_element(&record)?),
+            Section::Other => {}
+        }
+    }
+    document.into_network()
+}
+
+/// Writes a network as a UCTE-DEF document. The buses must be named with UCTE node codes.
+pub fn to_string(network: &Network) -> Result<String, UcteError> {
+    let mut output = Vec::new();
+    to_writer(network, &mut output)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Writes a network as a UCTE-DEF document into any writer
+pub fn to_writer<W: Write>(network: &Network, mut output: W) -> Result<(), UcteError> {
+    for line in Writer::new(network).write()? {
+        writeln!(output, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Nodes,
+    Lines,
+    Transformers,
+    Regulations,
+    Other,
+}
+
+/// Characters of a line, to cut its fields by column
+struct Record {
+    line: usize,
+    chars: Vec<char>,
+}
+
+impl Record {
+    fn new(line: usize, text: &str) -> Self {
+        Self {
+            line,
+            chars: text.chars().collect(),
+        }
+    }
+
+    /// Field content padded with spaces, node codes may end with a space
+    fn raw(&self, column: Column) -> String {
+        (column.first - 1..column.last)
+            .map(|index| self.chars.get(index).copied().unwrap_or(' '))
+            .collect()
+    }
+
+    fn text(&self, column: Column) -> String {
+        self.raw(column).trim().to_string()
+    }
+
+    fn invalid(&self, column: Column) -> UcteError {
+        UcteError::InvalidValue {
+            line: self.line,
+            field: column.name,
+            value: self.text(column),
+        }
+    }
+
+    fn node(&self, column: Column) -> Result<String, UcteError> {
+        let code = self.raw(column);
+        if code.trim().is_empty() {
+            return Err(self.invalid(column));
+        }
+        Ok(code)
+    }
+
+    /// Blank fields are not given
+    fn number<T: std::str::FromStr>(&self, column: Column) -> Result<Option<T>, UcteError> {
+        let text = self.text(column);
+        if text.is_empty() {
+            return Ok(None);
+        }
+        text.parse().map(Some).map_err(|_| self.invalid(column))
+    }
+
+    fn value(&self, column: Column) -> Result<f64, UcteError> {
+        Ok(self.number(column)?.unwrap_or(0.0))
+    }
+}
+
+/// Line, transformer or regulation record, kept with its line number until the nodes are known
+struct ElementRecord {
+    record: Record,
+    node1: String,
+    node2: String,
+    order: char,
+}
+
+impl ElementRecord {
+    fn id(&self) -> String {
+        element_id(&self.node1, &self.node2, self.order)
+    }
+
+    /// Status of lines and transformers: 0 to 2 in operation, 7 to 9 out of operation
+    fn in_operation(&self) -> Result<bool, UcteError> {
+        match self.record.number::<u8>(ELEMENT_STATUS)?.unwrap_or(0) {
+            0..=2 => Ok(true),
+            7..=9 => Ok(false),
+            _ => Err(self.record.invalid(ELEMENT_STATUS)),
+        }
+    }
+
+    fn is_coupler(&self) -> Result<bool, UcteError> {
+        Ok(matches!(
+            self.record.number::<u8>(ELEMENT_STATUS)?,
+            Some(2) | Some(7)
+        ))
+    }
+}
+
+fn read_element(record: &Record) -> Result<ElementRecord, UcteError> {
+    let order = record.raw(ELEMENT_ORDER).chars().next().unwrap_or(' ');
+    if order == ' ' {
+        return Err(record.invalid(ELEMENT_ORDER));
+    }
+    Ok(ElementRecord {
+        record: Record {
+            line: record.line,
+            chars: record.chars.clone(),
+        },
+        node1: record.node(ELEMENT_NODE1)?,
+        node2: record.node(ELEMENT_NODE2)?,
+        order,
+    })
+}
+
+fn element_id(node1: &str, node2: &str, order: char) -> String {
+    format!("{} {} {}", node1, node2, order)
+}
+
+fn is_xnode(code: &str) -> bool {
+    code.starts_with('X')
+}
+
+/// Location part of a node code, naming its substation
+fn substation_code(code: &str) -> String {
+    code.chars().take(6).collect()
+}
+
+fn voltage_level_code(code: &str) -> String {
+    code.chars().take(7).collect()
+}
+
+fn nominal_voltage(code: &str) -> Option<f64> {
+    match code.chars().nth(6)? {
+        '0' => Some(750.0),
+        '1' => Some(380.0),
+        '2' => Some(220.0),
+        '3' => Some(150.0),
+        '4' => Some(120.0),
+        '5' => Some(110.0),
+        '6' => Some(70.0),
+        '7' => Some(27.0),
+        '8' => Some(330.0),
+        '9' => Some(500.0),
+        _ => None,
+    }
+}
+
+/// Voltage code of a nominal voltage, the closest one when it is not standard
+fn voltage_code(nominal_v: f64) -> char {
+    "0123456789"
+        .chars()
+        .min_by(|a, b| {
+            let distance = |code: &char| {
+                let voltage = nominal_voltage(&format!("      {}", code)).unwrap_or(0.0);
+                (voltage - nominal_v).abs()
+            };
+            distance(a).total_cmp(&distance(b))
+        })
+        .unwrap_or('1')
+}
+
+struct Node {
+    code: String,
+    country: String,
+    node_type: u8,
+    voltage: Option<f64>,
+    active_load: f64,
+    reactive_load: f64,
+    active_generation: f64,
+    reactive_generation: f64,
+    min_active_generation: Option<f64>,
+    max_active_generation: Option<f64>,
+    min_reactive_generation: Option<f64>,
+    max_reactive_generation: Option<f64>,
+    plant_type: Option<char>,
+}
+
+impl Node {
+    fn has_load(&self) -> bool {
+        self.active_load != 0.0 || self.reactive_load != 0.0
+    }
+
+    /// Nodes regulating their voltage, with a generation or with generation limits
+    fn has_generator(&self) -> bool {
+        self.node_type >= 2
+            || self.active_generation != 0.0
+            || self.reactive_generation != 0.0
+            || self.min_active_generation.is_some()
+            || self.max_active_generation.is_some()
+    }
+}
+
+#[derive(Default)]
+struct Document {
+    date: Option<NaiveDate>,
+    /// Country of the `##Z` block being read
+    country: String,
+    nodes: Vec<Node>,
+    lines: Vec<ElementRecord>,
+    transformers: Vec<ElementRecord>,
+    regulations: Vec<ElementRecord>,
+}
+
+/// Disjoint sets of substation codes, merged along the transformers
+#[derive(Default)]
+struct SubstationGroups {
+    parents: HashMap<String, String>,
+}
+
+impl SubstationGroups {
+    fn find(&mut self, code: &str) -> String {
+        let parent = self
+            .parents
+            .get(code)
+            .cloned()
+            .unwrap_or_else(|| code.to_string());
+        if parent == code {
+            return parent;
+        }
+        let root = self.find(&parent);
+        self.parents.insert(code.to_string(), root.clone());
+        root
+    }
+
+    /// The smallest code names the merged substation
+    fn union(&mut self, a: &str, b: &str) {
+        let (a, b) = (self.find(a), self.find(b));
+        let (root, child) = if a <= b { (a, b) } else { (b, a) };
+        self.parents.insert(child, root);
+    }
+}
+
+/// Bus and connectable bus of a branch end
+fn ends(node: &str, in_operation: bool) -> (String, String) {
+    let bus = if in_operation {
+        node.to_string()
+    } else {
+        String::new()
+    };
+    (bus, node.to_string())
+}
+
+fn current_limits(limit: Option<f64>) -> Option<CurrentLimits> {
+    limit
+        .filter(|limit| *limit > 0.0)
+        .map(|limit| CurrentLimits {
+            permanent_limit: limit,
+            temporary_limits: Vec::new(),
+        })
+}
+
+impl Document {
+    fn read_node(&mut self, record: &Record) -> Result<(), UcteError> {
+        let node_type = record.number(NODE_TYPE)?.unwrap_or(0);
+        if node_type > 3 {
+            return Err(record.invalid(NODE_TYPE));
+        }
+        record.number::<u8>(NODE_STATUS)?;
+        let code = record.node(NODE_CODE)?;
+        if !is_xnode(&code) && nominal_voltage(&code).is_none() {
+            return Err(record.invalid(NODE_CODE));
+        }
+        self.nodes.push(Node {
+            code,
+            country: self.country.clone(),
+            node_type,
+            voltage: record.number(NODE_VOLTAGE)?,
+            active_load: record.value(NODE_ACTIVE_LOAD)?,
+            reactive_load: record.value(NODE_REACTIVE_LOAD)?,
+            active_generation: record.value(NODE_ACTIVE_GENERATION)?,
+            reactive_generation: record.value(NODE_REACTIVE_GENERATION)?,
+            min_active_generation: record.number(NODE_MIN_ACTIVE_GENERATION)?,
+            max_active_generation: record.number(NODE_MAX_ACTIVE_GENERATION)?,
+            min_reactive_generation: record.number(NODE_MIN_REACTIVE_GENERATION)?,
+            max_reactive_generation: record.number(NODE_MAX_REACTIVE_GENERATION)?,
+            plant_type: record.raw(NODE_PLANT_TYPE).chars().find(|c| *c != ' '),
+        });
+        Ok(())
+    }
+
+    fn into_network(self) -> Result<Network, UcteError> {
+        let nodes: HashMap<&str, &Node> = self
+            .nodes
+            .iter()
+            .map(|node| (node.code.as_str(), node))
+            .collect();
+        let known = |element: &ElementRecord, code: &str| {
+            if nodes.contains_key(code) {
+                Ok(())
+            } else {
+                Err(UcteError::UnknownNode {
+                    line: element.record.line,
+                    node: code.to_string(),
+                })
+            }
+        };
+
+        let mut groups = SubstationGroups::default();
+        for transformer in &self.transformers {
+            known(transformer, &transformer.node1)?;
+            known(transformer, &transformer.node2)?;
+            groups.union(
+                &substation_code(&transformer.node1),
+                &substation_code(&transformer.node2),
+            );
+        }
+
+        // Voltage levels and their buses, by substation
+        let mut substations: BTreeMap<String, Substation> = BTreeMap::new();
+        for node in self.nodes.iter().filter(|node| !is_xnode(&node.code)) {
+            let substation_id = groups.find(&substation_code(&node.code));
+            let substation =
+                substations
+                    .entry(substation_id.clone())
+                    .or_insert_with(|| Substation {
+                        id: substation_id,
+                        country: node.country.clone(),
+                        tso: String::new(),
+                        geographical_tags: Vec::new(),
+                        voltage_levels: Vec::new(),
+                        two_windings_transformers: Vec::new(),
+                    });
+            let voltage_level_id = voltage_level_code(&node.code);
+            let position = match substation
+                .voltage_levels
+                .iter()
+                .position(|voltage_level| voltage_level.id == voltage_level_id)
+            {
+                Some(position) => position,
+                None => {
+                    substation.voltage_levels.push(VoltageLevel {
+                        id: voltage_level_id,
+                        nominal_v: nominal_voltage(&node.code).unwrap_or_default(),
+                        topology_kind: TopologyKind::BusBreaker,
+                        generators: None,
+                        loads: None,
+                        busbar_sections: None,
+                        node_breaker_topology: None,
+                        bus_breaker_topology: Some(BusBreakerTopology {
+                            buses: Vec::new(),
+                            switches: Vec::new(),
+                        }),
+                    });
+                    substation.voltage_levels.len() - 1
+                }
+            };
+            let voltage_level = &mut substation.voltage_levels[position];
+            if let Some(topology) = voltage_level.bus_breaker_topology.as_mut() {
+                topology.buses.push(Bus {
+                    id: node.code.clone(),
+                });
+            }
+            if node.has_generator() {
+                let generator = read_generator(node, voltage_level.nominal_v);
+                voltage_level
+                    .generators
+                    .get_or_insert_with(Vec::new)
+                    .push(generator);
+            }
+            if node.has_load() {
+                voltage_level.loads.get_or_insert_with(Vec::new).push(Load {
+                    id: format!("{}_load", node.code.trim_end()),
+                    load_type: LoadType::Undefined,
+                    p0: node.active_load,
+                    q0: node.reactive_load,
+                    bus: node.code.clone(),
+                    connectable_bus: node.code.clone(),
+                    exponential_model: None,
+                    zip_model: None,
+                });
+            }
+        }
+        let mut voltage_levels: HashMap<String, (String, usize)> = HashMap::new();
+        for substation in substations.values() {
+            for (position, voltage_level) in substation.voltage_levels.iter().enumerate() {
+                voltage_levels.insert(voltage_level.id.clone(), (substation.id.clone(), position));
+            }
+        }
+
+        let mut lines = Vec::new();
+        // Lines to each X-node, before they are paired into tie lines
+        let mut boundary_lines: BTreeMap<String, Vec<DanglingLine>> = BTreeMap::new();
+        for line in &self.lines {
+            known(line, &line.node1)?;
+            known(line, &line.node2)?;
+            let in_operation = line.in_operation()?;
+            let record = &line.record;
+
+            if line.is_coupler()? {
+                let voltage_level_id = voltage_level_code(&line.node1);
+                if voltage_level_id != voltage_level_code(&line.node2) {
+                    return Err(UcteError::CouplerAcrossVoltageLevels {
+                        line: record.line,
+                        id: line.id(),
+                    });
+                }
+                let (substation_id, position) = &voltage_levels[&voltage_level_id];
+                let voltage_level = substations
+                    .get_mut(substation_id)
+                    .map(|substation| &mut substation.voltage_levels[*position]);
+                if let Some(topology) =
+                    voltage_level.and_then(|level| level.bus_breaker_topology.as_mut())
+                {
+                    topology.switches.push(Switch {
+                        id: line.id(),
+                        kind: SwitchKind::Breaker,
+                        open: !in_operation,
+                        retained: false,
+                        bus1: line.node1.clone(),
+                        bus2: line.node2.clone(),
+                        voltage_level_id,
+                    });
+                }
+                continue;
+            }
+
+            let (r, x) = (record.value(LINE_R)?, record.value(LINE_X)?);
+            // Susceptances are given in µS for the whole line
+            let b = record.value(LINE_B)? * 1e-6;
+            let limits = current_limits(record.number(LINE_CURRENT_LIMIT)?);
+            match (is_xnode(&line.node1), is_xnode(&line.node2)) {
+                (false, false) => {
+                    let (bus1, connectable_bus1) = ends(&line.node1, in_operation);
+                    let (bus2, connectable_bus2) = ends(&line.node2, in_operation);
+                    lines.push(Line {
+                        id: line.id(),
+                        r,
+                        x,
+                        b1: b / 2.0,
+                        b2: b / 2.0,
+                        g1: 0.0,
+                        g2: 0.0,
+                        voltage_level_id1: voltage_level_code(&line.node1),
+                        bus1,
+                        connectable_bus1,
+                        voltage_level_id2: voltage_level_code(&line.node2),
+                        bus2,
+                        connectable_bus2,
+                        current_limits1: limits.clone(),
+                        current_limits2: limits,
+                    });
+                }
+                (true, true) => {
+                    return Err(UcteError::InvalidValue {
+                        line: record.line,
+                        field: "node 2",
+                        value: format!("{}, a line cannot join two X-nodes", line.node2),
+                    })
+                }
+                (xnode_first, _) => {
+                    let (xnode, node) = if xnode_first {
+                        (&line.node1, &line.node2)
+                    } else {
+                        (&line.node2, &line.node1)
+                    };
+                    let (bus, connectable_bus) = ends(node, in_operation);
+                    boundary_lines
+                        .entry(xnode.clone())
+                        .or_default()
+                        .push(DanglingLine {
+                            id: line.id(),
+                            p0: 0.0,
+                            q0: 0.0,
+                            r,
+                            x,
+                            g: 0.0,
+                            b,
+                            bus,
+                            connectable_bus,
+                        });
+                }
+            }
+        }
+
+        let mut dangling_lines = Vec::new();
+        let mut tie_lines = Vec::new();
+        for (xnode, mut halves) in boundary_lines {
+            if halves.len() == 2 {
+                let dangling_line2 = halves.pop().unwrap_or_else(|| unreachable!());
+                let dangling_line1 = halves.pop().unwrap_or_else(|| unreachable!());
+                tie_lines.push(TieLine {
+                    id: format!("{} + {}", dangling_line1.id, dangling_line2.id),
+                    name: String::new(),
+                    dangling_line1,
+                    dangling_line2,
+                    ucte_xnode_code: xnode,
+                });
+                continue;
+            }
+            // The power exchanged at the X-node, generation being negative
+            let node = nodes[xnode.as_str()];
+            for mut dangling_line in halves {
+                dangling_line.p0 = node.active_load + node.active_generation;
+                dangling_line.q0 = node.reactive_load + node.reactive_generation;
+                dangling_lines.push(dangling_line);
+            }
+        }
+
+        let mut transformers = HashMap::new();
+        for transformer in &self.transformers {
+            let (substation_id, _) = &voltage_levels[&voltage_level_code(&transformer.node1)];
+            transformers.insert(
+                transformer.id(),
+                (substation_id.clone(), read_transformer(transformer)?),
+            );
+        }
+        for regulation in &self.regulations {
+            let id = regulation.id();
+            let Some((_, transformer)) = transformers.get_mut(&id) else {
+                return Err(UcteError::UnknownTransformer {
+                    line: regulation.record.line,
+                    id,
+                });
+            };
+            read_regulation(regulation, transformer)?;
+        }
+        // Transformers keep the order of the document
+        for transformer in &self.transformers {
+            if let Some((substation_id, transformer)) = transformers.remove(&transformer.id()) {
+                if let Some(substation) = substations.get_mut(&substation_id) {
+                    substation.two_windings_transformers.push(transformer);
+                }
+            }
+        }
+
+        let case_date = self.date.unwrap_or_else(|| Utc::now().date_naive());
+        Ok(Network {
+            version: IIDM_VERSION.to_string(),
+            id: NETWORK_ID.to_string(),
+            case_date: Utc
+                .from_utc_datetime(&case_date.and_time(Default::default()))
+                .fixed_offset(),
+            forecast_distance: 0,
+            source_format: SOURCE_FORMAT.to_string(),
+            minimum_validation_level: ValidationLevel::default(),
+            substations: substations.into_values().collect(),
+            lines,
+            three_windings_transformers: Vec::new(),
+            switches: Vec::new(),
+            shunt_compensators: Vec::new(),
+            static_var_compensators: Vec::new(),
+            dangling_lines,
+            tie_lines,
+            hvdc_lines: Vec::new(),
+        })
+    }
+}
+
+/// Generation is negative in UCTE, so are its limits
+fn read_generator(node: &Node, nominal_v: f64) -> Generator {
+    let ordered = |a: f64, b: f64| if a <= b { (a, b) } else { (b, a) };
+    let (min_p, max_p) = ordered(
+        node.min_active_generation
+            .map_or(-DEFAULT_POWER_LIMIT, |limit| -limit),
+        node.max_active_generation
+            .map_or(DEFAULT_POWER_LIMIT, |limit| -limit),
+    );
+    let regulating = node.node_type >= 2 && node.voltage.is_some_and(|voltage| voltage > 0.0);
+    Generator {
+        id: format!("{}_generator", node.code.trim_end()),
+        energy_source: match node.plant_type {
+            Some('H') => EnergySource::Hydro,
+            Some('N') => EnergySource::Nuclear,
+            Some('W') => EnergySource::Wind,
+            Some('L' | 'C' | 'G' | 'O') => EnergySource::Thermal,
+            _ => EnergySource::Other,
+        },
+        min_p,
+        max_p,
+        voltage_regulator_on: regulating,
+        target_p: -node.active_generation,
+        target_v: match node.voltage {
+            Some(voltage) if regulating => voltage,
+            _ => nominal_v,
+        },
+        target_q: -node.reactive_generation,
+        bus: node.code.clone(),
+        connectable_bus: node.code.clone(),
+        reactive_capability_curve: None,
+        min_max_reactive_limits: node
+            .min_reactive_generation
+            .zip(node.max_reactive_generation)
+            .map(|(min, max)| {
+                let (min_q, max_q) = ordered(-min, -max);
+                MinMaxReactiveLimits { min_q, max_q }
+            }),
+    }
+}
+
+/// The node 2 is the regulated winding, on the side 1 of the transformer, while the impedances
+/// are given on the side of the node 1
+fn read_transformer(element: &ElementRecord) -> Result<TwoWindingsTransformer, UcteError> {
+    let record = &element.record;
+    let in_operation = element.in_operation()?;
+    record.number::<f64>(TRANSFORMER_NOMINAL_POWER)?;
+    let (bus1, connectable_bus1) = ends(&element.node2, in_operation);
+    let (bus2, connectable_bus2) = ends(&element.node1, in_operation);
+    Ok(TwoWindingsTransformer {
+        id: element.id(),
+        r: record.value(TRANSFORMER_R)?,
+        x: record.value(TRANSFORMER_X)?,
+        g: record.value(TRANSFORMER_G)? * 1e-6,
+        b: record.value(TRANSFORMER_B)? * 1e-6,
+        rated_u1: record.value(TRANSFORMER_RATED_U2)?,
+        rated_u2: record.value(TRANSFORMER_RATED_U1)?,
+        voltage_level_id1: voltage_level_code(&element.node2),
+        bus1,
+        connectable_bus1,
+        voltage_level_id2: voltage_level_code(&element.node1),
+        bus2,
+        connectable_bus2,
+        ratio_tap_changer: None,
+        phase_tap_changer: None,
+        current_limits1: None,
+        current_limits2: current_limits(record.number(TRANSFORMER_CURRENT_LIMIT)?),
+    })
+}
+
+fn read_regulation(
+    element: &ElementRecord,
+    transformer: &mut TwoWindingsTransformer,
+) -> Result<(), UcteError> {
+    let record = &element.record;
+    let terminal_ref = TerminalRef {
+        id: transformer.id.clone(),
+        side: Side::One,
+    };
+
+    if let (Some(step), Some(taps)) = (
+        record.number::<f64>(PHASE_STEP)?,
+        record.number::<i32>(PHASE_TAPS)?,
+    ) {
+        let voltage = record.number::<f64>(PHASE_VOLTAGE)?;
+        transformer.ratio_tap_changer = Some(RatioTapChanger {
+            regulating: voltage.is_some_and(|voltage| voltage > 0.0),
+            low_tap_position: -taps,
+            tap_position: record.number(PHASE_TAP)?.unwrap_or(0),
+            target_deadband: 0.0,
+            load_tap_changing_capabilities: true,
+            regulation_mode: RatioRegulationMode::Voltage,
+            regulation_value: voltage.unwrap_or(0.0),
+            terminal_ref: terminal_ref.clone(),
+            steps: (-taps..=taps)
+                .map(|tap| TapStep {
+                    r: 0.0,
+                    x: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    rho: 1.0 / (1.0 + f64::from(tap) * step / 100.0),
+                })
+                .collect(),
+        });
+    }
+
+    if let (Some(step), Some(taps)) = (
+        record.number::<f64>(ANGLE_STEP)?,
+        record.number::<i32>(ANGLE_TAPS)?,
+    ) {
+        let theta = record.value(ANGLE_THETA)?.to_radians();
+        let symmetrical = match record.text(ANGLE_TYPE).as_str() {
+            "SYMM" => true,
+            "ASYM" | "" => false,
+            _ => return Err(record.invalid(ANGLE_TYPE)),
+        };
+        let power = record.number::<f64>(ANGLE_POWER)?;
+        transformer.phase_tap_changer = Some(PhaseTapChanger {
+            regulating: power.is_some(),
+            low_tap_position: -taps,
+            tap_position: record.number(ANGLE_TAP)?.unwrap_or(0),
+            regulation_mode: match power {
+                Some(_) => PhaseRegulationMode::ActivePowerControl,
+                None => PhaseRegulationMode::FixedTap,
+            },
+            regulation_value: power.unwrap_or(0.0),
+            target_deadband: 0.0,
+            terminal_ref,
+            steps: (-taps..=taps)
+                .map(|tap| {
+                    let shift = f64::from(tap) * step / 100.0;
+                    let (rho, alpha) = if symmetrical {
+                        (1.0, 2.0 * (shift / 2.0).atan())
+                    } else {
+                        let (dx, dy) = (shift * theta.cos(), shift * theta.sin());
+                        (1.0 / dy.hypot(1.0 + dx), dy.atan2(1.0 + dx))
+                    };
+                    PhaseTapStep {
+                        r: 0.0,
+                        x: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        rho,
+                        alpha: alpha.to_degrees(),
+                    }
+                })
+                .collect(),
+        });
+    }
+    Ok(())
+}
+
+/// Record being written, its fields placed by column
+struct Fields {
+    chars: Vec<char>,
+}
+
+impl Fields {
+    fn new() -> Self {
+        Self { chars: Vec::new() }
+    }
+
+    fn text(mut self, column: Column, text: &str) -> Self {
+        if self.chars.len() < column.last {
+            self.chars.resize(column.last, ' ');
+        }
+        let width = column.last - column.first + 1;
+        for (offset, c) in text.chars().take(width).enumerate() {
+            self.chars[column.first - 1 + offset] = c;
+        }
+        self
+    }
+
+    /// Right-aligned number, with as many decimals as the columns allow
+    fn number(self, column: Column, value: f64, id: &str) -> Result<Self, UcteError> {
+        let width = column.last - column.first + 1;
+        let text = (0..=4)
+            .rev()
+            .map(|decimals| format!("{:.*}", decimals, value))
+            .find(|text| text.len() <= width)
+            .ok_or_else(|| UcteError::ValueTooWide {
+                id: id.to_string(),
+                field: column.name,
+                value,
+            })?;
+        Ok(self.text(column, &format!("{:>width$}", text, width = width)))
+    }
+
+    fn integer(self, column: Column, value: i32) -> Self {
+        let width = column.last - column.first + 1;
+        self.text(column, &format!("{:>width$}", value, width = width))
+    }
+
+    fn optional(self, column: Column, value: Option<f64>, id: &str) -> Result<Self, UcteError> {
+        match value {
+            Some(value) => self.number(column, value, id),
+            None => Ok(self),
+        }
+    }
+
+    fn element(self, node1: &str, node2: &str, order: char, status: u8) -> Self {
+        self.text(ELEMENT_NODE1, node1)
+            .text(ELEMENT_NODE2, node2)
+            .text(ELEMENT_ORDER, &order.to_string())
+            .text(ELEMENT_STATUS, &status.to_string())
+    }
+
+    fn build(self) -> String {
+        self.chars.into_iter().collect()
+    }
+}
+
+fn check_node_code(code: &str) -> Result<(), UcteError> {
+    if code.chars().count() == 8 && code.is_ascii() && !code.trim().is_empty() {
+        Ok(())
+    } else {
+        Err(UcteError::InvalidNodeCode(code.to_string()))
+    }
+}
+
+/// Injections and country of a node to write
+#[derive(Default)]
+struct NodeRecord {
+    country: String,
+    nominal_v: f64,
+    loads: Vec<(f64, f64)>,
+    generators: Vec<Generator>,
+}
+
+/// Branch end, the connectable bus of a disconnected one
+fn end_node<'a>(bus: &'a str, connectable_bus: &'a str) -> &'a str {
+    if bus.is_empty() {
+        connectable_bus
+    } else {
+        bus
+    }
+}
+
+struct Writer<'a> {
+    network: &'a Network,
+    /// Order codes already given to each pair of nodes
+    orders: HashMap<(String, String), Vec<char>>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(network: &'a Network) -> Self {
+        Self {
+            network,
+            orders: HashMap::new(),
+        }
+    }
+
+    /// Order code found in an element ID following the UCTE naming, or the next free one
+    fn order(&mut self, id: &str, node1: &str, node2: &str) -> char {
+        let used = self
+            .orders
+            .entry((node1.to_string(), node2.to_string()))
+            .or_default();
+        let chars: Vec<char> = id.chars().collect();
+        let named = (chars.len() == 19
+            && id.starts_with(node1)
+            && chars[9..17].iter().copied().eq(node2.chars()))
+        .then(|| chars[18])
+        .filter(|order| *order != ' ' && !used.contains(order));
+        let order = named.unwrap_or_else(|| {
+            "123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ"
+                .chars()
+                .find(|order| !used.contains(order))
+                .unwrap_or('0')
+        });
+        used.push(order);
+        order
+    }
+
+    fn write(mut self) -> Result<Vec<String>, UcteError> {
+        let network = self.network;
+        let mut nodes: BTreeMap<String, NodeRecord> = BTreeMap::new();
+        let mut couplers = Vec::new();
+        for substation in &network.substations {
+            for voltage_level in &substation.voltage_levels {
+                let Some(topology) = &voltage_level.bus_breaker_topology else {
+                    continue;
+                };
+                for bus in &topology.buses {
+                    check_node_code(&bus.id)?;
+                    nodes.insert(
+                        bus.id.clone(),
+                        NodeRecord {
+                            country: substation.country.clone(),
+                            nominal_v: voltage_level.nominal_v,
+                            ..Default::default()
+                        },
+                    );
+                }
+                couplers.extend(&topology.switches);
+                for load in voltage_level.loads.iter().flatten() {
+                    let node = end_node(&load.bus, &load.connectable_bus);
+                    if let Some(record) = nodes.get_mut(node) {
+                        record.loads.push((load.p0, load.q0));
+                    }
+                }
+                for generator in voltage_level.generators.iter().flatten() {
+                    let node = end_node(&generator.bus, &generator.connectable_bus);
+                    if let Some(record) = nodes.get_mut(node) {
+                        record.generators.push(generator.clone());
+                    }
+                }
+            }
+        }
+
+        let mut line_records = Vec::new();
+        for coupler in couplers {
+            let order = self.order(&coupler.id, &coupler.bus1, &coupler.bus2);
+            let status = if coupler.open { 7 } else { 2 };
+            line_records.push(
+                Fields::new()
+                    .element(&coupler.bus1, &coupler.bus2, order, status)
+                    .build(),
+            );
+        }
+        for line in &network.lines {
+            let node1 = end_node(&line.bus1, &line.connectable_bus1);
+            let node2 = end_node(&line.bus2, &line.connectable_bus2);
+            check_node_code(node1)?;
+            check_node_code(node2)?;
+            let status = if line.bus1.is_empty() || line.bus2.is_empty() {
+                8
+            } else {
+                0
+            };
+            let order = self.order(&line.id, node1, node2);
+            let limit = line
+                .current_limits1
+                .as_ref()
+                .or(line.current_limits2.as_ref());
+            line_records.push(write_line(
+                &line.id,
+                Fields::new().element(node1, node2, order, status),
+                (line.r, line.x, line.b1 + line.b2),
+                limit.map(|limits| limits.permanent_limit),
+            )?);
+        }
+
+        // X-nodes and the lines leading to them
+        let mut xnodes: BTreeMap<String, (f64, f64)> = BTreeMap::new();
+        let mut boundary = Vec::new();
+        for (index, dangling_line) in network.dangling_lines.iter().enumerate() {
+            let xnode = self.xnode_code(&dangling_line.id, dangling_line, index, &nodes);
+            xnodes.insert(xnode.clone(), (dangling_line.p0, dangling_line.q0));
+            boundary.push((dangling_line, xnode));
+        }
+        for (index, tie_line) in network.tie_lines.iter().enumerate() {
+            let xnode = if check_node_code(&tie_line.ucte_xnode_code).is_ok()
+                && is_xnode(&tie_line.ucte_xnode_code)
+            {
+                tie_line.ucte_xnode_code.clone()
+            } else {
+                let index = network.dangling_lines.len() + index;
+                self.xnode_code("", &tie_line.dangling_line1, index, &nodes)
+            };
+            xnodes.insert(xnode.clone(), (0.0, 0.0));
+            boundary.push((&tie_line.dangling_line1, xnode.clone()));
+            boundary.push((&tie_line.dangling_line2, xnode));
+        }
+        for (dangling_line, xnode) in boundary {
+            let node = end_node(&dangling_line.bus, &dangling_line.connectable_bus);
+            check_node_code(node)?;
+            // Lines keep the direction given by their UCTE ID
+            let (node1, node2) = if dangling_line.id.starts_with(xnode.as_str()) {
+                (xnode.as_str(), node)
+            } else {
+                (node, xnode.as_str())
+            };
+            let status = if dangling_line.bus.is_empty() { 8 } else { 0 };
+            let order = self.order(&dangling_line.id, node1, node2);
+            line_records.push(write_line(
+                &dangling_line.id,
+                Fields::new().element(node1, node2, order, status),
+                (dangling_line.r, dangling_line.x, dangling_line.b),
+                None,
+            )?);
+        }
+
+        let mut transformer_records = Vec::new();
+        let mut regulation_records = Vec::new();
+        for transformer in network
+            .substations
+            .iter()
+            .flat_map(|substation| &substation.two_windings_transformers)
+        {
+            let node2 = end_node(&transformer.bus1, &transformer.connectable_bus1);
+            let node1 = end_node(&transformer.bus2, &transformer.connectable_bus2);
+            check_node_code(node1)?;
+            check_node_code(node2)?;
+            let status = if transformer.bus1.is_empty() || transformer.bus2.is_empty() {
+                8
+            } else {
+                0
+            };
+            let order = self.order(&transformer.id, node1, node2);
+            let id = &transformer.id;
+            let limit = transformer
+                .current_limits2
+                .as_ref()
+                .or(transformer.current_limits1.as_ref());
+            transformer_records.push(
+                Fields::new()
+                    .element(node1, node2, order, status)
+                    .number(TRANSFORMER_RATED_U1, transformer.rated_u2, id)?
+                    .number(TRANSFORMER_RATED_U2, transformer.rated_u1, id)?
+                    .number(TRANSFORMER_R, transformer.r, id)?
+                    .number(TRANSFORMER_X, transformer.x, id)?
+                    .number(TRANSFORMER_B, transformer.b * 1e6, id)?
+                    .number(TRANSFORMER_G, transformer.g * 1e6, id)?
+                    .optional(
+                        TRANSFORMER_CURRENT_LIMIT,
+                        limit.map(|limits| limits.permanent_limit),
+                        id,
+                    )?
+                    .build(),
+            );
+            if transformer.ratio_tap_changer.is_some() || transformer.phase_tap_changer.is_some() {
+                let line = Fields::new()
+                    .element(node1, node2, order, 0)
+                    .text(ELEMENT_STATUS, " ");
+                regulation_records.push(write_regulation(transformer, line)?);
+            }
+        }
+
+        let date = network.case_date.format(DATE_FORMAT);
+        let mut output = vec![format!("##C {}", date), "##N".to_string()];
+        let mut by_country: BTreeMap<&str, Vec<(&String, &NodeRecord)>> = BTreeMap::new();
+        for (code, record) in &nodes {
+            by_country
+                .entry(record.country.as_str())
+                .or_default()
+                .push((code, record));
+        }
+        for (country, records) in by_country {
+            output.push(format!("##Z{}", country));
+            for (code, record) in records {
+                output.push(write_node(code, record)?);
+            }
+        }
+        if !xnodes.is_empty() {
+            output.push("##ZXX".to_string());
+            for (code, (p0, q0)) in &xnodes {
+                output.push(
+                    Fields::new()
+                        .text(NODE_CODE, code)
+                        .text(NODE_STATUS, "0")
+                        .text(NODE_TYPE, "0")
+                        .number(NODE_ACTIVE_LOAD, *p0, code)?
+                        .number(NODE_REACTIVE_LOAD, *q0, code)?
+                        .build(),
+                );
+            }
+        }
+        output.push("##L".to_string());
+        output.extend(line_records);
+        output.push("##T".to_string());
+        output.extend(transformer_records);
+        output.push("##R".to_string());
+        output.extend(regulation_records);
+        Ok(output)
+    }
+
+    /// X-node named in a dangling line ID following the UCTE naming, or a generated one
+    fn xnode_code(
+        &self,
+        id: &str,
+        dangling_line: &DanglingLine,
+        index: usize,
+        nodes: &BTreeMap<String, NodeRecord>,
+    ) -> String {
+        let chars: Vec<char> = id.chars().collect();
+        if chars.len() == 19 {
+            for range in [0..8, 9..17] {
+                let code: String = chars[range].iter().collect();
+                if is_xnode(&code) {
+                    return code;
+                }
+            }
+        }
+        let node = end_node(&dangling_line.bus, &dangling_line.connectable_bus);
+        let voltage = nodes
+            .get(node)
+            .map_or('1', |record| voltage_code(record.nominal_v));
+        format!("X{:0>6}{}", index + 1, voltage)
+    }
+}
+
+fn write_line(
+    id: &str,
+    line: Fields,
+    (r, x, b): (f64, f64, f64),
+    current_limit: Option<f64>,
+) -> Result<String, UcteError> {
+    Ok(line
+        .number(LINE_R, r, id)?
+        .number(LINE_X, x, id)?
+        .number(LINE_B, b * 1e6, id)?
+        .optional(LINE_CURRENT_LIMIT, current_limit, id)?
+        .build())
+}
+
+fn write_node(code: &str, record: &NodeRecord) -> Result<String, UcteError> {
+    let (p_load, q_load) = record
+        .loads
+        .iter()
+        .fold((0.0, 0.0), |(p, q), (p0, q0)| (p + p0, q + q0));
+    let mut line = Fields::new()
+        .text(NODE_CODE, code)
+        .text(NODE_STATUS, "0")
+        .number(NODE_ACTIVE_LOAD, p_load, code)?
+        .number(NODE_REACTIVE_LOAD, q_load, code)?;
+
+    let generators = &record.generators;
+    if generators.is_empty() {
+        return Ok(line.text(NODE_TYPE, "0").build());
+    }
+    let sum = |value: fn(&Generator) -> f64| generators.iter().map(value).sum::<f64>();
+    let regulating = generators
+        .iter()
+        .find(|generator| generator.voltage_regulator_on);
+    line = line
+        .text(NODE_TYPE, if regulating.is_some() { "2" } else { "0" })
+        .optional(
+            NODE_VOLTAGE,
+            regulating.map(|generator| generator.target_v),
+            code,
+        )?
+        .number(NODE_ACTIVE_GENERATION, -sum(|g| g.target_p), code)?
+        .number(NODE_REACTIVE_GENERATION, -sum(|g| g.target_q), code)?
+        .number(NODE_MIN_ACTIVE_GENERATION, -sum(|g| g.min_p), code)?
+        .number(NODE_MAX_ACTIVE_GENERATION, -sum(|g| g.max_p), code)?;
+    let limits: Option<Vec<_>> = generators
+        .iter()
+        .map(|generator| generator.min_max_reactive_limits.as_ref())
+        .collect();
+    if let Some(limits) = limits {
+        let min_q: f64 = limits.iter().map(|limits| limits.min_q).sum();
+        let max_q: f64 = limits.iter().map(|limits| limits.max_q).sum();
+        line = line
+            .number(NODE_MIN_REACTIVE_GENERATION, -min_q, code)?
+            .number(NODE_MAX_REACTIVE_GENERATION, -max_q, code)?;
+    }
+    let plant_type = match generators[0].energy_source {
+        EnergySource::Hydro => "H",
+        EnergySource::Nuclear => "N",
+        EnergySource::Wind => "W",
+        EnergySource::Thermal => "C",
+        EnergySource::Solar | EnergySource::Other => "F",
+    };
+    Ok(line.text(NODE_PLANT_TYPE, plant_type).build())
+}
+
+/// Regulations are rebuilt from the first and last steps, the steps in between being evenly
+/// spaced in UCTE
+fn write_regulation(
+    transformer: &TwoWindingsTransformer,
+    mut line: Fields,
+) -> Result<String, UcteError> {
+    let id = &transformer.id;
+    if let Some(tap_changer) = &transformer.ratio_tap_changer {
+        let taps = -tap_changer.low_tap_position;
+        let step = match tap_changer.steps.last() {
+            Some(last) if taps > 0 => (1.0 / last.rho - 1.0) * 100.0 / f64::from(taps),
+            _ => 0.0,
+        };
+        line = line
+            .number(PHASE_STEP, step, id)?
+            .integer(PHASE_TAPS, taps)
+            .integer(PHASE_TAP, tap_changer.tap_position)
+            .optional(
+                PHASE_VOLTAGE,
+                tap_changer
+                    .regulating
+                    .then_some(tap_changer.regulation_value),
+                id,
+            )?;
+    }
+    if let Some(tap_changer) = &transformer.phase_tap_changer {
+        let taps = -tap_changer.low_tap_position;
+        let symmetrical = tap_changer.steps.iter().all(|step| step.rho == 1.0);
+        let (step, theta) = match tap_changer.steps.last() {
+            Some(last) if taps > 0 => {
+                let alpha = last.alpha.to_radians();
+                if symmetrical {
+                    (2.0 * (alpha / 2.0).tan() * 100.0 / f64::from(taps), 90.0)
+                } else {
+                    let (dx, dy) = (alpha.cos() / last.rho - 1.0, alpha.sin() / last.rho);
+                    (
+                        dx.hypot(dy) * 100.0 / f64::from(taps),
+                        dy.atan2(dx).to_degrees(),
+                    )
+                }
+            }
+            _ => (0.0, 90.0),
+        };
+        line = line
+            .number(ANGLE_STEP, step, id)?
+            .number(ANGLE_THETA, theta, id)?
+            .integer(ANGLE_TAPS, taps)
+            .integer(ANGLE_TAP, tap_changer.tap_position)
+            .optional(
+                ANGLE_POWER,
+                tap_changer
+                    .regulating
+                    .then_some(tap_changer.regulation_value),
+                id,
+            )?
+            .text(ANGLE_TYPE, if symmetrical { "SYMM" } else { "ASYM" });
+    }
+    Ok(line.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_columns() {
+        let record = Record::new(7, "BNODE1 1 Name         0 2 400.00");
+        // Node codes keep their inner and trailing spaces
+        assert_eq!(record.node(NODE_CODE).unwrap(), "BNODE1 1");
+        assert_eq!(record.number::<u8>(NODE_TYPE).unwrap(), Some(2));
+        assert_eq!(record.value(NODE_VOLTAGE).unwrap(), 400.0);
+        // Past the end of the line, fields are blank
+        assert_eq!(record.number::<f64>(NODE_ACTIVE_LOAD).unwrap(), None);
+        assert!(matches!(
+            Record::new(7, "BNODE111              0 a").number::<u8>(NODE_TYPE),
+            Err(UcteError::InvalidValue { line: 7, .. })
+        ));
+    }
+
+    #[test]
+    fn test_numbers_fit_their_columns() {
+        let line = Fields::new()
+            .number(LINE_R, 1.2, "L")
+            .unwrap()
+            .number(LINE_X, -123.456789, "L")
+            .unwrap()
+            .build();
+        assert_eq!(line, "                      1.2000 -123.5");
+        assert!(matches!(
+            Fields::new().number(LINE_R, 12345678.0, "L"),
+            Err(UcteError::ValueTooWide {
+                field: "resistance",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_voltage_codes() {
+        assert_eq!(nominal_voltage("BNODE111"), Some(380.0));
+        assert_eq!(nominal_voltage("BNODE1X1"), None);
+        assert_eq!(voltage_code(400.0), '1');
+        assert_eq!(voltage_code(225.0), '2');
+    }
+
+    #[test]
+    fn test_substations_merged_by_transformers() {
+        let mut groups = SubstationGroups::default();
+        groups.union("FNODE2", "FNODE3");
+        groups.union("FNODE3", "FNODE1");
+        assert_eq!(groups.find("FNODE2"), "FNODE1");
+        assert_eq!(groups.find("FNODE4"), "FNODE4");
+    }
+}
@@ -0,0 +1,1324 @@
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use chrono::{DateTime, SecondsFormat};
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+use crate::entities::*;
+use crate::libs::xml::{read_document, write_document, Element, XmlError};
+
+const NAMESPACE_BASE: &str = "http://www.powsybl.org/schema/iidm/";
+const PREFIX: &str = "iidm";
+const DEFAULT_VERSION: &str = "1.12";
+
+#[derive(Debug, Error)]
+pub enum XiidmError {
+    #[error("{0}")]
+    Xml(#[from] XmlError),
+
+    #[error("Unexpected root element '{0}', expected 'network'")]
+    UnexpectedRoot(String),
+
+    #[error("Missing attribute '{attribute}' on element '{element}'")]
+    MissingAttribute { element: String, attribute: String },
+
+    #[error("Invalid value '{value}' for attribute '{attribute}' on element '{element}'")]
+    InvalidAttribute {
+        element: String,
+        attribute: String,
+        value: String,
+    },
+
+    #[error("Missing child element '{child}' in element '{element}'")]
+    MissingChild { element: String, child: String },
+
+    #[error("Date parsing error: {0}")]
+    DateParse(#[from] chrono::ParseError),
+}
+
+/// Reads a network from an XIIDM document
+pub fn from_str(xml: &str) -> Result<Network, XiidmError> {
+    from_reader(xml.as_bytes())
+}
+
+/// Reads a network from any buffered XIIDM source
+pub fn from_reader<R: BufRead>(input: R) -> Result<Network, XiidmError> {
+    let root = read_document(input)?;
+    read_network(&root)
+}
+
+/// Writes a network as an XIIDM document
+pub fn to_string(network: &Network) -> Result<String, XiidmError> {
+    let mut output = Vec::new();
+    to_writer(network, &mut output)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Writes a network as an XIIDM document into any writer
+pub fn to_writer<W: Write>(network: &Network, output: W) -> Result<(), XiidmError> {
+    write_document(&write_network(network), PREFIX, output)?;
+    Ok(())
+}
+
+// Attribute helpers
+
+fn required<'a>(element: &'a Element, attribute: &str) -> Result<&'a str, XiidmError> {
+    element
+        .get(attribute)
+        .ok_or_else(|| XiidmError::MissingAttribute {
+            element: element.name.clone(),
+            attribute: attribute.to_string(),
+        })
+}
+
+fn string(element: &Element, attribute: &str) -> Result<String, XiidmError> {
+    required(element, attribute).map(str::to_string)
+}
+
+fn optional_string(element: &Element, attribute: &str) -> String {
+    element.get(attribute).unwrap_or_default().to_string()
+}
+
+/// Bus of a terminal, or its node in a node-breaker voltage level kept as its string form like
+/// the nodes of the switches
+fn bus_or_node(element: &Element, bus: &str, node: &str) -> String {
+    element
+        .get(bus)
+        .or_else(|| element.get(node))
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn number<T: FromStr>(element: &Element, attribute: &str) -> Result<T, XiidmError> {
+    let value = required(element, attribute)?;
+    value
+        .parse()
+        .map_err(|_| invalid(element, attribute, value))
+}
+
+/// Parses an enum through its serde representation so XML and JSON share the same names
+fn enumeration<T: DeserializeOwned>(element: &Element, attribute: &str) -> Result<T, XiidmError> {
+    let value = required(element, attribute)?;
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| invalid(element, attribute, value))
+}
+
+fn enum_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn invalid(element: &Element, attribute: &str, value: &str) -> XiidmError {
+    XiidmError::InvalidAttribute {
+        element: element.name.clone(),
+        attribute: attribute.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn child<'a>(element: &'a Element, name: &str) -> Result<&'a Element, XiidmError> {
+    element.first(name).ok_or_else(|| XiidmError::MissingChild {
+        element: element.name.clone(),
+        child: name.to_string(),
+    })
+}
+
+fn collect<T>(
+    element: &Element,
+    name: &str,
+    read: fn(&Element) -> Result<T, XiidmError>,
+) -> Result<Vec<T>, XiidmError> {
+    element.all(name).map(read).collect()
+}
+
+fn optional<T>(
+    element: &Element,
+    name: &str,
+    read: fn(&Element) -> Result<T, XiidmError>,
+) -> Result<Option<T>, XiidmError> {
+    element.first(name).map(read).transpose()
+}
+
+fn version_from_namespace(root: &Element) -> String {
+    root.attributes
+        .iter()
+        .filter(|(key, _)| key == "xmlns" || key.starts_with("xmlns:"))
+        .find_map(|(_, value)| value.strip_prefix(NAMESPACE_BASE))
+        .map(|version| version.replace('_', "."))
+        .unwrap_or_else(|| DEFAULT_VERSION.to_string())
+}
+
+// Readers
+
+fn read_network(root: &Element) -> Result<Network, XiidmError> {
+    if root.name != "network" {
+        return Err(XiidmError::UnexpectedRoot(root.name.clone()));
+    }
+
+    Ok(Network {
+        version: version_from_namespace(root),
+        id: string(root, "id")?,
+        case_date: DateTime::parse_from_rfc3339(required(root, "caseDate")?)?,
+        forecast_distance: number(root, "forecastDistance")?,
+        source_format: string(root, "sourceFormat")?,
+        minimum_validation_level: match root.get("minimumValidationLevel") {
+            Some(_) => enumeration(root, "minimumValidationLevel")?,
+            None => ValidationLevel::default(),
+        },
+        substations: collect(root, "substation", read_substation)?,
+        lines: collect(root, "line", read_line)?,
+        three_windings_transformers: collect(
+            root,
+            "threeWindingsTransformer",
+            read_three_windings_transformer,
+        )?,
+        switches: collect(root, "switch", read_switch)?,
+        shunt_compensators: collect(root, "shunt", read_shunt_compensator)?,
+        static_var_compensators: collect(
+            root,
+            "staticVarCompensator",
+            read_static_var_compensator,
+        )?,
+        dangling_lines: collect(root, "danglingLine", read_dangling_line)?,
+        tie_lines: collect(root, "tieLine", read_tie_line)?,
+        hvdc_lines: collect(root, "hvdcLine", read_hvdc_line)?,
+    })
+}
+
+fn read_substation(element: &Element) -> Result<Substation, XiidmError> {
+    Ok(Substation {
+        id: string(element, "id")?,
+        country: optional_string(element, "country"),
+        tso: optional_string(element, "tso"),
+        geographical_tags: element
+            .get("geographicalTags")
+            .map(|tags| {
+                tags.split(',')
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        voltage_levels: collect(element, "voltageLevel", read_voltage_level)?,
+        two_windings_transformers: collect(
+            element,
+            "twoWindingsTransformer",
+            read_two_windings_transformer,
+        )?,
+    })
+}
+
+fn optional_collect<T>(
+    element: &Element,
+    name: &str,
+    read: fn(&Element) -> Result<T, XiidmError>,
+) -> Result<Option<Vec<T>>, XiidmError> {
+    let items = collect(element, name, read)?;
+    Ok((!items.is_empty()).then_some(items))
+}
+
+fn read_voltage_level(element: &Element) -> Result<VoltageLevel, XiidmError> {
+    Ok(VoltageLevel {
+        id: string(element, "id")?,
+        nominal_v: number(element, "nominalV")?,
+        topology_kind: enumeration(element, "topologyKind")?,
+        generators: optional_collect(element, "generator", read_generator)?,
+        loads: optional_collect(element, "load", read_load)?,
+        busbar_sections: optional_collect(element, "busbarSection", read_busbar_section)?,
+        node_breaker_topology: optional(
+            element,
+            "nodeBreakerTopology",
+            read_node_breaker_topology,
+        )?,
+        bus_breaker_topology: optional(element, "busBreakerTopology", read_bus_breaker_topology)?,
+    })
+}
+
+fn read_generator(element: &Element) -> Result<Generator, XiidmError> {
+    Ok(Generator {
+        id: string(element, "id")?,
+        energy_source: enumeration(element, "energySource")?,
+        min_p: number(element, "minP")?,
+        max_p: number(element, "maxP")?,
+        voltage_regulator_on: number(element, "voltageRegulatorOn")?,
+        target_p: number(element, "targetP")?,
+        target_v: number(element, "targetV")?,
+        target_q: number(element, "targetQ")?,
+        bus: bus_or_node(element, "bus", "node"),
+        connectable_bus: bus_or_node(element, "connectableBus", "node"),
+        reactive_capability_curve: optional(
+            element,
+            "reactiveCapabilityCurve",
+            read_reactive_capability_curve,
+        )?,
+        min_max_reactive_limits: optional(
+            element,
+            "minMaxReactiveLimits",
+            read_min_max_reactive_limits,
+        )?,
+    })
+}
+
+fn read_reactive_capability_curve(
+    element: &Element,
+) -> Result<ReactiveCapabilityCurve, XiidmError> {
+    Ok(ReactiveCapabilityCurve {
+        points: collect(element, "point", |point| {
+            Ok(ReactiveCapabilityCurvePoint {
+                p: number(point, "p")?,
+                min_q: number(point, "minQ")?,
+                max_q: number(point, "maxQ")?,
+            })
+        })?,
+    })
+}
+
+fn read_min_max_reactive_limits(element: &Element) -> Result<MinMaxReactiveLimits, XiidmError> {
+    Ok(MinMaxReactiveLimits {
+        min_q: number(element, "minQ")?,
+        max_q: number(element, "maxQ")?,
+    })
+}
+
+fn read_load(element: &Element) -> Result<Load, XiidmError> {
+    Ok(Load {
+        id: string(element, "id")?,
+        load_type: enumeration(element, "loadType")?,
+        p0: number(element, "p0")?,
+        q0: number(element, "q0")?,
+        bus: bus_or_node(element, "bus", "node"),
+        connectable_bus: bus_or_node(element, "connectableBus", "node"),
+        exponential_model: optional(element, "exponentialModel", |model| {
+            Ok(ExponentialLoadModel {
+                p0: number(model, "p0")?,
+                q0: number(model, "q0")?,
+                np: number(model, "np")?,
+                nq: number(model, "nq")?,
+            })
+        })?,
+        zip_model: optional(element, "zipModel", |model| {
+            Ok(ZipLoadModel {
+                p0: number(model, "p0")?,
+                q0: number(model, "q0")?,
+                z_p: number(model, "zP")?,
+                z_q: number(model, "zQ")?,
+                i_p: number(model, "iP")?,
+                i_q: number(model, "iQ")?,
+                p_p: number(model, "pP")?,
+                p_q: number(model, "pQ")?,
+            })
+        })?,
+    })
+}
+
+fn read_busbar_section(element: &Element) -> Result<BusbarSection, XiidmError> {
+    Ok(BusbarSection {
+        id: string(element, "id")?,
+        name: optional_string(element, "name"),
+        bus: bus_or_node(element, "bus", "node"),
+        connectable_bus: bus_or_node(element, "connectableBus", "node"),
+    })
+}
+
+fn read_bus_breaker_topology(element: &Element) -> Result<BusBreakerTopology, XiidmError> {
+    Ok(BusBreakerTopology {
+        buses: collect(element, "bus", |bus| {
+            Ok(Bus {
+                id: string(bus, "id")?,
+            })
+        })?,
+        switches: collect(element, "switch", read_switch)?,
+    })
+}
+
+fn read_node_breaker_topology(element: &Element) -> Result<NodeBreakerTopology, XiidmError> {
+    Ok(NodeBreakerTopology {
+        nodes: collect(element, "node", |node| {
+            Ok(Node {
+                id: number(node, "id")?,
+            })
+        })?,
+        switches: collect(element, "switch", read_node_switch)?,
+        internal_connections: collect(element, "internalConnection", |connection| {
+            Ok(InternalConnection {
+                node1: number(connection, "node1")?,
+                node2: number(connection, "node2")?,
+            })
+        })?,
+    })
+}
+
+fn read_switch(element: &Element) -> Result<Switch, XiidmError> {
+    Ok(Switch {
+        id: string(element, "id")?,
+        kind: enumeration(element, "kind")?,
+        open: number(element, "open")?,
+        retained: element
+            .get("retained")
+            .map(|_| number(element, "retained"))
+            .transpose()?
+            .unwrap_or(false),
+        bus1: optional_string(element, "bus1"),
+        bus2: optional_string(element, "bus2"),
+        voltage_level_id: optional_string(element, "voltageLevelId"),
+    })
+}
+
+/// Node-breaker switches connect nodes, kept as their string form in `bus1` and `bus2`
+fn read_node_switch(element: &Element) -> Result<Switch, XiidmError> {
+    let mut switch = read_switch(element)?;
+    if switch.bus1.is_empty() {
+        switch.bus1 = optional_string(element, "node1");
+    }
+    if switch.bus2.is_empty() {
+        switch.bus2 = optional_string(element, "node2");
+    }
+    Ok(switch)
+}
+
+fn read_current_limits(element: &Element) -> Result<CurrentLimits, XiidmError> {
+    Ok(CurrentLimits {
+        permanent_limit: number(element, "permanentLimit")?,
+        temporary_limits: collect(element, "temporaryLimit", |limit| {
+            Ok(TemporaryLimit {
+                name: string(limit, "name")?,
+                acceptable_duration: number(limit, "acceptableDuration")?,
+                value: number(limit, "value")?,
+            })
+        })?,
+    })
+}
+
+fn read_terminal_ref(element: &Element) -> Result<TerminalRef, XiidmError> {
+    let terminal = child(element, "terminalRef")?;
+    Ok(TerminalRef {
+        id: string(terminal, "id")?,
+        side: enumeration(terminal, "side")?,
+    })
+}
+
+fn read_ratio_tap_changer(element: &Element) -> Result<RatioTapChanger, XiidmError> {
+    Ok(RatioTapChanger {
+        regulating: number(element, "regulating")?,
+        low_tap_position: number(element, "lowTapPosition")?,
+        tap_position: number(element, "tapPosition")?,
+        target_deadband: number(element, "targetDeadband")?,
+        load_tap_changing_capabilities: number(element, "loadTapChangingCapabilities")?,
+        regulation_mode: enumeration(element, "regulationMode")?,
+        regulation_value: number(element, "regulationValue")?,
+        terminal_ref: read_terminal_ref(element)?,
+        steps: collect(element, "step", |step| {
+            Ok(TapStep {
+                r: number(step, "r")?,
+                x: number(step, "x")?,
+                g: number(step, "g")?,
+                b: number(step, "b")?,
+                rho: number(step, "rho")?,
+            })
+        })?,
+    })
+}
+
+fn read_phase_tap_changer(element: &Element) -> Result<PhaseTapChanger, XiidmError> {
+    Ok(PhaseTapChanger {
+        regulating: number(element, "regulating")?,
+        low_tap_position: number(element, "lowTapPosition")?,
+        tap_position: number(element, "tapPosition")?,
+        regulation_mode: enumeration(element, "regulationMode")?,
+        regulation_value: number(element, "regulationValue")?,
+        target_deadband: number(element, "targetDeadband")?,
+        terminal_ref: read_terminal_ref(element)?,
+        steps: collect(element, "step", |step| {
+            Ok(PhaseTapStep {
+                r: number(step, "r")?,
+                x: number(step, "x")?,
+                g: number(step, "g")?,
+                b: number(step, "b")?,
+                rho: number(step, "rho")?,
+                alpha: number(step, "alpha")?,
+            })
+        })?,
+    })
+}
+
+fn read_two_windings_transformer(element: &Element) -> Result<TwoWindingsTransformer, XiidmError> {
+    Ok(TwoWindingsTransformer {
+        id: string(element, "id")?,
+        r: number(element, "r")?,
+        x: number(element, "x")?,
+        g: number(element, "g")?,
+        b: number(element, "b")?,
+        rated_u1: number(element, "ratedU1")?,
+        rated_u2: number(element, "ratedU2")?,
+        voltage_level_id1: string(element, "voltageLevelId1")?,
+        bus1: bus_or_node(element, "bus1", "node1"),
+        connectable_bus1: bus_or_node(element, "connectableBus1", "node1"),
+        voltage_level_id2: string(element, "voltageLevelId2")?,
+        bus2: bus_or_node(element, "bus2", "node2"),
+        connectable_bus2: bus_or_node(element, "connectableBus2", "node2"),
+        ratio_tap_changer: optional(element, "ratioTapChanger", read_ratio_tap_changer)?,
+        phase_tap_changer: optional(element, "phaseTapChanger", read_phase_tap_changer)?,
+        current_limits1: optional(element, "currentLimits1", read_current_limits)?,
+        current_limits2: optional(element, "currentLimits2", read_current_limits)?,
+    })
+}
+
+fn read_three_windings_transformer(
+    element: &Element,
+) -> Result<ThreeWindingsTransformer, XiidmError> {
+    Ok(ThreeWindingsTransformer {
+        id: string(element, "id")?,
+        r1: number(element, "r1")?,
+        x1: number(element, "x1")?,
+        g1: number(element, "g1")?,
+        b1: number(element, "b1")?,
+        rated_u1: number(element, "ratedU1")?,
+        r2: number(element, "r2")?,
+        x2: number(element, "x2")?,
+        g2: number(element, "g2")?,
+        b2: number(element, "b2")?,
+        rated_u2: number(element, "ratedU2")?,
+        r3: number(element, "r3")?,
+        x3: number(element, "x3")?,
+        g3: number(element, "g3")?,
+        b3: number(element, "b3")?,
+        rated_u3: number(element, "ratedU3")?,
+        voltage_level_id1: string(element, "voltageLevelId1")?,
+        voltage_level_id2: string(element, "voltageLevelId2")?,
+        voltage_level_id3: string(element, "voltageLevelId3")?,
+        bus1: bus_or_node(element, "bus1", "node1"),
+        bus2: bus_or_node(element, "bus2", "node2"),
+        bus3: bus_or_node(element, "bus3", "node3"),
+        connectable_bus1: bus_or_node(element, "connectableBus1", "node1"),
+        connectable_bus2: bus_or_node(element, "connectableBus2", "node2"),
+        connectable_bus3: bus_or_node(element, "connectableBus3", "node3"),
+        ratio_tap_changer2: optional(element, "ratioTapChanger2", read_ratio_tap_changer)?,
+        ratio_tap_changer3: optional(element, "ratioTapChanger3", read_ratio_tap_changer)?,
+        current_limits1: optional(element, "currentLimits1", read_current_limits)?,
+        current_limits2: optional(element, "currentLimits2", read_current_limits)?,
+        current_limits3: optional(element, "currentLimits3", read_current_limits)?,
+    })
+}
+
+fn read_line(element: &Element) -> Result<Line, XiidmError> {
+    Ok(Line {
+        id: string(element, "id")?,
+        r: number(element, "r")?,
+        x: number(element, "x")?,
+        b1: number(element, "b1")?,
+        b2: number(element, "b2")?,
+        g1: number(element, "g1")?,
+        g2: number(element, "g2")?,
+        voltage_level_id1: string(element, "voltageLevelId1")?,
+        bus1: bus_or_node(element, "bus1", "node1"),
+        connectable_bus1: bus_or_node(element, "connectableBus1", "node1"),
+        voltage_level_id2: string(element, "voltageLevelId2")?,
+        bus2: bus_or_node(element, "bus2", "node2"),
+        connectable_bus2: bus_or_node(element, "connectableBus2", "node2"),
+        current_limits1: optional(element, "currentLimits1", read_current_limits)?,
+        current_limits2: optional(element, "currentLimits2", read_current_limits)?,
+    })
+}
+
+fn read_shunt_compensator(element: &Element) -> Result<ShuntCompensator, XiidmError> {
+    Ok(ShuntCompensator {
+        id: string(element, "id")?,
+        b_per_section: number(element, "bPerSection")?,
+        maximum_section_count: number(element, "maximumSectionCount")?,
+        section_count: number(element, "sectionCount")?,
+        bus: bus_or_node(element, "bus", "node"),
+        connectable_bus: bus_or_node(element, "connectableBus", "node"),
+    })
+}
+
+fn read_static_var_compensator(element: &Element) -> Result<StaticVarCompensator, XiidmError> {
+    Ok(StaticVarCompensator {
+        id: string(element, "id")?,
+        b_min: number(element, "bMin")?,
+        b_max: number(element, "bMax")?,
+        regulation_mode: enumeration(element, "regulationMode")?,
+        voltage_setpoint: number(element, "voltageSetpoint")?,
+        reactive_power_setpoint: number(element, "reactivePowerSetpoint")?,
+        bus: bus_or_node(element, "bus", "node"),
+        connectable_bus: bus_or_node(element, "connectableBus", "node"),
+    })
+}
+
+fn read_dangling_line(element: &Element) -> Result<DanglingLine, XiidmError> {
+    Ok(DanglingLine {
+        id: string(element, "id")?,
+        p0: number(element, "p0")?,
+        q0: number(element, "q0")?,
+        r: number(element, "r")?,
+        x: number(element, "x")?,
+        g: number(element, "g")?,
+        b: number(element, "b")?,
+        bus: bus_or_node(element, "bus", "node"),
+        connectable_bus: bus_or_node(element, "connectableBus", "node"),
+    })
+}
+
+fn read_tie_line(element: &Element) -> Result<TieLine, XiidmError> {
+    Ok(TieLine {
+        id: string(element, "id")?,
+        name: optional_string(element, "name"),
+        dangling_line1: read_dangling_line(child(element, "danglingLine1")?)?,
+        dangling_line2: read_dangling_line(child(element, "danglingLine2")?)?,
+        ucte_xnode_code: optional_string(element, "ucteXnodeCode"),
+    })
+}
+
+fn read_hvdc_converter_station(element: &Element) -> Result<HvdcConverterStation, XiidmError> {
+    Ok(HvdcConverterStation {
+        id: string(element, "id")?,
+        name: optional_string(element, "name"),
+        voltage_level_id: string(element, "voltageLevelId")?,
+        bus: bus_or_node(element, "bus", "node"),
+        connectable_bus: bus_or_node(element, "connectableBus", "node"),
+        loss_factor: number(element, "lossFactor")?,
+        reactive_power_setpoint: number(element, "reactivePowerSetpoint")?,
+    })
+}
+
+fn read_hvdc_line(element: &Element) -> Result<HvdcLine, XiidmError> {
+    Ok(HvdcLine {
+        id: string(element, "id")?,
+        name: optional_string(element, "name"),
+        resistance: number(element, "r")?,
+        nominal_v: number(element, "nominalV")?,
+        converters_mode: enumeration(element, "convertersMode")?,
+        active_power_setpoint: number(element, "activePowerSetpoint")?,
+        max_p: number(element, "maxP")?,
+        converter_station1: read_hvdc_converter_station(child(element, "converterStation1")?)?,
+        converter_station2: read_hvdc_converter_station(child(element, "converterStation2")?)?,
+    })
+}
+
+// Writers
+
+/// Voltage levels in node-breaker topology, whose equipment is written with node numbers
+struct NodeBreaker<'n> {
+    voltage_levels: HashSet<&'n str>,
+    /// Configured buses of the bus-breaker voltage levels, unique in the network
+    buses: HashSet<&'n str>,
+    /// Nodes of the node-breaker voltage levels
+    nodes: HashSet<String>,
+}
+
+impl<'n> NodeBreaker<'n> {
+    fn new(network: &'n Network) -> Self {
+        let mut node_breaker = Self {
+            voltage_levels: HashSet::new(),
+            buses: HashSet::new(),
+            nodes: HashSet::new(),
+        };
+        let voltage_levels = network
+            .substations
+            .iter()
+            .flat_map(|substation| &substation.voltage_levels);
+        for voltage_level in voltage_levels {
+            if voltage_level.topology_kind == TopologyKind::NodeBreaker {
+                node_breaker.voltage_levels.insert(&voltage_level.id);
+            }
+            if let Some(topology) = &voltage_level.bus_breaker_topology {
+                node_breaker
+                    .buses
+                    .extend(topology.buses.iter().map(|bus| bus.id.as_str()));
+            }
+            if let Some(topology) = &voltage_level.node_breaker_topology {
+                node_breaker
+                    .nodes
+                    .extend(topology.nodes.iter().map(|node| node.id.to_string()));
+            }
+        }
+        node_breaker
+    }
+
+    fn contains(&self, voltage_level_id: &str) -> bool {
+        self.voltage_levels.contains(voltage_level_id)
+    }
+
+    /// Whether the terminal of an element without voltage level id is held by a node-breaker
+    /// voltage level: it is no configured bus, but one of their nodes
+    fn holds(&self, bus: &str, connectable_bus: &str) -> bool {
+        let bus = if connectable_bus.is_empty() {
+            bus
+        } else {
+            connectable_bus
+        };
+        !self.buses.contains(bus) && self.nodes.contains(bus)
+    }
+}
+
+/// Attributes locating the terminal of a side, `side` being empty for the injections
+trait TerminalAttributes {
+    fn terminal(self, node_breaker: bool, side: &str, bus: &str, connectable_bus: &str) -> Self;
+}
+
+impl TerminalAttributes for Element {
+    /// Node-breaker terminals are written with the node their buses hold
+    fn terminal(self, node_breaker: bool, side: &str, bus: &str, connectable_bus: &str) -> Self {
+        if node_breaker {
+            let node = if connectable_bus.is_empty() {
+                bus
+            } else {
+                connectable_bus
+            };
+            self.attr(format!("node{}", side), node)
+        } else {
+            self.attr(format!("bus{}", side), bus)
+                .attr(format!("connectableBus{}", side), connectable_bus)
+        }
+    }
+}
+
+fn write_network(network: &Network) -> Element {
+    let node_breaker = NodeBreaker::new(network);
+    let version = if network.version.is_empty() {
+        DEFAULT_VERSION
+    } else {
+        &network.version
+    };
+
+    Element::new("network")
+        .attr(
+            format!("xmlns:{}", PREFIX),
+            format!("{}{}", NAMESPACE_BASE, version.replace('.', "_")),
+        )
+        .attr("id", &network.id)
+        .attr(
+            "caseDate",
+            network
+                .case_date
+                .to_rfc3339_opts(SecondsFormat::Millis, false),
+        )
+        .attr("forecastDistance", network.forecast_distance)
+        .attr("sourceFormat", &network.source_format)
+        .attr(
+            "minimumValidationLevel",
+            enum_name(&network.minimum_validation_level),
+        )
+        .children(
+            network
+                .substations
+                .iter()
+                .map(|substation| write_substation(substation, &node_breaker)),
+        )
+        .children(
+            network
+                .lines
+                .iter()
+                .map(|line| write_line(line, &node_breaker)),
+        )
+        .children(
+            network
+                .three_windings_transformers
+                .iter()
+                .map(|transformer| write_three_windings_transformer(transformer, &node_breaker)),
+        )
+        .children(network.switches.iter().map(write_switch))
+        .children(
+            network
+                .shunt_compensators
+                .iter()
+                .map(|shunt| write_shunt_compensator(shunt, &node_breaker)),
+        )
+        .children(
+            network
+                .static_var_compensators
+                .iter()
+                .map(|compensator| write_static_var_compensator(compensator, &node_breaker)),
+        )
+        .children(
+            network
+                .dangling_lines
+                .iter()
+                .map(|line| write_dangling_line("danglingLine", line, &node_breaker)),
+        )
+        .children(
+            network
+                .tie_lines
+                .iter()
+                .map(|tie_line| write_tie_line(tie_line, &node_breaker)),
+        )
+        .children(
+            network
+                .hvdc_lines
+                .iter()
+                .map(|line| write_hvdc_line(line, &node_breaker)),
+        )
+}
+
+fn write_substation(substation: &Substation, node_breaker: &NodeBreaker) -> Element {
+    Element::new("substation")
+        .attr("id", &substation.id)
+        .attr("country", &substation.country)
+        .attr("tso", &substation.tso)
+        .attr("geographicalTags", substation.geographical_tags.join(","))
+        .children(substation.voltage_levels.iter().map(write_voltage_level))
+        .children(
+            substation
+                .two_windings_transformers
+                .iter()
+                .map(|transformer| write_two_windings_transformer(transformer, node_breaker)),
+        )
+}
+
+fn write_voltage_level(voltage_level: &VoltageLevel) -> Element {
+    let node_breaker = voltage_level.topology_kind == TopologyKind::NodeBreaker;
+    Element::new("voltageLevel")
+        .attr("id", &voltage_level.id)
+        .attr("nominalV", voltage_level.nominal_v)
+        .attr("topologyKind", enum_name(&voltage_level.topology_kind))
+        .children(
+            voltage_level
+                .node_breaker_topology
+                .iter()
+                .map(write_node_breaker_topology),
+        )
+        .children(
+            voltage_level
+                .bus_breaker_topology
+                .iter()
+                .map(write_bus_breaker_topology),
+        )
+        .children(
+            voltage_level
+                .busbar_sections
+                .iter()
+                .flatten()
+                .map(|section| write_busbar_section(section, node_breaker)),
+        )
+        .children(
+            voltage_level
+                .generators
+                .iter()
+                .flatten()
+                .map(|generator| write_generator(generator, node_breaker)),
+        )
+        .children(
+            voltage_level
+                .loads
+                .iter()
+                .flatten()
+                .map(|load| write_load(load, node_breaker)),
+        )
+}
+
+fn write_generator(generator: &Generator, node_breaker: bool) -> Element {
+    Element::new("generator")
+        .attr("id", &generator.id)
+        .attr("energySource", enum_name(&generator.energy_source))
+        .attr("minP", generator.min_p)
+        .attr("maxP", generator.max_p)
+        .attr("voltageRegulatorOn", generator.voltage_regulator_on)
+        .attr("targetP", generator.target_p)
+        .attr("targetV", generator.target_v)
+        .attr("targetQ", generator.target_q)
+        .terminal(node_breaker, "", &generator.bus, &generator.connectable_bus)
+        .children(generator.reactive_capability_curve.iter().map(|curve| {
+            Element::new("reactiveCapabilityCurve").children(curve.points.iter().map(|point| {
+                Element::new("point")
+                    .attr("p", point.p)
+                    .attr("minQ", point.min_q)
+                    .attr("maxQ", point.max_q)
+            }))
+        }))
+        .children(generator.min_max_reactive_limits.iter().map(|limits| {
+            Element::new("minMaxReactiveLimits")
+                .attr("minQ", limits.min_q)
+                .attr("maxQ", limits.max_q)
+        }))
+}
+
+fn write_load(load: &Load, node_breaker: bool) -> Element {
+    Element::new("load")
+        .attr("id", &load.id)
+        .attr("loadType", enum_name(&load.load_type))
+        .attr("p0", load.p0)
+        .attr("q0", load.q0)
+        .terminal(node_breaker, "", &load.bus, &load.connectable_bus)
+        .children(load.exponential_model.iter().map(|model| {
+            Element::new("exponentialModel")
+                .attr("p0", model.p0)
+                .attr("q0", model.q0)
+                .attr("np", model.np)
+                .attr("nq", model.nq)
+        }))
+        .children(load.zip_model.iter().map(|model| {
+            Element::new("zipModel")
+                .attr("p0", model.p0)
+                .attr("q0", model.q0)
+                .attr("zP", model.z_p)
+                .attr("zQ", model.z_q)
+                .attr("iP", model.i_p)
+                .attr("iQ", model.i_q)
+                .attr("pP", model.p_p)
+                .attr("pQ", model.p_q)
+        }))
+}
+
+fn write_busbar_section(section: &BusbarSection, node_breaker: bool) -> Element {
+    Element::new("busbarSection")
+        .attr("id", &section.id)
+        .attr("name", &section.name)
+        .terminal(node_breaker, "", &section.bus, &section.connectable_bus)
+}
+
+fn write_bus_breaker_topology(topology: &BusBreakerTopology) -> Element {
+    Element::new("busBreakerTopology")
+        .children(
+            topology
+                .buses
+                .iter()
+                .map(|bus| Element::new("bus").attr("id", &bus.id)),
+        )
+        .children(topology.switches.iter().map(write_switch))
+}
+
+fn write_node_breaker_topology(topology: &NodeBreakerTopology) -> Element {
+    Element::new("nodeBreakerTopology")
+        .children(
+            topology
+                .nodes
+                .iter()
+                .map(|node| Element::new("node").attr("id", node.id)),
+        )
+        .children(topology.switches.iter().map(write_node_switch))
+        .children(topology.internal_connections.iter().map(|connection| {
+            Element::new("internalConnection")
+                .attr("node1", connection.node1)
+                .attr("node2", connection.node2)
+        }))
+}
+
+fn write_switch(switch: &Switch) -> Element {
+    Element::new("switch")
+        .attr("id", &switch.id)
+        .attr("kind", enum_name(&switch.kind))
+        .attr("retained", switch.retained)
+        .attr("open", switch.open)
+        .attr("bus1", &switch.bus1)
+        .attr("bus2", &switch.bus2)
+        .attr("voltageLevelId", &switch.voltage_level_id)
+}
+
+fn write_node_switch(switch: &Switch) -> Element {
+    Element::new("switch")
+        .attr("id", &switch.id)
+        .attr("kind", enum_name(&switch.kind))
+        .attr("retained", switch.retained)
+        .attr("open", switch.open)
+        .attr("node1", &switch.bus1)
+        .attr("node2", &switch.bus2)
+        .attr("voltageLevelId", &switch.voltage_level_id)
+}
+
+fn write_current_limits(name: &str, limits: &CurrentLimits) -> Element {
+    Element::new(name)
+        .attr("permanentLimit", limits.permanent_limit)
+        .children(limits.temporary_limits.iter().map(|limit| {
+            Element::new("temporaryLimit")
+                .attr("name", &limit.name)
+                .attr("acceptableDuration", limit.acceptable_duration)
+                .attr("value", limit.value)
+        }))
+}
+
+fn write_terminal_ref(terminal: &TerminalRef) -> Element {
+    Element::new("terminalRef")
+        .attr("id", &terminal.id)
+        .attr("side", enum_name(&terminal.side))
+}
+
+fn write_ratio_tap_changer(name: &str, changer: &RatioTapChanger) -> Element {
+    Element::new(name)
+        .attr("regulating", changer.regulating)
+        .attr("lowTapPosition", changer.low_tap_position)
+        .attr("tapPosition", changer.tap_position)
+        .attr("targetDeadband", changer.target_deadband)
+        .attr(
+            "loadTapChangingCapabilities",
+            changer.load_tap_changing_capabilities,
+        )
+        .attr("regulationMode", enum_name(&changer.regulation_mode))
+        .attr("regulationValue", changer.regulation_value)
+        .child(write_terminal_ref(&changer.terminal_ref))
+        .children(changer.steps.iter().map(|step| {
+            Element::new("step")
+                .attr("r", step.r)
+                .attr("x", step.x)
+                .attr("g", step.g)
+                .attr("b", step.b)
+                .attr("rho", step.rho)
+        }))
+}
+
+fn write_phase_tap_changer(changer: &PhaseTapChanger) -> Element {
+    Element::new("phaseTapChanger")
+        .attr("regulating", changer.regulating)
+        .attr("lowTapPosition", changer.low_tap_position)
+        .attr("tapPosition", changer.tap_position)
+        .attr("regulationMode", enum_name(&changer.regulation_mode))
+        .attr("regulationValue", changer.regulation_value)
+        .attr("targetDeadband", changer.target_deadband)
+        .child(write_terminal_ref(&changer.terminal_ref))
+        .children(changer.steps.iter().map(|step| {
+            Element::new("step")
+                .attr("r", step.r)
+                .attr("x", step.x)
+                .attr("g", step.g)
+                .attr("b", step.b)
+                .attr("rho", step.rho)
+                .attr("alpha", step.alpha)
+        }))
+}
+
+fn write_two_windings_transformer(
+    transformer: &TwoWindingsTransformer,
+    node_breaker: &NodeBreaker,
+) -> Element {
+    Element::new("twoWindingsTransformer")
+        .attr("id", &transformer.id)
+        .attr("r", transformer.r)
+        .attr("x", transformer.x)
+        .attr("g", transformer.g)
+        .attr("b", transformer.b)
+        .attr("ratedU1", transformer.rated_u1)
+        .attr("ratedU2", transformer.rated_u2)
+        .attr("voltageLevelId1", &transformer.voltage_level_id1)
+        .terminal(
+            node_breaker.contains(&transformer.voltage_level_id1),
+            "1",
+            &transformer.bus1,
+            &transformer.connectable_bus1,
+        )
+        .attr("voltageLevelId2", &transformer.voltage_level_id2)
+        .terminal(
+            node_breaker.contains(&transformer.voltage_level_id2),
+            "2",
+            &transformer.bus2,
+            &transformer.connectable_bus2,
+        )
+        .children(
+            transformer
+                .ratio_tap_changer
+                .iter()
+                .map(|changer| write_ratio_tap_changer("ratioTapChanger", changer)),
+        )
+        .children(
+            transformer
+                .phase_tap_changer
+                .iter()
+                .map(write_phase_tap_changer),
+        )
+        .children(
+            transformer
+                .current_limits1
+                .iter()
+                .map(|limits| write_current_limits("currentLimits1", limits)),
+        )
+        .children(
+            transformer
+                .current_limits2
+                .iter()
+                .map(|limits| write_current_limits("currentLimits2", limits)),
+        )
+}
+
+fn write_three_windings_transformer(
+    transformer: &ThreeWindingsTransformer,
+    node_breaker: &NodeBreaker,
+) -> Element {
+    Element::new("threeWindingsTransformer")
+        .attr("id", &transformer.id)
+        .attr("r1", transformer.r1)
+        .attr("x1", transformer.x1)
+        .attr("g1", transformer.g1)
+        .attr("b1", transformer.b1)
+        .attr("ratedU1", transformer.rated_u1)
+        .attr("r2", transformer.r2)
+        .attr("x2", transformer.x2)
+        .attr("g2", transformer.g2)
+        .attr("b2", transformer.b2)
+        .attr("ratedU2", transformer.rated_u2)
+        .attr("r3", transformer.r3)
+        .attr("x3", transformer.x3)
+        .attr("g3", transformer.g3)
+        .attr("b3", transformer.b3)
+        .attr("ratedU3", transformer.rated_u3)
+        .attr("voltageLevelId1", &transformer.voltage_level_id1)
+        .attr("voltageLevelId2", &transformer.voltage_level_id2)
+        .attr("voltageLevelId3", &transformer.voltage_level_id3)
+        .terminal(
+            node_breaker.contains(&transformer.voltage_level_id1),
+            "1",
+            &transformer.bus1,
+            &transformer.connectable_bus1,
+        )
+        .terminal(
+            node_breaker.contains(&transformer.voltage_level_id2),
+            "2",
+            &transformer.bus2,
+            &transformer.connectable_bus2,
+        )
+        .terminal(
+            node_breaker.contains(&transformer.voltage_level_id3),
+            "3",
+            &transformer.bus3,
+            &transformer.connectable_bus3,
+        )
+        .children(
+            transformer
+                .ratio_tap_changer2
+                .iter()
+                .map(|changer| write_ratio_tap_changer("ratioTapChanger2", changer)),
+        )
+        .children(
+            transformer
+                .ratio_tap_changer3
+                .iter()
+                .map(|changer| write_ratio_tap_changer("ratioTapChanger3", changer)),
+        )
+        .children(
+            transformer
+                .current_limits1
+                .iter()
+                .map(|limits| write_current_limits("currentLimits1", limits)),
+        )
+        .children(
+            transformer
+                .current_limits2
+                .iter()
+                .map(|limits| write_current_limits("currentLimits2", limits)),
+        )
+        .children(
+            transformer
+                .current_limits3
+                .iter()
+                .map(|limits| write_current_limits("currentLimits3", limits)),
+        )
+}
+
+fn write_line(line: &Line, node_breaker: &NodeBreaker) -> Element {
+    Element::new("line")
+        .attr("id", &line.id)
+        .attr("r", line.r)
+        .attr("x", line.x)
+        .attr("g1", line.g1)
+        .attr("b1", line.b1)
+        .attr("g2", line.g2)
+        .attr("b2", line.b2)
+        .attr("voltageLevelId1", &line.voltage_level_id1)
+        .terminal(
+            node_breaker.contains(&line.voltage_level_id1),
+            "1",
+            &line.bus1,
+            &line.connectable_bus1,
+        )
+        .attr("voltageLevelId2", &line.voltage_level_id2)
+        .terminal(
+            node_breaker.contains(&line.voltage_level_id2),
+            "2",
+            &line.bus2,
+            &line.connectable_bus2,
+        )
+        .children(
+            line.current_limits1
+                .iter()
+                .map(|limits| write_current_limits("currentLimits1", limits)),
+        )
+        .children(
+            line.current_limits2
+                .iter()
+                .map(|limits| write_current_limits("currentLimits2", limits)),
+        )
+}
+
+fn write_shunt_compensator(shunt: &ShuntCompensator, node_breaker: &NodeBreaker) -> Element {
+    Element::new("shunt")
+        .attr("id", &shunt.id)
+        .attr("bPerSection", shunt.b_per_section)
+        .attr("maximumSectionCount", shunt.maximum_section_count)
+        .attr("sectionCount", shunt.section_count)
+        .terminal(
+            node_breaker.holds(&shunt.bus, &shunt.connectable_bus),
+            "",
+            &shunt.bus,
+            &shunt.connectable_bus,
+        )
+}
+
+fn write_static_var_compensator(
+    compensator: &StaticVarCompensator,
+    node_breaker: &NodeBreaker,
+) -> Element {
+    Element::new("staticVarCompensator")
+        .attr("id", &compensator.id)
+        .attr("bMin", compensator.b_min)
+        .attr("bMax", compensator.b_max)
+        .attr("regulationMode", enum_name(&compensator.regulation_mode))
+        .attr("voltageSetpoint", compensator.voltage_setpoint)
+        .attr("reactivePowerSetpoint", compensator.reactive_power_setpoint)
+        .terminal(
+            node_breaker.holds(&compensator.bus, &compensator.connectable_bus),
+            "",
+            &compensator.bus,
+            &compensator.connectable_bus,
+        )
+}
+
+fn write_dangling_line(name: &str, line: &DanglingLine, node_breaker: &NodeBreaker) -> Element {
+    Element::new(name)
+        .attr("id", &line.id)
+        .attr("p0", line.p0)
+        .attr("q0", line.q0)
+        .attr("r", line.r)
+        .attr("x", line.x)
+        .attr("g", line.g)
+        .attr("b", line.b)
+        .terminal(
+            node_breaker.holds(&line.bus, &line.connectable_bus),
+            "",
+            &line.bus,
+            &line.connectable_bus,
+        )
+}
+
+fn write_tie_line(tie_line: &TieLine, node_breaker: &NodeBreaker) -> Element {
+    Element::new("tieLine")
+        .attr("id", &tie_line.id)
+        .attr("name", &tie_line.name)
+        .attr("ucteXnodeCode", &tie_line.ucte_xnode_code)
+        .child(write_dangling_line(
+            "danglingLine1",
+            &tie_line.dangling_line1,
+            node_breaker,
+        ))
+        .child(write_dangling_line(
+            "danglingLine2",
+            &tie_line.dangling_line2,
+            node_breaker,
+        ))
+}
+
+fn write_hvdc_converter_station(
+    name: &str,
+    station: &HvdcConverterStation,
+    node_breaker: &NodeBreaker,
+) -> Element {
+    Element::new(name)
+        .attr("id", &station.id)
+        .attr("name", &station.name)
+        .attr("voltageLevelId", &station.voltage_level_id)
+        .terminal(
+            node_breaker.contains(&station.voltage_level_id),
+            "",
+            &station.bus,
+            &station.connectable_bus,
+        )
+        .attr("lossFactor", station.loss_factor)
+        .attr("reactivePowerSetpoint", station.reactive_power_setpoint)
+}
+
+fn write_hvdc_line(line: &HvdcLine, node_breaker: &NodeBreaker) -> Element {
+    Element::new("hvdcLine")
+        .attr("id", &line.id)
+        .attr("name", &line.name)
+        .attr("r", line.resistance)
+        .attr("nominalV", line.nominal_v)
+        .attr("convertersMode", enum_name(&line.converters_mode))
+        .attr("activePowerSetpoint", line.active_power_setpoint)
+        .attr("maxP", line.max_p)
+        .child(write_hvdc_converter_station(
+            "converterStation1",
+            &line.converter_station1,
+            node_breaker,
+        ))
+        .child(write_hvdc_converter_station(
+            "converterStation2",
+            &line.converter_station2,
+            node_breaker,
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINIMAL_XIIDM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <iidm:network xmlns:iidm="http://www.powsybl.org/schema/iidm/1_11" id="sim1"
+            caseDate="2013-01-15T18:45:00.000+01:00" forecastDistance="0" sourceFormat="test"/>"#;
+
+    #[test]
+    fn test_version_from_namespace() {
+        let network = from_str(MINIMAL_XIIDM).unwrap();
+        assert_eq!(network.version, "1.11");
+        assert_eq!(network.id, "sim1");
+        assert_eq!(
+            network.minimum_validation_level,
+            ValidationLevel::SteadyStateHypothesis
+        );
+    }
+
+    #[test]
+    fn test_unexpected_root() {
+        let result = from_str(r#"<iidm:substation id="P1"/>"#);
+        assert!(matches!(result, Err(XiidmError::UnexpectedRoot(name)) if name == "substation"));
+    }
+
+    #[test]
+    fn test_missing_attribute() {
+        let xml = MINIMAL_XIIDM.replace(r#"id="sim1""#, "");
+        let result = from_str(&xml);
+        assert!(
+            matches!(result, Err(XiidmError::MissingAttribute { attribute, .. }) if attribute == "id")
+        );
+    }
+
+    #[test]
+    fn test_invalid_enumeration() {
+        let xml = MINIMAL_XIIDM.replace(
+            "/>",
+            r#"><iidm:substation id="P1"><iidm:voltageLevel id="VL" nominalV="400" topologyKind="MESHED"/></iidm:substation></iidm:network>"#,
+        );
+        let result = from_str(&xml);
+        assert!(
+            matches!(result, Err(XiidmError::InvalidAttribute { value, .. }) if value == "MESHED")
+        );
+    }
+
+    #[test]
+    fn test_write_uses_versioned_namespace() {
+        let network = from_str(MINIMAL_XIIDM).unwrap();
+        let xml = to_string(&network).unwrap();
+        assert!(xml.contains(r#"xmlns:iidm="http://www.powsybl.org/schema/iidm/1_11""#));
+        assert!(xml.contains(r#"caseDate="2013-01-15T18:45:00.000+01:00""#));
+    }
+
+    #[test]
+    fn test_node_breaker_switch_nodes() {
+        let xml = MINIMAL_XIIDM.replace(
+            "/>",
+            r#"><iidm:substation id="P1"><iidm:voltageLevel id="VL" nominalV="400" topologyKind="NODE_BREAKER">
+                <iidm:nodeBreakerTopology>
+                    <iidm:switch id="BR1" kind="BREAKER" retained="true" open="false" node1="0" node2="1"/>
+                </iidm:nodeBreakerTopology>
+            </iidm:voltageLevel></iidm:substation></iidm:network>"#,
+        );
+        let network = from_str(&xml).unwrap();
+        let topology = network.substations[0].voltage_levels[0]
+            .node_breaker_topology
+            .as_ref()
+            .unwrap();
+        assert_eq!(topology.switches[0].bus1, "0");
+        assert_eq!(topology.switches[0].bus2, "1");
+
+        let xml = to_string(&network).unwrap();
+        assert!(xml.contains(r#"node1="0" node2="1""#));
+    }
+}
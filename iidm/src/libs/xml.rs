@@ -0,0 +1,246 @@
+use std::io::{BufRead, Write};
+
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event},
+    Reader, Writer,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum XmlError {
+    #[error("XML syntax error: {0}")]
+    Syntax(#[from] quick_xml::Error),
+
+    #[error("XML attribute error: {0}")]
+    Attribute(#[from] quick_xml::events::attributes::AttrError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Document has no root element")]
+    EmptyDocument,
+
+    #[error("Unexpected closing tag: {0}")]
+    UnbalancedTag(String),
+}
+
+/// Minimal in-memory XML element, namespace prefixes are stripped on read
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    /// Text content, trimmed
+    pub text: String,
+}
+
+impl Element {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds an attribute, builder style
+    pub fn attr<K: Into<String>, V: ToString>(mut self, key: K, value: V) -> Self {
+        self.attributes.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Adds a child element, builder style
+    pub fn child(mut self, child: Element) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Adds several children, builder style
+    pub fn children<I: IntoIterator<Item = Element>>(mut self, children: I) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    /// Sets the text content, builder style
+    pub fn text<T: ToString>(mut self, text: T) -> Self {
+        self.text = text.to_string();
+        self
+    }
+
+    /// Finds an attribute value by its local name
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Finds the first child with the given local name
+    pub fn first(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// Iterates over all children with the given local name
+    pub fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+}
+
+/// Parses a whole document into its root element
+pub fn read_document<R: BufRead>(input: R) -> Result<Element, XmlError> {
+    let mut reader = Reader::from_reader(input);
+    reader.config_mut().trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => stack.push(element_from_start(&start)?),
+            Event::Empty(start) => {
+                let element = element_from_start(&start)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::End(end) => {
+                let element = stack.pop().ok_or_else(|| {
+                    XmlError::UnbalancedTag(String::from_utf8_lossy(end.name().as_ref()).into())
+                })?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::Text(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text.unescape()?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    root.ok_or(XmlError::EmptyDocument)
+}
+
+fn element_from_start(start: &BytesStart) -> Result<Element, XmlError> {
+    let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+    let mut attributes = Vec::new();
+
+    for attribute in start.attributes() {
+        let attribute = attribute?;
+        let key = attribute.key;
+        // Keep namespace declarations untouched, the caller may need them
+        let key = if key.as_namespace_binding().is_some() {
+            String::from_utf8_lossy(key.as_ref()).into_owned()
+        } else {
+            String::from_utf8_lossy(key.local_name().as_ref()).into_owned()
+        };
+        let value = attribute.unescape_value()?.into_owned();
+        attributes.push((key, value));
+    }
+
+    Ok(Element {
+        name,
+        attributes,
+        children: Vec::new(),
+        text: String::new(),
+    })
+}
+
+/// Writes a document with an XML declaration, prefixing every element name with `prefix`
+pub fn write_document<W: Write>(root: &Element, prefix: &str, output: W) -> Result<(), XmlError> {
+    let mut writer = Writer::new_with_indent(output, b' ', 4);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    write_element(&mut writer, root, prefix)?;
+    Ok(())
+}
+
+fn write_element<W: Write>(
+    writer: &mut Writer<W>,
+    element: &Element,
+    prefix: &str,
+) -> Result<(), XmlError> {
+    let name = if prefix.is_empty() {
+        element.name.clone()
+    } else {
+        format!("{}:{}", prefix, element.name)
+    };
+
+    let mut start = BytesStart::new(name.as_str());
+    for (key, value) in &element.attributes {
+        start.push_attribute((key.as_str(), value.as_str()));
+    }
+
+    if element.children.is_empty() && element.text.is_empty() {
+        writer.write_event(Event::Empty(start))?;
+    } else {
+        writer.write_event(Event::Start(start))?;
+        if !element.text.is_empty() {
+            writer.write_event(Event::Text(BytesText::new(&element.text)))?;
+        }
+        for child in &element.children {
+            write_element(writer, child, prefix)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new(name.as_str())))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_nested_document() {
+        let xml = r#"<?xml version="1.0"?>
+            <a:root xmlns:a="urn:test" id="r">
+                <a:child id="c1"/>
+                <a:child id="c2"><a:leaf v="1 &amp; 2"/></a:child>
+                <a:name> R &amp; D </a:name>
+            </a:root>"#;
+
+        let root = read_document(xml.as_bytes()).unwrap();
+        assert_eq!(root.name, "root");
+        assert_eq!(root.get("id"), Some("r"));
+        assert_eq!(root.get("xmlns:a"), Some("urn:test"));
+        assert_eq!(root.all("child").count(), 2);
+        assert_eq!(root.text, "");
+
+        let leaf = root.children[1].first("leaf").unwrap();
+        assert_eq!(leaf.get("v"), Some("1 & 2"));
+        assert_eq!(root.first("name").unwrap().text, "R & D");
+    }
+
+    #[test]
+    fn test_empty_document() {
+        let result = read_document("".as_bytes());
+        assert!(matches!(result, Err(XmlError::EmptyDocument)));
+    }
+
+    #[test]
+    fn test_write_then_read() {
+        let root = Element::new("root")
+            .attr("id", "r")
+            .child(Element::new("child").attr("value", 1.5))
+            .child(Element::new("name").text("a < b"));
+
+        let mut output = Vec::new();
+        write_document(&root, "p", &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("<p:child value=\"1.5\"/>"));
+
+        let parsed = read_document(text.as_bytes()).unwrap();
+        assert_eq!(parsed, root);
+    }
+}
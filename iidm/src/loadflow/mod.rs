@@ -0,0 +1,280 @@
+//! AC load flow running on the ECS world.
+//!
+//! The network model is rebuilt from the live components on every run: buses come from the
+//! bus views of the `NetworkTopology` when it is available, otherwise from the bus-breaker
+//! topologies of `VoltageLevel`. Branches from `Line`, `TwoWindingsTransformer` and
+//! `ThreeWindingsTransformer`, injections from `Generator`, `Load`, `DanglingLine` and
+//! `ShuntCompensator`. Only the synchronous island holding the slack bus is solved.
+
+mod model;
+mod solver;
+
+pub use model::{BranchFlow, ModelError, NetworkModel, NetworkState};
+
+use bevy_ecs::{prelude::*, system::SystemParam};
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::entities::*;
+use crate::{update_topology, ChildOf, NetworkTopology};
+
+/// Request to run a load flow during the next schedule run
+#[derive(Event, Debug, Clone, Default)]
+pub struct LoadFlowEvent;
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct LoadFlowParameters {
+    /// Base power used for the per-unit conversion, in MVA
+    pub base_mva: f64,
+    pub max_iterations: usize,
+    /// Maximum active and reactive power mismatch, in per-unit
+    pub tolerance: f64,
+    /// Solve the linear DC approximation when Newton-Raphson diverges
+    pub dc_fallback: bool,
+}
+
+impl Default for LoadFlowParameters {
+    fn default() -> Self {
+        Self {
+            base_mva: 100.0,
+            max_iterations: 20,
+            tolerance: 1e-6,
+            dc_fallback: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LoadFlowStatus {
+    #[default]
+    NotRun,
+    Converged,
+    DcFallback,
+    Failed,
+}
+
+/// Outcome of the last load flow
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadFlowResult {
+    pub status: LoadFlowStatus,
+    pub iterations: usize,
+    #[serde(rename = "maxMismatch")]
+    pub max_mismatch: f64,
+    #[serde(rename = "slackBus")]
+    pub slack_bus: Option<String>,
+    #[serde(rename = "busCount")]
+    pub bus_count: usize,
+    pub message: Option<String>,
+}
+
+/// Calculated voltage of a bus
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BusState {
+    /// Voltage magnitude, in kV
+    pub v: f64,
+    /// Voltage angle, in degrees
+    pub angle: f64,
+}
+
+/// Calculated voltages of the buses of a voltage level, by bus id
+#[derive(Component, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BusStates(pub BTreeMap<String, BusState>);
+
+/// Calculated flows at both ends of a line or a two windings transformer
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BranchState {
+    pub p1: f64,
+    pub q1: f64,
+    pub i1: f64,
+    pub p2: f64,
+    pub q2: f64,
+    pub i2: f64,
+}
+
+impl From<BranchFlow> for BranchState {
+    fn from(flow: BranchFlow) -> Self {
+        Self {
+            p1: flow.p1,
+            q1: flow.q1,
+            i1: flow.i1,
+            p2: flow.p2,
+            q2: flow.q2,
+            i2: flow.i2,
+        }
+    }
+}
+
+/// Calculated active and reactive power produced by a generator
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct InjectionState {
+    pub p: f64,
+    pub q: f64,
+}
+
+/// Read access to every component taking part in the load flow
+#[derive(SystemParam)]
+pub struct LoadFlowInputs<'w, 's> {
+    voltage_levels: Query<'w, 's, (Entity, &'static VoltageLevel)>,
+    lines: Query<'w, 's, (Entity, &'static Line)>,
+    two_windings_transformers: Query<'w, 's, (Entity, &'static TwoWindingsTransformer)>,
+    three_windings_transformers: Query<'w, 's, &'static ThreeWindingsTransformer>,
+    generators: Query<'w, 's, (Entity, &'static Generator, Option<&'static ChildOf>)>,
+    loads: Query<'w, 's, (&'static Load, Option<&'static ChildOf>)>,
+    dangling_lines: Query<'w, 's, (&'static DanglingLine, Option<&'static ChildOf>)>,
+    shunt_compensators: Query<'w, 's, (&'static ShuntCompensator, Option<&'static ChildOf>)>,
+    topology: Option<Res<'w, NetworkTopology>>,
+}
+
+pub fn handle_load_flow_events(
+    mut events: EventReader<LoadFlowEvent>,
+    inputs: LoadFlowInputs,
+    parameters: Res<LoadFlowParameters>,
+    mut result: ResMut<LoadFlowResult>,
+    mut commands: Commands,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+
+    let mut model = NetworkModel::new(parameters.base_mva);
+    // Injections do not carry their voltage level, which is needed to resolve node numbers
+    let mut voltage_level_ids = HashMap::new();
+    // Voltage level entity of each bus, which holds its calculated state
+    let mut bus_voltage_levels = HashMap::new();
+    for (entity, voltage_level) in inputs.voltage_levels.iter() {
+        match inputs
+            .topology
+            .as_ref()
+            .and_then(|topology| topology.bus_view(&voltage_level.id))
+        {
+            Some(view) => {
+                model.add_bus_view(voltage_level, view);
+                for bus in &view.buses {
+                    bus_voltage_levels.insert(bus.id.as_str(), entity);
+                }
+            }
+            None => {
+                model.add_voltage_level(voltage_level);
+                for bus in voltage_level
+                    .bus_breaker_topology
+                    .iter()
+                    .flat_map(|topology| &topology.buses)
+                {
+                    bus_voltage_levels.insert(bus.id.as_str(), entity);
+                }
+            }
+        }
+        voltage_level_ids.insert(entity, voltage_level.id.as_str());
+    }
+    let voltage_level_of = |relation: Option<&ChildOf>| {
+        relation
+            .and_then(|relation| voltage_level_ids.get(&relation.parent))
+            .copied()
+            .unwrap_or("")
+    };
+
+    let mut branches = Vec::new();
+    for (entity, line) in inputs.lines.iter() {
+        branches.push((model.add_line(line), entity));
+    }
+    for (entity, transformer) in inputs.two_windings_transformers.iter() {
+        branches.push((model.add_two_windings_transformer(transformer), entity));
+    }
+    for transformer in inputs.three_windings_transformers.iter() {
+        model.add_three_windings_transformer(transformer);
+    }
+
+    let mut generators = Vec::new();
+    for (entity, generator, relation) in inputs.generators.iter() {
+        generators.push((
+            model.add_generator(generator, voltage_level_of(relation)),
+            entity,
+        ));
+    }
+    for (load, relation) in inputs.loads.iter() {
+        model.add_load(load, voltage_level_of(relation));
+    }
+    for (line, relation) in inputs.dangling_lines.iter() {
+        model.add_dangling_line(line, voltage_level_of(relation));
+    }
+    for (shunt, relation) in inputs.shunt_compensators.iter() {
+        model.add_shunt_compensator(shunt, voltage_level_of(relation));
+    }
+
+    let solved = model.solve(
+        parameters.max_iterations,
+        parameters.tolerance,
+        parameters.dc_fallback,
+    );
+
+    // States outside the solved island, or of a failed run, are removed rather than kept stale
+    let mut bus_states: HashMap<Entity, BTreeMap<String, BusState>> = HashMap::new();
+    if let Ok(state) = &solved {
+        for (id, v, angle) in &state.buses {
+            if let Some(entity) = bus_voltage_levels.get(id.as_str()) {
+                bus_states.entry(*entity).or_default().insert(
+                    id.clone(),
+                    BusState {
+                        v: *v,
+                        angle: *angle,
+                    },
+                );
+            }
+        }
+    }
+    for (entity, _) in inputs.voltage_levels.iter() {
+        match bus_states.remove(&entity) {
+            Some(states) => commands.entity(entity).insert(BusStates(states)),
+            None => commands.entity(entity).remove::<BusStates>(),
+        };
+    }
+    let flow_of = |index| solved.as_ref().ok()?.branches.get(&index).copied();
+    for (index, entity) in branches {
+        match flow_of(index) {
+            Some(flow) => commands.entity(entity).insert(BranchState::from(flow)),
+            None => commands.entity(entity).remove::<BranchState>(),
+        };
+    }
+    let injection_of = |index| solved.as_ref().ok()?.generators.get(&index).copied();
+    for (index, entity) in generators {
+        match injection_of(index) {
+            Some((p, q)) => commands.entity(entity).insert(InjectionState { p, q }),
+            None => commands.entity(entity).remove::<InjectionState>(),
+        };
+    }
+
+    let state = match solved {
+        Ok(state) => state,
+        Err(error) => {
+            *result = LoadFlowResult {
+                status: LoadFlowStatus::Failed,
+                message: Some(error.to_string()),
+                ..Default::default()
+            };
+            return;
+        }
+    };
+
+    *result = LoadFlowResult {
+        status: if state.dc {
+            LoadFlowStatus::DcFallback
+        } else {
+            LoadFlowStatus::Converged
+        },
+        iterations: state.iterations,
+        max_mismatch: state.max_mismatch,
+        slack_bus: Some(state.slack_bus),
+        bus_count: state.buses.len(),
+        message: None,
+    };
+}
+
+/// Registers the load flow event, parameters, result and system, which runs after the topology
+pub fn init_load_flow(world: &mut World, schedule: &mut Schedule) {
+    world.init_resource::<Events<LoadFlowEvent>>();
+    world.init_resource::<LoadFlowParameters>();
+    world.init_resource::<LoadFlowResult>();
+    schedule.add_systems(handle_load_flow_events.after(update_topology));
+}
@@ -0,0 +1,763 @@
+use std::collections::{HashMap, VecDeque};
+
+use thiserror::Error;
+
+use crate::entities::*;
+use crate::topology::BusView;
+
+use super::solver::{solve_dc, BusType, Complex, Matrix, PowerFlowProblem, PowerFlowSolution};
+
+/// Impedances below this value (in per-unit) are clamped to avoid singular admittances
+const MIN_IMPEDANCE: f64 = 1e-5;
+
+#[derive(Debug, Clone, Default)]
+struct ModelGenerator {
+    index: usize,
+    target_p: f64,
+    target_q: f64,
+    max_p: f64,
+    regulating: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelBus {
+    pub id: String,
+    pub nominal_v: f64,
+    /// Internal buses (three windings transformer star points) are not reported
+    pub fictitious: bool,
+    generators: Vec<ModelGenerator>,
+    target_v: Option<f64>,
+    load_p: f64,
+    load_q: f64,
+    shunt: Complex,
+}
+
+impl ModelBus {
+    fn new(id: String, nominal_v: f64, fictitious: bool) -> Self {
+        Self {
+            id,
+            nominal_v,
+            fictitious,
+            generators: Vec::new(),
+            target_v: None,
+            load_p: 0.0,
+            load_q: 0.0,
+            shunt: Complex::ZERO,
+        }
+    }
+}
+
+/// Two-port branch: ideal transformer `rho * e^(j alpha)` on side 1 followed by a pi model
+#[derive(Debug, Clone)]
+pub struct ModelBranch {
+    pub index: usize,
+    pub bus1: usize,
+    pub bus2: usize,
+    y11: Complex,
+    y12: Complex,
+    y21: Complex,
+    y22: Complex,
+    x: f64,
+    alpha: f64,
+}
+
+impl ModelBranch {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        index: usize,
+        bus1: usize,
+        bus2: usize,
+        z: Complex,
+        y1: Complex,
+        y2: Complex,
+        rho: f64,
+        alpha: f64,
+    ) -> Self {
+        let z = if z.norm() < MIN_IMPEDANCE {
+            Complex::new(0.0, MIN_IMPEDANCE)
+        } else {
+            z
+        };
+        let y = z.inv();
+        let shift = Complex::from_polar(rho, alpha);
+
+        Self {
+            index,
+            bus1,
+            bus2,
+            y11: (y + y1).scale(rho * rho),
+            y12: -(y * shift.conj()),
+            y21: -(y * shift),
+            y22: y + y2,
+            x: z.im,
+            alpha,
+        }
+    }
+}
+
+/// Power flows at both ends of a branch, in MW, MVar and A
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BranchFlow {
+    pub p1: f64,
+    pub q1: f64,
+    pub i1: f64,
+    pub p2: f64,
+    pub q2: f64,
+    pub i2: f64,
+}
+
+/// Per-unit network model assembled from IIDM equipment.
+/// Branch and generator indices refer to the order in which they were added.
+#[derive(Debug, Clone)]
+pub struct NetworkModel {
+    base_mva: f64,
+    buses: Vec<ModelBus>,
+    bus_index: HashMap<String, usize>,
+    /// Configured buses and nodes merged into a calculated bus, see `terminal_key`
+    aliases: HashMap<String, usize>,
+    branches: Vec<ModelBranch>,
+    branch_count: usize,
+    generator_count: usize,
+}
+
+/// Solved state of the synchronous island that contains the slack bus
+#[derive(Debug, Clone)]
+pub struct NetworkState {
+    pub converged: bool,
+    pub dc: bool,
+    pub iterations: usize,
+    pub max_mismatch: f64,
+    pub slack_bus: String,
+    /// Bus id, voltage magnitude in kV and angle in degrees
+    pub buses: Vec<(String, f64, f64)>,
+    /// Flows indexed like the branches added to the model
+    pub branches: HashMap<usize, BranchFlow>,
+    /// Generated active and reactive power indexed like the generators added to the model
+    pub generators: HashMap<usize, (f64, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ModelError {
+    #[error("No generator available as slack bus")]
+    NoSlack,
+    #[error("Load flow diverged")]
+    Diverged,
+}
+
+fn terminal_key(voltage_level_id: &str, bus: &str) -> String {
+    format!("{}/{}", voltage_level_id, bus)
+}
+
+impl NetworkModel {
+    pub fn new(base_mva: f64) -> Self {
+        Self {
+            base_mva,
+            buses: Vec::new(),
+            bus_index: HashMap::new(),
+            aliases: HashMap::new(),
+            branches: Vec::new(),
+            branch_count: 0,
+            generator_count: 0,
+        }
+    }
+
+    /// Resolves a terminal, `voltage_level_id` may be empty when the equipment does not carry it
+    fn bus(&self, voltage_level_id: &str, id: &str) -> Option<usize> {
+        if id.is_empty() {
+            return None;
+        }
+        self.aliases
+            .get(&terminal_key(voltage_level_id, id))
+            .or_else(|| self.bus_index.get(id))
+            .or_else(|| self.aliases.get(id))
+            .copied()
+    }
+
+    fn add_bus(&mut self, id: &str, nominal_v: f64, fictitious: bool) -> usize {
+        if let Some(index) = self.bus_index.get(id) {
+            return *index;
+        }
+        let index = self.buses.len();
+        self.buses
+            .push(ModelBus::new(id.to_string(), nominal_v, fictitious));
+        self.bus_index.insert(id.to_string(), index);
+        index
+    }
+
+    fn impedance_base(&self, nominal_v: f64) -> f64 {
+        nominal_v * nominal_v / self.base_mva
+    }
+
+    /// Declares the buses of a bus-breaker voltage level
+    pub fn add_voltage_level(&mut self, voltage_level: &VoltageLevel) {
+        if let Some(topology) = &voltage_level.bus_breaker_topology {
+            for bus in &topology.buses {
+                self.add_bus(&bus.id, voltage_level.nominal_v, false);
+            }
+        }
+    }
+
+    /// Declares the calculated buses of a voltage level, replacing its configured buses or nodes
+    pub fn add_bus_view(&mut self, voltage_level: &VoltageLevel, view: &BusView) {
+        for calculated in &view.buses {
+            let index = self.add_bus(&calculated.id, voltage_level.nominal_v, false);
+            for member in &calculated.members {
+                self.aliases
+                    .insert(terminal_key(&voltage_level.id, member), index);
+                // Bus-breaker bus ids are unique in the network, unlike node numbers
+                if view.topology_kind == TopologyKind::BusBreaker {
+                    self.aliases.insert(member.clone(), index);
+                }
+            }
+        }
+    }
+
+    fn next_branch(&mut self) -> usize {
+        self.branch_count += 1;
+        self.branch_count - 1
+    }
+
+    /// Adds a line, returns its branch index
+    pub fn add_line(&mut self, line: &Line) -> usize {
+        let index = self.next_branch();
+        let (Some(bus1), Some(bus2)) = (
+            self.bus(&line.voltage_level_id1, &line.bus1),
+            self.bus(&line.voltage_level_id2, &line.bus2),
+        ) else {
+            return index;
+        };
+
+        let nominal_v1 = self.buses[bus1].nominal_v;
+        let nominal_v2 = self.buses[bus2].nominal_v;
+        let z_base = self.impedance_base(nominal_v2);
+
+        self.branches.push(ModelBranch::new(
+            index,
+            bus1,
+            bus2,
+            Complex::new(line.r, line.x).scale(1.0 / z_base),
+            Complex::new(line.g1, line.b1).scale(z_base),
+            Complex::new(line.g2, line.b2).scale(z_base),
+            nominal_v1 / nominal_v2,
+            0.0,
+        ));
+        index
+    }
+
+    /// Adds a two windings transformer, returns its branch index
+    pub fn add_two_windings_transformer(&mut self, transformer: &TwoWindingsTransformer) -> usize {
+        let index = self.next_branch();
+        let (Some(bus1), Some(bus2)) = (
+            self.bus(&transformer.voltage_level_id1, &transformer.bus1),
+            self.bus(&transformer.voltage_level_id2, &transformer.bus2),
+        ) else {
+            return index;
+        };
+
+        let mut rho = transformer.rated_u2 / transformer.rated_u1;
+        let mut alpha = 0.0;
+        let mut factors = (1.0, 1.0, 1.0, 1.0);
+
+        if let Some(changer) = &transformer.ratio_tap_changer {
+            if let Some(step) = current_step(
+                &changer.steps,
+                changer.tap_position,
+                changer.low_tap_position,
+            ) {
+                rho *= step.rho;
+                factors = apply_step(factors, step.r, step.x, step.g, step.b);
+            }
+        }
+        if let Some(changer) = &transformer.phase_tap_changer {
+            if let Some(step) = current_step(
+                &changer.steps,
+                changer.tap_position,
+                changer.low_tap_position,
+            ) {
+                rho *= step.rho;
+                alpha = step.alpha.to_radians();
+                factors = apply_step(factors, step.r, step.x, step.g, step.b);
+            }
+        }
+
+        let nominal_v1 = self.buses[bus1].nominal_v;
+        let nominal_v2 = self.buses[bus2].nominal_v;
+        let z_base = self.impedance_base(nominal_v2);
+        let (fr, fx, fg, fb) = factors;
+
+        self.branches.push(ModelBranch::new(
+            index,
+            bus1,
+            bus2,
+            Complex::new(transformer.r * fr, transformer.x * fx).scale(1.0 / z_base),
+            Complex::new(transformer.g * fg, transformer.b * fb).scale(z_base),
+            Complex::ZERO,
+            rho * nominal_v1 / nominal_v2,
+            alpha,
+        ));
+        index
+    }
+
+    /// Adds the three legs of a three windings transformer around a fictitious star bus.
+    /// Legs take part in the calculation but their flows are not reported.
+    pub fn add_three_windings_transformer(&mut self, transformer: &ThreeWindingsTransformer) {
+        let rated_u0 = transformer.rated_u1;
+        let star = self.add_bus(&format!("{}_STAR", transformer.id), rated_u0, true);
+        let z_base = self.impedance_base(rated_u0);
+
+        let legs = [
+            (
+                (&transformer.voltage_level_id1, &transformer.bus1),
+                transformer.rated_u1,
+                (
+                    transformer.r1,
+                    transformer.x1,
+                    transformer.g1,
+                    transformer.b1,
+                ),
+                None,
+            ),
+            (
+                (&transformer.voltage_level_id2, &transformer.bus2),
+                transformer.rated_u2,
+                (
+                    transformer.r2,
+                    transformer.x2,
+                    transformer.g2,
+                    transformer.b2,
+                ),
+                transformer.ratio_tap_changer2.as_ref(),
+            ),
+            (
+                (&transformer.voltage_level_id3, &transformer.bus3),
+                transformer.rated_u3,
+                (
+                    transformer.r3,
+                    transformer.x3,
+                    transformer.g3,
+                    transformer.b3,
+                ),
+                transformer.ratio_tap_changer3.as_ref(),
+            ),
+        ];
+
+        for ((voltage_level_id, bus), rated_u, (r, x, g, b), changer) in legs {
+            let index = self.next_branch();
+            let Some(bus) = self.bus(voltage_level_id, bus) else {
+                continue;
+            };
+
+            let mut rho = rated_u0 / rated_u;
+            let mut factors = (1.0, 1.0, 1.0, 1.0);
+            if let Some(changer) = changer {
+                if let Some(step) = current_step(
+                    &changer.steps,
+                    changer.tap_position,
+                    changer.low_tap_position,
+                ) {
+                    rho *= step.rho;
+                    factors = apply_step(factors, step.r, step.x, step.g, step.b);
+                }
+            }
+            let (fr, fx, fg, fb) = factors;
+            let nominal_v = self.buses[bus].nominal_v;
+
+            self.branches.push(ModelBranch::new(
+                index,
+                bus,
+                star,
+                Complex::new(r * fr, x * fx).scale(1.0 / z_base),
+                Complex::new(g * fg, b * fb).scale(z_base),
+                Complex::ZERO,
+                rho * nominal_v / rated_u0,
+                0.0,
+            ));
+        }
+    }
+
+    /// Adds a generator of the given voltage level, returns its generator index
+    pub fn add_generator(&mut self, generator: &Generator, voltage_level_id: &str) -> usize {
+        self.generator_count += 1;
+        let index = self.generator_count - 1;
+        let Some(bus) = self.bus(voltage_level_id, &generator.bus) else {
+            return index;
+        };
+
+        let model_bus = &mut self.buses[bus];
+        if generator.voltage_regulator_on && model_bus.target_v.is_none() {
+            model_bus.target_v = Some(generator.target_v / model_bus.nominal_v);
+        }
+        model_bus.generators.push(ModelGenerator {
+            index,
+            target_p: generator.target_p,
+            target_q: generator.target_q,
+            max_p: generator.max_p,
+            regulating: generator.voltage_regulator_on,
+        });
+        index
+    }
+
+    pub fn add_load(&mut self, load: &Load, voltage_level_id: &str) {
+        self.add_consumption(voltage_level_id, &load.bus, load.p0, load.q0);
+    }
+
+    /// Dangling lines are modelled by their boundary consumption
+    pub fn add_dangling_line(&mut self, line: &DanglingLine, voltage_level_id: &str) {
+        self.add_consumption(voltage_level_id, &line.bus, line.p0, line.q0);
+    }
+
+    fn add_consumption(&mut self, voltage_level_id: &str, bus: &str, p: f64, q: f64) {
+        if let Some(bus) = self.bus(voltage_level_id, bus) {
+            self.buses[bus].load_p += p;
+            self.buses[bus].load_q += q;
+        }
+    }
+
+    pub fn add_shunt_compensator(&mut self, shunt: &ShuntCompensator, voltage_level_id: &str) {
+        if let Some(bus) = self.bus(voltage_level_id, &shunt.bus) {
+            let z_base = self.impedance_base(self.buses[bus].nominal_v);
+            let b = shunt.b_per_section * shunt.section_count as f64;
+            self.buses[bus].shunt += Complex::new(0.0, b * z_base);
+        }
+    }
+
+    /// Picks the bus with the largest regulating capacity as the angle reference
+    fn slack(&self) -> Option<usize> {
+        let capacity = |bus: &ModelBus, regulating: bool| {
+            bus.generators
+                .iter()
+                .filter(|generator| generator.regulating || !regulating)
+                .map(|generator| generator.max_p)
+                .sum::<f64>()
+        };
+
+        let best = |regulating: bool| {
+            self.buses
+                .iter()
+                .enumerate()
+                .filter(|(_, bus)| {
+                    bus.generators
+                        .iter()
+                        .any(|generator| generator.regulating || !regulating)
+                })
+                .max_by(|(_, a), (_, b)| {
+                    capacity(a, regulating).total_cmp(&capacity(b, regulating))
+                })
+                .map(|(index, _)| index)
+        };
+
+        best(true).or_else(|| best(false))
+    }
+
+    /// Buses reachable from the slack through connected branches
+    fn island(&self, slack: usize) -> Vec<usize> {
+        let mut adjacency = vec![Vec::new(); self.buses.len()];
+        for branch in &self.branches {
+            adjacency[branch.bus1].push(branch.bus2);
+            adjacency[branch.bus2].push(branch.bus1);
+        }
+
+        let mut visited = vec![false; self.buses.len()];
+        let mut island = Vec::new();
+        let mut queue = VecDeque::from([slack]);
+        visited[slack] = true;
+
+        while let Some(bus) = queue.pop_front() {
+            island.push(bus);
+            for &next in &adjacency[bus] {
+                if !visited[next] {
+                    visited[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        island
+    }
+
+    /// Solves the island of the slack bus with Newton-Raphson, falling back to DC if requested
+    pub fn solve(
+        &self,
+        max_iterations: usize,
+        tolerance: f64,
+        dc_fallback: bool,
+    ) -> Result<NetworkState, ModelError> {
+        let slack = self.slack().ok_or(ModelError::NoSlack)?;
+        let island = self.island(slack);
+
+        let mut local = vec![None; self.buses.len()];
+        for (position, &bus) in island.iter().enumerate() {
+            local[bus] = Some(position);
+        }
+        let branches: Vec<&ModelBranch> = self
+            .branches
+            .iter()
+            .filter(|branch| local[branch.bus1].is_some() && local[branch.bus2].is_some())
+            .collect();
+
+        let n = island.len();
+        let mut g = Matrix::zeros(n);
+        let mut b = Matrix::zeros(n);
+        for branch in &branches {
+            let (i, k) = (local[branch.bus1].unwrap(), local[branch.bus2].unwrap());
+            for (row, col, value) in [
+                (i, i, branch.y11),
+                (i, k, branch.y12),
+                (k, i, branch.y21),
+                (k, k, branch.y22),
+            ] {
+                g.add(row, col, value.re);
+                b.add(row, col, value.im);
+            }
+        }
+
+        let mut bus_types = Vec::with_capacity(n);
+        let mut p = Vec::with_capacity(n);
+        let mut q = Vec::with_capacity(n);
+        let mut v = Vec::with_capacity(n);
+        for (position, &index) in island.iter().enumerate() {
+            let bus = &self.buses[index];
+            g.add(position, position, bus.shunt.re);
+            b.add(position, position, bus.shunt.im);
+
+            let bus_type = match (index == slack, bus.target_v) {
+                (true, _) => BusType::Slack,
+                (false, Some(_)) => BusType::Pv,
+                (false, None) => BusType::Pq,
+            };
+            let generated_q: f64 = bus
+                .generators
+                .iter()
+                .filter(|generator| !generator.regulating)
+                .map(|generator| generator.target_q)
+                .sum();
+            let generated_p: f64 = bus
+                .generators
+                .iter()
+                .map(|generator| generator.target_p)
+                .sum();
+
+            bus_types.push(bus_type);
+            p.push((generated_p - bus.load_p) / self.base_mva);
+            q.push((generated_q - bus.load_q) / self.base_mva);
+            v.push(bus.target_v.unwrap_or(1.0));
+        }
+
+        let problem = PowerFlowProblem {
+            bus_types,
+            p,
+            q,
+            v,
+            g,
+            b,
+        };
+        let solution = problem.solve_newton_raphson(max_iterations, tolerance);
+
+        let (solution, dc) = if solution.converged {
+            (solution, false)
+        } else if dc_fallback {
+            let solution = self
+                .solve_dc(&problem, &branches, &local, solution)
+                .ok_or(ModelError::Diverged)?;
+            (solution, true)
+        } else {
+            return Err(ModelError::Diverged);
+        };
+
+        Ok(self.state(&problem, &island, &branches, &local, solution, dc))
+    }
+
+    fn solve_dc(
+        &self,
+        problem: &PowerFlowProblem,
+        branches: &[&ModelBranch],
+        local: &[Option<usize>],
+        failed: PowerFlowSolution,
+    ) -> Option<PowerFlowSolution> {
+        let n = problem.size();
+        let mut susceptance = Matrix::zeros(n);
+        let mut shift = vec![0.0; n];
+
+        for branch in branches {
+            let (i, k) = (local[branch.bus1]?, local[branch.bus2]?);
+            let b = 1.0 / branch.x;
+            susceptance.add(i, i, b);
+            susceptance.add(k, k, b);
+            susceptance.add(i, k, -b);
+            susceptance.add(k, i, -b);
+            shift[i] += branch.alpha * b;
+            shift[k] -= branch.alpha * b;
+        }
+
+        let angle = solve_dc(&problem.bus_types, &problem.p, &susceptance, &shift)?;
+        Some(PowerFlowSolution {
+            v: vec![1.0; n],
+            angle,
+            iterations: failed.iterations,
+            max_mismatch: failed.max_mismatch,
+            converged: true,
+        })
+    }
+
+    fn state(
+        &self,
+        problem: &PowerFlowProblem,
+        island: &[usize],
+        branches: &[&ModelBranch],
+        local: &[Option<usize>],
+        solution: PowerFlowSolution,
+        dc: bool,
+    ) -> NetworkState {
+        let voltage = |bus: usize| {
+            let position = local[bus].unwrap();
+            Complex::from_polar(solution.v[position], solution.angle[position])
+        };
+        let current_base =
+            |bus: usize| 1000.0 * self.base_mva / (3f64.sqrt() * self.buses[bus].nominal_v);
+
+        let mut flows = HashMap::new();
+        for branch in branches {
+            let flow = if dc {
+                let position1 = local[branch.bus1].unwrap();
+                let position2 = local[branch.bus2].unwrap();
+                let p = (solution.angle[position1] + branch.alpha - solution.angle[position2])
+                    / branch.x;
+                BranchFlow {
+                    p1: p * self.base_mva,
+                    q1: 0.0,
+                    i1: p.abs() * current_base(branch.bus1),
+                    p2: -p * self.base_mva,
+                    q2: 0.0,
+                    i2: p.abs() * current_base(branch.bus2),
+                }
+            } else {
+                let (v1, v2) = (voltage(branch.bus1), voltage(branch.bus2));
+                let i1 = branch.y11 * v1 + branch.y12 * v2;
+                let i2 = branch.y21 * v1 + branch.y22 * v2;
+                let s1 = v1 * i1.conj();
+                let s2 = v2 * i2.conj();
+                BranchFlow {
+                    p1: s1.re * self.base_mva,
+                    q1: s1.im * self.base_mva,
+                    i1: i1.norm() * current_base(branch.bus1),
+                    p2: s2.re * self.base_mva,
+                    q2: s2.im * self.base_mva,
+                    i2: i2.norm() * current_base(branch.bus2),
+                }
+            };
+            flows.insert(branch.index, flow);
+        }
+
+        let (p, q) = if dc {
+            // Injections are the flows leaving each bus, so that the slack takes the imbalance
+            let mut p = vec![0.0; island.len()];
+            for branch in branches {
+                let flow = flows[&branch.index].p1 / self.base_mva;
+                p[local[branch.bus1].unwrap()] += flow;
+                p[local[branch.bus2].unwrap()] -= flow;
+            }
+            (p, problem.q.clone())
+        } else {
+            problem.injections(&solution.v, &solution.angle)
+        };
+
+        let mut generators = HashMap::new();
+        for (position, &index) in island.iter().enumerate() {
+            let bus = &self.buses[index];
+            if bus.generators.is_empty() {
+                continue;
+            }
+            let generated_p = p[position] * self.base_mva + bus.load_p;
+            let generated_q = q[position] * self.base_mva + bus.load_q;
+            generators.extend(dispatch(&bus.generators, generated_p, generated_q));
+        }
+
+        let buses = island
+            .iter()
+            .filter(|&&index| !self.buses[index].fictitious)
+            .map(|&index| {
+                let bus = &self.buses[index];
+                let position = local[index].unwrap();
+                (
+                    bus.id.clone(),
+                    solution.v[position] * bus.nominal_v,
+                    solution.angle[position].to_degrees(),
+                )
+            })
+            .collect();
+
+        NetworkState {
+            converged: !dc,
+            dc,
+            iterations: solution.iterations,
+            max_mismatch: solution.max_mismatch,
+            slack_bus: self.buses[island[0]].id.clone(),
+            buses,
+            branches: flows,
+            generators,
+        }
+    }
+}
+
+/// Shares the power generated at a bus between its generators: fixed set points are kept,
+/// the remainder is spread over regulating units proportionally to their capacity.
+fn dispatch(
+    generators: &[ModelGenerator],
+    generated_p: f64,
+    generated_q: f64,
+) -> Vec<(usize, (f64, f64))> {
+    let regulating: Vec<&ModelGenerator> = generators
+        .iter()
+        .filter(|generator| generator.regulating)
+        .collect();
+    let fixed_q: f64 = generators
+        .iter()
+        .filter(|generator| !generator.regulating)
+        .map(|generator| generator.target_q)
+        .sum();
+    let capacity: f64 = generators
+        .iter()
+        .map(|generator| generator.max_p.abs())
+        .sum();
+    let target_p: f64 = generators.iter().map(|generator| generator.target_p).sum();
+
+    generators
+        .iter()
+        .map(|generator| {
+            let share = if capacity > 0.0 {
+                generator.max_p.abs() / capacity
+            } else {
+                1.0 / generators.len() as f64
+            };
+            let p = generator.target_p + (generated_p - target_p) * share;
+            let q = if generator.regulating {
+                (generated_q - fixed_q) / regulating.len() as f64
+            } else {
+                generator.target_q
+            };
+            (generator.index, (p, q))
+        })
+        .collect()
+}
+
+fn current_step<T>(steps: &[T], tap_position: i32, low_tap_position: i32) -> Option<&T> {
+    usize::try_from(tap_position - low_tap_position)
+        .ok()
+        .and_then(|position| steps.get(position))
+}
+
+/// Tap steps express r, x, g and b as percentage corrections
+fn apply_step(
+    (fr, fx, fg, fb): (f64, f64, f64, f64),
+    r: f64,
+    x: f64,
+    g: f64,
+    b: f64,
+) -> (f64, f64, f64, f64) {
+    (
+        fr * (1.0 + r / 100.0),
+        fx * (1.0 + x / 100.0),
+        fg * (1.0 + g / 100.0),
+        fb * (1.0 + b / 100.0),
+    )
+}
@@ -0,0 +1,481 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn from_polar(magnitude: f64, angle: f64) -> Self {
+        Self::new(magnitude * angle.cos(), magnitude * angle.sin())
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn norm(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn scale(self, factor: f64) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    pub fn inv(self) -> Self {
+        let denominator = self.re * self.re + self.im * self.im;
+        Self::new(self.re / denominator, -self.im / denominator)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl AddAssign for Complex {
+    fn add_assign(&mut self, rhs: Complex) {
+        self.re += rhs.re;
+        self.im += rhs.im;
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+/// Dense row-major square matrix, large enough for training-sized networks
+#[derive(Debug, Clone)]
+pub struct Matrix {
+    size: usize,
+    values: Vec<f64>,
+}
+
+impl Matrix {
+    pub fn zeros(size: usize) -> Self {
+        Self {
+            size,
+            values: vec![0.0; size * size],
+        }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        self.values[row * self.size + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: f64) {
+        self.values[row * self.size + col] = value;
+    }
+
+    pub fn add(&mut self, row: usize, col: usize, value: f64) {
+        self.values[row * self.size + col] += value;
+    }
+
+    /// Solves `self * x = rhs` by Gaussian elimination with partial pivoting.
+    /// Returns `None` when the matrix is singular.
+    pub fn solve(mut self, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+        let n = self.size;
+
+        for col in 0..n {
+            let pivot = (col..n)
+                .max_by(|&a, &b| self.get(a, col).abs().total_cmp(&self.get(b, col).abs()))?;
+            if self.get(pivot, col).abs() < 1e-12 {
+                return None;
+            }
+            if pivot != col {
+                for k in 0..n {
+                    self.values.swap(col * n + k, pivot * n + k);
+                }
+                rhs.swap(col, pivot);
+            }
+
+            let diagonal = self.get(col, col);
+            for row in col + 1..n {
+                let factor = self.get(row, col) / diagonal;
+                if factor == 0.0 {
+                    continue;
+                }
+                for k in col..n {
+                    let value = self.get(col, k);
+                    self.add(row, k, -factor * value);
+                }
+                rhs[row] -= factor * rhs[col];
+            }
+        }
+
+        let mut solution = vec![0.0; n];
+        for row in (0..n).rev() {
+            let mut sum = rhs[row];
+            for (k, value) in solution.iter().enumerate().skip(row + 1) {
+                sum -= self.get(row, k) * value;
+            }
+            solution[row] = sum / self.get(row, row);
+        }
+
+        Some(solution)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusType {
+    Slack,
+    Pv,
+    Pq,
+}
+
+/// Per-unit inputs of a single synchronous island
+#[derive(Debug, Clone)]
+pub struct PowerFlowProblem {
+    pub bus_types: Vec<BusType>,
+    /// Specified net injections (generation minus consumption), in per-unit
+    pub p: Vec<f64>,
+    pub q: Vec<f64>,
+    /// Voltage magnitude targets for slack and PV buses, flat start elsewhere
+    pub v: Vec<f64>,
+    pub g: Matrix,
+    pub b: Matrix,
+}
+
+#[derive(Debug, Clone)]
+pub struct PowerFlowSolution {
+    pub v: Vec<f64>,
+    pub angle: Vec<f64>,
+    pub iterations: usize,
+    pub max_mismatch: f64,
+    pub converged: bool,
+}
+
+impl PowerFlowProblem {
+    pub fn size(&self) -> usize {
+        self.bus_types.len()
+    }
+
+    /// Computes the injected active and reactive powers for the given state
+    pub fn injections(&self, v: &[f64], angle: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let n = self.size();
+        let mut p = vec![0.0; n];
+        let mut q = vec![0.0; n];
+
+        for i in 0..n {
+            for k in 0..n {
+                let g = self.g.get(i, k);
+                let b = self.b.get(i, k);
+                if g == 0.0 && b == 0.0 {
+                    continue;
+                }
+                let (sin, cos) = (angle[i] - angle[k]).sin_cos();
+                p[i] += v[i] * v[k] * (g * cos + b * sin);
+                q[i] += v[i] * v[k] * (g * sin - b * cos);
+            }
+        }
+
+        (p, q)
+    }
+
+    /// Newton-Raphson in polar coordinates
+    pub fn solve_newton_raphson(&self, max_iterations: usize, tolerance: f64) -> PowerFlowSolution {
+        let n = self.size();
+        let mut v = self.v.clone();
+        let mut angle = vec![0.0; n];
+
+        // Unknown layout: angles of every non-slack bus, then magnitudes of PQ buses
+        let angle_index: Vec<Option<usize>> = {
+            let mut next = 0;
+            self.bus_types
+                .iter()
+                .map(|bus_type| match bus_type {
+                    BusType::Slack => None,
+                    _ => {
+                        next += 1;
+                        Some(next - 1)
+                    }
+                })
+                .collect()
+        };
+        let angle_count = angle_index.iter().flatten().count();
+        let magnitude_index: Vec<Option<usize>> = {
+            let mut next = angle_count;
+            self.bus_types
+                .iter()
+                .map(|bus_type| match bus_type {
+                    BusType::Pq => {
+                        next += 1;
+                        Some(next - 1)
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+        let size = angle_count + magnitude_index.iter().flatten().count();
+
+        let mut iterations = 0;
+        let max_mismatch = loop {
+            let (p, q) = self.injections(&v, &angle);
+
+            let mut mismatch = vec![0.0; size];
+            for i in 0..n {
+                if let Some(row) = angle_index[i] {
+                    mismatch[row] = self.p[i] - p[i];
+                }
+                if let Some(row) = magnitude_index[i] {
+                    mismatch[row] = self.q[i] - q[i];
+                }
+            }
+            let max_mismatch = mismatch
+                .iter()
+                .fold(0.0, |max: f64, value| value.abs().max(max));
+
+            if !max_mismatch.is_finite() {
+                break max_mismatch;
+            }
+            if max_mismatch < tolerance {
+                return PowerFlowSolution {
+                    v,
+                    angle,
+                    iterations,
+                    max_mismatch,
+                    converged: true,
+                };
+            }
+            if iterations >= max_iterations {
+                break max_mismatch;
+            }
+
+            let jacobian = self.jacobian(&v, &angle, &p, &q, &angle_index, &magnitude_index, size);
+            let Some(correction) = jacobian.solve(mismatch) else {
+                break max_mismatch;
+            };
+
+            for i in 0..n {
+                if let Some(row) = angle_index[i] {
+                    angle[i] += correction[row];
+                }
+                if let Some(row) = magnitude_index[i] {
+                    v[i] += correction[row];
+                }
+            }
+            iterations += 1;
+        };
+
+        PowerFlowSolution {
+            v,
+            angle,
+            iterations,
+            max_mismatch,
+            converged: false,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn jacobian(
+        &self,
+        v: &[f64],
+        angle: &[f64],
+        p: &[f64],
+        q: &[f64],
+        angle_index: &[Option<usize>],
+        magnitude_index: &[Option<usize>],
+        size: usize,
+    ) -> Matrix {
+        let n = self.size();
+        let mut jacobian = Matrix::zeros(size);
+
+        for i in 0..n {
+            let row_p = angle_index[i];
+            let row_q = magnitude_index[i];
+            if row_p.is_none() && row_q.is_none() {
+                continue;
+            }
+
+            for k in 0..n {
+                let g = self.g.get(i, k);
+                let b = self.b.get(i, k);
+                if i != k && g == 0.0 && b == 0.0 {
+                    continue;
+                }
+
+                let (dp_dangle, dp_dv, dq_dangle, dq_dv) = if i == k {
+                    (
+                        -q[i] - b * v[i] * v[i],
+                        p[i] / v[i] + g * v[i],
+                        p[i] - g * v[i] * v[i],
+                        q[i] / v[i] - b * v[i],
+                    )
+                } else {
+                    let (sin, cos) = (angle[i] - angle[k]).sin_cos();
+                    (
+                        v[i] * v[k] * (g * sin - b * cos),
+                        v[i] * (g * cos + b * sin),
+                        -v[i] * v[k] * (g * cos + b * sin),
+                        v[i] * (g * sin - b * cos),
+                    )
+                };
+
+                if let Some(row) = row_p {
+                    if let Some(col) = angle_index[k] {
+                        jacobian.set(row, col, dp_dangle);
+                    }
+                    if let Some(col) = magnitude_index[k] {
+                        jacobian.set(row, col, dp_dv);
+                    }
+                }
+                if let Some(row) = row_q {
+                    if let Some(col) = angle_index[k] {
+                        jacobian.set(row, col, dq_dangle);
+                    }
+                    if let Some(col) = magnitude_index[k] {
+                        jacobian.set(row, col, dq_dv);
+                    }
+                }
+            }
+        }
+
+        jacobian
+    }
+}
+
+/// Linear DC approximation: flat voltage magnitudes and `B' * angle = P`.
+/// `susceptance` is the B' matrix and `shift` the phase shifter contribution to each bus.
+pub fn solve_dc(
+    bus_types: &[BusType],
+    p: &[f64],
+    susceptance: &Matrix,
+    shift: &[f64],
+) -> Option<Vec<f64>> {
+    let reduced: Vec<usize> = (0..bus_types.len())
+        .filter(|&i| bus_types[i] != BusType::Slack)
+        .collect();
+
+    let mut matrix = Matrix::zeros(reduced.len());
+    for (row, &i) in reduced.iter().enumerate() {
+        for (col, &k) in reduced.iter().enumerate() {
+            matrix.set(row, col, susceptance.get(i, k));
+        }
+    }
+    let rhs = reduced.iter().map(|&i| p[i] - shift[i]).collect();
+
+    let solution = matrix.solve(rhs)?;
+    let mut angle = vec![0.0; bus_types.len()];
+    for (row, &i) in reduced.iter().enumerate() {
+        angle[i] = solution[row];
+    }
+    Some(angle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_bus_problem(load_p: f64, load_q: f64) -> PowerFlowProblem {
+        // Single line with z = 0.01 + j0.1 p.u.
+        let y = Complex::new(0.01, 0.1).inv();
+        let mut g = Matrix::zeros(2);
+        let mut b = Matrix::zeros(2);
+        for (i, k, value) in [(0, 0, y), (1, 1, y), (0, 1, -y), (1, 0, -y)] {
+            g.set(i, k, value.re);
+            b.set(i, k, value.im);
+        }
+
+        PowerFlowProblem {
+            bus_types: vec![BusType::Slack, BusType::Pq],
+            p: vec![0.0, -load_p],
+            q: vec![0.0, -load_q],
+            v: vec![1.0, 1.0],
+            g,
+            b,
+        }
+    }
+
+    #[test]
+    fn test_matrix_solve() {
+        let mut matrix = Matrix::zeros(2);
+        matrix.set(0, 0, 0.0);
+        matrix.set(0, 1, 2.0);
+        matrix.set(1, 0, 3.0);
+        matrix.set(1, 1, 1.0);
+
+        let solution = matrix.solve(vec![4.0, 5.0]).unwrap();
+        assert!((solution[0] - 1.0).abs() < 1e-12);
+        assert!((solution[1] - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_singular_matrix() {
+        let matrix = Matrix::zeros(2);
+        assert!(matrix.solve(vec![1.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn test_two_bus_newton_raphson() {
+        let problem = two_bus_problem(0.5, 0.2);
+        let solution = problem.solve_newton_raphson(10, 1e-8);
+        assert!(solution.converged);
+
+        // The computed injection at the load bus matches the specification
+        let (p, q) = problem.injections(&solution.v, &solution.angle);
+        assert!((p[1] + 0.5).abs() < 1e-8);
+        assert!((q[1] + 0.2).abs() < 1e-8);
+
+        // The slack supplies the load plus the series losses
+        let drop = Complex::from_polar(solution.v[1], solution.angle[1]) - Complex::new(1.0, 0.0);
+        let losses = drop.norm().powi(2) * Complex::new(0.01, 0.1).inv().norm().powi(2) * 0.01;
+        assert!((p[0] - 0.5 - losses).abs() < 1e-6);
+        assert!(solution.v[1] < 1.0);
+        assert!(solution.angle[1] < 0.0);
+    }
+
+    #[test]
+    fn test_two_bus_divergence() {
+        // Far beyond the maximum transfer capacity of the line
+        let problem = two_bus_problem(50.0, 20.0);
+        let solution = problem.solve_newton_raphson(15, 1e-8);
+        assert!(!solution.converged);
+    }
+
+    #[test]
+    fn test_dc() {
+        let mut susceptance = Matrix::zeros(2);
+        susceptance.set(0, 0, 10.0);
+        susceptance.set(1, 1, 10.0);
+        susceptance.set(0, 1, -10.0);
+        susceptance.set(1, 0, -10.0);
+
+        let angle = solve_dc(
+            &[BusType::Slack, BusType::Pq],
+            &[0.0, -0.5],
+            &susceptance,
+            &[0.0, 0.0],
+        )
+        .unwrap();
+        assert!((angle[1] + 0.05).abs() < 1e-12);
+    }
+}
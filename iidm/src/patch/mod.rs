@@ -0,0 +1,301 @@
+//! Partial updates of components from JSON documents.
+//!
+//! Two request formats are supported besides the generated updaters: JSON Merge Patch
+//! (RFC 7396), applied field by field through `Patchable`, and JSON Patch (RFC 6902), applied
+//! on the serialized component. Both are atomic, the component is left untouched on error.
+
+mod updaters;
+
+pub use updaters::*;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::entities::*;
+use crate::libs::json_patch::{apply_operations, PatchOperation};
+use crate::Patchable;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PatchError {
+    #[error("Unknown field '{0}'")]
+    UnknownField(String),
+
+    #[error("Field '{0}' cannot be modified")]
+    ReadOnly(String),
+
+    #[error("Field '{0}' holds elements stored in their own entities, patch them directly")]
+    NestedIdentifiable(String),
+
+    #[error("Field '{0}' cannot be cleared")]
+    NotNullable(String),
+
+    #[error("No element at '{0}'")]
+    UnknownElement(String),
+
+    #[error("Invalid value at '{path}': {message}")]
+    InvalidValue { path: String, message: String },
+
+    #[error("Test failed at '{0}'")]
+    TestFailed(String),
+}
+
+impl PatchError {
+    pub fn invalid_value(message: impl ToString) -> Self {
+        PatchError::InvalidValue {
+            path: String::new(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Prefixes the JSON pointer of the error with a parent segment
+    pub fn nested(self, segment: &str) -> Self {
+        let segment = segment.replace('~', "~0").replace('/', "~1");
+        let prefix = |path: String| format!("/{}{}", segment, path);
+        match self {
+            PatchError::UnknownField(path) => PatchError::UnknownField(prefix(path)),
+            PatchError::ReadOnly(path) => PatchError::ReadOnly(prefix(path)),
+            PatchError::NestedIdentifiable(path) => PatchError::NestedIdentifiable(prefix(path)),
+            PatchError::NotNullable(path) => PatchError::NotNullable(prefix(path)),
+            PatchError::UnknownElement(path) => PatchError::UnknownElement(prefix(path)),
+            PatchError::InvalidValue { path, message } => PatchError::InvalidValue {
+                path: prefix(path),
+                message,
+            },
+            PatchError::TestFailed(path) => PatchError::TestFailed(prefix(path)),
+        }
+    }
+}
+
+/// Request body of a patch, in one of the two standard formats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Patch {
+    Merge(Value),
+    Json(Vec<PatchOperation>),
+}
+
+impl Patch {
+    /// Patched copy of a component
+    pub fn apply<T>(&self, component: &T) -> Result<T, PatchError>
+    where
+        T: Patchable + Serialize + DeserializeOwned + Clone,
+    {
+        match self {
+            Patch::Merge(patch) => {
+                let mut patched = component.clone();
+                patched.merge_patch(patch)?;
+                Ok(patched)
+            }
+            Patch::Json(operations) => {
+                let mut document =
+                    serde_json::to_value(component).map_err(PatchError::invalid_value)?;
+                let id = document.get("id").cloned();
+                apply_operations(&mut document, operations)?;
+                if document.get("id").cloned() != id {
+                    return Err(PatchError::ReadOnly("/id".to_string()));
+                }
+                serde_json::from_value(document).map_err(PatchError::invalid_value)
+            }
+        }
+    }
+}
+
+/// Values without inner structure are replaced as a whole
+macro_rules! replace_patch {
+    ($($value:ty),* $(,)?) => {
+        $(
+            impl Patchable for $value {
+                fn merge_patch(&mut self, patch: &Value) -> Result<(), PatchError> {
+                    if patch.is_null() {
+                        return Err(PatchError::NotNullable(String::new()));
+                    }
+                    *self = <$value>::deserialize(patch).map_err(PatchError::invalid_value)?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+replace_patch!(
+    String,
+    f64,
+    i32,
+    bool,
+    DateTime<FixedOffset>,
+    TopologyKind,
+    EnergySource,
+    LoadType,
+    SwitchKind,
+    StaticVarCompensatorRegulationMode,
+    ConvertersMode,
+    PhaseRegulationMode,
+    RatioRegulationMode,
+    Side,
+    ValidationLevel,
+);
+
+impl<T: Patchable + DeserializeOwned> Patchable for Option<T> {
+    fn merge_patch(&mut self, patch: &Value) -> Result<(), PatchError> {
+        match (self.as_mut(), patch) {
+            (_, Value::Null) => *self = None,
+            (Some(value), patch) => value.merge_patch(patch)?,
+            // A missing value can only be created from a complete one
+            (None, patch) => {
+                *self = Some(T::deserialize(patch).map_err(PatchError::invalid_value)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Patchable + DeserializeOwned> Patchable for Vec<T> {
+    /// An array replaces the list. An object patches its elements: keys are element keys or
+    /// indices, `null` removes the element and unknown keys append a complete new element.
+    fn merge_patch(&mut self, patch: &Value) -> Result<(), PatchError> {
+        let elements = match patch {
+            Value::Null => return Err(PatchError::NotNullable(String::new())),
+            Value::Object(elements) => elements,
+            patch => {
+                *self = Vec::<T>::deserialize(patch).map_err(PatchError::invalid_value)?;
+                return Ok(());
+            }
+        };
+
+        let mut removed = Vec::new();
+        for (key, value) in elements {
+            let position = self
+                .iter()
+                .position(|element| element.patch_key().as_deref() == Some(key))
+                .or_else(|| {
+                    key.parse::<usize>()
+                        .ok()
+                        .filter(|index| *index < self.len())
+                });
+
+            match (position, value) {
+                (Some(position), Value::Null) => removed.push(position),
+                (Some(position), value) => self[position]
+                    .merge_patch(value)
+                    .map_err(|error| error.nested(key))?,
+                (None, Value::Null) => return Err(PatchError::UnknownElement(format!("/{}", key))),
+                (None, value) => {
+                    let element = T::deserialize(value)
+                        .map_err(|error| PatchError::invalid_value(error).nested(key))?;
+                    if element
+                        .patch_key()
+                        .is_some_and(|element_key| element_key != *key)
+                        && key.parse::<usize>().is_err()
+                    {
+                        return Err(PatchError::InvalidValue {
+                            path: format!("/{}", key),
+                            message: "the element key does not match".to_string(),
+                        });
+                    }
+                    self.push(element);
+                }
+            }
+        }
+
+        removed.sort_unstable();
+        removed.dedup();
+        for position in removed.into_iter().rev() {
+            self.remove(position);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn limits() -> CurrentLimits {
+        serde_json::from_value(json!({
+            "permanentLimit": 1000.0,
+            "temporaryLimits": [
+                {"name": "20'", "acceptableDuration": 1200, "value": 1200.0},
+                {"name": "1'", "acceptableDuration": 60, "value": 1500.0}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_list_patched_by_key_and_index() {
+        let mut limits = limits();
+
+        limits
+            .merge_patch(&json!({"temporaryLimits": {"20'": {"value": 1300.0}, "1": null}}))
+            .unwrap();
+
+        assert_eq!(limits.permanent_limit, 1000.0);
+        assert_eq!(limits.temporary_limits.len(), 1);
+        assert_eq!(limits.temporary_limits[0].value, 1300.0);
+    }
+
+    #[test]
+    fn test_list_element_appended_with_its_key() {
+        let mut limits = limits();
+
+        limits
+            .merge_patch(&json!({"temporaryLimits": {
+                "10'": {"name": "10'", "acceptableDuration": 600, "value": 1400.0}
+            }}))
+            .unwrap();
+        assert_eq!(limits.temporary_limits[2].name, "10'");
+
+        let error = limits
+            .merge_patch(&json!({"temporaryLimits": {"5'": {"value": 1.0}}}))
+            .unwrap_err();
+        assert!(
+            matches!(error, PatchError::InvalidValue { path, .. } if path == "/temporaryLimits/5'")
+        );
+    }
+
+    #[test]
+    fn test_errors_point_to_the_field() {
+        let mut limits = limits();
+
+        assert_eq!(
+            limits.merge_patch(&json!({"temporaryLimits": {"0": {"valeur": 1.0}}})),
+            Err(PatchError::UnknownField(
+                "/temporaryLimits/0/valeur".to_string()
+            ))
+        );
+        assert_eq!(
+            limits.merge_patch(&json!({"permanentLimit": null})),
+            Err(PatchError::NotNullable("/permanentLimit".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_json_patch_keeps_id() {
+        let line: Line = serde_json::from_value(json!({
+            "id": "L", "r": 1.0, "x": 10.0, "g1": 0.0, "b1": 0.0, "g2": 0.0, "b2": 0.0,
+            "voltageLevelId1": "VL1", "bus1": "B1", "connectableBus1": "B1",
+            "voltageLevelId2": "VL2", "bus2": "B2", "connectableBus2": "B2"
+        }))
+        .unwrap();
+
+        let patch: Patch = Patch::Json(
+            serde_json::from_value(json!([
+                {"op": "test", "path": "/r", "value": 1.0},
+                {"op": "replace", "path": "/r", "value": 2.0}
+            ]))
+            .unwrap(),
+        );
+        assert_eq!(patch.apply(&line).unwrap().r, 2.0);
+
+        let patch = Patch::Json(
+            serde_json::from_value(json!([{"op": "replace", "path": "/id", "value": "M"}]))
+                .unwrap(),
+        );
+        assert_eq!(
+            patch.apply(&line).unwrap_err(),
+            PatchError::ReadOnly("/id".to_string())
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::PatchError;
+use crate::{Patchable, Updatable};
+
+/// Update of an optional nested structure: `null` clears it, a complete object sets it and a
+/// partial one updates the current value
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum OptionalUpdate<T, U> {
+    Clear,
+    Set(T),
+    Update(U),
+}
+
+impl<T: Updatable<Updater = U>, U> OptionalUpdate<T, U> {
+    pub fn apply(self, value: &mut Option<T>) -> Result<(), PatchError> {
+        match (self, value.as_mut()) {
+            (OptionalUpdate::Clear, _) => *value = None,
+            (OptionalUpdate::Set(set), _) => *value = Some(set),
+            (OptionalUpdate::Update(update), Some(current)) => current.update(update)?,
+            // A missing value can only be created from a complete one
+            (OptionalUpdate::Update(_), None) => {
+                return Err(PatchError::invalid_value(
+                    "no value to update, a complete one is expected",
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Element of a list, found by index when given a number and by key (id, or name for the
+/// limits) when given a string
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ElementKey {
+    Index(usize),
+    Key(String),
+}
+
+impl ElementKey {
+    fn position<T: Patchable>(&self, list: &[T]) -> Option<usize> {
+        match self {
+            ElementKey::Index(index) => (*index < list.len()).then_some(*index),
+            ElementKey::Key(key) => list
+                .iter()
+                .position(|element| element.patch_key().as_deref() == Some(key)),
+        }
+    }
+}
+
+impl fmt::Display for ElementKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElementKey::Index(index) => write!(f, "{}", index),
+            ElementKey::Key(key) => write!(f, "{}", key),
+        }
+    }
+}
+
+/// Change of one element of a list
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum ListEdit<T, U> {
+    /// Inserts a complete element at `index`, at the end when missing
+    Insert {
+        #[serde(default)]
+        index: Option<usize>,
+        value: T,
+    },
+    Update {
+        key: ElementKey,
+        value: U,
+    },
+    Remove {
+        key: ElementKey,
+    },
+}
+
+/// Update of a list: an array of elements replaces it, an array of edits changes its elements in
+/// order
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ListUpdate<T, U> {
+    Set(Vec<T>),
+    Edit(Vec<ListEdit<T, U>>),
+}
+
+impl<T: Updatable<Updater = U> + Patchable, U> ListUpdate<T, U> {
+    pub fn apply(self, list: &mut Vec<T>) -> Result<(), PatchError> {
+        let edits = match self {
+            ListUpdate::Set(elements) => {
+                *list = elements;
+                return Ok(());
+            }
+            ListUpdate::Edit(edits) => edits,
+        };
+
+        for edit in edits {
+            match edit {
+                ListEdit::Insert { index, value } => {
+                    let index = index.unwrap_or(list.len());
+                    if index > list.len() {
+                        return Err(
+                            PatchError::UnknownElement(String::new()).nested(&index.to_string())
+                        );
+                    }
+                    if let Some(key) = value.patch_key() {
+                        if list
+                            .iter()
+                            .any(|element| element.patch_key() == Some(key.clone()))
+                        {
+                            return Err(PatchError::invalid_value(
+                                "an element with this key already exists",
+                            )
+                            .nested(&key));
+                        }
+                    }
+                    list.insert(index, value);
+                }
+                ListEdit::Update { key, value } => {
+                    let position = key.position(list).ok_or_else(|| {
+                        PatchError::UnknownElement(String::new()).nested(&key.to_string())
+                    })?;
+                    list[position]
+                        .update(value)
+                        .map_err(|error| error.nested(&key.to_string()))?;
+                }
+                ListEdit::Remove { key } => {
+                    let position = key.position(list).ok_or_else(|| {
+                        PatchError::UnknownElement(String::new()).nested(&key.to_string())
+                    })?;
+                    list.remove(position);
+                }
+            }
+        }
+        Ok(())
+    }
+}
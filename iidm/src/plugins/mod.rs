@@ -1,6 +1,23 @@
+mod referrers;
+
+use std::any::TypeId;
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
 use bevy_ecs::prelude::*;
 
-use crate::{AssetRegistry, Identifiable, Updatable};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub use referrers::Referrers;
+
+use crate::relations::{
+    attach_child, descendants, free_field, ChildOf, Children, Placement, Required,
+};
+use crate::{
+    references_in, short_type_name, validate, AssetRegistry, Hierarchy, History, Identifiable,
+    Network, Patch, Patchable, Reference, References, Updatable, Validate, ValidationLevel,
+    Violation, VoltageLevel,
+};
 
 #[derive(Event)]
 pub struct UpdateEvent<T: Updatable>
@@ -12,8 +29,9 @@ where
     pub updater: T::Updater,
 }
 
+/// Raised instead of applying a registration, update, patch or removal which fails
 #[derive(Event, Debug, Clone)]
-pub struct EntityNotFoundEvent {
+pub struct ComponentErrorEvent {
     pub id: String,
     pub error_type: ErrorType,
     pub component_type: String,
@@ -23,15 +41,157 @@ pub struct EntityNotFoundEvent {
 pub enum ErrorType {
     EntityNotFound,
     ComponentNotFound,
+    EntityAlreadyExists,
+    /// The patch does not apply to the component, which is left unchanged
+    InvalidPatch(String),
+    /// The modified component breaks the invariants of the network validation level
+    Invalid(Vec<Violation>),
+    /// The parent of a created element is missing, unknown or cannot hold it
+    InvalidParent(String),
+    /// The element cannot be removed without its parent, which is named
+    Required(String),
+    /// The element cannot be removed while the named elements point to it or to an element
+    /// nested in it
+    Referenced(Vec<String>),
+}
+
+/// Raised instead of applying a change which points to voltage levels, buses or equipment
+/// missing from the world
+#[derive(Event, Debug, Clone)]
+pub struct InvalidReferenceEvent {
+    pub id: String,
+    pub component_type: String,
+    /// References added by the change which cannot be resolved
+    pub references: Vec<Reference>,
+}
+
+/// Component modified by a handler, with the network, voltage levels and parents its checks read
+type ChangeQueries<'w, 's, T> = (
+    Query<'w, 's, &'static mut T>,
+    Query<'w, 's, &'static Network>,
+    Query<'w, 's, &'static VoltageLevel>,
+    Query<'w, 's, &'static ChildOf>,
+);
+
+/// Why a modified copy of a component cannot replace it
+enum Rejection {
+    Invalid(Vec<Violation>),
+    References(Vec<Reference>),
 }
 
-pub fn handle_update_events<T: Component + Updatable>(
+/// Level declared by the loaded network, read alongside the modified components
+fn level_of(networks: &Query<&Network>) -> ValidationLevel {
+    networks
+        .iter()
+        .next()
+        .map(|network| network.minimum_validation_level)
+        .unwrap_or_default()
+}
+
+/// References of `changed` which `current` does not have and which cannot be resolved, the
+/// buses of injections being looked up in their voltage level `holder`. The references left
+/// untouched by the change are not checked again.
+fn new_missing_references<T: References>(
+    current: &T,
+    changed: &T,
+    holder: Option<&str>,
+    registry: &AssetRegistry,
+    voltage_levels: &Query<&VoltageLevel>,
+) -> Vec<Reference> {
+    let existing = references_in(current, holder);
+    references_in(changed, holder)
+        .into_iter()
+        .filter(|reference| !existing.contains(reference))
+        .filter(|reference| {
+            !reference.resolves(
+                registry,
+                |entity| voltage_levels.get(entity).ok(),
+                voltage_levels.iter(),
+            )
+        })
+        .collect()
+}
+
+/// Replaces a component by its modified copy when the copy passes the checks, and records the
+/// change in the history
+fn commit<T>(
+    id: &str,
+    entity: Entity,
+    current: &T,
+    changed: T,
+    registry: &AssetRegistry,
+    queries: &mut ParamSet<ChangeQueries<T>>,
+    history: &mut Option<ResMut<History>>,
+) -> Result<(), Rejection>
+where
+    T: Component + Validate + References + Serialize + DeserializeOwned,
+{
+    let violations = validate(&changed, level_of(&queries.p1()));
+    if !violations.is_empty() {
+        return Err(Rejection::Invalid(violations));
+    }
+    let parent = queries
+        .p3()
+        .get(entity)
+        .map(|relation| relation.parent)
+        .ok();
+    let holder =
+        parent.and_then(|parent| queries.p2().get(parent).ok().map(|level| level.id.clone()));
+    let missing = new_missing_references(
+        current,
+        &changed,
+        holder.as_deref(),
+        registry,
+        &queries.p2(),
+    );
+    if !missing.is_empty() {
+        return Err(Rejection::References(missing));
+    }
+
+    if let Ok(mut component) = queries.p0().get_mut(entity) {
+        let previous = std::mem::replace(&mut *component, changed);
+        if let (Some(history), Ok(previous)) = (history.as_mut(), serde_json::to_value(&previous)) {
+            history.record(id, previous, &*component);
+        }
+    }
+    Ok(())
+}
+
+/// Sends the event matching a rejected change
+fn reject<T>(
+    id: &str,
+    rejection: Rejection,
+    error_events: &mut EventWriter<ComponentErrorEvent>,
+    reference_events: &mut EventWriter<InvalidReferenceEvent>,
+) {
+    let component_type = std::any::type_name::<T>().to_string();
+    match rejection {
+        Rejection::Invalid(violations) => {
+            error_events.send(ComponentErrorEvent {
+                id: id.to_string(),
+                error_type: ErrorType::Invalid(violations),
+                component_type,
+            });
+        }
+        Rejection::References(references) => {
+            reference_events.send(InvalidReferenceEvent {
+                id: id.to_string(),
+                component_type,
+                references,
+            });
+        }
+    }
+}
+
+pub fn handle_update_events<T>(
     mut update_events: EventReader<UpdateEvent<T>>,
-    mut error_events: EventWriter<EntityNotFoundEvent>,
+    mut error_events: EventWriter<ComponentErrorEvent>,
+    mut reference_events: EventWriter<InvalidReferenceEvent>,
     registery: Res<AssetRegistry>,
-    mut query: Query<&mut T>,
+    mut queries: ParamSet<ChangeQueries<T>>,
+    mut history: Option<ResMut<History>>,
 ) where
-    T: 'static,
+    T: Component + Updatable + Validate + References + Clone + 'static,
     T::Updater: Send + Sync + Clone,
 {
     for UpdateEvent {
@@ -39,34 +199,109 @@ pub fn handle_update_events<T: Component + Updatable>(
         updater: update,
     } in update_events.read()
     {
-        match registery.find(id) {
-            Some(entity) => {
-                match query.get_mut(entity) {
-                    Ok(mut component) => {
-                        component.update(update.clone());
-                    }
-                    Err(_) => {
-                        // Component exists but has wrong type
-                        error_events.send(EntityNotFoundEvent {
+        let error_type = match registery.find_typed::<T>(id) {
+            Some(entity) => match queries.p0().get(entity).cloned() {
+                Ok(current) => {
+                    let mut updated = current.clone();
+                    if let Err(error) = updated.update(update.clone()) {
+                        error_events.send(ComponentErrorEvent {
                             id: id.clone(),
-                            error_type: ErrorType::ComponentNotFound,
+                            error_type: ErrorType::InvalidPatch(error.to_string()),
                             component_type: std::any::type_name::<T>().to_string(),
                         });
+                        continue;
                     }
+                    let result = commit(
+                        id,
+                        entity,
+                        &current,
+                        updated,
+                        &registery,
+                        &mut queries,
+                        &mut history,
+                    );
+                    if let Err(rejection) = result {
+                        reject::<T>(id, rejection, &mut error_events, &mut reference_events);
+                    }
+                    continue;
                 }
-            }
-            None => {
-                // Entity with this ID doesn't exist
-                error_events.send(EntityNotFoundEvent {
-                    id: id.clone(),
-                    error_type: ErrorType::EntityNotFound,
-                    component_type: std::any::type_name::<T>().to_string(),
-                });
-            }
+                Err(_) => ErrorType::ComponentNotFound,
+            },
+            // Entity with this ID exists for another type only
+            None if registery.find(id).is_some() => ErrorType::ComponentNotFound,
+            // Entity with this ID doesn't exist
+            None => ErrorType::EntityNotFound,
+        };
+        error_events.send(ComponentErrorEvent {
+            id: id.clone(),
+            error_type,
+            component_type: std::any::type_name::<T>().to_string(),
+        });
+    }
+}
+
+/// Modifies an element with a merge patch or a JSON Patch, applied as a whole or not at all
+#[derive(Event)]
+pub struct PatchEvent<T: Component> {
+    pub id: String,
+    pub patch: Patch,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> PatchEvent<T> {
+    pub fn new<S: Into<String>>(id: S, patch: Patch) -> Self {
+        Self {
+            id: id.into(),
+            patch,
+            marker: PhantomData,
         }
     }
 }
 
+pub fn handle_patch_events<T>(
+    mut patch_events: EventReader<PatchEvent<T>>,
+    mut error_events: EventWriter<ComponentErrorEvent>,
+    mut reference_events: EventWriter<InvalidReferenceEvent>,
+    registery: Res<AssetRegistry>,
+    mut queries: ParamSet<ChangeQueries<T>>,
+    mut history: Option<ResMut<History>>,
+) where
+    T: Component + Patchable + Validate + References + Serialize + DeserializeOwned + Clone,
+{
+    for PatchEvent { id, patch, .. } in patch_events.read() {
+        let error_type = match registery.find_typed::<T>(id) {
+            Some(entity) => match queries.p0().get(entity).cloned() {
+                Ok(current) => match patch.apply(&current) {
+                    Ok(patched) => {
+                        let result = commit(
+                            id,
+                            entity,
+                            &current,
+                            patched,
+                            &registery,
+                            &mut queries,
+                            &mut history,
+                        );
+                        if let Err(rejection) = result {
+                            reject::<T>(id, rejection, &mut error_events, &mut reference_events);
+                        }
+                        continue;
+                    }
+                    Err(error) => ErrorType::InvalidPatch(error.to_string()),
+                },
+                Err(_) => ErrorType::ComponentNotFound,
+            },
+            None if registery.find(id).is_some() => ErrorType::ComponentNotFound,
+            None => ErrorType::EntityNotFound,
+        };
+        error_events.send(ComponentErrorEvent {
+            id: id.clone(),
+            error_type,
+            component_type: std::any::type_name::<T>().to_string(),
+        });
+    }
+}
+
 #[derive(Event)]
 pub struct RegisterEvent<T: Component + Identifiable>
 where
@@ -76,14 +311,239 @@ where
     pub component: T,
 }
 
-pub fn handle_register_events<T: Component + Identifiable + Clone>(
+/// Registers an element or replaces the one of the same type and ID, refused when the ID is
+/// taken by another type in a registry with unique IDs. The refusal is only reported when the
+/// world has error events
+pub fn handle_register_events<T: Component + Identifiable + Clone + 'static>(
     mut register_events: EventReader<RegisterEvent<T>>,
+    mut error_events: Option<ResMut<Events<ComponentErrorEvent>>>,
     mut commands: Commands,
     mut registery: ResMut<AssetRegistry>,
-) where
+) {
+    for RegisterEvent { id, component } in register_events.read() {
+        let result = registery.add_component(&mut commands, id, component.clone());
+        if let (Err(_), Some(error_events)) = (result, error_events.as_mut()) {
+            error_events.send(ComponentErrorEvent {
+                id: id.clone(),
+                error_type: ErrorType::EntityAlreadyExists,
+                component_type: std::any::type_name::<T>().to_string(),
+            });
+        }
+    }
+}
+
+/// Adds a new element and its nested identifiables, refused when one of their IDs is already
+/// registered. The element is linked to its parent, which defaults to the loaded network
+#[derive(Event)]
+pub struct CreateEvent<T: Component + Identifiable>
+where
     T: 'static,
 {
-    for RegisterEvent { id, component } in register_events.read() {
-        registery.add_component(&mut commands, id, component.clone());
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub component: T,
+}
+
+/// Removes an element and despawns its entity, along with the elements nested in it
+#[derive(Event)]
+pub struct DeleteEvent<T: Component> {
+    pub id: String,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> DeleteEvent<T> {
+    pub fn new<S: Into<String>>(id: S) -> Self {
+        Self {
+            id: id.into(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Creates the elements which pass the checks, linked to their parent. The buses of an element
+/// created in a voltage level are looked up in it.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_create_events<T>(
+    mut create_events: EventReader<CreateEvent<T>>,
+    mut error_events: EventWriter<ComponentErrorEvent>,
+    mut reference_events: EventWriter<InvalidReferenceEvent>,
+    mut commands: Commands,
+    registery: Res<AssetRegistry>,
+    networks: Query<&Network>,
+    voltage_levels: Query<&VoltageLevel>,
+    (children, relations): (Query<&Children>, Query<&ChildOf>),
+) where
+    T: Component + Identifiable + Hierarchy + Placement + Validate + References + Clone + 'static,
+{
+    let level = level_of(&networks);
+    // Created by the previous events of the run, their commands are not applied yet
+    let mut created = HashSet::new();
+    for CreateEvent {
+        id,
+        parent_id,
+        component,
+    } in create_events.read()
+    {
+        let violations = validate(component, level);
+        let holder = parent_id
+            .as_deref()
+            .filter(|_| TypeId::of::<T::Parent>() == TypeId::of::<VoltageLevel>());
+        let missing: Vec<Reference> = references_in(component, holder)
+            .into_iter()
+            .filter(|reference| {
+                !reference.resolves(
+                    &registery,
+                    |entity| voltage_levels.get(entity).ok(),
+                    voltage_levels.iter(),
+                )
+            })
+            .collect();
+        let error_type = if component.check_available(&registery).is_err() || created.contains(id) {
+            ErrorType::EntityAlreadyExists
+        } else if !violations.is_empty() {
+            ErrorType::Invalid(violations)
+        } else if !missing.is_empty() {
+            reference_events.send(InvalidReferenceEvent {
+                id: id.clone(),
+                component_type: std::any::type_name::<T>().to_string(),
+                references: missing,
+            });
+            continue;
+        } else {
+            match parent_field::<T>(
+                parent_id.as_deref(),
+                &registery,
+                &networks,
+                &children,
+                &relations,
+            ) {
+                Ok(link) => {
+                    created.insert(id.clone());
+                    let (id, component) = (id.clone(), component.clone());
+                    commands.queue(move |world: &mut World| {
+                        component.spawn(world);
+                        if let Some((parent_id, field)) = link {
+                            attach_child::<T::Parent, T>(world, &parent_id, &id, field);
+                        }
+                    });
+                    continue;
+                }
+                Err(message) => ErrorType::InvalidParent(message),
+            }
+        };
+        error_events.send(ComponentErrorEvent {
+            id: id.clone(),
+            error_type,
+            component_type: std::any::type_name::<T>().to_string(),
+        });
+    }
+}
+
+/// Parent id and field a created `T` is linked with, none for the elements which are not nested
+fn parent_field<T: Placement>(
+    parent_id: Option<&str>,
+    registery: &AssetRegistry,
+    networks: &Query<&Network>,
+    children: &Query<&Children>,
+    relations: &Query<&ChildOf>,
+) -> Result<Option<(String, &'static str)>, String> {
+    if T::FIELDS.is_empty() {
+        return Ok(None);
+    }
+    let parent_type = short_type_name::<T::Parent>();
+    let parent_id = match parent_id {
+        Some(parent_id) => parent_id.to_string(),
+        None => networks
+            .iter()
+            .map(|network| network.id.clone())
+            .find(|network_id| registery.find_typed::<T::Parent>(network_id).is_some())
+            .ok_or_else(|| format!("The id of the parent {} is required", parent_type))?,
+    };
+    let parent = registery
+        .find_typed::<T::Parent>(&parent_id)
+        .ok_or_else(|| format!("Unknown parent {} '{}'", parent_type, parent_id))?;
+    let field = free_field::<T>(children, relations, parent).ok_or_else(|| {
+        format!(
+            "{} '{}' cannot hold another {}",
+            parent_type,
+            parent_id,
+            short_type_name::<T>()
+        )
+    })?;
+    Ok(Some((parent_id, field)))
+}
+
+/// Removes the elements along with the ones nested in them, refused while other elements point
+/// to one of them
+#[allow(clippy::too_many_arguments)]
+pub fn handle_delete_events<T: Component>(
+    mut delete_events: EventReader<DeleteEvent<T>>,
+    mut error_events: EventWriter<ComponentErrorEvent>,
+    mut commands: Commands,
+    mut registery: ResMut<AssetRegistry>,
+    query: Query<(), With<T>>,
+    children: Query<&Children>,
+    required: Query<&ChildOf, With<Required>>,
+    referrers: Referrers,
+) {
+    // Removed by the previous events of the run, their commands are not applied yet
+    let mut removed = HashSet::new();
+    for DeleteEvent { id, .. } in delete_events.read() {
+        let required_by = |entity| {
+            let parent = required.get(entity).ok()?.parent;
+            Some(format!(
+                "{} '{}'",
+                registery.type_name_of(parent)?,
+                registery.id_of(parent)?
+            ))
+        };
+        let error_type = match registery.find_typed::<T>(id) {
+            Some(entity) if query.contains(entity) => {
+                if let Some(parent) = required_by(entity) {
+                    error_events.send(ComponentErrorEvent {
+                        id: id.clone(),
+                        error_type: ErrorType::Required(parent),
+                        component_type: std::any::type_name::<T>().to_string(),
+                    });
+                    continue;
+                }
+                let nested = descendants(&children, entity);
+                let mut removing = removed.clone();
+                removing.extend(nested.iter().copied());
+                let referenced_by = referrers.referring_to(&removing, &registery);
+                if !referenced_by.is_empty() {
+                    let mut names: Vec<String> = referenced_by
+                        .into_iter()
+                        .filter_map(|referrer| {
+                            Some(format!(
+                                "{} '{}'",
+                                registery.type_name_of(referrer)?,
+                                registery.id_of(referrer)?
+                            ))
+                        })
+                        .collect();
+                    names.sort();
+                    error_events.send(ComponentErrorEvent {
+                        id: id.clone(),
+                        error_type: ErrorType::Referenced(names),
+                        component_type: std::any::type_name::<T>().to_string(),
+                    });
+                    continue;
+                }
+                for nested in nested {
+                    registery.remove_entity(&mut commands, nested);
+                }
+                removed = removing;
+                continue;
+            }
+            Some(_) => ErrorType::ComponentNotFound,
+            None if registery.find(id).is_some() => ErrorType::ComponentNotFound,
+            None => ErrorType::EntityNotFound,
+        };
+        error_events.send(ComponentErrorEvent {
+            id: id.clone(),
+            error_type,
+            component_type: std::any::type_name::<T>().to_string(),
+        });
     }
 }
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+use bevy_ecs::{prelude::*, system::SystemParam};
+
+use crate::entities::*;
+use crate::relations::ChildOf;
+use crate::{references_in, AssetRegistry, Reference};
+
+/// Every element which may point to another one, read to find the references a deletion would
+/// leave dangling
+#[derive(SystemParam)]
+pub struct Referrers<'w, 's> {
+    lines: Query<'w, 's, (Entity, &'static Line)>,
+    two_windings_transformers: Query<'w, 's, (Entity, &'static TwoWindingsTransformer)>,
+    three_windings_transformers: Query<'w, 's, (Entity, &'static ThreeWindingsTransformer)>,
+    switches: Query<'w, 's, (Entity, &'static Switch)>,
+    converter_stations: Query<'w, 's, (Entity, &'static HvdcConverterStation)>,
+    terminal_refs: Query<'w, 's, (Entity, &'static TerminalRef)>,
+    generators: Query<'w, 's, (Entity, &'static Generator)>,
+    loads: Query<'w, 's, (Entity, &'static Load)>,
+    busbar_sections: Query<'w, 's, (Entity, &'static BusbarSection)>,
+    shunt_compensators: Query<'w, 's, (Entity, &'static ShuntCompensator)>,
+    static_var_compensators: Query<'w, 's, (Entity, &'static StaticVarCompensator)>,
+    dangling_lines: Query<'w, 's, (Entity, &'static DanglingLine)>,
+    relations: Query<'w, 's, &'static ChildOf>,
+    voltage_levels: Query<'w, 's, &'static VoltageLevel>,
+    buses: Query<'w, 's, &'static Bus>,
+}
+
+/// Voltage levels, buses and equipment about to be removed
+#[derive(Default)]
+struct Targets {
+    voltage_levels: HashSet<String>,
+    /// Configured buses, with the voltage level holding them
+    buses: HashSet<(Option<String>, String)>,
+    ids: HashSet<String>,
+}
+
+impl Targets {
+    fn contains(&self, reference: &Reference) -> bool {
+        match reference {
+            Reference::VoltageLevel { id } => self.voltage_levels.contains(id),
+            Reference::Bus {
+                voltage_level_id: Some(voltage_level_id),
+                bus,
+            } => {
+                self.voltage_levels.contains(voltage_level_id)
+                    || self
+                        .buses
+                        .contains(&(Some(voltage_level_id.clone()), bus.clone()))
+            }
+            Reference::Bus {
+                voltage_level_id: None,
+                bus,
+            } => self.buses.iter().any(|(_, id)| id == bus),
+            Reference::Equipment { id } => self.ids.contains(id),
+        }
+    }
+}
+
+impl Referrers<'_, '_> {
+    /// Id of the voltage level holding an element, if any
+    fn holder(&self, entity: Entity) -> Option<&str> {
+        let parent = self.relations.get(entity).ok()?.parent;
+        self.voltage_levels
+            .get(parent)
+            .ok()
+            .map(|voltage_level| voltage_level.id.as_str())
+    }
+
+    /// Elements outside of `removed` pointing to one of its elements
+    pub fn referring_to(&self, removed: &HashSet<Entity>, registry: &AssetRegistry) -> Vec<Entity> {
+        let mut targets = Targets::default();
+        for &entity in removed {
+            if let Ok(voltage_level) = self.voltage_levels.get(entity) {
+                targets.voltage_levels.insert(voltage_level.id.clone());
+            }
+            if let Ok(bus) = self.buses.get(entity) {
+                let holder = self.holder(entity).map(str::to_string);
+                targets.buses.insert((holder, bus.id.clone()));
+            }
+            if let Some(id) = registry.id_of(entity) {
+                targets.ids.insert(id.to_string());
+            }
+        }
+
+        let mut found = Vec::new();
+        let mut check = |entity: Entity, references: Vec<Reference>| {
+            if !removed.contains(&entity) && references.iter().any(|r| targets.contains(r)) {
+                found.push(entity);
+            }
+        };
+        macro_rules! scan {
+            ($($query:ident),*) => {
+                $(
+                    for (entity, element) in &self.$query {
+                        check(entity, references_in(element, self.holder(entity)));
+                    }
+                )*
+            };
+        }
+        scan!(
+            lines,
+            two_windings_transformers,
+            three_windings_transformers,
+            switches,
+            converter_stations,
+            terminal_refs,
+            generators,
+            loads,
+            busbar_sections,
+            shunt_compensators,
+            static_var_compensators,
+            dangling_lines
+        );
+        found
+    }
+}
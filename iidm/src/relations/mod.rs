@@ -0,0 +1,271 @@
+//! Parent/child relations between nested identifiables.
+//!
+//! Registering a network stores every nested identifiable (substations, voltage levels, lines,
+//! generators...) in its own entity only: the parent component keeps empty lists, and the child
+//! entity points back to it through `ChildOf`. The nesting is rebuilt on demand by `assemble`, so
+//! an update of a `Line` is seen by whoever serializes the `Network` afterwards.
+
+use std::collections::BTreeSet;
+
+use bevy_ecs::prelude::*;
+
+use crate::{AssetRegistry, Hierarchy, Reference, References, Substation, VoltageLevel};
+
+/// Parent entity of a nested identifiable and the parent field holding it
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChildOf {
+    pub parent: Entity,
+    pub field: &'static str,
+}
+
+/// Child held in a required single field of its parent, which cannot be removed on its own
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Required;
+
+/// Nested identifiables of an entity, in declaration order.
+///
+/// A component with `Children` is assembled from its relations only, components created
+/// without them keep their nested elements as they were given.
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Children(Vec<Entity>);
+
+impl Children {
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Parent of the elements created on their own, which links them where the export expects them
+pub trait Placement {
+    /// Type of the parent, named by the `parentId` of the create requests unless it is the network
+    type Parent: Component;
+    /// Fields of the parent which may hold the element, none when it is not nested
+    const FIELDS: &'static [&'static str];
+    /// Each field holds a single element, the first free one taking the created element
+    const SINGLE: bool = false;
+}
+
+/// Field of `parent` taking a new `T`, none when its single fields are all taken
+pub fn free_field<T: Placement>(
+    children: &Query<&Children>,
+    relations: &Query<&ChildOf>,
+    parent: Entity,
+) -> Option<&'static str> {
+    let taken = |field: &str| {
+        children.get(parent).is_ok_and(|children| {
+            children.iter().any(|child| {
+                relations
+                    .get(child)
+                    .is_ok_and(|relation| relation.field == field)
+            })
+        })
+    };
+    T::FIELDS
+        .iter()
+        .copied()
+        .find(|field| !T::SINGLE || !taken(field))
+}
+
+/// Marks a registered element as a parent whose nesting comes from its relations
+pub fn init_children<T: Component>(world: &mut World, id: &str) {
+    let Some(entity) = world.resource::<AssetRegistry>().find_typed::<T>(id) else {
+        return;
+    };
+    if let Ok(mut entity) = world.get_entity_mut(entity) {
+        if !entity.contains::<Children>() {
+            entity.insert(Children::default());
+        }
+    }
+}
+
+/// Links two registered elements, nothing is done when one of them has no entity
+pub fn attach_child<P: Component, C: Component>(
+    world: &mut World,
+    parent_id: &str,
+    child_id: &str,
+    field: &'static str,
+) {
+    let registry = world.resource::<AssetRegistry>();
+    let (Some(parent), Some(child)) = (
+        registry.find_typed::<P>(parent_id),
+        registry.find_typed::<C>(child_id),
+    ) else {
+        return;
+    };
+    if parent == child || world.get_entity(child).is_err() {
+        return;
+    }
+
+    // A re-registered child may move to another parent
+    if let Some(previous) = world.get::<ChildOf>(child).copied() {
+        if previous.parent != parent {
+            if let Some(mut children) = world.get_mut::<Children>(previous.parent) {
+                children.0.retain(|candidate| *candidate != child);
+            }
+        }
+    }
+
+    world.entity_mut(child).insert(ChildOf { parent, field });
+    match world.get_mut::<Children>(parent) {
+        Some(mut children) if !children.0.contains(&child) => children.0.push(child),
+        Some(_) => {}
+        None => {
+            if let Ok(mut parent) = world.get_entity_mut(parent) {
+                parent.insert(Children(vec![child]));
+            }
+        }
+    }
+}
+
+/// Links a child which its parent cannot lack, the parent field being left blank
+pub fn attach_required_child<P: Component, C: Component>(
+    world: &mut World,
+    parent_id: &str,
+    child_id: &str,
+    field: &'static str,
+) {
+    attach_child::<P, C>(world, parent_id, child_id, field);
+    let child = world.resource::<AssetRegistry>().find_typed::<C>(child_id);
+    if let Some(mut child) = child.and_then(|child| world.get_entity_mut(child).ok()) {
+        child.insert(Required);
+    }
+}
+
+/// Assembled children of `entity` held in `field`, despawned children are skipped
+pub fn children<T>(world: &World, entity: Entity, field: &str) -> Vec<T>
+where
+    T: Component + Hierarchy + Clone,
+{
+    let Some(children) = world.get::<Children>(entity) else {
+        return Vec::new();
+    };
+
+    children
+        .iter()
+        .filter(|child| {
+            world
+                .get::<ChildOf>(*child)
+                .is_some_and(|relation| relation.parent == entity && relation.field == field)
+        })
+        .filter_map(|child| assemble::<T>(world, child))
+        .collect()
+}
+
+/// Component of an entity with its nested identifiables put back in place
+pub fn assemble<T>(world: &World, entity: Entity) -> Option<T>
+where
+    T: Component + Hierarchy + Clone,
+{
+    let mut component = world.get::<T>(entity)?.clone();
+    if world.get::<Children>(entity).is_some() {
+        component.attach_children(world, entity);
+    }
+    Some(component)
+}
+
+/// Whether the parent of an element is still alive, elements without parent are roots
+pub fn has_parent(world: &World, entity: Entity) -> bool {
+    world
+        .get::<ChildOf>(entity)
+        .is_some_and(|relation| world.get_entity(relation.parent).is_ok())
+}
+
+/// Entity and every entity nested below it, parents first
+pub fn descendants(children: &Query<&Children>, entity: Entity) -> Vec<Entity> {
+    let mut entities = vec![entity];
+    let mut position = 0;
+    while position < entities.len() {
+        if let Ok(nested) = children.get(entities[position]) {
+            entities.extend(nested.iter());
+        }
+        position += 1;
+    }
+    entities
+}
+
+/// Unregisters and despawns the entities along with every entity nested below them
+pub fn remove_with_descendants(world: &mut World, roots: impl IntoIterator<Item = Entity>) {
+    let mut entities = Vec::new();
+    let mut pending: Vec<Entity> = roots.into_iter().collect();
+    while let Some(entity) = pending.pop() {
+        if let Some(children) = world.get::<Children>(entity) {
+            pending.extend(children.iter());
+        }
+        entities.push(entity);
+    }
+    world.resource_scope(|world, mut registry: Mut<AssetRegistry>| {
+        let mut commands = world.commands();
+        for entity in entities {
+            registry.remove_entity(&mut commands, entity);
+        }
+    });
+    world.flush();
+}
+
+/// Voltage levels and substations an element is part of or connected to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    pub voltage_levels: BTreeSet<String>,
+    pub substations: BTreeSet<String>,
+}
+
+impl Location {
+    /// Adds the voltage levels and substations found from `entity` up to its root
+    fn add_ancestors(&mut self, world: &World, entity: Entity) {
+        let mut current = Some(entity);
+        while let Some(entity) = current {
+            if let Some(voltage_level) = world.get::<VoltageLevel>(entity) {
+                self.voltage_levels.insert(voltage_level.id.clone());
+            }
+            if let Some(substation) = world.get::<Substation>(entity) {
+                self.substations.insert(substation.id.clone());
+            }
+            current = world.get::<ChildOf>(entity).map(|relation| relation.parent);
+        }
+    }
+}
+
+/// Location of the element of `entity`, from its parents and the voltage levels its terminals
+/// refer to
+pub fn location<T: Component + References>(world: &World, entity: Entity) -> Location {
+    let references = world
+        .get::<T>(entity)
+        .map(References::references)
+        .unwrap_or_default();
+    locate(world, entity, &references)
+}
+
+/// Location of the element of `entity` whose references are already known
+pub fn locate(world: &World, entity: Entity, references: &[Reference]) -> Location {
+    let mut location = Location::default();
+    location.add_ancestors(world, entity);
+
+    let Some(registry) = world.get_resource::<AssetRegistry>() else {
+        return location;
+    };
+    for reference in references {
+        let voltage_level_id = match reference {
+            Reference::VoltageLevel { id } => id,
+            Reference::Bus {
+                voltage_level_id: Some(id),
+                ..
+            } => id,
+            _ => continue,
+        };
+        match registry.find_typed::<VoltageLevel>(voltage_level_id) {
+            Some(voltage_level) => location.add_ancestors(world, voltage_level),
+            None => {
+                location.voltage_levels.insert(voltage_level_id.clone());
+            }
+        }
+    }
+    location
+}
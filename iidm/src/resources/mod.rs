@@ -1,5 +1,7 @@
 use bevy_ecs::prelude::*;
-use std::collections::HashMap;
+use std::any::TypeId;
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
 
 /// Unique identifier component for an entity
 #[derive(Debug, Clone, Component)]
@@ -15,46 +17,330 @@ impl Id {
     }
 }
 
-/// Registry for managing entities with unique identifiers
+/// Type name without its module path
+pub fn short_type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// Registration refused by the registry
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RegistryError {
+    #[error("{type_name} '{id}' is already registered")]
+    AlreadyRegistered { id: String, type_name: &'static str },
+
+    #[error("ID '{id}' is already used by a {type_name}")]
+    IdTaken { id: String, type_name: &'static str },
+}
+
+/// Component type and ID an entity is registered under
+#[derive(Debug, Clone)]
+struct Registration {
+    type_id: TypeId,
+    type_name: &'static str,
+    id: String,
+}
+
+/// Registry for managing entities by component type and identifier.
+///
+/// Each component type has its own namespace of IDs, so a generator and a load may share one
+/// and still get their own entity. With `unique_ids`, an ID can only be registered once over
+/// all the types, as IIDM requires.
 #[derive(Resource, Default)]
 pub struct AssetRegistry {
-    entities: HashMap<String, Entity>,
+    entities: HashMap<(TypeId, String), Entity>,
+    /// Entities sharing an ID, in registration order, sorted by ID for the prefix searches
+    ids: BTreeMap<String, Vec<Entity>>,
+    registrations: HashMap<Entity, Registration>,
+    /// Number of entities registered for each type
+    counts: HashMap<TypeId, (&'static str, usize)>,
+    unique_ids: bool,
 }
 
 impl AssetRegistry {
-    /// Creates a new entity with an ID and registers it
-    pub fn register<S: Into<String>>(&mut self, commands: &mut Commands, id: S) -> Entity {
+    /// Registry refusing to register an ID twice, whatever the types
+    pub fn with_unique_ids() -> Self {
+        Self {
+            unique_ids: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn unique_ids(&self) -> bool {
+        self.unique_ids
+    }
+
+    pub fn set_unique_ids(&mut self, unique_ids: bool) {
+        self.unique_ids = unique_ids;
+    }
+
+    /// Checks that an ID can be registered for the component type `C`
+    pub fn check_available<C: Component>(&self, id: &str) -> Result<(), RegistryError> {
+        if self.find_typed::<C>(id).is_some() {
+            return Err(RegistryError::AlreadyRegistered {
+                id: id.to_string(),
+                type_name: short_type_name::<C>(),
+            });
+        }
+        match self
+            .find(id)
+            .and_then(|entity| self.registrations.get(&entity))
+        {
+            Some(registration) if self.unique_ids => Err(RegistryError::IdTaken {
+                id: id.to_string(),
+                type_name: registration.type_name,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates a new entity with an ID and registers it for the component type `C`, which is
+    /// left to the caller to insert
+    pub fn register<C: Component>(
+        &mut self,
+        commands: &mut Commands,
+        id: impl Into<String>,
+    ) -> Result<Entity, RegistryError> {
         let id = id.into();
+        self.check_available::<C>(&id)?;
+
         let entity = commands.spawn(Id::new(id.clone())).id();
-        self.entities.insert(id, entity);
-        entity
+        self.entities
+            .insert((TypeId::of::<C>(), id.clone()), entity);
+        self.ids.entry(id.clone()).or_default().push(entity);
+        self.counts
+            .entry(TypeId::of::<C>())
+            .or_insert((short_type_name::<C>(), 0))
+            .1 += 1;
+        self.registrations.insert(
+            entity,
+            Registration {
+                type_id: TypeId::of::<C>(),
+                type_name: short_type_name::<C>(),
+                id,
+            },
+        );
+        Ok(entity)
+    }
+
+    /// Finds an entity by its ID whatever its type, the first registered when several types
+    /// share the ID
+    pub fn find<S: AsRef<str>>(&self, id: S) -> Option<Entity> {
+        self.ids
+            .get(id.as_ref())
+            .and_then(|entities| entities.first())
+            .copied()
+    }
+
+    /// Finds the entity registered under an ID for the component type `C`
+    pub fn find_typed<C: Component>(&self, id: impl AsRef<str>) -> Option<Entity> {
+        self.entities
+            .get(&(TypeId::of::<C>(), id.as_ref().to_string()))
+            .copied()
     }
 
-    /// Finds an entity by its ID
-    pub fn find<S: Into<String>>(&self, id: S) -> Option<Entity> {
-        self.entities.get(&id.into()).copied()
+    /// Entities registered under an ID, one per component type, in registration order
+    pub fn find_all(&self, id: impl AsRef<str>) -> &[Entity] {
+        self.ids.get(id.as_ref()).map_or(&[], Vec::as_slice)
     }
 
-    /// Adds or updates a component on an entity, creating the entity if it doesn't exist
-    pub fn add_component<S, C>(&mut self, commands: &mut Commands, id: S, component: C)
+    /// ID an entity is registered under
+    pub fn id_of(&self, entity: Entity) -> Option<&str> {
+        self.registrations
+            .get(&entity)
+            .map(|registration| registration.id.as_str())
+    }
+
+    /// Component type an entity is registered for, without its module path
+    pub fn type_name_of(&self, entity: Entity) -> Option<&'static str> {
+        self.registrations
+            .get(&entity)
+            .map(|registration| registration.type_name)
+    }
+
+    /// Registered IDs, sorted, each given once whatever the number of types sharing it
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.ids.keys().map(String::as_str)
+    }
+
+    /// Every registered entity with its ID, sorted by ID
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Entity)> {
+        Self::flatten(self.ids.iter())
+    }
+
+    /// Entities registered for the component type `C` with their ID, sorted by ID
+    pub fn iter_typed<C: Component>(&self) -> impl Iterator<Item = (&str, Entity)> {
+        self.of_type::<C>(self.iter())
+    }
+
+    /// Registered entities whose ID starts with `prefix`, sorted by ID
+    pub fn with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a str, Entity)> {
+        Self::flatten(
+            self.ids
+                .range::<str, _>((
+                    std::ops::Bound::Included(prefix),
+                    std::ops::Bound::Unbounded,
+                ))
+                .take_while(move |(id, _)| id.starts_with(prefix)),
+        )
+    }
+
+    /// Registered entities whose ID matches a pattern where `*` stands for any sequence of
+    /// characters and `?` for one character, sorted by ID
+    pub fn matching<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = (&'a str, Entity)> {
+        // The searched IDs all start with the literal part of the pattern
+        let prefix = pattern
+            .find(['*', '?'])
+            .map_or(pattern, |wildcard| &pattern[..wildcard]);
+        self.with_prefix(prefix)
+            .filter(move |(id, _)| matches_pattern(id, pattern))
+    }
+
+    /// Number of registered entities
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+
+    /// Number of entities registered for the component type `C`
+    pub fn count<C: Component>(&self) -> usize {
+        self.counts
+            .get(&TypeId::of::<C>())
+            .map_or(0, |(_, count)| *count)
+    }
+
+    /// Number of registered entities by component type, named without their module path
+    pub fn counts(&self) -> BTreeMap<&'static str, usize> {
+        self.counts.values().copied().collect()
+    }
+
+    fn flatten<'a>(
+        ids: impl Iterator<Item = (&'a String, &'a Vec<Entity>)>,
+    ) -> impl Iterator<Item = (&'a str, Entity)> {
+        ids.flat_map(|(id, entities)| entities.iter().map(move |entity| (id.as_str(), *entity)))
+    }
+
+    fn of_type<'a, C: Component>(
+        &'a self,
+        entities: impl Iterator<Item = (&'a str, Entity)>,
+    ) -> impl Iterator<Item = (&'a str, Entity)> {
+        entities.filter(move |(_, entity)| {
+            self.registrations
+                .get(entity)
+                .is_some_and(|registration| registration.type_id == TypeId::of::<C>())
+        })
+    }
+
+    /// Adds or replaces the component `C` registered under an ID, creating its entity if it
+    /// doesn't exist
+    pub fn add_component<S, C>(
+        &mut self,
+        commands: &mut Commands,
+        id: S,
+        component: C,
+    ) -> Result<Entity, RegistryError>
     where
         S: Into<String>,
         C: Component,
     {
         let id = id.into();
-        let entity = self
-            .find(&id)
-            .unwrap_or_else(|| self.register(commands, id));
+        let entity = match self.find_typed::<C>(&id) {
+            Some(entity) => entity,
+            None => self.register::<C>(commands, id)?,
+        };
 
         commands.entity(entity).insert(component);
+        Ok(entity)
+    }
+
+    /// Unregisters an ID and despawns its entity, the first registered when several types share
+    /// the ID. Returns the removed entity if any
+    pub fn remove<S: AsRef<str>>(&mut self, commands: &mut Commands, id: S) -> Option<Entity> {
+        let entity = self.find(id)?;
+        self.remove_entity(commands, entity).then_some(entity)
+    }
+
+    /// Unregisters the ID of the component type `C` and despawns its entity
+    pub fn remove_typed<C: Component>(
+        &mut self,
+        commands: &mut Commands,
+        id: impl AsRef<str>,
+    ) -> Option<Entity> {
+        let entity = self.find_typed::<C>(id)?;
+        self.remove_entity(commands, entity).then_some(entity)
+    }
+
+    /// Unregisters a registered entity and despawns it, returns false when it is unknown
+    pub fn remove_entity(&mut self, commands: &mut Commands, entity: Entity) -> bool {
+        let Some(registration) = self.registrations.remove(&entity) else {
+            return false;
+        };
+
+        self.entities
+            .remove(&(registration.type_id, registration.id.clone()));
+        if let Some(entities) = self.ids.get_mut(&registration.id) {
+            entities.retain(|candidate| *candidate != entity);
+            if entities.is_empty() {
+                self.ids.remove(&registration.id);
+            }
+        }
+        if let Some((_, count)) = self.counts.get_mut(&registration.type_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&registration.type_id);
+            }
+        }
+        commands.entity(entity).despawn();
+        true
     }
 }
 
+/// Glob matching of a whole ID, `*` matching any sequence and `?` a single character
+fn matches_pattern(id: &str, pattern: &str) -> bool {
+    let id: Vec<char> = id.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut position, mut next) = (0, 0);
+    // Last star seen in the pattern and the ID position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while position < id.len() {
+        match pattern.get(next) {
+            Some('*') => {
+                backtrack = Some((next, position));
+                next += 1;
+            }
+            Some(&character) if character == '?' || character == id[position] => {
+                position += 1;
+                next += 1;
+            }
+            _ => match backtrack {
+                Some((star, tried)) => {
+                    // Let the star swallow one more character
+                    next = star + 1;
+                    position = tried + 1;
+                    backtrack = Some((star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[next..].iter().all(|character| *character == '*')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use bevy_ecs::world::{CommandQueue, World};
 
+    #[derive(Component, Debug)]
+    struct TestComponent;
+
+    #[derive(Component, Debug)]
+    struct OtherComponent;
+
     #[test]
     fn test_id_creation() {
         let id = Id::new("test_entity");
@@ -64,31 +350,33 @@ mod tests {
     #[test]
     fn test_register_new_entity() {
         let mut world = World::new();
-        let mut registry = AssetRegistry {
-            entities: HashMap::new(),
-        };
+        let mut registry = AssetRegistry::default();
 
         {
             let mut queue = CommandQueue::default();
-            let mut commands = Commands::new(&mut queue, &mut world);
-            registry.register(&mut commands, "test_entity");
+            let mut commands = Commands::new(&mut queue, &world);
+            registry
+                .register::<TestComponent>(&mut commands, "test_entity")
+                .unwrap();
             queue.apply(&mut world);
         }
 
-        assert!(registry.entities.contains_key("test_entity"));
+        assert!(registry
+            .find_typed::<TestComponent>("test_entity")
+            .is_some());
     }
 
     #[test]
     fn test_find_entity() {
         let mut world = World::new();
-        let mut registry = AssetRegistry {
-            entities: HashMap::new(),
-        };
+        let mut registry = AssetRegistry::default();
 
         {
             let mut queue = CommandQueue::default();
-            let mut commands = Commands::new(&mut queue, &mut world);
-            registry.register(&mut commands, "test_entity");
+            let mut commands = Commands::new(&mut queue, &world);
+            registry
+                .register::<TestComponent>(&mut commands, "test_entity")
+                .unwrap();
             queue.apply(&mut world);
         }
 
@@ -98,9 +386,7 @@ mod tests {
 
     #[test]
     fn test_find_nonexistent_entity() {
-        let registry = AssetRegistry {
-            entities: HashMap::new(),
-        };
+        let registry = AssetRegistry::default();
         let found = registry.find("nonexistent");
         assert!(found.is_none());
     }
@@ -108,17 +394,14 @@ mod tests {
     #[test]
     fn test_add_component() {
         let mut world = World::new();
-        let mut registry = AssetRegistry {
-            entities: HashMap::new(),
-        };
-
-        #[derive(Component, Debug)]
-        struct TestComponent;
+        let mut registry = AssetRegistry::default();
 
         {
             let mut queue = CommandQueue::default();
-            let mut commands = Commands::new(&mut queue, &mut world);
-            registry.add_component(&mut commands, "test_entity", TestComponent);
+            let mut commands = Commands::new(&mut queue, &world);
+            registry
+                .add_component(&mut commands, "test_entity", TestComponent)
+                .unwrap();
             queue.apply(&mut world);
         }
 
@@ -129,25 +412,24 @@ mod tests {
     #[test]
     fn test_add_component_to_existing_entity() {
         let mut world = World::new();
-        let mut registry = AssetRegistry {
-            entities: HashMap::new(),
-        };
+        let mut registry = AssetRegistry::default();
 
         let entity = {
             let mut queue = CommandQueue::default();
-            let mut commands = Commands::new(&mut queue, &mut world);
-            let entity = registry.register(&mut commands, "test_entity");
+            let mut commands = Commands::new(&mut queue, &world);
+            let entity = registry
+                .register::<TestComponent>(&mut commands, "test_entity")
+                .unwrap();
             queue.apply(&mut world);
             entity
         };
 
-        #[derive(Component, Debug)]
-        struct TestComponent;
-
         {
             let mut queue = CommandQueue::default();
-            let mut commands = Commands::new(&mut queue, &mut world);
-            registry.add_component(&mut commands, "test_entity", TestComponent);
+            let mut commands = Commands::new(&mut queue, &world);
+            registry
+                .add_component(&mut commands, "test_entity", TestComponent)
+                .unwrap();
             queue.apply(&mut world);
         }
 
@@ -155,4 +437,164 @@ mod tests {
         assert_eq!(entity, found);
         assert!(world.entity(entity).contains::<TestComponent>());
     }
+
+    #[test]
+    fn test_remove_entity() {
+        let mut world = World::new();
+        let mut registry = AssetRegistry::default();
+
+        let entity = {
+            let mut queue = CommandQueue::default();
+            let mut commands = Commands::new(&mut queue, &world);
+            let entity = registry
+                .register::<TestComponent>(&mut commands, "test_entity")
+                .unwrap();
+            queue.apply(&mut world);
+            entity
+        };
+
+        {
+            let mut queue = CommandQueue::default();
+            let mut commands = Commands::new(&mut queue, &world);
+            assert_eq!(registry.remove(&mut commands, "test_entity"), Some(entity));
+            assert_eq!(registry.remove(&mut commands, "test_entity"), None);
+            queue.apply(&mut world);
+        }
+
+        assert!(registry.find("test_entity").is_none());
+        assert!(world.get_entity(entity).is_err());
+    }
+
+    #[test]
+    fn test_types_have_their_own_ids() {
+        let mut world = World::new();
+        let mut registry = AssetRegistry::default();
+
+        let (test, other) = {
+            let mut queue = CommandQueue::default();
+            let mut commands = Commands::new(&mut queue, &world);
+            let test = registry
+                .add_component(&mut commands, "shared", TestComponent)
+                .unwrap();
+            let other = registry
+                .add_component(&mut commands, "shared", OtherComponent)
+                .unwrap();
+            queue.apply(&mut world);
+            (test, other)
+        };
+
+        assert_ne!(test, other);
+        assert_eq!(registry.find_typed::<TestComponent>("shared"), Some(test));
+        assert_eq!(registry.find_typed::<OtherComponent>("shared"), Some(other));
+        assert_eq!(registry.find("shared"), Some(test));
+        assert_eq!(registry.find_all("shared"), &[test, other]);
+        assert!(!world.entity(test).contains::<OtherComponent>());
+
+        {
+            let mut queue = CommandQueue::default();
+            let mut commands = Commands::new(&mut queue, &world);
+            assert_eq!(
+                registry.remove_typed::<TestComponent>(&mut commands, "shared"),
+                Some(test)
+            );
+            queue.apply(&mut world);
+        }
+        assert_eq!(registry.find("shared"), Some(other));
+    }
+
+    #[test]
+    fn test_conflicting_registration() {
+        let world = World::new();
+        let mut registry = AssetRegistry::with_unique_ids();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+
+        registry
+            .register::<TestComponent>(&mut commands, "shared")
+            .unwrap();
+
+        assert_eq!(
+            registry.register::<TestComponent>(&mut commands, "shared"),
+            Err(RegistryError::AlreadyRegistered {
+                id: "shared".to_string(),
+                type_name: "TestComponent",
+            })
+        );
+        assert_eq!(
+            registry.add_component(&mut commands, "shared", OtherComponent),
+            Err(RegistryError::IdTaken {
+                id: "shared".to_string(),
+                type_name: "TestComponent",
+            })
+        );
+        assert!(registry.find_typed::<OtherComponent>("shared").is_none());
+    }
+
+    #[test]
+    fn test_iteration_and_search() {
+        let world = World::new();
+        let mut registry = AssetRegistry::default();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        for id in ["NHV2", "NHV1", "GEN", "NGEN"] {
+            registry
+                .register::<TestComponent>(&mut commands, id)
+                .unwrap();
+        }
+        let other = registry
+            .register::<OtherComponent>(&mut commands, "NHV1")
+            .unwrap();
+
+        assert_eq!(
+            registry.ids().collect::<Vec<_>>(),
+            vec!["GEN", "NGEN", "NHV1", "NHV2"]
+        );
+        assert_eq!(registry.id_of(other), Some("NHV1"));
+        assert_eq!(registry.type_name_of(other), Some("OtherComponent"));
+
+        let ids = |entities: Vec<(&str, Entity)>| -> Vec<String> {
+            entities.into_iter().map(|(id, _)| id.to_string()).collect()
+        };
+        assert_eq!(
+            ids(registry.with_prefix("NHV").collect()),
+            vec!["NHV1", "NHV1", "NHV2"]
+        );
+        assert_eq!(
+            ids(registry.iter_typed::<TestComponent>().collect()),
+            vec!["GEN", "NGEN", "NHV1", "NHV2"]
+        );
+        assert_eq!(
+            ids(registry.matching("N*1").collect()),
+            vec!["NHV1", "NHV1"]
+        );
+        assert_eq!(
+            ids(registry.matching("*GEN").collect()),
+            vec!["GEN", "NGEN"]
+        );
+        assert_eq!(ids(registry.matching("NHV?").collect()).len(), 3);
+        assert!(registry.matching("NHV").next().is_none());
+
+        assert_eq!(registry.len(), 5);
+        assert_eq!(registry.count::<TestComponent>(), 4);
+        assert_eq!(
+            registry.counts(),
+            BTreeMap::from([("OtherComponent", 1), ("TestComponent", 4)])
+        );
+
+        registry.remove_entity(&mut commands, other);
+        assert_eq!(registry.count::<OtherComponent>(), 0);
+        assert!(!registry.counts().contains_key("OtherComponent"));
+        assert_eq!(registry.id_of(other), None);
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("NHV1_NHV2_1", "NHV1_*_1"));
+        assert!(matches_pattern("NHV1_NHV2_1", "*"));
+        assert!(matches_pattern("ab", "a*b*"));
+        assert!(matches_pattern("aXbYb", "a*b"));
+        assert!(!matches_pattern("aXbYc", "a*b"));
+        assert!(!matches_pattern("", "?"));
+        assert!(matches_pattern("", "**"));
+    }
 }
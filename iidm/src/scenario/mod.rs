@@ -0,0 +1,511 @@
+//! Time-stepped scenarios for operator training.
+//!
+//! A scenario is a list of events scheduled on a simulated clock: load ramps, generator trips
+//! and switch operations. Advancing the clock sends the update events of the actions which are
+//! due, the caller then runs the schedule so the handlers, the topology and the load flow follow
+//! them like any other update. The steps of the ramps are set on the loads directly, so that the
+//! history records each ramp once, when it completes.
+
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::entities::*;
+use crate::{AssetRegistry, History, LoadFlowEvent, UpdateEvent};
+
+#[derive(Debug, Error)]
+pub enum ScenarioError {
+    #[error("Failed to parse the scenario: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("Event {index} of the scenario is invalid: {reason}")]
+    InvalidEvent { index: usize, reason: String },
+
+    #[error("Event {index} of the scenario refers to unknown {component_type} '{id}'")]
+    UnknownElement {
+        index: usize,
+        component_type: &'static str,
+        id: String,
+    },
+
+    #[error("Invalid speed {0}, it must be a positive number")]
+    InvalidSpeed(f64),
+
+    #[error("Invalid step {0}, it must be a positive number of seconds")]
+    InvalidStep(f64),
+
+    #[error("No scenario is loaded")]
+    NotLoaded,
+
+    #[error("The scenario is finished")]
+    Finished,
+}
+
+/// Events to apply on a simulated clock, starting at 0 seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Run a load flow after each step, so the flows follow the events
+    #[serde(default = "default_load_flow")]
+    pub load_flow: bool,
+    pub events: Vec<ScenarioEvent>,
+}
+
+fn default_load_flow() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioEvent {
+    /// Simulated time of the event, in seconds
+    pub at: f64,
+    #[serde(flatten)]
+    pub action: ScenarioAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScenarioAction {
+    /// Brings the consumption of a load linearly to the targets over `duration` seconds
+    #[serde(rename_all = "camelCase")]
+    LoadRamp {
+        id: String,
+        p0: f64,
+        q0: Option<f64>,
+        #[serde(default)]
+        duration: f64,
+    },
+    /// Disconnects a generator from its bus
+    GeneratorTrip {
+        id: String,
+    },
+    SwitchOpen {
+        id: String,
+    },
+    SwitchClose {
+        id: String,
+    },
+}
+
+impl ScenarioAction {
+    fn target(&self) -> (&'static str, &str) {
+        match self {
+            ScenarioAction::LoadRamp { id, .. } => ("Load", id),
+            ScenarioAction::GeneratorTrip { id } => ("Generator", id),
+            ScenarioAction::SwitchOpen { id } | ScenarioAction::SwitchClose { id } => {
+                ("Switch", id)
+            }
+        }
+    }
+}
+
+impl Scenario {
+    /// Reads a scenario and sorts its events by time, the events given at the same time keeping
+    /// their order
+    pub fn from_json(json: &str) -> Result<Self, ScenarioError> {
+        let mut scenario: Scenario = serde_json::from_str(json)?;
+        scenario.validate()?;
+        scenario.sort();
+        Ok(scenario)
+    }
+
+    pub fn validate(&self) -> Result<(), ScenarioError> {
+        for (index, event) in self.events.iter().enumerate() {
+            let invalid = |reason: &str| ScenarioError::InvalidEvent {
+                index,
+                reason: reason.to_string(),
+            };
+            if !event.at.is_finite() || event.at < 0.0 {
+                return Err(invalid("the time must be a positive number of seconds"));
+            }
+            if let ScenarioAction::LoadRamp { duration, .. } = &event.action {
+                if !duration.is_finite() || *duration < 0.0 {
+                    return Err(invalid("the duration must be a positive number of seconds"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn sort(&mut self) {
+        self.events.sort_by(|a, b| a.at.total_cmp(&b.at));
+    }
+
+    /// Time of the last action, ramps included
+    pub fn duration(&self) -> f64 {
+        self.events
+            .iter()
+            .map(|event| match &event.action {
+                ScenarioAction::LoadRamp { duration, .. } => event.at + duration,
+                _ => event.at,
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ScenarioStatus {
+    #[default]
+    Paused,
+    Running,
+    Finished,
+}
+
+/// Load ramp in progress
+#[derive(Debug, Clone)]
+struct Ramp {
+    id: String,
+    start: f64,
+    duration: f64,
+    from: (f64, f64),
+    to: (f64, f64),
+}
+
+impl Ramp {
+    fn value_at(&self, time: f64) -> (f64, f64) {
+        let progress = if self.duration > 0.0 {
+            ((time - self.start) / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        (
+            self.from.0 + (self.to.0 - self.from.0) * progress,
+            self.from.1 + (self.to.1 - self.from.1) * progress,
+        )
+    }
+
+    fn is_done(&self, time: f64) -> bool {
+        time >= self.start + self.duration
+    }
+}
+
+/// Loaded scenario and its simulated clock
+#[derive(Resource, Debug, Clone)]
+pub struct ScenarioClock {
+    scenario: Scenario,
+    /// Simulated seconds since the start of the scenario
+    time: f64,
+    /// Simulated seconds per real second
+    speed: f64,
+    status: ScenarioStatus,
+    /// Index of the next event to apply
+    next: usize,
+    ramps: Vec<Ramp>,
+}
+
+impl ScenarioClock {
+    pub fn new(scenario: Scenario) -> Self {
+        Self {
+            scenario,
+            time: 0.0,
+            speed: 1.0,
+            status: ScenarioStatus::Paused,
+            next: 0,
+            ramps: Vec::new(),
+        }
+    }
+
+    pub fn scenario(&self) -> &Scenario {
+        &self.scenario
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn status(&self) -> ScenarioStatus {
+        self.status
+    }
+
+    pub fn report(&self) -> ScenarioReport {
+        ScenarioReport {
+            name: self.scenario.name.clone(),
+            status: self.status,
+            time: self.time,
+            speed: self.speed,
+            duration: self.scenario.duration(),
+            applied_events: self.next,
+            total_events: self.scenario.events.len(),
+            next_event_at: self.scenario.events.get(self.next).map(|event| event.at),
+            active_ramps: self.ramps.iter().map(|ramp| ramp.id.clone()).collect(),
+        }
+    }
+}
+
+/// State of the loaded scenario, as shown to the operators
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScenarioReport {
+    pub name: String,
+    pub status: ScenarioStatus,
+    pub time: f64,
+    pub speed: f64,
+    /// Time of the last action, ramps included
+    pub duration: f64,
+    pub applied_events: usize,
+    pub total_events: usize,
+    pub next_event_at: Option<f64>,
+    /// Loads being ramped
+    pub active_ramps: Vec<String>,
+}
+
+/// Loads a scenario, paused at its start, in place of the current one. Refused when an event
+/// refers to an element missing from the world.
+pub fn load_scenario(
+    world: &mut World,
+    mut scenario: Scenario,
+) -> Result<ScenarioReport, ScenarioError> {
+    scenario.validate()?;
+    let registry = world.get_resource::<AssetRegistry>();
+    for (index, event) in scenario.events.iter().enumerate() {
+        let (component_type, id) = event.action.target();
+        let found = registry.and_then(|registry| match &event.action {
+            ScenarioAction::LoadRamp { .. } => registry.find_typed::<Load>(id),
+            ScenarioAction::GeneratorTrip { .. } => registry.find_typed::<Generator>(id),
+            ScenarioAction::SwitchOpen { .. } | ScenarioAction::SwitchClose { .. } => {
+                registry.find_typed::<Switch>(id)
+            }
+        });
+        if found.is_none() {
+            return Err(ScenarioError::UnknownElement {
+                index,
+                component_type,
+                id: id.to_string(),
+            });
+        }
+    }
+
+    scenario.sort();
+    let clock = ScenarioClock::new(scenario);
+    let report = clock.report();
+    world.insert_resource(clock);
+    Ok(report)
+}
+
+fn clock_mut(world: &mut World) -> Result<Mut<'_, ScenarioClock>, ScenarioError> {
+    world
+        .get_resource_mut::<ScenarioClock>()
+        .ok_or(ScenarioError::NotLoaded)
+}
+
+pub fn scenario_report(world: &World) -> Result<ScenarioReport, ScenarioError> {
+    world
+        .get_resource::<ScenarioClock>()
+        .map(ScenarioClock::report)
+        .ok_or(ScenarioError::NotLoaded)
+}
+
+/// Lets the clock advance with the real time
+pub fn start_scenario(world: &mut World) -> Result<ScenarioReport, ScenarioError> {
+    let mut clock = clock_mut(world)?;
+    if clock.status == ScenarioStatus::Finished {
+        return Err(ScenarioError::Finished);
+    }
+    clock.status = ScenarioStatus::Running;
+    Ok(clock.report())
+}
+
+pub fn pause_scenario(world: &mut World) -> Result<ScenarioReport, ScenarioError> {
+    let mut clock = clock_mut(world)?;
+    if clock.status == ScenarioStatus::Running {
+        clock.status = ScenarioStatus::Paused;
+    }
+    Ok(clock.report())
+}
+
+/// Sets the simulated seconds elapsing per real second
+pub fn set_scenario_speed(world: &mut World, speed: f64) -> Result<ScenarioReport, ScenarioError> {
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(ScenarioError::InvalidSpeed(speed));
+    }
+    let mut clock = clock_mut(world)?;
+    clock.speed = speed;
+    Ok(clock.report())
+}
+
+/// Advances a running scenario by `elapsed` real seconds, scaled by its speed. Does nothing
+/// when it is paused or finished.
+pub fn advance_scenario(world: &mut World, elapsed: f64) -> Result<ScenarioReport, ScenarioError> {
+    let clock = clock_mut(world)?;
+    if clock.status != ScenarioStatus::Running {
+        return Ok(clock.report());
+    }
+    let step = elapsed * clock.speed;
+    advance(world, step)
+}
+
+/// Advances the scenario by `seconds` of simulated time, whether it runs or not
+pub fn step_scenario(world: &mut World, seconds: f64) -> Result<ScenarioReport, ScenarioError> {
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(ScenarioError::InvalidStep(seconds));
+    }
+    if clock_mut(world)?.status == ScenarioStatus::Finished {
+        return Err(ScenarioError::Finished);
+    }
+    advance(world, seconds)
+}
+
+/// Sends the events of the actions due by the new time, and the values of the ramps at it
+fn advance(world: &mut World, step: f64) -> Result<ScenarioReport, ScenarioError> {
+    world.resource_scope(|world, mut clock: Mut<ScenarioClock>| {
+        let time = clock.time + step;
+        clock.time = time;
+
+        while let Some(event) = clock.scenario.events.get(clock.next).cloned() {
+            if event.at > time {
+                break;
+            }
+            clock.next += 1;
+            match event.action {
+                ScenarioAction::LoadRamp {
+                    id,
+                    p0,
+                    q0,
+                    duration,
+                } => {
+                    let Some(load) = find::<Load>(world, &id) else {
+                        continue;
+                    };
+                    let from = (load.p0, load.q0);
+                    clock.ramps.push(Ramp {
+                        id,
+                        start: event.at,
+                        duration,
+                        from,
+                        to: (p0, q0.unwrap_or(from.1)),
+                    });
+                }
+                ScenarioAction::GeneratorTrip { id } => {
+                    world.send_event(UpdateEvent::<Generator> {
+                        id,
+                        updater: GeneratorUpdater {
+                            bus: Some(String::new()),
+                            ..Default::default()
+                        },
+                    });
+                }
+                ScenarioAction::SwitchOpen { id } => send_switch(world, id, true),
+                ScenarioAction::SwitchClose { id } => send_switch(world, id, false),
+            }
+        }
+
+        for ramp in &clock.ramps {
+            apply_ramp(world, ramp, time);
+        }
+        clock.ramps.retain(|ramp| !ramp.is_done(time));
+
+        if clock.scenario.load_flow {
+            world.send_event(LoadFlowEvent);
+        }
+        if clock.next == clock.scenario.events.len() && clock.ramps.is_empty() {
+            clock.status = ScenarioStatus::Finished;
+        }
+        Ok(clock.report())
+    })
+}
+
+/// Sets the load of a ramp to its value at `time`, without recording the step. A completed ramp
+/// is recorded as a single change, from its start to its target.
+fn apply_ramp(world: &mut World, ramp: &Ramp, time: f64) {
+    let Some(entity) = world
+        .get_resource::<AssetRegistry>()
+        .and_then(|registry| registry.find_typed::<Load>(&ramp.id))
+    else {
+        return;
+    };
+    let Some(mut load) = world.get_mut::<Load>(entity) else {
+        return;
+    };
+    (load.p0, load.q0) = ramp.value_at(time);
+    if !ramp.is_done(time) {
+        return;
+    }
+
+    let load = load.clone();
+    let mut before = load.clone();
+    (before.p0, before.q0) = ramp.from;
+    if let (Some(mut history), Ok(before)) = (
+        world.get_resource_mut::<History>(),
+        serde_json::to_value(&before),
+    ) {
+        history.record(&ramp.id, before, &load);
+    }
+}
+
+fn send_switch(world: &mut World, id: String, open: bool) {
+    world.send_event(UpdateEvent::<Switch> {
+        id,
+        updater: SwitchUpdater {
+            open: Some(open),
+            ..Default::default()
+        },
+    });
+}
+
+fn find<C: Component + Clone>(world: &World, id: &str) -> Option<C> {
+    let entity = world.get_resource::<AssetRegistry>()?.find_typed::<C>(id)?;
+    world.get::<C>(entity).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp(duration: f64) -> Ramp {
+        Ramp {
+            id: "LOAD".to_string(),
+            start: 10.0,
+            duration,
+            from: (600.0, 200.0),
+            to: (800.0, 200.0),
+        }
+    }
+
+    #[test]
+    fn test_ramp_is_linear() {
+        let ramp = ramp(20.0);
+        assert_eq!(ramp.value_at(5.0), (600.0, 200.0));
+        assert_eq!(ramp.value_at(15.0), (650.0, 200.0));
+        assert_eq!(ramp.value_at(40.0), (800.0, 200.0));
+        assert!(!ramp.is_done(29.0));
+        assert!(ramp.is_done(30.0));
+
+        assert_eq!(self::ramp(0.0).value_at(10.0), (800.0, 200.0));
+    }
+
+    #[test]
+    fn test_scenario_events_sorted() {
+        let scenario = Scenario::from_json(
+            r#"{"name": "Peak", "events": [
+                {"at": 60, "type": "generatorTrip", "id": "GEN"},
+                {"at": 0, "type": "loadRamp", "id": "LOAD", "p0": 700, "duration": 30},
+                {"at": 60, "type": "switchOpen", "id": "SW"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let actions: Vec<_> = scenario
+            .events
+            .iter()
+            .map(|event| event.action.target().1)
+            .collect();
+        assert_eq!(actions, vec!["LOAD", "GEN", "SW"]);
+        assert!(scenario.load_flow);
+        assert_eq!(scenario.duration(), 60.0);
+
+        assert!(matches!(
+            Scenario::from_json(
+                r#"{"name": "Bad", "events": [{"at": -1, "type": "switchOpen", "id": "SW"}]}"#
+            ),
+            Err(ScenarioError::InvalidEvent { index: 0, .. })
+        ));
+    }
+}
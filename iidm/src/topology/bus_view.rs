@@ -0,0 +1,289 @@
+use std::collections::{BTreeSet, HashMap};
+
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::*;
+
+/// Union-find over dense indices, with path halving
+#[derive(Debug, Clone)]
+pub(crate) struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            parent: (0..size).collect(),
+        }
+    }
+
+    pub(crate) fn find(&mut self, mut index: usize) -> usize {
+        while self.parent[index] != index {
+            self.parent[index] = self.parent[self.parent[index]];
+            index = self.parent[index];
+        }
+        index
+    }
+
+    pub(crate) fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            // Keep the smallest index as root so that groups follow declaration order
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    /// Groups of indices, ordered by their smallest member
+    pub(crate) fn groups(&mut self) -> Vec<Vec<usize>> {
+        let mut positions = HashMap::new();
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for index in 0..self.parent.len() {
+            let root = self.find(index);
+            let position = *positions.entry(root).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[position].push(index);
+        }
+        groups
+    }
+}
+
+/// Electrical bus obtained by merging the buses or nodes joined by closed switches
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalculatedBus {
+    pub id: String,
+    /// Configured bus ids (bus-breaker) or node numbers (node-breaker) merged in this bus
+    pub members: Vec<String>,
+    /// Synchronous island of the bus once the branches are taken into account
+    pub island: Option<usize>,
+}
+
+/// Bus view of a voltage level, attached to its entity
+#[derive(Component, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BusView {
+    #[serde(rename = "voltageLevelId")]
+    pub voltage_level_id: String,
+    #[serde(rename = "topologyKind")]
+    pub topology_kind: TopologyKind,
+    pub buses: Vec<CalculatedBus>,
+}
+
+impl BusView {
+    /// Merges the voltage level topology, `is_open` gives the current state of each switch.
+    ///
+    /// Bus-breaker buses keep the id of their first configured bus, node-breaker buses are
+    /// named `{voltage_level_id}_{index}`.
+    pub fn compute(voltage_level: &VoltageLevel, is_open: impl Fn(&Switch) -> bool) -> Self {
+        let buses = match voltage_level.topology_kind {
+            TopologyKind::BusBreaker => voltage_level
+                .bus_breaker_topology
+                .as_ref()
+                .map(|topology| bus_breaker_buses(topology, is_open))
+                .unwrap_or_default(),
+            TopologyKind::NodeBreaker => voltage_level
+                .node_breaker_topology
+                .as_ref()
+                .map(|topology| node_breaker_buses(&voltage_level.id, topology, is_open))
+                .unwrap_or_default(),
+        };
+
+        Self {
+            voltage_level_id: voltage_level.id.clone(),
+            topology_kind: voltage_level.topology_kind.clone(),
+            buses,
+        }
+    }
+
+    /// Finds the calculated bus holding a configured bus or a node
+    pub fn bus(&self, member: &str) -> Option<&CalculatedBus> {
+        self.buses
+            .iter()
+            .find(|bus| bus.members.iter().any(|candidate| candidate == member))
+    }
+
+    /// Distinct islands reached by the buses of the voltage level
+    pub fn islands(&self) -> Vec<usize> {
+        let islands: BTreeSet<usize> = self.buses.iter().filter_map(|bus| bus.island).collect();
+        islands.into_iter().collect()
+    }
+}
+
+fn bus_breaker_buses(
+    topology: &BusBreakerTopology,
+    is_open: impl Fn(&Switch) -> bool,
+) -> Vec<CalculatedBus> {
+    let index: HashMap<&str, usize> = topology
+        .buses
+        .iter()
+        .enumerate()
+        .map(|(position, bus)| (bus.id.as_str(), position))
+        .collect();
+
+    let mut set = DisjointSet::new(topology.buses.len());
+    for switch in topology.switches.iter().filter(|switch| !is_open(switch)) {
+        if let (Some(&a), Some(&b)) = (
+            index.get(switch.bus1.as_str()),
+            index.get(switch.bus2.as_str()),
+        ) {
+            set.union(a, b);
+        }
+    }
+
+    set.groups()
+        .into_iter()
+        .map(|group| CalculatedBus {
+            id: topology.buses[group[0]].id.clone(),
+            members: group
+                .into_iter()
+                .map(|position| topology.buses[position].id.clone())
+                .collect(),
+            island: None,
+        })
+        .collect()
+}
+
+fn node_breaker_buses(
+    voltage_level_id: &str,
+    topology: &NodeBreakerTopology,
+    is_open: impl Fn(&Switch) -> bool,
+) -> Vec<CalculatedBus> {
+    let switch_nodes =
+        |switch: &Switch| match (switch.bus1.parse::<i32>(), switch.bus2.parse::<i32>()) {
+            (Ok(node1), Ok(node2)) => Some((node1, node2)),
+            _ => None,
+        };
+
+    // Nodes may only be referenced by switches or internal connections
+    let mut nodes: BTreeSet<i32> = topology.nodes.iter().map(|node| node.id).collect();
+    for (node1, node2) in topology.switches.iter().filter_map(switch_nodes) {
+        nodes.extend([node1, node2]);
+    }
+    for connection in &topology.internal_connections {
+        nodes.extend([connection.node1, connection.node2]);
+    }
+
+    let nodes: Vec<i32> = nodes.into_iter().collect();
+    let index: HashMap<i32, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(position, node)| (*node, position))
+        .collect();
+
+    let mut set = DisjointSet::new(nodes.len());
+    for connection in &topology.internal_connections {
+        set.union(index[&connection.node1], index[&connection.node2]);
+    }
+    for (node1, node2) in topology
+        .switches
+        .iter()
+        .filter(|switch| !is_open(switch))
+        .filter_map(switch_nodes)
+    {
+        set.union(index[&node1], index[&node2]);
+    }
+
+    set.groups()
+        .into_iter()
+        .enumerate()
+        .map(|(position, group)| CalculatedBus {
+            id: format!("{}_{}", voltage_level_id, position),
+            members: group
+                .into_iter()
+                .map(|node| nodes[node].to_string())
+                .collect(),
+            island: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn switch(id: &str, bus1: &str, bus2: &str, open: bool) -> Switch {
+        Switch {
+            id: id.to_string(),
+            kind: SwitchKind::Breaker,
+            open,
+            retained: false,
+            bus1: bus1.to_string(),
+            bus2: bus2.to_string(),
+            voltage_level_id: String::new(),
+        }
+    }
+
+    fn voltage_level(kind: TopologyKind) -> VoltageLevel {
+        VoltageLevel {
+            id: "VL".to_string(),
+            nominal_v: 225.0,
+            topology_kind: kind,
+            generators: None,
+            loads: None,
+            busbar_sections: None,
+            node_breaker_topology: None,
+            bus_breaker_topology: None,
+        }
+    }
+
+    #[test]
+    fn test_disjoint_set_groups() {
+        let mut set = DisjointSet::new(5);
+        set.union(3, 1);
+        set.union(4, 3);
+
+        assert_eq!(set.groups(), vec![vec![0], vec![1, 3, 4], vec![2]]);
+    }
+
+    #[test]
+    fn test_bus_breaker_merges_closed_switches() {
+        let mut vl = voltage_level(TopologyKind::BusBreaker);
+        vl.bus_breaker_topology = Some(BusBreakerTopology {
+            buses: ["B1", "B2", "B3"]
+                .map(|id| Bus { id: id.to_string() })
+                .to_vec(),
+            switches: vec![
+                switch("S1", "B1", "B2", false),
+                switch("S2", "B2", "B3", true),
+            ],
+        });
+
+        let view = BusView::compute(&vl, |switch| switch.open);
+
+        assert_eq!(view.buses.len(), 2);
+        assert_eq!(view.buses[0].id, "B1");
+        assert_eq!(view.buses[0].members, vec!["B1", "B2"]);
+        assert_eq!(view.bus("B2").map(|bus| bus.id.as_str()), Some("B1"));
+        assert_eq!(view.bus("B3").map(|bus| bus.id.as_str()), Some("B3"));
+
+        // The state callback takes precedence over the stored switch state
+        let view = BusView::compute(&vl, |_| false);
+        assert_eq!(view.buses.len(), 1);
+    }
+
+    #[test]
+    fn test_node_breaker_merges_switches_and_internal_connections() {
+        let mut vl = voltage_level(TopologyKind::NodeBreaker);
+        vl.node_breaker_topology = Some(NodeBreakerTopology {
+            nodes: (0..4).map(|id| Node { id }).collect(),
+            switches: vec![switch("D1", "0", "1", false), switch("B1", "1", "5", true)],
+            internal_connections: vec![InternalConnection { node1: 2, node2: 3 }],
+        });
+
+        let view = BusView::compute(&vl, |switch| switch.open);
+
+        let members: Vec<&Vec<String>> = view.buses.iter().map(|bus| &bus.members).collect();
+        assert_eq!(members, vec![&vec!["0", "1"], &vec!["2", "3"], &vec!["5"]]);
+        assert_eq!(view.buses[2].id, "VL_2");
+        assert_eq!(view.bus("3").map(|bus| bus.id.as_str()), Some("VL_1"));
+    }
+
+    #[test]
+    fn test_missing_topology_gives_empty_view() {
+        let view = BusView::compute(&voltage_level(TopologyKind::NodeBreaker), |_| false);
+
+        assert!(view.buses.is_empty());
+        assert!(view.islands().is_empty());
+    }
+}
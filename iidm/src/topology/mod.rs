@@ -0,0 +1,362 @@
+//! Topology processing: merges buses and nodes joined by closed switches into the bus view of
+//! each voltage level, then groups the calculated buses into synchronous islands through the
+//! branches. Only the voltage levels touched by a changed switch or voltage level are rebuilt.
+
+mod bus_view;
+
+pub use bus_view::{BusView, CalculatedBus};
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_ecs::{prelude::*, system::SystemParam};
+
+use crate::entities::*;
+use crate::relations::{ChildOf, Children};
+pub(crate) use bus_view::DisjointSet;
+
+/// Relation fields of the switches of each topology kind, as named by the `Identifiable` derive
+const BUS_BREAKER_SWITCHES: &str = "bus_breaker_topology.switches";
+const NODE_BREAKER_SWITCHES: &str = "node_breaker_topology.switches";
+
+/// Bus views of every voltage level and the island numbering of their buses
+#[derive(Resource, Debug, Clone, Default)]
+pub struct NetworkTopology {
+    views: HashMap<String, BusView>,
+    /// Voltage level of each bus-breaker configured bus, whose ids are unique in the network
+    configured_buses: HashMap<String, String>,
+    /// Voltage level of each switch entity held by a voltage level topology
+    switch_voltage_levels: HashMap<Entity, String>,
+    /// Voltage level ids by entity, to forget the views of despawned voltage levels
+    voltage_level_entities: HashMap<Entity, String>,
+    island_count: usize,
+}
+
+impl NetworkTopology {
+    pub fn bus_view(&self, voltage_level_id: &str) -> Option<&BusView> {
+        self.views.get(voltage_level_id)
+    }
+
+    pub fn bus_views(&self) -> impl Iterator<Item = &BusView> {
+        self.views.values()
+    }
+
+    /// Resolves the calculated bus of a terminal. Node numbers are only meaningful with their
+    /// voltage level, bus-breaker bus ids are also found without it.
+    pub fn calculated_bus(
+        &self,
+        voltage_level_id: Option<&str>,
+        bus: &str,
+    ) -> Option<&CalculatedBus> {
+        self.locate(voltage_level_id, bus)
+            .map(|(_, calculated)| calculated)
+    }
+
+    fn locate(
+        &self,
+        voltage_level_id: Option<&str>,
+        bus: &str,
+    ) -> Option<(&BusView, &CalculatedBus)> {
+        if bus.is_empty() {
+            return None;
+        }
+        let in_view = |view| Some((view, BusView::bus(view, bus)?));
+        voltage_level_id
+            .and_then(|id| self.views.get(id))
+            .and_then(in_view)
+            .or_else(|| in_view(self.views.get(self.configured_buses.get(bus)?)?))
+    }
+
+    /// Islands reached by the buses of a voltage level
+    pub fn islands(&self, voltage_level_id: &str) -> Vec<usize> {
+        self.views
+            .get(voltage_level_id)
+            .map(BusView::islands)
+            .unwrap_or_default()
+    }
+
+    pub fn island_count(&self) -> usize {
+        self.island_count
+    }
+
+    fn insert_view(&mut self, voltage_level: &VoltageLevel, switches: &[Entity], view: BusView) {
+        self.switch_voltage_levels
+            .retain(|_, id| *id != voltage_level.id);
+        for switch in switches {
+            self.switch_voltage_levels
+                .insert(*switch, voltage_level.id.clone());
+        }
+        if view.topology_kind == TopologyKind::BusBreaker {
+            for member in view.buses.iter().flat_map(|bus| &bus.members) {
+                self.configured_buses
+                    .insert(member.clone(), voltage_level.id.clone());
+            }
+        }
+        self.views.insert(voltage_level.id.clone(), view);
+    }
+
+    fn remove_view(&mut self, voltage_level_id: &str) {
+        self.views.remove(voltage_level_id);
+        self.configured_buses.retain(|_, id| id != voltage_level_id);
+        self.switch_voltage_levels
+            .retain(|_, id| id != voltage_level_id);
+    }
+
+    /// Numbers the islands formed by the calculated buses and the given branch terminals
+    fn compute_islands(&mut self, branches: &[Vec<(&str, &str)>]) {
+        let mut voltage_level_ids: Vec<String> = self.views.keys().cloned().collect();
+        voltage_level_ids.sort();
+
+        let mut index = HashMap::new();
+        for voltage_level_id in &voltage_level_ids {
+            for bus in &self.views[voltage_level_id].buses {
+                let position = index.len();
+                index.insert((voltage_level_id.clone(), bus.id.clone()), position);
+            }
+        }
+
+        let mut set = DisjointSet::new(index.len());
+        for terminals in branches {
+            let connected: Vec<usize> = terminals
+                .iter()
+                .filter_map(|(voltage_level_id, bus)| {
+                    let (view, calculated) = self.locate(Some(voltage_level_id), bus)?;
+                    index
+                        .get(&(view.voltage_level_id.clone(), calculated.id.clone()))
+                        .copied()
+                })
+                .collect();
+            for pair in connected.windows(2) {
+                set.union(pair[0], pair[1]);
+            }
+        }
+
+        let mut islands = vec![0; index.len()];
+        let groups = set.groups();
+        for (island, group) in groups.iter().enumerate() {
+            for position in group {
+                islands[*position] = island;
+            }
+        }
+        self.island_count = groups.len();
+
+        for ((voltage_level_id, bus_id), position) in index {
+            if let Some(view) = self.views.get_mut(&voltage_level_id) {
+                if let Some(bus) = view.buses.iter_mut().find(|bus| bus.id == bus_id) {
+                    bus.island = Some(islands[position]);
+                }
+            }
+        }
+    }
+}
+
+/// Branches linking the voltage levels together
+#[derive(SystemParam)]
+pub struct TopologyBranches<'w, 's> {
+    lines: Query<'w, 's, Ref<'static, Line>>,
+    two_windings_transformers: Query<'w, 's, Ref<'static, TwoWindingsTransformer>>,
+    three_windings_transformers: Query<'w, 's, Ref<'static, ThreeWindingsTransformer>>,
+    removed_lines: RemovedComponents<'w, 's, Line>,
+    removed_two_windings_transformers: RemovedComponents<'w, 's, TwoWindingsTransformer>,
+    removed_three_windings_transformers: RemovedComponents<'w, 's, ThreeWindingsTransformer>,
+}
+
+impl TopologyBranches<'_, '_> {
+    /// Whether a branch was added, modified or removed since the last run
+    fn changed(&mut self) -> bool {
+        // Every reader is drained so that old removals are not reported again
+        let removed = self.removed_lines.read().count()
+            + self.removed_two_windings_transformers.read().count()
+            + self.removed_three_windings_transformers.read().count();
+
+        removed > 0
+            || self.lines.iter().any(|line| line.is_changed())
+            || self
+                .two_windings_transformers
+                .iter()
+                .any(|transformer| transformer.is_changed())
+            || self
+                .three_windings_transformers
+                .iter()
+                .any(|transformer| transformer.is_changed())
+    }
+
+    fn terminals(&self) -> Vec<Vec<(&str, &str)>> {
+        let lines = self.lines.iter().map(|line| {
+            let line = line.into_inner();
+            vec![
+                (line.voltage_level_id1.as_str(), line.bus1.as_str()),
+                (line.voltage_level_id2.as_str(), line.bus2.as_str()),
+            ]
+        });
+        let two_windings_transformers = self.two_windings_transformers.iter().map(|transformer| {
+            let transformer = transformer.into_inner();
+            vec![
+                (
+                    transformer.voltage_level_id1.as_str(),
+                    transformer.bus1.as_str(),
+                ),
+                (
+                    transformer.voltage_level_id2.as_str(),
+                    transformer.bus2.as_str(),
+                ),
+            ]
+        });
+        let three_windings_transformers =
+            self.three_windings_transformers.iter().map(|transformer| {
+                let transformer = transformer.into_inner();
+                vec![
+                    (
+                        transformer.voltage_level_id1.as_str(),
+                        transformer.bus1.as_str(),
+                    ),
+                    (
+                        transformer.voltage_level_id2.as_str(),
+                        transformer.bus2.as_str(),
+                    ),
+                    (
+                        transformer.voltage_level_id3.as_str(),
+                        transformer.bus3.as_str(),
+                    ),
+                ]
+            });
+
+        lines
+            .chain(two_windings_transformers)
+            .chain(three_windings_transformers)
+            .collect()
+    }
+}
+
+/// Switches of the voltage level topologies, which live in their own entities
+#[derive(SystemParam)]
+pub struct TopologySwitches<'w, 's> {
+    switches: Query<'w, 's, (Entity, Ref<'static, Switch>, Option<&'static ChildOf>)>,
+    children: Query<'w, 's, Ref<'static, Children>>,
+    removed: RemovedComponents<'w, 's, Switch>,
+}
+
+impl TopologySwitches<'_, '_> {
+    /// Whether elements were nested in or removed from an entity, switches being attached to
+    /// their voltage level once registered
+    fn nesting_changed(&self, entity: Entity) -> bool {
+        self.children
+            .get(entity)
+            .is_ok_and(|children| children.is_changed())
+    }
+
+    /// Voltage level with the current switches of its topology, and their entities
+    fn assemble(
+        &self,
+        entity: Entity,
+        voltage_level: &VoltageLevel,
+    ) -> (VoltageLevel, Vec<Entity>) {
+        let mut voltage_level = voltage_level.clone();
+        let mut entities = Vec::new();
+        let children = self.children.get(entity).ok();
+        let nested = children.iter().flat_map(|children| children.iter());
+        for (child, switch, relation) in nested.filter_map(|child| self.switches.get(child).ok()) {
+            let topology_switches = match relation.map(|relation| relation.field) {
+                Some(BUS_BREAKER_SWITCHES) => voltage_level
+                    .bus_breaker_topology
+                    .as_mut()
+                    .map(|topology| &mut topology.switches),
+                Some(NODE_BREAKER_SWITCHES) => voltage_level
+                    .node_breaker_topology
+                    .as_mut()
+                    .map(|topology| &mut topology.switches),
+                _ => None,
+            };
+            if let Some(topology_switches) = topology_switches {
+                topology_switches.push(switch.clone());
+                entities.push(child);
+            }
+        }
+        (voltage_level, entities)
+    }
+}
+
+/// Rebuilds the bus views of the voltage levels whose topology or switches changed.
+///
+/// The switches of a topology are children of their voltage level, so that a `SwitchUpdater`
+/// flipping `open` or the deletion of a switch is enough to trigger the recomputation.
+pub fn update_topology(
+    voltage_levels: Query<(Entity, Ref<VoltageLevel>)>,
+    mut removed_voltage_levels: RemovedComponents<VoltageLevel>,
+    mut switches: TopologySwitches,
+    mut branches: TopologyBranches,
+    mut topology: ResMut<NetworkTopology>,
+    mut commands: Commands,
+) {
+    let mut removed = false;
+    for entity in removed_voltage_levels.read() {
+        if let Some(voltage_level_id) = topology.voltage_level_entities.remove(&entity) {
+            topology.remove_view(&voltage_level_id);
+            removed = true;
+        }
+    }
+
+    let mut dirty: HashSet<String> = voltage_levels
+        .iter()
+        .filter(|(entity, voltage_level)| {
+            voltage_level.is_changed() || switches.nesting_changed(*entity)
+        })
+        .map(|(_, voltage_level)| voltage_level.id.clone())
+        .collect();
+    for entity in switches.removed.read() {
+        if let Some(voltage_level_id) = topology.switch_voltage_levels.remove(&entity) {
+            dirty.insert(voltage_level_id);
+        }
+    }
+    for (entity, switch, relation) in switches.switches.iter() {
+        if !switch.is_changed() {
+            continue;
+        }
+        let voltage_level_id = topology
+            .switch_voltage_levels
+            .get(&entity)
+            .cloned()
+            .or_else(|| {
+                let (_, holder) = voltage_levels.get(relation?.parent).ok()?;
+                Some(holder.id.clone())
+            })
+            .or_else(|| {
+                (!switch.voltage_level_id.is_empty()).then(|| switch.voltage_level_id.clone())
+            });
+        dirty.extend(voltage_level_id);
+    }
+
+    if !branches.changed() && dirty.is_empty() && !removed {
+        return;
+    }
+
+    let previous = topology.views.clone();
+    for (entity, voltage_level) in voltage_levels
+        .iter()
+        .filter(|(_, voltage_level)| dirty.contains(&voltage_level.id))
+    {
+        topology
+            .voltage_level_entities
+            .insert(entity, voltage_level.id.clone());
+        let (assembled, switch_entities) = switches.assemble(entity, &voltage_level);
+        let view = BusView::compute(&assembled, |switch| switch.open);
+        topology.insert_view(&assembled, &switch_entities, view);
+    }
+
+    topology.compute_islands(&branches.terminals());
+
+    // Island numbers may move for any voltage level, only touch the views that differ
+    for (entity, voltage_level) in voltage_levels.iter() {
+        if let Some(view) = topology.bus_view(&voltage_level.id) {
+            if previous.get(&voltage_level.id) != Some(view) {
+                // The voltage level may be deleted by the same run
+                commands.entity(entity).try_insert(view.clone());
+            }
+        }
+    }
+}
+
+/// Registers the topology resource and system
+pub fn init_topology(world: &mut World, schedule: &mut Schedule) {
+    world.init_resource::<NetworkTopology>();
+    schedule.add_systems(update_topology);
+}
@@ -0,0 +1,620 @@
+//! Semantic checks of the network data, beyond the shape of the JSON documents.
+//!
+//! Every check belongs to a validation level: `EQUIPMENT` covers the static description of the
+//! equipment, `STEADY_STATE_HYPOTHESIS` adds the setpoints and positions a load flow starts
+//! from. Validating at a level runs its checks and the ones of the lower levels.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy_ecs::world::World;
+use serde::Serialize;
+
+use crate::entities::*;
+use crate::libs::json_stream::NetworkElement;
+use crate::topology::BusView;
+use crate::{AssetRegistry, Identifiable, Reference, References, Validate};
+
+/// Broken invariant of an element
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    pub id: String,
+    /// JSON pointer to the offending field of the element, empty for the whole element
+    pub path: String,
+    /// Lowest level at which the check is run
+    pub level: ValidationLevel,
+    pub message: String,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}: {}", self.id, self.path, self.message)
+    }
+}
+
+/// Collects the violations of one element
+struct Checks<'a> {
+    id: &'a str,
+    violations: Vec<Violation>,
+}
+
+impl<'a> Checks<'a> {
+    fn new(id: &'a str) -> Self {
+        Self {
+            id,
+            violations: Vec::new(),
+        }
+    }
+
+    fn check(&mut self, level: ValidationLevel, valid: bool, path: &str, message: impl ToString) {
+        if !valid {
+            self.violations.push(Violation {
+                id: self.id.to_string(),
+                path: path.to_string(),
+                level,
+                message: message.to_string(),
+            });
+        }
+    }
+
+    fn positive(&mut self, level: ValidationLevel, value: f64, path: &str) {
+        self.check(
+            level,
+            value > 0.0,
+            path,
+            format!("must be positive, got {}", value),
+        );
+    }
+
+    fn current_limits(&mut self, limits: &Option<CurrentLimits>, path: &str) {
+        let Some(limits) = limits else {
+            return;
+        };
+        self.positive(
+            ValidationLevel::Equipment,
+            limits.permanent_limit,
+            &format!("{}/permanentLimit", path),
+        );
+        for (index, pair) in limits.temporary_limits.windows(2).enumerate() {
+            self.check(
+                ValidationLevel::Equipment,
+                pair[1].acceptable_duration < pair[0].acceptable_duration,
+                &format!("{}/temporaryLimits/{}/acceptableDuration", path, index + 1),
+                format!(
+                    "temporary limits must be sorted by decreasing duration, {} follows {}",
+                    pair[1].acceptable_duration, pair[0].acceptable_duration
+                ),
+            );
+        }
+    }
+
+    fn tap_changer(&mut self, low_tap_position: i32, tap_position: i32, steps: usize, path: &str) {
+        self.check(
+            ValidationLevel::Equipment,
+            steps > 0,
+            &format!("{}/steps", path),
+            "a tap changer needs at least one step",
+        );
+        let high_tap_position = low_tap_position as i64 + steps as i64 - 1;
+        self.check(
+            ValidationLevel::SteadyStateHypothesis,
+            steps == 0
+                || (low_tap_position as i64..=high_tap_position).contains(&(tap_position as i64)),
+            &format!("{}/tapPosition", path),
+            format!(
+                "tap position {} is outside [{}, {}]",
+                tap_position, low_tap_position, high_tap_position
+            ),
+        );
+    }
+
+    fn finish(self) -> Vec<Violation> {
+        self.violations
+    }
+}
+
+macro_rules! no_violations {
+    ($($component:ty),* $(,)?) => {
+        $(
+            impl Validate for $component {
+                fn violations(&self) -> Vec<Violation> {
+                    Vec::new()
+                }
+            }
+        )*
+    };
+}
+
+no_violations!(
+    Network,
+    Substation,
+    Bus,
+    BusbarSection,
+    Load,
+    Switch,
+    DanglingLine,
+    TieLine,
+    HvdcConverterStation,
+    TerminalRef
+);
+
+// Nested structures are checked through the element holding them
+no_violations!(
+    ReactiveCapabilityCurve,
+    ReactiveCapabilityCurvePoint,
+    MinMaxReactiveLimits,
+    ExponentialLoadModel,
+    ZipLoadModel,
+    BusBreakerTopology,
+    NodeBreakerTopology,
+    Node,
+    InternalConnection,
+    RatioTapChanger,
+    PhaseTapChanger,
+    TapStep,
+    PhaseTapStep,
+    CurrentLimits,
+    TemporaryLimit
+);
+
+impl Validate for VoltageLevel {
+    fn violations(&self) -> Vec<Violation> {
+        let mut checks = Checks::new(&self.id);
+        checks.positive(ValidationLevel::Equipment, self.nominal_v, "/nominalV");
+        checks.finish()
+    }
+}
+
+impl Validate for Generator {
+    fn violations(&self) -> Vec<Violation> {
+        let mut checks = Checks::new(&self.id);
+        checks.check(
+            ValidationLevel::Equipment,
+            self.min_p <= self.max_p,
+            "/minP",
+            format!("minP {} is greater than maxP {}", self.min_p, self.max_p),
+        );
+        if let Some(limits) = &self.min_max_reactive_limits {
+            checks.check(
+                ValidationLevel::Equipment,
+                limits.min_q <= limits.max_q,
+                "/minMaxReactiveLimits/minQ",
+                format!(
+                    "minQ {} is greater than maxQ {}",
+                    limits.min_q, limits.max_q
+                ),
+            );
+        }
+        checks.check(
+            ValidationLevel::SteadyStateHypothesis,
+            self.min_p <= self.target_p && self.target_p <= self.max_p,
+            "/targetP",
+            format!(
+                "targetP {} is outside [{}, {}]",
+                self.target_p, self.min_p, self.max_p
+            ),
+        );
+        if self.voltage_regulator_on {
+            checks.positive(
+                ValidationLevel::SteadyStateHypothesis,
+                self.target_v,
+                "/targetV",
+            );
+        }
+        checks.finish()
+    }
+}
+
+impl Validate for Line {
+    fn violations(&self) -> Vec<Violation> {
+        let mut checks = Checks::new(&self.id);
+        checks.current_limits(&self.current_limits1, "/currentLimits1");
+        checks.current_limits(&self.current_limits2, "/currentLimits2");
+        checks.finish()
+    }
+}
+
+impl Validate for TwoWindingsTransformer {
+    fn violations(&self) -> Vec<Violation> {
+        let mut checks = Checks::new(&self.id);
+        checks.positive(ValidationLevel::Equipment, self.rated_u1, "/ratedU1");
+        checks.positive(ValidationLevel::Equipment, self.rated_u2, "/ratedU2");
+        if let Some(tap_changer) = &self.ratio_tap_changer {
+            checks.tap_changer(
+                tap_changer.low_tap_position,
+                tap_changer.tap_position,
+                tap_changer.steps.len(),
+                "/ratioTapChanger",
+            );
+        }
+        if let Some(tap_changer) = &self.phase_tap_changer {
+            checks.tap_changer(
+                tap_changer.low_tap_position,
+                tap_changer.tap_position,
+                tap_changer.steps.len(),
+                "/phaseTapChanger",
+            );
+        }
+        checks.current_limits(&self.current_limits1, "/currentLimits1");
+        checks.current_limits(&self.current_limits2, "/currentLimits2");
+        checks.finish()
+    }
+}
+
+impl Validate for ThreeWindingsTransformer {
+    fn violations(&self) -> Vec<Violation> {
+        let mut checks = Checks::new(&self.id);
+        checks.positive(ValidationLevel::Equipment, self.rated_u1, "/ratedU1");
+        checks.positive(ValidationLevel::Equipment, self.rated_u2, "/ratedU2");
+        checks.positive(ValidationLevel::Equipment, self.rated_u3, "/ratedU3");
+        for (tap_changer, path) in [
+            (&self.ratio_tap_changer2, "/ratioTapChanger2"),
+            (&self.ratio_tap_changer3, "/ratioTapChanger3"),
+        ] {
+            if let Some(tap_changer) = tap_changer {
+                checks.tap_changer(
+                    tap_changer.low_tap_position,
+                    tap_changer.tap_position,
+                    tap_changer.steps.len(),
+                    path,
+                );
+            }
+        }
+        checks.current_limits(&self.current_limits1, "/currentLimits1");
+        checks.current_limits(&self.current_limits2, "/currentLimits2");
+        checks.current_limits(&self.current_limits3, "/currentLimits3");
+        checks.finish()
+    }
+}
+
+impl Validate for ShuntCompensator {
+    fn violations(&self) -> Vec<Violation> {
+        let mut checks = Checks::new(&self.id);
+        checks.check(
+            ValidationLevel::Equipment,
+            self.maximum_section_count > 0,
+            "/maximumSectionCount",
+            format!("must be positive, got {}", self.maximum_section_count),
+        );
+        checks.check(
+            ValidationLevel::SteadyStateHypothesis,
+            (0..=self.maximum_section_count).contains(&self.section_count),
+            "/sectionCount",
+            format!(
+                "section count {} is outside [0, {}]",
+                self.section_count, self.maximum_section_count
+            ),
+        );
+        checks.finish()
+    }
+}
+
+impl Validate for StaticVarCompensator {
+    fn violations(&self) -> Vec<Violation> {
+        let mut checks = Checks::new(&self.id);
+        checks.check(
+            ValidationLevel::Equipment,
+            self.b_min <= self.b_max,
+            "/bMin",
+            format!("bMin {} is greater than bMax {}", self.b_min, self.b_max),
+        );
+        if matches!(
+            self.regulation_mode,
+            StaticVarCompensatorRegulationMode::Voltage
+        ) {
+            checks.positive(
+                ValidationLevel::SteadyStateHypothesis,
+                self.voltage_setpoint,
+                "/voltageSetpoint",
+            );
+        }
+        checks.finish()
+    }
+}
+
+impl Validate for HvdcLine {
+    fn violations(&self) -> Vec<Violation> {
+        let mut checks = Checks::new(&self.id);
+        checks.positive(ValidationLevel::Equipment, self.nominal_v, "/nominalV");
+        checks.check(
+            ValidationLevel::SteadyStateHypothesis,
+            (0.0..=self.max_p).contains(&self.active_power_setpoint),
+            "/activePowerSetpoint",
+            format!(
+                "active power setpoint {} is outside [0, {}]",
+                self.active_power_setpoint, self.max_p
+            ),
+        );
+        checks.finish()
+    }
+}
+
+/// Violations of an element checked at `level` or below
+pub fn validate<T: Validate>(element: &T, level: ValidationLevel) -> Vec<Violation> {
+    let mut violations = element.violations();
+    violations.retain(|violation| violation.level <= level);
+    violations
+}
+
+/// Level declared by the network loaded in the world, the strictest one when there is none
+pub fn validation_level(world: &World) -> ValidationLevel {
+    world
+        .get_resource::<AssetRegistry>()
+        .and_then(|registry| registry.iter_typed::<Network>().next())
+        .and_then(|(_, entity)| world.get::<Network>(entity))
+        .map(|network| network.minimum_validation_level)
+        .unwrap_or_default()
+}
+
+fn walk(network: &Network, validator: &mut NetworkValidator) {
+    validator.visit(network);
+    network
+        .substations
+        .iter()
+        .for_each(|substation| walk_substation(substation, validator));
+    network
+        .lines
+        .iter()
+        .for_each(|element| validator.visit(element));
+    network
+        .three_windings_transformers
+        .iter()
+        .for_each(|element| validator.visit(element));
+    network
+        .switches
+        .iter()
+        .for_each(|element| validator.visit(element));
+    network
+        .shunt_compensators
+        .iter()
+        .for_each(|element| validator.visit(element));
+    network
+        .static_var_compensators
+        .iter()
+        .for_each(|element| validator.visit(element));
+    network
+        .dangling_lines
+        .iter()
+        .for_each(|element| validator.visit(element));
+    network
+        .tie_lines
+        .iter()
+        .for_each(|tie_line| walk_tie_line(tie_line, validator));
+    network
+        .hvdc_lines
+        .iter()
+        .for_each(|hvdc_line| walk_hvdc_line(hvdc_line, validator));
+}
+
+fn walk_substation(substation: &Substation, validator: &mut NetworkValidator) {
+    validator.visit(substation);
+    for voltage_level in &substation.voltage_levels {
+        validator.visit(voltage_level);
+        validator.index.add_voltage_level(voltage_level);
+        let holder = Some(voltage_level.id.as_str());
+        voltage_level
+            .generators
+            .iter()
+            .flatten()
+            .for_each(|element| validator.visit_in(element, holder));
+        voltage_level
+            .loads
+            .iter()
+            .flatten()
+            .for_each(|element| validator.visit_in(element, holder));
+        voltage_level
+            .busbar_sections
+            .iter()
+            .flatten()
+            .for_each(|element| validator.visit_in(element, holder));
+        if let Some(topology) = &voltage_level.bus_breaker_topology {
+            topology.buses.iter().for_each(|bus| validator.visit(bus));
+            topology
+                .switches
+                .iter()
+                .for_each(|switch| validator.visit_in(switch, holder));
+        }
+        if let Some(topology) = &voltage_level.node_breaker_topology {
+            topology
+                .switches
+                .iter()
+                .for_each(|switch| validator.visit_in(switch, holder));
+        }
+    }
+    substation
+        .two_windings_transformers
+        .iter()
+        .for_each(|element| validator.visit(element));
+}
+
+fn walk_tie_line(tie_line: &TieLine, validator: &mut NetworkValidator) {
+    validator.visit(tie_line);
+    validator.visit(&tie_line.dangling_line1);
+    validator.visit(&tie_line.dangling_line2);
+}
+
+fn walk_hvdc_line(hvdc_line: &HvdcLine, validator: &mut NetworkValidator) {
+    validator.visit(hvdc_line);
+    validator.visit(&hvdc_line.converter_station1);
+    validator.visit(&hvdc_line.converter_station2);
+}
+
+/// Identifiers and voltage levels of a network document, to resolve references in it
+#[derive(Default)]
+struct Index {
+    ids: HashSet<String>,
+    bus_views: HashMap<String, BusView>,
+    buses: HashSet<String>,
+}
+
+impl Index {
+    fn add_voltage_level(&mut self, voltage_level: &VoltageLevel) {
+        self.bus_views.insert(
+            voltage_level.id.clone(),
+            BusView::compute(voltage_level, |switch| switch.open),
+        );
+        if let Some(topology) = &voltage_level.bus_breaker_topology {
+            self.buses
+                .extend(topology.buses.iter().map(|bus| bus.id.clone()));
+        }
+    }
+
+    /// Same resolution as `Reference::exists`, against the document
+    fn resolves(&self, reference: &Reference) -> bool {
+        match reference {
+            Reference::VoltageLevel { id } => self.bus_views.contains_key(id),
+            Reference::Bus {
+                voltage_level_id: Some(voltage_level_id),
+                bus,
+            } => self
+                .bus_views
+                .get(voltage_level_id)
+                .is_some_and(|view| view.bus(bus).is_some()),
+            Reference::Bus {
+                voltage_level_id: None,
+                bus,
+            } => self.buses.contains(bus),
+            Reference::Equipment { id } => self.ids.contains(id),
+        }
+    }
+}
+
+/// Checks of an identifiable, its references being resolved once the document is known
+struct Checked {
+    id: String,
+    violations: Vec<Violation>,
+    references: Vec<Reference>,
+}
+
+/// Validates a network document element by element, as it is read, without keeping the
+/// elements. The references are resolved by `finish`, once every element is known.
+#[derive(Default)]
+pub struct NetworkValidator {
+    index: Index,
+    checked: Vec<Checked>,
+}
+
+impl NetworkValidator {
+    fn visit<T: Identifiable + Validate + References>(&mut self, element: &T) {
+        self.visit_in(element, None);
+    }
+
+    /// Checks an element held by the voltage level `holder`, its buses being looked up in it
+    fn visit_in<T: Identifiable + Validate + References>(
+        &mut self,
+        element: &T,
+        holder: Option<&str>,
+    ) {
+        let id = element.id();
+        self.index.ids.insert(id.clone());
+        self.checked.push(Checked {
+            id,
+            violations: element.violations(),
+            references: references_in(element, holder),
+        });
+    }
+
+    /// Checks the network itself, without the elements it lists
+    pub fn network(&mut self, network: &Network) {
+        self.visit(network);
+    }
+
+    /// Checks a listed element along with the elements nested in it
+    pub fn element(&mut self, element: &NetworkElement) {
+        match element {
+            NetworkElement::Substation(substation) => walk_substation(substation, self),
+            NetworkElement::Line(line) => self.visit(line),
+            NetworkElement::ThreeWindingsTransformer(transformer) => self.visit(transformer),
+            NetworkElement::Switch(switch) => self.visit(switch),
+            NetworkElement::ShuntCompensator(shunt) => self.visit(shunt),
+            NetworkElement::StaticVarCompensator(compensator) => self.visit(compensator),
+            NetworkElement::DanglingLine(dangling_line) => self.visit(dangling_line),
+            NetworkElement::TieLine(tie_line) => walk_tie_line(tie_line, self),
+            NetworkElement::HvdcLine(hvdc_line) => walk_hvdc_line(hvdc_line, self),
+        }
+    }
+
+    /// Violations at `level` or below, in the order the elements were checked
+    pub fn finish(self, level: ValidationLevel) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for checked in self.checked {
+            violations.extend(
+                checked
+                    .violations
+                    .into_iter()
+                    .filter(|violation| violation.level <= level),
+            );
+            for reference in checked.references {
+                if !self.index.resolves(&reference) {
+                    violations.push(Violation {
+                        id: checked.id.clone(),
+                        path: String::new(),
+                        level: ValidationLevel::Equipment,
+                        message: format!("unknown {}", reference),
+                    });
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// Violations of every element of a network document checked at `level` or below, including
+/// the references to voltage levels, buses or equipment missing from the document
+pub fn validate_network(network: &Network, level: ValidationLevel) -> Vec<Violation> {
+    let mut validator = NetworkValidator::default();
+    walk(network, &mut validator);
+    validator.finish(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn generator(target_p: f64) -> Generator {
+        serde_json::from_value(json!({
+            "id": "GEN", "energySource": "OTHER", "minP": 0.0, "maxP": 100.0,
+            "voltageRegulatorOn": true, "targetP": target_p, "targetV": 24.5, "targetQ": 0.0,
+            "bus": "B", "connectableBus": "B"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_levels_filter_checks() {
+        let generator = generator(150.0);
+
+        assert!(validate(&generator, ValidationLevel::Equipment).is_empty());
+        assert_eq!(
+            validate(&generator, ValidationLevel::SteadyStateHypothesis),
+            vec![Violation {
+                id: "GEN".to_string(),
+                path: "/targetP".to_string(),
+                level: ValidationLevel::SteadyStateHypothesis,
+                message: "targetP 150 is outside [0, 100]".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_temporary_limits_order() {
+        let line: Line = serde_json::from_value(json!({
+            "id": "L", "r": 1.0, "x": 10.0, "g1": 0.0, "b1": 0.0, "g2": 0.0, "b2": 0.0,
+            "voltageLevelId1": "VL1", "bus1": "B1", "connectableBus1": "B1",
+            "voltageLevelId2": "VL2", "bus2": "B2", "connectableBus2": "B2",
+            "currentLimits1": {"permanentLimit": 1000.0, "temporaryLimits": [
+                {"name": "1'", "acceptableDuration": 60, "value": 1500.0},
+                {"name": "20'", "acceptableDuration": 1200, "value": 1200.0}
+            ]}
+        }))
+        .unwrap();
+
+        let violations = validate(&line, ValidationLevel::Equipment);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].path,
+            "/currentLimits1/temporaryLimits/1/acceptableDuration"
+        );
+    }
+}
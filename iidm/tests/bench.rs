@@ -0,0 +1,167 @@
+//! Time and peak memory of reading a large network JSON document, as a whole or element by
+//! element. Run with `cargo test --release --test bench -- --ignored --nocapture`, the size
+//! being set by `BENCH_SUBSTATIONS`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use iidm::libs::json_stream::{read_network, Registration};
+use iidm::*;
+use serde_json::json;
+
+#[allow(dead_code)]
+#[path = "it/fixture.rs"]
+mod fixture;
+
+use fixture::setup_world;
+
+/// Counts the bytes allocated, to report the peak of each run
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const DEFAULT_SUBSTATIONS: usize = 20_000;
+
+/// Time and peak of the bytes allocated on top of the ones allocated before `run`
+fn measure<T>(run: impl FnOnce() -> T) -> (T, Duration, usize) {
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let start = Instant::now();
+    let result = run();
+    let elapsed = start.elapsed();
+    (result, elapsed, PEAK.load(Ordering::Relaxed) - baseline)
+}
+
+fn report(name: &str, elapsed: Duration, peak: usize) {
+    println!(
+        "{:<24} {:>10.1} ms {:>10.1} MB",
+        name,
+        elapsed.as_secs_f64() * 1000.0,
+        peak as f64 / 1e6
+    );
+}
+
+/// Substations of one bus-breaker voltage level with a generator and a load, in a chain of lines
+fn network_json(substations: usize) -> Vec<u8> {
+    let substation = |n: usize| {
+        json!({
+            "id": format!("S{}", n), "country": "FR", "tso": "RTE", "geographicalTags": [],
+            "twoWindingsTransformers": [],
+            "voltageLevels": [{
+                "id": format!("VL{}", n), "nominalV": 400.0, "topologyKind": "BUS_BREAKER",
+                "generators": [{
+                    "id": format!("G{}", n), "energySource": "OTHER", "minP": 0.0, "maxP": 500.0,
+                    "voltageRegulatorOn": true, "targetP": 100.0, "targetV": 400.0,
+                    "targetQ": 0.0, "bus": format!("B{}", n), "connectableBus": format!("B{}", n)
+                }],
+                "loads": [{
+                    "id": format!("L{}", n), "loadType": "UNDEFINED", "p0": 100.0, "q0": 10.0,
+                    "bus": format!("B{}", n), "connectableBus": format!("B{}", n)
+                }],
+                "busBreakerTopology": {"buses": [{"id": format!("B{}", n)}], "switches": []}
+            }]
+        })
+    };
+    let line = |n: usize| {
+        json!({
+            "id": format!("LINE{}", n), "r": 1.0, "x": 10.0, "g1": 0.0, "b1": 1e-5,
+            "g2": 0.0, "b2": 1e-5,
+            "voltageLevelId1": format!("VL{}", n), "bus1": format!("B{}", n),
+            "connectableBus1": format!("B{}", n),
+            "voltageLevelId2": format!("VL{}", n + 1), "bus2": format!("B{}", n + 1),
+            "connectableBus2": format!("B{}", n + 1),
+            "currentLimits1": {"permanentLimit": 1000.0, "temporaryLimits": []}
+        })
+    };
+    serde_json::to_vec(&json!({
+        "version": "1.12",
+        "id": "bench",
+        "caseDate": "2026-01-15T00:00:00.000+01:00",
+        "forecastDistance": 0,
+        "sourceFormat": "bench",
+        "minimumValidationLevel": "STEADY_STATE_HYPOTHESIS",
+        "substations": (0..substations).map(substation).collect::<Vec<_>>(),
+        "lines": (1..substations).map(|n| line(n - 1)).collect::<Vec<_>>()
+    }))
+    .unwrap()
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_network_json() {
+    let substations = std::env::var("BENCH_SUBSTATIONS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_SUBSTATIONS);
+    let json = network_json(substations);
+    println!(
+        "{} substations, {:.1} MB of JSON",
+        substations,
+        json.len() as f64 / 1e6
+    );
+
+    let (network, elapsed, peak) = measure(|| serde_json::from_slice::<Network>(&json).unwrap());
+    report("parse document", elapsed, peak);
+    drop(network);
+
+    let (count, elapsed, peak) = measure(|| {
+        let mut count = 0;
+        read_network(json.as_slice(), |_| count += 1).unwrap();
+        count
+    });
+    report("parse elements", elapsed, peak);
+    assert_eq!(count, 2 * substations - 1);
+
+    let (world, elapsed, peak) = measure(|| {
+        let (mut world, mut schedule) = setup_world();
+        let network: Network = serde_json::from_slice(&json).unwrap();
+        assert!(validate_network(&network, network.minimum_validation_level).is_empty());
+        network.register(&mut world, &mut schedule);
+        world
+    });
+    report("validate, register", elapsed, peak);
+    drop(world);
+
+    let (world, elapsed, peak) = measure(|| {
+        let (mut world, mut schedule) = setup_world();
+        let mut registration = Registration::default();
+        let mut validator = NetworkValidator::default();
+        let network = read_network(json.as_slice(), |element| {
+            validator.element(&element);
+            registration.register(&mut world, &mut schedule, &element);
+        })
+        .unwrap();
+        validator.network(&network);
+        assert!(validator
+            .finish(network.minimum_validation_level)
+            .is_empty());
+        registration.finish(&mut world, &mut schedule, &network);
+        world
+    });
+    report("stream, register", elapsed, peak);
+    assert_eq!(
+        world.resource::<AssetRegistry>().count::<Load>(),
+        substations
+    );
+}
@@ -0,0 +1,32 @@
+function mpc = case4_tap
+% Four buses over two voltages: a tap changing, phase shifting transformer, a bus shunt,
+% two generators on one bus and an isolated bus.
+
+mpc.version = '2';
+mpc.baseMVA = 100;
+
+mpc.bus_name = {
+	'North 400';
+	'North 225';
+};
+
+%	bus_i	type	Pd	Qd	Gs	Bs	area	Vm	Va	baseKV	zone	Vmax	Vmin
+mpc.bus = [
+	1	3	0	0	0	0	1	1.02	0	400	1	1.1	0.9;
+	2	1	50	20	0	30	1	1	0	225	1	1.1	0.9;
+	3	1	40	10	0	0	1	1	0	225	1	1.1	0.9;
+	4	4	10	5	0	0	1	1	0	225	1	1.1	0.9
+];
+
+%	bus	Pg	Qg	Qmax	Qmin	Vg	mBase	status	Pmax	Pmin
+mpc.gen = [
+	1	60	0	200	-200	1.02	100	1	300	0;
+	1	30	0	100	-100	1.02	100	0	100	0;
+];
+
+%	fbus	tbus	r	x	b	rateA	rateB	rateC	ratio	angle	status
+mpc.branch = [
+	1, 2, 0.001, 0.05, 0, 300, 300, 300, 0.98, -2, 1;
+	2, 3, 0.01, 0.04, 0.02, 150, 150, 150, 0, 0, 1;
+	3, 4, 0.01, 0.04, 0.02, 150, 150, 150, 0, 0, 0;
+];
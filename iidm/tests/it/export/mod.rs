@@ -0,0 +1,213 @@
+use bevy_ecs::{event::Events, world::World};
+use iidm::*;
+
+use super::{load_network, setup};
+
+fn export(world: &World) -> NetworkExport {
+    export_network(world).unwrap()
+}
+
+#[test]
+fn test_export_without_updates_matches_upload() {
+    let (world, _) = setup();
+
+    let exported = export(&world);
+
+    assert_eq!(
+        serde_json::to_value(&exported.network).unwrap(),
+        serde_json::to_value(load_network()).unwrap()
+    );
+    assert!(exported.unplaced.is_empty());
+}
+
+#[test]
+fn test_export_honours_nested_updates() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(UpdateEvent::<Line> {
+        id: "NHV1_NHV2_1".to_string(),
+        updater: LineUpdater {
+            r: Some(42.0),
+            ..Default::default()
+        },
+    });
+    world.send_event(UpdateEvent::<Generator> {
+        id: "GEN".to_string(),
+        updater: GeneratorUpdater {
+            target_p: Some(500.0),
+            ..Default::default()
+        },
+    });
+    schedule.run(&mut world);
+
+    let network = export(&world).network;
+
+    let line = network
+        .lines
+        .iter()
+        .find(|l| l.id == "NHV1_NHV2_1")
+        .unwrap();
+    assert_eq!(line.r, 42.0);
+    let generator = network
+        .substations
+        .iter()
+        .flat_map(|s| &s.voltage_levels)
+        .flat_map(|vl| vl.generators.iter().flatten())
+        .find(|g| g.id == "GEN")
+        .unwrap();
+    assert_eq!(generator.target_p, 500.0);
+}
+
+#[test]
+fn test_export_drops_deleted_and_attaches_created() {
+    let (mut world, mut schedule) = setup();
+
+    let mut line = load_network().lines[0].clone();
+    line.id = "NEW_LINE".to_string();
+    let mut generator = load_network().substations[0].voltage_levels[0]
+        .generators
+        .clone()
+        .unwrap()[0]
+        .clone();
+    generator.id = "GEN2".to_string();
+
+    world.send_event(DeleteEvent::<Line>::new("NHV1_NHV2_2"));
+    world.send_event(CreateEvent {
+        id: "NEW_LINE".to_string(),
+        parent_id: None,
+        component: line,
+    });
+    world.send_event(CreateEvent {
+        id: "GEN2".to_string(),
+        parent_id: Some("VLGEN".to_string()),
+        component: generator.clone(),
+    });
+    schedule.run(&mut world);
+
+    let exported = export(&world);
+    let line_ids: Vec<&str> = exported
+        .network
+        .lines
+        .iter()
+        .map(|l| l.id.as_str())
+        .collect();
+    assert_eq!(line_ids, vec!["NHV1_NHV2_1", "NEW_LINE"]);
+
+    // The generator lands in the voltage level holding its bus
+    let voltage_level = exported
+        .network
+        .substations
+        .iter()
+        .flat_map(|s| &s.voltage_levels)
+        .find(|vl| vl.generators.iter().flatten().any(|g| g.id == "GEN2"))
+        .unwrap();
+    assert!(voltage_level
+        .bus_breaker_topology
+        .as_ref()
+        .unwrap()
+        .buses
+        .iter()
+        .any(|bus| bus.id == generator.bus));
+    assert!(exported.unplaced.is_empty());
+}
+
+/// Voltage level "VLNEW" holding the generator "GENNEW" on its bus "NNEW"
+fn new_voltage_level() -> VoltageLevel {
+    let mut voltage_level = load_network().substations[0].voltage_levels[0].clone();
+    voltage_level.id = "VLNEW".to_string();
+    voltage_level.loads = None;
+    voltage_level.bus_breaker_topology = Some(BusBreakerTopology {
+        buses: vec![Bus {
+            id: "NNEW".to_string(),
+        }],
+        switches: Vec::new(),
+    });
+    let generator = &mut voltage_level.generators.as_mut().unwrap()[0];
+    generator.id = "GENNEW".to_string();
+    generator.bus = "NNEW".to_string();
+    generator.connectable_bus = "NNEW".to_string();
+    voltage_level
+}
+
+#[test]
+fn test_export_places_created_voltage_level() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(CreateEvent {
+        id: "VLNEW".to_string(),
+        parent_id: Some("P1".to_string()),
+        component: new_voltage_level(),
+    });
+    schedule.run(&mut world);
+
+    // The nested generator lives in its own entity, below the voltage level
+    let registry = world.resource::<AssetRegistry>();
+    let voltage_level = registry.find_typed::<VoltageLevel>("VLNEW").unwrap();
+    let generator = registry.find_typed::<Generator>("GENNEW").unwrap();
+    assert_eq!(
+        world.get::<ChildOf>(generator).unwrap().parent,
+        voltage_level
+    );
+    assert!(world
+        .get::<VoltageLevel>(voltage_level)
+        .unwrap()
+        .generators
+        .as_ref()
+        .is_none_or(Vec::is_empty));
+
+    let exported = export(&world);
+    assert!(exported.unplaced.is_empty());
+    let substation = &exported.network.substations[0];
+    assert_eq!(substation.id, "P1");
+    let created = substation
+        .voltage_levels
+        .iter()
+        .find(|vl| vl.id == "VLNEW")
+        .unwrap();
+    assert_eq!(created.generators.as_ref().unwrap()[0].id, "GENNEW");
+}
+
+#[test]
+fn test_create_needs_a_known_parent() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(CreateEvent {
+        id: "VLNEW".to_string(),
+        parent_id: None,
+        component: new_voltage_level(),
+    });
+    world.send_event(CreateEvent {
+        id: "VLNEW".to_string(),
+        parent_id: Some("VLGEN".to_string()),
+        component: new_voltage_level(),
+    });
+    schedule.run(&mut world);
+
+    let events = world.resource::<Events<ComponentErrorEvent>>();
+    let errors: Vec<_> = events
+        .get_cursor()
+        .read(events)
+        .map(|error| error.error_type.clone())
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            ErrorType::InvalidParent("The id of the parent Substation is required".to_string()),
+            ErrorType::InvalidParent("Unknown parent Substation 'VLGEN'".to_string()),
+        ]
+    );
+    let registry = world.resource::<AssetRegistry>();
+    assert!(registry.find("VLNEW").is_none());
+    assert!(registry.find("GENNEW").is_none());
+}
+
+#[test]
+fn test_export_without_network() {
+    let mut world = World::new();
+    world.init_resource::<AssetRegistry>();
+
+    assert!(matches!(
+        export_network(&world),
+        Err(ExportError::NoNetwork)
+    ));
+}
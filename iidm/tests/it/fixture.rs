@@ -0,0 +1,79 @@
+//! World shared by the integration tests, set up as the server sets up each network
+
+use bevy_ecs::{event::Events, schedule::Schedule, world::World};
+use iidm::*;
+
+pub const NETWORK_JSON_FILE: &str = "tests/data/network.json";
+
+/// Reference network of the tests
+pub fn load_network() -> Network {
+    serde_json::from_str(&std::fs::read_to_string(NETWORK_JSON_FILE).unwrap()).unwrap()
+}
+
+macro_rules! init_components {
+    ($world:expr, $schedule:expr, $($component:ty),* $(,)?) => {
+        $(
+            $world.init_resource::<Events<RegisterEvent<$component>>>();
+            $world.init_resource::<Events<CreateEvent<$component>>>();
+            $world.init_resource::<Events<DeleteEvent<$component>>>();
+            $world.init_resource::<Events<UpdateEvent<$component>>>();
+            $world.init_resource::<Events<PatchEvent<$component>>>();
+            $schedule.add_systems(handle_register_events::<$component>);
+            $schedule.add_systems(handle_create_events::<$component>);
+            $schedule.add_systems(handle_delete_events::<$component>);
+            $schedule.add_systems(handle_update_events::<$component>);
+            $schedule.add_systems(handle_patch_events::<$component>);
+        )*
+    };
+}
+
+/// Empty world handling the events of every element type, with the topology, the load flow and
+/// the edit journal
+pub fn setup_world() -> (World, Schedule) {
+    let mut world = World::new();
+    let mut schedule = Schedule::default();
+
+    world.init_resource::<AssetRegistry>();
+    world.init_resource::<Events<ComponentErrorEvent>>();
+    world.init_resource::<Events<InvalidReferenceEvent>>();
+    init_components!(
+        world,
+        schedule,
+        Network,
+        Substation,
+        VoltageLevel,
+        Line,
+        TwoWindingsTransformer,
+        ThreeWindingsTransformer,
+        Generator,
+        Load,
+        Bus,
+        BusbarSection,
+        Switch,
+        ShuntCompensator,
+        StaticVarCompensator,
+        DanglingLine,
+        TieLine,
+        HvdcLine,
+        HvdcConverterStation,
+        TerminalRef
+    );
+    init_topology(&mut world, &mut schedule);
+    init_load_flow(&mut world, &mut schedule);
+    init_history(&mut world);
+
+    (world, schedule)
+}
+
+/// World holding a network
+pub fn setup_with(network: &Network) -> (World, Schedule) {
+    let (mut world, mut schedule) = setup_world();
+    network.register(&mut world, &mut schedule);
+    schedule.run(&mut world);
+    (world, schedule)
+}
+
+/// World holding the reference network
+pub fn setup() -> (World, Schedule) {
+    setup_with(&load_network())
+}
@@ -0,0 +1,157 @@
+use bevy_ecs::{schedule::Schedule, world::World};
+use iidm::*;
+use serde_json::json;
+
+use super::setup;
+
+fn line(world: &World, id: &str) -> Option<Line> {
+    let entity = world.resource::<AssetRegistry>().find(id)?;
+    world.get::<Line>(entity).cloned()
+}
+
+fn set_line_r(world: &mut World, schedule: &mut Schedule, r: f64) {
+    world.send_event(UpdateEvent::<Line> {
+        id: "NHV1_NHV2_1".to_string(),
+        updater: LineUpdater {
+            r: Some(r),
+            ..Default::default()
+        },
+    });
+    schedule.run(world);
+}
+
+#[test]
+fn test_updates_are_recorded() {
+    let (mut world, mut schedule) = setup();
+
+    set_line_r(&mut world, &mut schedule, 4.0);
+    world.send_event(PatchEvent::<Line>::new(
+        "NHV1_NHV2_1",
+        Patch::Merge(json!({"x": 40.0})),
+    ));
+    schedule.run(&mut world);
+    // Nothing changes, nothing is recorded
+    set_line_r(&mut world, &mut schedule, 4.0);
+
+    let history = world.resource::<History>();
+    let entries = history.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].component_type, "Line");
+    assert_eq!(entries[0].id, "NHV1_NHV2_1");
+    assert_eq!(entries[0].before, json!({"r": 3.0}));
+    assert_eq!(entries[0].after, json!({"r": 4.0}));
+    assert_eq!(entries[1].after, json!({"x": 40.0}));
+}
+
+#[test]
+fn test_undo_redo() {
+    let (mut world, mut schedule) = setup();
+    set_line_r(&mut world, &mut schedule, 4.0);
+    set_line_r(&mut world, &mut schedule, 5.0);
+
+    assert_eq!(undo(&mut world).unwrap().after, json!({"r": 5.0}));
+    assert_eq!(line(&world, "NHV1_NHV2_1").unwrap().r, 4.0);
+    undo(&mut world).unwrap();
+    assert_eq!(line(&world, "NHV1_NHV2_1").unwrap().r, 3.0);
+    assert!(matches!(undo(&mut world), Err(HistoryError::NothingToUndo)));
+
+    redo(&mut world).unwrap();
+    assert_eq!(line(&world, "NHV1_NHV2_1").unwrap().r, 4.0);
+    assert_eq!(world.resource::<History>().undone().len(), 1);
+
+    // A new change forgets what was undone
+    set_line_r(&mut world, &mut schedule, 6.0);
+    assert!(matches!(redo(&mut world), Err(HistoryError::NothingToRedo)));
+    assert_eq!(world.resource::<History>().entries().len(), 2);
+}
+
+#[test]
+fn test_rollback_to_mark() {
+    let (mut world, mut schedule) = setup();
+    set_line_r(&mut world, &mut schedule, 4.0);
+    set_line_r(&mut world, &mut schedule, 5.0);
+    undo(&mut world).unwrap();
+
+    let mark = world.resource::<History>().mark();
+    set_line_r(&mut world, &mut schedule, 6.0);
+    assert!(world.resource::<History>().undone().is_empty());
+
+    // The caller reverts the component, the journal forgets the change
+    set_line_r(&mut world, &mut schedule, 4.0);
+    world.resource_mut::<History>().rollback(mark);
+
+    let history = world.resource::<History>();
+    assert_eq!(history.entries().len(), 1);
+    assert_eq!(history.undone().len(), 1);
+    redo(&mut world).unwrap();
+    assert_eq!(line(&world, "NHV1_NHV2_1").unwrap().r, 5.0);
+}
+
+#[test]
+fn test_undo_deleted_element_keeps_entry() {
+    let (mut world, mut schedule) = setup();
+    set_line_r(&mut world, &mut schedule, 4.0);
+
+    world.send_event(DeleteEvent::<Line>::new("NHV1_NHV2_1"));
+    schedule.run(&mut world);
+
+    assert!(matches!(
+        undo(&mut world),
+        Err(HistoryError::ElementNotFound(id)) if id == "NHV1_NHV2_1"
+    ));
+    assert_eq!(world.resource::<History>().entries().len(), 1);
+}
+
+#[test]
+fn test_checkpoint_restore() {
+    let (mut world, mut schedule) = setup();
+    set_line_r(&mut world, &mut schedule, 4.0);
+
+    let checkpoint = save_checkpoint(&mut world, "before exercise").unwrap();
+    assert_eq!(checkpoint.sequence, 1);
+
+    set_line_r(&mut world, &mut schedule, 8.0);
+    world.send_event(DeleteEvent::<Line>::new("NHV1_NHV2_2"));
+    schedule.run(&mut world);
+    assert!(line(&world, "NHV1_NHV2_2").is_none());
+
+    restore_checkpoint(&mut world, &mut schedule, "before exercise").unwrap();
+
+    assert_eq!(line(&world, "NHV1_NHV2_1").unwrap().r, 4.0);
+    assert!(line(&world, "NHV1_NHV2_2").is_some());
+    assert_eq!(
+        export_network(&world).unwrap().network.lines.len(),
+        2,
+        "the restored network has no duplicate"
+    );
+    let history = world.resource::<History>();
+    assert!(history.entries().is_empty());
+    assert_eq!(history.checkpoints().count(), 1);
+
+    assert!(matches!(
+        restore_checkpoint(&mut world, &mut schedule, "unknown"),
+        Err(HistoryError::UnknownCheckpoint(_))
+    ));
+}
+
+#[test]
+fn test_checkpoint_refuses_unplaced_elements() {
+    let (mut world, mut schedule) = setup();
+    let generator: Generator = serde_json::from_value(json!({
+        "id": "LOST", "energySource": "OTHER", "minP": 0.0, "maxP": 100.0,
+        "voltageRegulatorOn": false, "targetP": 50.0, "targetV": 20.0, "targetQ": 0.0,
+        "bus": "NOWHERE", "connectableBus": "NOWHERE"
+    }))
+    .unwrap();
+    world.send_event(RegisterEvent {
+        id: "LOST".to_string(),
+        component: generator,
+    });
+    schedule.run(&mut world);
+
+    assert!(matches!(
+        save_checkpoint(&mut world, "lossy"),
+        Err(HistoryError::Unplaced(ids)) if ids == ["LOST"]
+    ));
+    assert_eq!(world.resource::<History>().checkpoints().count(), 0);
+}
@@ -1,4 +1,6 @@
-use iidm::{CurrentLimitsError, CurrentLimitsUpdater, JsonSchema, TemporaryLimitUpdater};
+use iidm::{
+    CurrentLimitsError, CurrentLimitsUpdater, JsonSchema, ListUpdate, TemporaryLimitUpdater,
+};
 
 #[test]
 fn test_update_from_not_json_updater() {
@@ -122,7 +124,7 @@ fn test_update_from_empty_temporary_limits() {
             "temporary_limits should be Some"
         );
         assert!(
-            validated.temporary_limits.unwrap().is_empty(),
+            matches!(validated.temporary_limits, Some(ListUpdate::Set(limits)) if limits.is_empty()),
             "temporary_limits should be empty"
         );
     }
@@ -180,7 +182,9 @@ fn test_update_with_valid_temporary_limits() {
             "temporary_limits should be Some"
         );
 
-        let limits = validated.temporary_limits.unwrap();
+        let Some(ListUpdate::Set(limits)) = validated.temporary_limits else {
+            panic!("temporary_limits should replace the list");
+        };
         assert_eq!(limits.len(), 2, "Should have 2 temporary limits");
 
         assert_eq!(limits[0].name, "limit1");
@@ -262,6 +266,8 @@ fn test_partial_updates() {
     if let Ok(validated) = validation {
         assert!(validated.permanent_limit.is_none());
         assert!(validated.temporary_limits.is_some());
-        assert_eq!(validated.temporary_limits.unwrap().len(), 1);
+        assert!(
+            matches!(validated.temporary_limits, Some(ListUpdate::Set(limits)) if limits.len() == 1)
+        );
     }
 }
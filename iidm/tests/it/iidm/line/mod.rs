@@ -1,5 +1,6 @@
 use iidm::Line;
 
+mod patch;
 mod serialization;
 mod update;
 
@@ -0,0 +1,199 @@
+use super::*;
+use bevy_ecs::{event::Events, schedule::Schedule, world::World};
+use iidm::*;
+use serde_json::json;
+
+fn line_with_limits() -> Line {
+    let mut line = create_default_line();
+    line.current_limits1 = Some(CurrentLimits {
+        permanent_limit: 1000.0,
+        temporary_limits: vec![
+            TemporaryLimit {
+                name: "20'".to_string(),
+                acceptable_duration: 1200,
+                value: 1200.0,
+            },
+            TemporaryLimit {
+                name: "1'".to_string(),
+                acceptable_duration: 60,
+                value: 1500.0,
+            },
+        ],
+    });
+    line
+}
+
+#[test]
+fn test_updater_null_clears_optional_field() {
+    let mut line = line_with_limits();
+
+    let updater: LineUpdater = serde_json::from_value(json!({"currentLimits1": null})).unwrap();
+    line.update(updater).unwrap();
+    assert!(line.current_limits1.is_none());
+
+    let updater: LineUpdater = serde_json::from_value(json!({"r": 5.0})).unwrap();
+    assert!(updater.current_limits2.is_none());
+}
+
+#[test]
+fn test_merge_patch_nested_limit() {
+    let mut line = line_with_limits();
+
+    line.merge_patch(&json!({
+        "x": 30.0,
+        "currentLimits1": {"temporaryLimits": {"1'": {"value": 1400.0}}}
+    }))
+    .unwrap();
+
+    let limits = line.current_limits1.as_ref().unwrap();
+    assert_eq!(line.x, 30.0);
+    assert_eq!(limits.permanent_limit, 1000.0);
+    assert_eq!(limits.temporary_limits[0].value, 1200.0);
+    assert_eq!(limits.temporary_limits[1].value, 1400.0);
+}
+
+#[test]
+fn test_merge_patch_clears_and_creates_optional_field() {
+    let mut line = line_with_limits();
+
+    line.merge_patch(&json!({"currentLimits1": null})).unwrap();
+    assert!(line.current_limits1.is_none());
+
+    // A missing structure is created from a complete value only
+    assert!(line
+        .merge_patch(&json!({"currentLimits2": {"permanentLimit": 900.0}}))
+        .is_err());
+    line.merge_patch(&json!({"currentLimits2": {"permanentLimit": 900.0, "temporaryLimits": []}}))
+        .unwrap();
+    assert_eq!(line.current_limits2.unwrap().permanent_limit, 900.0);
+}
+
+#[test]
+fn test_merge_patch_refuses_id_change() {
+    let mut line = create_default_line();
+
+    assert!(line
+        .merge_patch(&json!({"id": "NHV1_NHV2_1", "r": 4.0}))
+        .is_ok());
+    assert_eq!(
+        line.merge_patch(&json!({"id": "OTHER"})),
+        Err(PatchError::ReadOnly("/id".to_string()))
+    );
+    assert_eq!(
+        line.merge_patch(&json!({"unknown": 1})),
+        Err(PatchError::UnknownField("/unknown".to_string()))
+    );
+}
+
+#[test]
+fn test_handle_line_patch() {
+    let mut world = World::default();
+    let mut schedule = Schedule::default();
+
+    world.init_resource::<Events<ComponentErrorEvent>>();
+    world.init_resource::<Events<InvalidReferenceEvent>>();
+    world.init_resource::<Events<RegisterEvent<Line>>>();
+    world.init_resource::<Events<PatchEvent<Line>>>();
+    world.init_resource::<AssetRegistry>();
+    schedule.add_systems(handle_register_events::<Line>);
+    schedule.add_systems(handle_patch_events::<Line>);
+
+    world.send_event(RegisterEvent {
+        id: "NHV1_NHV2_1".to_string(),
+        component: line_with_limits(),
+    });
+    schedule.run(&mut world);
+
+    let operations = serde_json::from_value(json!([
+        {"op": "replace", "path": "/currentLimits1/temporaryLimits/0/value", "value": 1250.0},
+        {"op": "remove", "path": "/currentLimits1/temporaryLimits/1"}
+    ]))
+    .unwrap();
+    world.send_event(PatchEvent::<Line>::new(
+        "NHV1_NHV2_1",
+        Patch::Json(operations),
+    ));
+    // Applied as a whole or not at all
+    world.send_event(PatchEvent::<Line>::new(
+        "NHV1_NHV2_1",
+        Patch::Merge(json!({"r": 9.0, "g1": null})),
+    ));
+    schedule.run(&mut world);
+
+    let entity = world
+        .resource::<AssetRegistry>()
+        .find("NHV1_NHV2_1")
+        .unwrap();
+    let line = world.get::<Line>(entity).unwrap();
+    let limits = line.current_limits1.as_ref().unwrap();
+    assert_eq!(limits.temporary_limits.len(), 1);
+    assert_eq!(limits.temporary_limits[0].value, 1250.0);
+    assert_eq!(line.r, 3.0);
+
+    let events = world.resource::<Events<ComponentErrorEvent>>();
+    let errors: Vec<_> = events.get_cursor().read(events).cloned().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].error_type,
+        ErrorType::InvalidPatch("Field '/g1' cannot be cleared".to_string())
+    );
+}
+
+#[test]
+fn test_updater_updates_nested_structure() {
+    let mut line = line_with_limits();
+
+    let updater: LineUpdater =
+        serde_json::from_value(json!({"currentLimits1": {"permanentLimit": 900.0}})).unwrap();
+    line.update(updater).unwrap();
+
+    let limits = line.current_limits1.as_ref().unwrap();
+    assert_eq!(limits.permanent_limit, 900.0);
+    assert_eq!(limits.temporary_limits.len(), 2);
+
+    // A missing structure is created from a complete value only
+    let updater: LineUpdater =
+        serde_json::from_value(json!({"currentLimits2": {"permanentLimit": 900.0}})).unwrap();
+    assert!(matches!(
+        line.update(updater),
+        Err(PatchError::InvalidValue { path, .. }) if path == "/currentLimits2"
+    ));
+}
+
+#[test]
+fn test_updater_edits_list_elements_by_key() {
+    let mut line = line_with_limits();
+
+    let updater: LineUpdater =
+        serde_json::from_value(json!({"currentLimits1": {"temporaryLimits": [
+            {"op": "update", "key": "1'", "value": {"value": 1400.0}},
+            {"op": "remove", "key": 0},
+            {"op": "insert", "value": {"name": "10'", "acceptableDuration": 600, "value": 1300.0}}
+        ]}}))
+        .unwrap();
+    line.update(updater).unwrap();
+
+    let limits = &line.current_limits1.as_ref().unwrap().temporary_limits;
+    assert_eq!(limits.len(), 2);
+    assert_eq!((limits[0].name.as_str(), limits[0].value), ("1'", 1400.0));
+    assert_eq!((limits[1].name.as_str(), limits[1].value), ("10'", 1300.0));
+
+    let updater: LineUpdater =
+        serde_json::from_value(json!({"currentLimits1": {"temporaryLimits": [
+            {"op": "remove", "key": "5'"}
+        ]}}))
+        .unwrap();
+    assert_eq!(
+        line.update(updater),
+        Err(PatchError::UnknownElement(
+            "/currentLimits1/temporaryLimits/5'".to_string()
+        ))
+    );
+
+    let updater: LineUpdater =
+        serde_json::from_value(json!({"currentLimits1": {"temporaryLimits": [
+            {"op": "insert", "value": {"name": "1'", "acceptableDuration": 60, "value": 1500.0}}
+        ]}}))
+        .unwrap();
+    assert!(line.update(updater).is_err());
+}
@@ -8,7 +8,8 @@ fn test_update_single_field() {
     line.update(LineUpdater {
         r: Some(10.0),
         ..Default::default()
-    });
+    })
+    .unwrap();
     assert_eq!(line.r, 10.0);
     assert_eq!(line.x, 33.0);
     assert_eq!(line.g1, 0.0);
@@ -24,7 +25,8 @@ fn test_update_multiple_fields() {
         g1: Some(1.0),
         b1: Some(2.0),
         ..Default::default()
-    });
+    })
+    .unwrap();
     assert_eq!(line.r, 10.0);
     assert_eq!(line.x, 20.0);
     assert_eq!(line.g1, 1.0);
@@ -41,7 +43,8 @@ fn test_update_connection_fields() {
         bus1: Some("NEW_BUS1".to_string()),
         connectable_bus1: Some("NEW_CBUS1".to_string()),
         ..Default::default()
-    });
+    })
+    .unwrap();
     assert_eq!(line.voltage_level_id1, "NEW_VL1");
     assert_eq!(line.bus1, "NEW_BUS1");
     assert_eq!(line.connectable_bus1, "NEW_CBUS1");
@@ -62,9 +65,10 @@ fn test_update_current_limits() {
     };
 
     line.update(LineUpdater {
-        current_limits1: Some(Some(new_limits)),
+        current_limits1: Some(OptionalUpdate::Set(new_limits)),
         ..Default::default()
-    });
+    })
+    .unwrap();
 
     assert!(line.current_limits1.is_some());
     assert!(line.current_limits2.is_none());
@@ -80,18 +84,20 @@ fn test_update_remove_current_limits() {
     let mut line = create_default_line();
     // Add
     line.update(LineUpdater {
-        current_limits1: Some(Some(CurrentLimits {
+        current_limits1: Some(OptionalUpdate::Set(CurrentLimits {
             permanent_limit: 1000.0,
             temporary_limits: vec![],
         })),
         ..Default::default()
-    });
+    })
+    .unwrap();
 
     // And remove
     line.update(LineUpdater {
-        current_limits1: Some(None),
+        current_limits1: Some(OptionalUpdate::Clear),
         ..Default::default()
-    });
+    })
+    .unwrap();
 
     assert!(line.current_limits1.is_none());
 }
@@ -101,7 +107,7 @@ fn test_update_with_empty_update() {
     let mut line = create_default_line();
     let original = create_default_line();
 
-    line.update(LineUpdater::default());
+    line.update(LineUpdater::default()).unwrap();
 
     assert_eq!(
         serde_json::to_value(&line).unwrap(),
@@ -116,7 +122,8 @@ fn test_handle_line_update() {
     let mut schedule = Schedule::default();
 
     // Init Resources and Systems
-    world.init_resource::<Events<EntityNotFoundEvent>>();
+    world.init_resource::<Events<ComponentErrorEvent>>();
+    world.init_resource::<Events<InvalidReferenceEvent>>();
     world.init_resource::<Events<RegisterEvent<Line>>>();
     world.init_resource::<Events<UpdateEvent<Line>>>();
     world.init_resource::<AssetRegistry>();
@@ -197,7 +204,8 @@ fn test_multiple_line_updates() {
     let mut schedule = Schedule::default();
 
     // Init Resources and Systems
-    world.init_resource::<Events<EntityNotFoundEvent>>();
+    world.init_resource::<Events<ComponentErrorEvent>>();
+    world.init_resource::<Events<InvalidReferenceEvent>>();
     world.init_resource::<Events<RegisterEvent<Line>>>();
     world.init_resource::<Events<UpdateEvent<Line>>>();
     world.init_resource::<AssetRegistry>();
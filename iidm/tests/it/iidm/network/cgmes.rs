@@ -0,0 +1,188 @@
+use std::io::{Cursor, Write};
+
+use iidm::libs::cgmes::{self, CgmesError};
+use iidm::*;
+
+const PROFILES: [&str; 3] = [
+    "tests/data/cgmes/small_SSH.xml",
+    "tests/data/cgmes/small_EQ.xml",
+    "tests/data/cgmes/small_SV.xml",
+];
+
+fn read_profiles() -> Vec<Vec<u8>> {
+    PROFILES
+        .iter()
+        .map(|path| std::fs::read(path).unwrap())
+        .collect()
+}
+
+fn voltage_level<'a>(network: &'a Network, id: &str) -> &'a VoltageLevel {
+    network
+        .substations
+        .iter()
+        .flat_map(|substation| &substation.voltage_levels)
+        .find(|voltage_level| voltage_level.id == id)
+        .unwrap()
+}
+
+#[test]
+fn test_cgmes_profiles_merged() -> Result<(), Box<dyn std::error::Error>> {
+    let profiles = read_profiles();
+    let import = cgmes::from_profiles(profiles.iter().map(Vec::as_slice))?;
+    let network = &import.network;
+
+    // Named after the equipment profile, whatever the order of the profiles
+    assert_eq!(network.id, "_small-grid");
+    assert_eq!(network.source_format, "CGMES");
+    assert_eq!(network.case_date.to_rfc3339(), "2024-03-01T10:30:00+00:00");
+    assert_eq!(network.substations.len(), 2);
+
+    let vl20 = voltage_level(network, "_VL20");
+    assert_eq!(vl20.nominal_v, 20.0);
+    assert_eq!(vl20.topology_kind, TopologyKind::NodeBreaker);
+    let generator = &vl20.generators.as_ref().unwrap()[0];
+    assert_eq!(generator.id, "_GEN");
+    assert_eq!(generator.energy_source, EnergySource::Hydro);
+    assert_eq!((generator.target_p, generator.target_q), (450.0, 50.0));
+    assert_eq!((generator.min_p, generator.max_p), (0.0, 500.0));
+    assert!(generator.voltage_regulator_on);
+    assert_eq!(generator.target_v, 21.0);
+    assert_eq!(generator.bus, "0");
+
+    let transformer = &network.substations[0].two_windings_transformers[0];
+    assert_eq!(transformer.id, "_TR");
+    assert_eq!((transformer.rated_u1, transformer.rated_u2), (20.0, 400.0));
+    // The impedance of the end 1 is brought to the side 2
+    assert!((transformer.r - (0.01 * 400.0 + 0.5)).abs() < 1e-9);
+    assert!((transformer.x - (0.1 * 400.0 + 10.0)).abs() < 1e-9);
+    assert_eq!(transformer.voltage_level_id2, "_VL400");
+    let tap_changer = transformer.ratio_tap_changer.as_ref().unwrap();
+    assert_eq!(tap_changer.steps.len(), 5);
+    assert_eq!(tap_changer.low_tap_position, 1);
+    assert_eq!(tap_changer.tap_position, 4);
+    assert!((tap_changer.steps[3].rho - 1.0125).abs() < 1e-9);
+    assert!(tap_changer.regulating);
+    assert_eq!(tap_changer.regulation_value, 405.0);
+    assert_eq!(tap_changer.terminal_ref.id, "_TR");
+    assert!(matches!(tap_changer.terminal_ref.side, Side::Two));
+
+    let line = &network.lines[0];
+    assert_eq!(
+        (line.r, line.x, line.b1, line.b2),
+        (3.0, 33.0, 0.0002, 0.0002)
+    );
+    assert_eq!(
+        (
+            line.voltage_level_id1.as_str(),
+            line.voltage_level_id2.as_str()
+        ),
+        ("_VL400", "_VLCITY")
+    );
+
+    // The nodes of a bay belong to its voltage level
+    let city = voltage_level(network, "_VLCITY");
+    let topology = city.node_breaker_topology.as_ref().unwrap();
+    assert_eq!(topology.nodes.len(), 3);
+    assert_eq!(topology.switches.len(), 2);
+    let breaker = topology
+        .switches
+        .iter()
+        .find(|switch| switch.id == "_BREAKER")
+        .unwrap();
+    assert!(matches!(breaker.kind, SwitchKind::Breaker));
+    assert!(!breaker.open && breaker.retained);
+    let load = &city.loads.as_ref().unwrap()[0];
+    assert_eq!((load.p0, load.q0), (420.0, 60.0));
+    assert_eq!(load.bus, breaker.bus2);
+    assert_eq!(
+        city.busbar_sections.as_ref().unwrap()[0].name,
+        "City busbar"
+    );
+
+    assert_eq!(
+        validate_network(network, ValidationLevel::SteadyStateHypothesis),
+        []
+    );
+    Ok(())
+}
+
+#[test]
+fn test_cgmes_unsupported_classes_reported() -> Result<(), Box<dyn std::error::Error>> {
+    let profiles = read_profiles();
+    let import = cgmes::from_profiles(profiles.iter().map(Vec::as_slice))?;
+
+    let unsupported: Vec<_> = import
+        .report
+        .unsupported_classes
+        .iter()
+        .map(|(class, count)| (class.as_str(), *count))
+        .collect();
+    assert_eq!(
+        unsupported,
+        [
+            ("CurrentLimit", 1),
+            ("GeographicalRegion", 1),
+            ("OperationalLimitSet", 1),
+            ("SubGeographicalRegion", 1),
+            ("SvVoltage", 1),
+        ]
+    );
+    assert!(import.report.skipped.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_cgmes_three_windings_transformer_skipped() -> Result<(), Box<dyn std::error::Error>> {
+    let equipment = std::fs::read_to_string(PROFILES[1])?.replace(
+        "</rdf:RDF>",
+        r##"<cim:PowerTransformerEnd rdf:ID="_TR_END3">
+            <cim:TransformerEnd.endNumber>3</cim:TransformerEnd.endNumber>
+            <cim:PowerTransformerEnd.PowerTransformer rdf:resource="#_TR"/>
+            <cim:PowerTransformerEnd.ratedU>63</cim:PowerTransformerEnd.ratedU>
+        </cim:PowerTransformerEnd></rdf:RDF>"##,
+    );
+    let import = cgmes::from_str(&equipment)?;
+
+    assert!(import.network.substations[0]
+        .two_windings_transformers
+        .is_empty());
+    assert_eq!(import.report.skipped.len(), 1);
+    assert!(import.report.skipped[0].starts_with("PowerTransformer '_TR'"));
+    Ok(())
+}
+
+#[test]
+fn test_cgmes_zip_archive() -> Result<(), Box<dyn std::error::Error>> {
+    let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (path, content) in PROFILES.iter().zip(read_profiles()) {
+        let name = path.rsplit('/').next().unwrap();
+        archive.start_file(name, zip::write::SimpleFileOptions::default())?;
+        archive.write_all(&content)?;
+    }
+    archive.start_file("README.txt", zip::write::SimpleFileOptions::default())?;
+    archive.write_all(b"not a profile")?;
+    let archive = archive.finish()?;
+
+    let import = cgmes::from_zip(Cursor::new(archive.into_inner()))?;
+    assert_eq!(import.network.id, "_small-grid");
+    assert_eq!(import.network.lines.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_cgmes_errors() {
+    let result = cgmes::from_str(r#"<iidm:network id="sim1"/>"#);
+    assert!(matches!(result, Err(CgmesError::UnexpectedRoot(name)) if name == "network"));
+
+    let result = cgmes::from_profiles(Vec::<&[u8]>::new());
+    assert!(matches!(result, Err(CgmesError::NoProfile)));
+
+    let equipment = std::fs::read_to_string(PROFILES[1])
+        .unwrap()
+        .replace("<cim:ACLineSegment.r>3<", "<cim:ACLineSegment.r>three<");
+    let result = cgmes::from_str(&equipment);
+    assert!(matches!(
+        result,
+        Err(CgmesError::InvalidValue { id, property, .. }) if id == "_LINE" && property == "ACLineSegment.r"
+    ));
+}
@@ -0,0 +1,109 @@
+use iidm::libs::json_stream::{read_network, NetworkElement, Registration};
+use iidm::*;
+
+use crate::it::{setup_world, NETWORK_JSON_FILE};
+
+fn read_elements(json: &[u8]) -> (Network, Vec<NetworkElement>) {
+    let mut elements = Vec::new();
+    let network = read_network(json, |element| elements.push(element)).unwrap();
+    (network, elements)
+}
+
+#[test]
+fn test_streamed_registration_matches_register() {
+    let json = std::fs::read(NETWORK_JSON_FILE).unwrap();
+
+    let (mut world, mut schedule) = setup_world();
+    serde_json::from_slice::<Network>(&json)
+        .unwrap()
+        .register(&mut world, &mut schedule);
+    let registered = export_network(&world).unwrap();
+
+    let (mut world, mut schedule) = setup_world();
+    let mut registration = Registration::default();
+    let network = read_network(json.as_slice(), |element| {
+        registration.register(&mut world, &mut schedule, &element);
+    })
+    .unwrap();
+    assert_eq!(
+        registration
+            .counts()
+            .iter()
+            .map(|(name, count)| (*name, *count))
+            .collect::<Vec<_>>(),
+        [("Line", 2), ("Substation", 2)]
+    );
+    registration.finish(&mut world, &mut schedule, &network);
+    let streamed = export_network(&world).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&streamed.network).unwrap(),
+        serde_json::to_value(&registered.network).unwrap()
+    );
+    assert!(streamed.unplaced.is_empty());
+}
+
+#[test]
+fn test_streamed_validation_matches_document() {
+    let mut document: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(NETWORK_JSON_FILE).unwrap()).unwrap();
+    document["lines"][0]["voltageLevelId1"] = "UNKNOWN".into();
+    document["lines"][1]["r"] = (-1.0).into();
+    let json = serde_json::to_vec(&document).unwrap();
+
+    let (network, elements) = read_elements(&json);
+    let mut validator = NetworkValidator::default();
+    elements
+        .iter()
+        .for_each(|element| validator.element(element));
+    validator.network(&network);
+
+    let expected = validate_network(
+        &serde_json::from_slice(&json).unwrap(),
+        network.minimum_validation_level,
+    );
+    assert!(!expected.is_empty());
+    assert_eq!(validator.finish(network.minimum_validation_level), expected);
+}
+
+#[test]
+fn test_aborted_registration_removes_elements() {
+    let json = std::fs::read(NETWORK_JSON_FILE).unwrap();
+    let (mut world, mut schedule) = setup_world();
+    let mut registration = Registration::default();
+    read_network(json.as_slice(), |element| {
+        registration.register(&mut world, &mut schedule, &element);
+    })
+    .unwrap();
+    assert!(world.resource::<AssetRegistry>().count::<VoltageLevel>() > 0);
+
+    registration.abort(&mut world);
+    assert!(world.resource::<AssetRegistry>().is_empty());
+    assert_eq!(world.entities().len(), 0);
+}
+
+#[test]
+fn test_aborted_registration_keeps_registered_elements() {
+    let json = std::fs::read(NETWORK_JSON_FILE).unwrap();
+    let (mut world, mut schedule) = setup_world();
+    serde_json::from_slice::<Network>(&json)
+        .unwrap()
+        .register(&mut world, &mut schedule);
+    let registered = world.resource::<AssetRegistry>().len();
+
+    // Same network read again, with a trailing byte
+    let mut registration = Registration::default();
+    let mut malformed = json.clone();
+    malformed.push(b'x');
+    let error = read_network(malformed.as_slice(), |element| {
+        registration.register(&mut world, &mut schedule, &element);
+    })
+    .unwrap_err();
+    assert!(error.to_string().contains("trailing characters"));
+
+    registration.abort(&mut world);
+    let registry = world.resource::<AssetRegistry>();
+    assert_eq!(registry.len(), registered);
+    assert!(registry.find_typed::<Substation>("P1").is_some());
+    assert!(registry.find_typed::<Line>("NHV1_NHV2_1").is_some());
+}
@@ -0,0 +1,154 @@
+use iidm::libs::matpower::{self, MatpowerError};
+use iidm::*;
+
+use crate::it::load_network;
+
+const CASE9: &str = "tests/data/matpower/case9.m";
+const CASE4_TAP: &str = "tests/data/matpower/case4_tap.m";
+
+fn read_case(path: &str) -> Network {
+    matpower::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn voltage_level<'a>(network: &'a Network, id: &str) -> &'a VoltageLevel {
+    network
+        .substations
+        .iter()
+        .flat_map(|substation| &substation.voltage_levels)
+        .find(|voltage_level| voltage_level.id == id)
+        .unwrap()
+}
+
+#[test]
+fn test_matpower_buses_and_injections() {
+    let network = read_case(CASE9);
+    assert_eq!(network.id, "case9");
+    assert_eq!(network.source_format, "MATPOWER");
+    // Lines only, one substation per bus
+    assert_eq!(network.substations.len(), 9);
+    assert_eq!(network.lines.len(), 9);
+
+    let vl5 = voltage_level(&network, "VL-5");
+    assert_eq!(vl5.nominal_v, 345.0);
+    assert_eq!(
+        vl5.bus_breaker_topology.as_ref().unwrap().buses[0].id,
+        "BUS-5"
+    );
+    let load = &vl5.loads.as_ref().unwrap()[0];
+    assert_eq!((load.id.as_str(), load.p0, load.q0), ("LOAD-5", 90.0, 30.0));
+
+    let generator = &voltage_level(&network, "VL-2").generators.as_ref().unwrap()[0];
+    assert_eq!(generator.id, "GEN-2");
+    assert_eq!((generator.target_p, generator.target_q), (163.0, 6.54));
+    assert_eq!((generator.min_p, generator.max_p), (10.0, 300.0));
+    assert!(generator.voltage_regulator_on);
+    assert!((generator.target_v - 1.025 * 345.0).abs() < 1e-9);
+
+    // Per-unit values are brought to ohms and siemens
+    let line = &network.lines[1];
+    assert_eq!(line.id, "LINE-4-5");
+    let z_base = 345.0 * 345.0 / 100.0;
+    assert!((line.r - 0.017 * z_base).abs() < 1e-9);
+    assert!((line.b1 - 0.158 / 2.0 / z_base).abs() < 1e-12);
+    let limit = line.current_limits1.as_ref().unwrap().permanent_limit;
+    assert!((limit - 250e3 / (3f64.sqrt() * 345.0)).abs() < 1e-9);
+
+    assert!(validate_network(&network, ValidationLevel::SteadyStateHypothesis).is_empty());
+}
+
+#[test]
+fn test_matpower_transformers() {
+    let network = read_case(CASE4_TAP);
+
+    // The transformer joins the buses 1 and 2 in one substation
+    let ids: Vec<_> = network.substations.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["SUB-1", "SUB-3", "SUB-4"]);
+    let transformer = &network.substations[0].two_windings_transformers[0];
+    assert_eq!(transformer.id, "TWT-1-2");
+    assert!((transformer.rated_u1 - 400.0 * 0.98).abs() < 1e-9);
+    assert_eq!(transformer.rated_u2, 225.0);
+    assert!((transformer.x - 0.05 * 225.0 * 225.0 / 100.0).abs() < 1e-9);
+    let phase = transformer.phase_tap_changer.as_ref().unwrap();
+    assert_eq!(phase.steps[0].alpha, 2.0);
+
+    let generators = voltage_level(&network, "VL-1").generators.as_ref().unwrap();
+    let ids: Vec<_> = generators.iter().map(|g| g.id.as_str()).collect();
+    assert_eq!(ids, ["GEN-1", "GEN-1-2"]);
+    // Out of service
+    assert_eq!(generators[1].bus, "");
+    assert!(!generators[1].voltage_regulator_on);
+
+    let shunt = &network.shunt_compensators[0];
+    assert_eq!(shunt.id, "SHUNT-2");
+    assert!((shunt.b_per_section - 30.0 / (225.0 * 225.0)).abs() < 1e-12);
+
+    // Nothing is connected to an isolated bus
+    let load = &voltage_level(&network, "VL-4").loads.as_ref().unwrap()[0];
+    assert_eq!(
+        (load.bus.as_str(), load.connectable_bus.as_str()),
+        ("", "BUS-4")
+    );
+    assert_eq!(network.lines[1].bus2, "");
+}
+
+#[test]
+fn test_matpower_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    for path in [CASE9, CASE4_TAP] {
+        let network = read_case(path);
+        let text = matpower::to_string(&network)?;
+        let mut reread = matpower::from_str(&text)?;
+        reread.case_date = network.case_date;
+        assert_eq!(
+            serde_json::to_value(&reread)?,
+            serde_json::to_value(&network)?,
+            "{}",
+            path
+        );
+        assert_eq!(matpower::to_string(&reread)?, text);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_matpower_export_of_iidm_network() -> Result<(), Box<dyn std::error::Error>> {
+    let network = load_network();
+    let case = matpower::from_str(&matpower::to_string(&network)?)?;
+    assert_eq!(case.id, "sim1");
+    // One bus per configured bus, the transformers merging them into substations
+    let buses = case
+        .substations
+        .iter()
+        .flat_map(|substation| &substation.voltage_levels)
+        .count();
+    assert_eq!(buses, 4);
+    assert_eq!(case.lines.len(), 2);
+    let transformers = case
+        .substations
+        .iter()
+        .flat_map(|substation| &substation.two_windings_transformers)
+        .count();
+    assert_eq!(transformers, 2);
+    Ok(())
+}
+
+#[test]
+fn test_matpower_errors() {
+    assert!(matches!(
+        matpower::from_str("mpc.version = '1';\nmpc.bus = [];"),
+        Err(MatpowerError::UnsupportedVersion(_))
+    ));
+    assert!(matches!(
+        matpower::from_str("mpc.bus = [\n1 3 0 0 0 0 1 1 0 345 1 1.1 0.9;\n"),
+        Err(MatpowerError::Syntax { line: 1, .. })
+    ));
+    let text = "mpc.bus = [1 3 0 0 0 0 1 1 0 345 1 1.1 0.9];\nmpc.gen = [];\n\
+                mpc.branch = [\n1 2 0 0.1 0 0 0 0 0 0 1;\n];";
+    assert!(matches!(
+        matpower::from_str(text),
+        Err(MatpowerError::UnknownBus { line: 4, bus: 2 })
+    ));
+    assert!(matches!(
+        matpower::from_str("mpc.bus = [1 3 0 0 0 0 1 1 0 x 1 1.1 0.9];"),
+        Err(MatpowerError::InvalidNumber { line: 1, .. })
+    ));
+}
@@ -1,6 +1,12 @@
+mod cgmes;
 mod identifiable;
+mod json_stream;
+mod matpower;
+mod psse;
 mod serialization;
+mod ucte;
 mod update;
+mod xiidm;
 
 use std::str::FromStr;
 
@@ -26,7 +32,7 @@ fn create_test_network() -> Network {
         case_date: DateTime::from_str("2024-02-23T10:00:00.000+01:00").unwrap(),
         forecast_distance: 0,
         source_format: "test".to_string(),
-        minimum_validation_level: "STEADY_STATE_HYPOTHESIS".to_string(),
+        minimum_validation_level: ValidationLevel::SteadyStateHypothesis,
         substations: vec![
             Substation {
                 id: "sub1".to_string(),
@@ -69,7 +75,10 @@ pub fn assert_default_values(network: &Network) {
     );
     assert_eq!(network.forecast_distance, 0);
     assert_eq!(network.source_format, "test");
-    assert_eq!(network.minimum_validation_level, "STEADY_STATE_HYPOTHESIS");
+    assert_eq!(
+        network.minimum_validation_level,
+        ValidationLevel::SteadyStateHypothesis
+    );
     assert!(network.substations.is_empty());
     assert!(network.lines.is_empty());
 }
@@ -0,0 +1,189 @@
+use iidm::libs::psse::{self, PsseError};
+use iidm::*;
+
+const SIX_BUS_33: &str = "tests/data/psse/six_bus_v33.raw";
+const SIX_BUS_35: &str = "tests/data/psse/six_bus_v35.raw";
+
+fn read_case(path: &str) -> Network {
+    psse::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn voltage_level<'a>(network: &'a Network, id: &str) -> &'a VoltageLevel {
+    network
+        .substations
+        .iter()
+        .flat_map(|substation| &substation.voltage_levels)
+        .find(|voltage_level| voltage_level.id == id)
+        .unwrap()
+}
+
+#[test]
+fn test_psse_buses_and_injections() {
+    let network = read_case(SIX_BUS_33);
+    assert_eq!(network.id, "SIX BUS PLANNING CASE");
+    assert_eq!(network.source_format, "PSS/E");
+    // Transformers join the first five buses
+    let ids: Vec<_> = network.substations.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["SUB-101", "SUB-106"]);
+    assert_eq!(network.substations[0].voltage_levels.len(), 5);
+    assert_eq!(voltage_level(&network, "VL-104").nominal_v, 115.0);
+
+    // Current and admittance parts are summed at 1 per-unit
+    let load = &voltage_level(&network, "VL-104").loads.as_ref().unwrap()[0];
+    assert_eq!(
+        (load.id.as_str(), load.p0, load.q0),
+        ("LOAD-104-1", 100.0, 30.0)
+    );
+    let zip = load.zip_model.as_ref().unwrap();
+    assert_eq!((zip.p_p, zip.i_p, zip.z_p), (0.85, 0.1, 0.05));
+    assert_eq!(zip.p_q, 1.0);
+    // Isolated bus
+    let load = &voltage_level(&network, "VL-106").loads.as_ref().unwrap()[0];
+    assert_eq!(
+        (load.bus.as_str(), load.connectable_bus.as_str()),
+        ("", "BUS-106")
+    );
+
+    let generator = &voltage_level(&network, "VL-105")
+        .generators
+        .as_ref()
+        .unwrap()[0];
+    assert_eq!(generator.id, "GEN-105-1");
+    assert_eq!((generator.target_p, generator.target_q), (60.0, 10.0));
+    assert_eq!((generator.min_p, generator.max_p), (10.0, 100.0));
+    assert!(generator.voltage_regulator_on);
+    assert!((generator.target_v - 1.01 * 13.8).abs() < 1e-9);
+    let limits = generator.min_max_reactive_limits.as_ref().unwrap();
+    assert_eq!((limits.min_q, limits.max_q), (-30.0, 50.0));
+
+    // Susceptances in MVar at 1 per-unit
+    let shunt = &network.shunt_compensators[0];
+    assert_eq!(shunt.id, "SHUNT-103-1");
+    assert!((shunt.b_per_section - 20.0 / (230.0 * 230.0)).abs() < 1e-12);
+    let switched = &network.shunt_compensators[1];
+    assert_eq!(switched.id, "SWITCHED-SHUNT-104");
+    assert!((switched.b_per_section - 10.0 / (115.0 * 115.0)).abs() < 1e-12);
+    assert_eq!(
+        (switched.section_count, switched.maximum_section_count),
+        (3, 3)
+    );
+
+    assert!(validate_network(&network, ValidationLevel::SteadyStateHypothesis).is_empty());
+}
+
+#[test]
+fn test_psse_branches() {
+    let network = read_case(SIX_BUS_33);
+
+    let z_base = 230.0 * 230.0 / 100.0;
+    let line = &network.lines[0];
+    assert_eq!(line.id, "LINE-102-103-1");
+    assert!((line.r - 0.01 * z_base).abs() < 1e-9);
+    assert!((line.x - 0.1 * z_base).abs() < 1e-9);
+    assert!((line.b1 - 0.1 / z_base).abs() < 1e-12);
+    let limit = line.current_limits1.as_ref().unwrap().permanent_limit;
+    assert!((limit - 250e3 / (3f64.sqrt() * 230.0)).abs() < 1e-9);
+    // Out of service, the negative bus number only marks the metered end
+    let line = &network.lines[1];
+    assert_eq!(line.id, "LINE-103-106-1");
+    assert_eq!(
+        (line.bus1.as_str(), line.connectable_bus2.as_str()),
+        ("", "BUS-106")
+    );
+
+    // The ratio of the winding 1 is the tap position in use
+    let transformers = &network.substations[0].two_windings_transformers;
+    let step_up = &transformers[0];
+    assert_eq!(step_up.id, "TWT-101-102-1");
+    assert!((step_up.rated_u1 - 1.025 * 18.0).abs() < 1e-9);
+    assert_eq!(step_up.rated_u2, 230.0);
+    assert!((step_up.x - 0.05 * z_base).abs() < 1e-9);
+    let ratio = step_up.ratio_tap_changer.as_ref().unwrap();
+    assert_eq!((ratio.steps.len(), ratio.tap_position), (33, 20));
+    assert_eq!(ratio.steps[20].rho, 1.0);
+    assert!((ratio.steps[0].rho - 1.025 / 0.9).abs() < 1e-12);
+    assert!(ratio.regulating);
+    assert!(matches!(ratio.terminal_ref.side, Side::Two));
+    assert!((ratio.regulation_value - 1.03 * 230.0).abs() < 1e-9);
+    assert!((ratio.target_deadband - 0.04 * 230.0).abs() < 1e-9);
+
+    // Impedance on the winding base, losses in W and exciting current
+    let shifter = &transformers[1];
+    assert_eq!(shifter.id, "TWT-102-103-2");
+    assert!((shifter.r - 0.001 * z_base).abs() < 1e-9);
+    assert!((shifter.x - 0.02 * z_base).abs() < 1e-9);
+    assert!((shifter.g - 2e-4 / z_base).abs() < 1e-12);
+    assert!((shifter.b + (0.02f64.powi(2) - 2e-4f64.powi(2)).sqrt() / z_base).abs() < 1e-12);
+    let phase = shifter.phase_tap_changer.as_ref().unwrap();
+    assert!(matches!(
+        phase.regulation_mode,
+        PhaseRegulationMode::FixedTap
+    ));
+    assert_eq!(phase.steps[0].alpha, -5.0);
+
+    let transformer = &network.three_windings_transformers[0];
+    assert_eq!(transformer.id, "TWT-103-104-105-1");
+    assert_eq!((transformer.rated_u1, transformer.rated_u3), (230.0, 13.8));
+    assert!((transformer.rated_u2 - 0.9875 * 115.0).abs() < 1e-9);
+    // Star impedances at the rated voltage of the winding 1
+    assert!((transformer.x1 - 0.03 * z_base).abs() < 1e-9);
+    assert!((transformer.x2 - 0.01 * z_base).abs() < 1e-9);
+    assert!((transformer.x3 - 0.05 * z_base).abs() < 1e-9);
+    assert!((transformer.b1 + 0.001 / z_base).abs() < 1e-12);
+    let ratio = transformer.ratio_tap_changer2.as_ref().unwrap();
+    assert_eq!((ratio.steps.len(), ratio.tap_position), (17, 6));
+    assert!((ratio.regulation_value - 1.02 * 115.0).abs() < 1e-9);
+    assert!(transformer.ratio_tap_changer3.is_none());
+    let limit = transformer
+        .current_limits3
+        .as_ref()
+        .unwrap()
+        .permanent_limit;
+    assert!((limit - 80e3 / (3f64.sqrt() * 13.8)).abs() < 1e-9);
+
+    assert!(validate_network(&network, ValidationLevel::SteadyStateHypothesis).is_empty());
+}
+
+#[test]
+fn test_psse_revisions_agree() -> Result<(), Box<dyn std::error::Error>> {
+    let network = read_case(SIX_BUS_33);
+    let mut revision_35 = read_case(SIX_BUS_35);
+    revision_35.case_date = network.case_date;
+
+    // Revision 35 names its switched shunts
+    let switched = &mut revision_35.shunt_compensators[1];
+    assert_eq!(switched.id, "SWITCHED-SHUNT-104-1");
+    switched.id = "SWITCHED-SHUNT-104".to_string();
+    assert_eq!(
+        serde_json::to_value(&revision_35)?,
+        serde_json::to_value(&network)?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_psse_errors() {
+    assert!(matches!(
+        psse::from_str("0, 100.0, 34\n\n\n"),
+        Err(PsseError::UnsupportedRevision(34))
+    ));
+    let case = "0, 100.0, 33\nTITLE\n\n1, 'A', 0.0, 3\n";
+    assert!(matches!(
+        psse::from_str(case),
+        Err(PsseError::MissingBaseVoltage { line: 4, bus: 1 })
+    ));
+    let case = "0, 100.0, 33\nTITLE\n\n1, 'A', 230.0, 3\n0 / END OF BUS DATA\n2, '1', 1, 1, 1, 10.0, 5.0\n";
+    assert!(matches!(
+        psse::from_str(case),
+        Err(PsseError::UnknownBus { line: 6, bus: 2 })
+    ));
+    let case = "0, 100.0, 33\nTITLE\n\n1, 'A', 230.0, x\n";
+    assert!(matches!(
+        psse::from_str(case),
+        Err(PsseError::InvalidValue {
+            line: 4,
+            field: "bus type",
+            ..
+        })
+    ));
+}
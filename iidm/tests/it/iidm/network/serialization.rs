@@ -26,7 +26,10 @@ fn test_network_basic_properties() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(network.id, "sim1");
     assert_eq!(network.forecast_distance, 0);
     assert_eq!(network.source_format, "test");
-    assert_eq!(network.minimum_validation_level, "STEADY_STATE_HYPOTHESIS");
+    assert_eq!(
+        network.minimum_validation_level,
+        ValidationLevel::SteadyStateHypothesis
+    );
 
     Ok(())
 }
@@ -0,0 +1,151 @@
+use iidm::libs::ucte::{self, UcteError};
+use iidm::*;
+
+use crate::it::load_network;
+
+const SMALL: &str = "tests/data/ucte/small.uct";
+
+fn read_small() -> Network {
+    ucte::from_str(&std::fs::read_to_string(SMALL).unwrap()).unwrap()
+}
+
+fn voltage_level<'a>(network: &'a Network, id: &str) -> &'a VoltageLevel {
+    network
+        .substations
+        .iter()
+        .flat_map(|substation| &substation.voltage_levels)
+        .find(|voltage_level| voltage_level.id == id)
+        .unwrap()
+}
+
+#[test]
+fn test_ucte_nodes_grouped_by_location() {
+    let network = read_small();
+    assert_eq!(network.source_format, "UCTE");
+    assert_eq!(network.case_date.to_rfc3339(), "2026-01-15T00:00:00+00:00");
+
+    let ids: Vec<_> = network.substations.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["BNODE1", "BNODE2", "FNODE1"]);
+    assert_eq!(network.substations[0].country, "BE");
+    assert_eq!(network.substations[2].country, "FR");
+
+    // The voltage code gives the nominal voltage
+    assert_eq!(voltage_level(&network, "BNODE11").nominal_v, 380.0);
+    assert_eq!(voltage_level(&network, "BNODE12").nominal_v, 220.0);
+
+    let slack = &voltage_level(&network, "BNODE11")
+        .generators
+        .as_ref()
+        .unwrap()[0];
+    assert_eq!(slack.id, "BNODE111_generator");
+    assert_eq!(slack.energy_source, EnergySource::Nuclear);
+    assert_eq!((slack.target_p, slack.target_q), (500.0, 50.0));
+    assert_eq!((slack.min_p, slack.max_p), (-9999.0, 9999.0));
+    assert!(slack.voltage_regulator_on);
+    assert_eq!(slack.target_v, 400.0);
+    let limits = slack.min_max_reactive_limits.as_ref().unwrap();
+    assert_eq!((limits.min_q, limits.max_q), (-400.0, 400.0));
+
+    let load = &voltage_level(&network, "BNODE12").loads.as_ref().unwrap()[0];
+    assert_eq!(
+        (load.id.as_str(), load.p0, load.q0),
+        ("BNODE121_load", 300.0, 100.0)
+    );
+
+    // Couplers are breakers of the voltage level
+    let topology = voltage_level(&network, "BNODE21")
+        .bus_breaker_topology
+        .as_ref()
+        .unwrap();
+    assert_eq!(topology.buses.len(), 2);
+    assert_eq!(topology.switches[0].id, "BNODE211 BNODE212 1");
+    assert!(!topology.switches[0].open);
+}
+
+#[test]
+fn test_ucte_branches() {
+    let network = read_small();
+
+    let line = &network.lines[0];
+    assert_eq!(line.id, "BNODE111 BNODE211 1");
+    assert_eq!((line.r, line.x), (1.2, 12.0));
+    assert!((line.b1 - 75e-6).abs() < 1e-12);
+    assert_eq!(
+        line.current_limits1.as_ref().unwrap().permanent_limit,
+        1500.0
+    );
+
+    // The regulated node 2 is on the side 1
+    let transformer = &network.substations[0].two_windings_transformers[0];
+    assert_eq!(transformer.id, "BNODE111 BNODE121 1");
+    assert_eq!(transformer.bus1, "BNODE121");
+    assert_eq!((transformer.rated_u1, transformer.rated_u2), (220.0, 400.0));
+    assert!((transformer.b + 5e-6).abs() < 1e-12);
+    let ratio = transformer.ratio_tap_changer.as_ref().unwrap();
+    assert_eq!((ratio.low_tap_position, ratio.tap_position), (-10, 2));
+    assert_eq!(ratio.steps.len(), 21);
+    assert!((ratio.steps[12].rho - 1.0 / 1.025).abs() < 1e-12);
+    assert_eq!(ratio.regulation_value, 225.0);
+    let phase = transformer.phase_tap_changer.as_ref().unwrap();
+    assert_eq!(phase.steps.len(), 11);
+    assert!(matches!(
+        phase.regulation_mode,
+        PhaseRegulationMode::FixedTap
+    ));
+    // 10 % in quadrature
+    let last = &phase.steps[10];
+    assert!((last.alpha - 0.1f64.atan().to_degrees()).abs() < 1e-9);
+    assert!((last.rho - 1.0 / 1.01f64.sqrt()).abs() < 1e-9);
+
+    // Two lines meet at XBE_FR11, a single one at XFR_DE11
+    let tie_line = &network.tie_lines[0];
+    assert_eq!(tie_line.ucte_xnode_code, "XBE_FR11");
+    assert_eq!(tie_line.dangling_line1.bus, "BNODE212");
+    assert_eq!(tie_line.dangling_line2.bus, "FNODE111");
+    let dangling_line = &network.dangling_lines[0];
+    assert_eq!(dangling_line.id, "FNODE111 XFR_DE11 1");
+    assert_eq!((dangling_line.p0, dangling_line.q0), (50.0, 10.0));
+    // Out of operation
+    assert_eq!(dangling_line.bus, "");
+    assert_eq!(dangling_line.connectable_bus, "FNODE111");
+
+    assert!(validate_network(&network, ValidationLevel::SteadyStateHypothesis).is_empty());
+}
+
+#[test]
+fn test_ucte_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let network = read_small();
+    let text = ucte::to_string(&network)?;
+    let reread = ucte::from_str(&text)?;
+    assert_eq!(
+        serde_json::to_value(&reread)?,
+        serde_json::to_value(&network)?
+    );
+    // Writing is stable once read back
+    assert_eq!(ucte::to_string(&reread)?, text);
+    Ok(())
+}
+
+#[test]
+fn test_ucte_errors() {
+    let network = load_network();
+    assert!(matches!(
+        ucte::to_string(&network),
+        Err(UcteError::InvalidNodeCode(_))
+    ));
+
+    let text = "##N\n##ZBE\nBNODE111 A            0 0\n##L\nBNODE111 BNODE999 1 0 1.0000 1.0000   0.0000   1000\n";
+    assert!(matches!(
+        ucte::from_str(text),
+        Err(UcteError::UnknownNode { line: 5, .. })
+    ));
+    let text = "##N\n##ZBE\nBNODE111 A            0 x\n";
+    assert!(matches!(
+        ucte::from_str(text),
+        Err(UcteError::InvalidValue {
+            line: 3,
+            field: "node type",
+            ..
+        })
+    ));
+}
@@ -0,0 +1,198 @@
+use iidm::libs::xiidm;
+use iidm::*;
+
+use crate::it::load_network;
+
+const NETWORK_XIIDM_FILE: &str = "tests/data/network.xiidm";
+const NODE_BREAKER_XIIDM_FILE: &str = "tests/data/node-breaker.xiidm";
+
+#[test]
+fn test_xiidm_matches_json() -> Result<(), Box<dyn std::error::Error>> {
+    let json = load_network();
+    let xml = xiidm::from_str(&std::fs::read_to_string(NETWORK_XIIDM_FILE)?)?;
+
+    assert_eq!(serde_json::to_value(&xml)?, serde_json::to_value(&json)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_xiidm_content() -> Result<(), Box<dyn std::error::Error>> {
+    let network = xiidm::from_str(&std::fs::read_to_string(NETWORK_XIIDM_FILE)?)?;
+
+    assert_eq!(network.version, "1.12");
+    assert_eq!(network.substations.len(), 2);
+    assert_eq!(network.lines.len(), 2);
+    assert_eq!(network.lines[0].b1, 1.93E-4);
+
+    let vlgen = &network.substations[0].voltage_levels[0];
+    assert_eq!(vlgen.nominal_v, 24.0);
+    assert_eq!(vlgen.topology_kind, TopologyKind::BusBreaker);
+    assert_eq!(vlgen.generators.as_ref().map(Vec::len), Some(1));
+    assert!(vlgen.loads.is_none());
+
+    let transformer = &network.substations[1].two_windings_transformers[0];
+    let tap_changer = transformer.ratio_tap_changer.as_ref().unwrap();
+    assert_eq!(tap_changer.steps.len(), 3);
+    assert_eq!(tap_changer.terminal_ref.id, "NHV2_NLOAD");
+
+    Ok(())
+}
+
+#[test]
+fn test_xiidm_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let network = load_network();
+
+    let xml = xiidm::to_string(&network)?;
+    let roundtrip = xiidm::from_str(&xml)?;
+
+    assert_eq!(
+        serde_json::to_value(&roundtrip)?,
+        serde_json::to_value(&network)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_xiidm_roundtrip_optional_elements() -> Result<(), Box<dyn std::error::Error>> {
+    let mut network = load_network();
+
+    let limits = CurrentLimits {
+        permanent_limit: 1000.0,
+        temporary_limits: vec![TemporaryLimit {
+            name: "20'".to_string(),
+            acceptable_duration: 1200,
+            value: 1200.0,
+        }],
+    };
+    network.lines[0].current_limits1 = Some(limits.clone());
+    network.lines[0].current_limits2 = Some(limits);
+
+    network.substations[0].voltage_levels.push(VoltageLevel {
+        id: "VLNB".to_string(),
+        nominal_v: 225.0,
+        topology_kind: TopologyKind::NodeBreaker,
+        generators: None,
+        loads: None,
+        busbar_sections: Some(vec![BusbarSection {
+            id: "BBS".to_string(),
+            name: "Busbar".to_string(),
+            bus: "".to_string(),
+            connectable_bus: "".to_string(),
+        }]),
+        node_breaker_topology: Some(NodeBreakerTopology {
+            nodes: vec![Node { id: 0 }, Node { id: 1 }, Node { id: 2 }],
+            switches: vec![Switch {
+                id: "BRK".to_string(),
+                kind: SwitchKind::Breaker,
+                open: true,
+                retained: true,
+                bus1: "0".to_string(),
+                bus2: "1".to_string(),
+                voltage_level_id: "VLNB".to_string(),
+            }],
+            internal_connections: vec![InternalConnection { node1: 1, node2: 2 }],
+        }),
+        bus_breaker_topology: None,
+    });
+
+    network.shunt_compensators.push(ShuntCompensator {
+        id: "SHUNT".to_string(),
+        b_per_section: 1e-5,
+        maximum_section_count: 3,
+        section_count: 1,
+        bus: "NHV1".to_string(),
+        connectable_bus: "NHV1".to_string(),
+    });
+
+    let xml = xiidm::to_string(&network)?;
+    let roundtrip = xiidm::from_str(&xml)?;
+
+    assert_eq!(
+        serde_json::to_value(&roundtrip)?,
+        serde_json::to_value(&network)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_xiidm_node_breaker_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let network = xiidm::from_str(&std::fs::read_to_string(NODE_BREAKER_XIIDM_FILE)?)?;
+
+    // Equipment of node-breaker voltage levels is placed on nodes
+    let vl1 = &network.substations[0].voltage_levels[0];
+    let generator = &vl1.generators.as_ref().unwrap()[0];
+    assert_eq!(
+        (generator.bus.as_str(), generator.connectable_bus.as_str()),
+        ("1", "1")
+    );
+    assert_eq!(vl1.busbar_sections.as_ref().unwrap()[0].bus, "0");
+    let load = &network.substations[1].voltage_levels[0]
+        .loads
+        .as_ref()
+        .unwrap()[0];
+    assert_eq!(load.connectable_bus, "2");
+    let line = &network.lines[0];
+    assert_eq!((line.bus1.as_str(), line.bus2.as_str()), ("2", "1"));
+    let transformer = &network.substations[0].two_windings_transformers[0];
+    assert_eq!(
+        (transformer.bus1.as_str(), transformer.bus2.as_str()),
+        ("3", "B3")
+    );
+    assert_eq!(
+        validate_network(&network, network.minimum_validation_level),
+        []
+    );
+
+    let xml = xiidm::to_string(&network)?;
+    assert!(xml.contains(r#"targetQ="0" node="1""#));
+    assert!(xml.contains(r#"voltageLevelId1="VL1" node1="2" voltageLevelId2="VL2" node2="1""#));
+    assert!(xml.contains(
+        r#"voltageLevelId1="VL1" node1="3" voltageLevelId2="VL3" bus2="B3" connectableBus2="B3""#
+    ));
+    let roundtrip = xiidm::from_str(&xml)?;
+    assert_eq!(
+        serde_json::to_value(&roundtrip)?,
+        serde_json::to_value(&network)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_xiidm_network_injections_on_nodes() -> Result<(), Box<dyn std::error::Error>> {
+    let mut network = xiidm::from_str(&std::fs::read_to_string(NODE_BREAKER_XIIDM_FILE)?)?;
+
+    // Elements held by the network carry no voltage level id, the node-breaker one is found
+    // from the node
+    network.shunt_compensators.push(ShuntCompensator {
+        id: "SHUNT".to_string(),
+        b_per_section: 1e-5,
+        maximum_section_count: 3,
+        section_count: 1,
+        bus: "4".to_string(),
+        connectable_bus: "4".to_string(),
+    });
+    network.shunt_compensators.push(ShuntCompensator {
+        id: "SHUNT_B3".to_string(),
+        b_per_section: 1e-5,
+        maximum_section_count: 3,
+        section_count: 1,
+        bus: "B3".to_string(),
+        connectable_bus: "B3".to_string(),
+    });
+
+    let xml = xiidm::to_string(&network)?;
+    assert!(xml.contains(r#"sectionCount="1" node="4""#));
+    assert!(xml.contains(r#"sectionCount="1" bus="B3" connectableBus="B3""#));
+    assert!(!xml.contains(r#"bus="4""#));
+    let roundtrip = xiidm::from_str(&xml)?;
+    assert_eq!(
+        serde_json::to_value(&roundtrip)?,
+        serde_json::to_value(&network)?
+    );
+
+    Ok(())
+}
@@ -0,0 +1,382 @@
+use bevy_ecs::{event::Events, world::World};
+use iidm::*;
+
+use super::{load_network, setup};
+
+fn errors(world: &World) -> Vec<ComponentErrorEvent> {
+    let events = world.resource::<Events<ComponentErrorEvent>>();
+    events.get_cursor().read(events).cloned().collect()
+}
+
+fn invalid_references(world: &World) -> Vec<InvalidReferenceEvent> {
+    let events = world.resource::<Events<InvalidReferenceEvent>>();
+    events.get_cursor().read(events).cloned().collect()
+}
+
+fn new_line(id: &str) -> Line {
+    let mut line = load_network().lines[0].clone();
+    line.id = id.to_string();
+    line
+}
+
+fn new_load(id: &str) -> Load {
+    let mut load = load_network()
+        .substations
+        .iter()
+        .flat_map(|substation| &substation.voltage_levels)
+        .find_map(|voltage_level| voltage_level.loads.as_ref()?.first().cloned())
+        .unwrap();
+    load.id = id.to_string();
+    load
+}
+
+#[test]
+fn test_create_event_spawns_entity() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(CreateEvent {
+        id: "NEW_LINE".to_string(),
+        parent_id: None,
+        component: new_line("NEW_LINE"),
+    });
+    schedule.run(&mut world);
+
+    let entity = world.resource::<AssetRegistry>().find("NEW_LINE").unwrap();
+    assert_eq!(world.get::<Line>(entity).unwrap().id, "NEW_LINE");
+    assert!(errors(&world).is_empty());
+}
+
+#[test]
+fn test_create_event_refuses_existing_id() {
+    let (mut world, mut schedule) = setup();
+
+    let mut line = new_line("NHV1_NHV2_1");
+    line.r = 42.0;
+    world.send_event(CreateEvent {
+        id: "NHV1_NHV2_1".to_string(),
+        parent_id: None,
+        component: line,
+    });
+    schedule.run(&mut world);
+
+    let errors = errors(&world);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, ErrorType::EntityAlreadyExists);
+
+    let entity = world
+        .resource::<AssetRegistry>()
+        .find("NHV1_NHV2_1")
+        .unwrap();
+    assert_eq!(world.get::<Line>(entity).unwrap().r, 3.0);
+}
+
+#[test]
+fn test_delete_event_despawns_entity() {
+    let (mut world, mut schedule) = setup();
+    let entity = world
+        .resource::<AssetRegistry>()
+        .find("NHV1_NHV2_1")
+        .unwrap();
+
+    world.send_event(DeleteEvent::<Line>::new("NHV1_NHV2_1"));
+    schedule.run(&mut world);
+
+    assert!(world
+        .resource::<AssetRegistry>()
+        .find("NHV1_NHV2_1")
+        .is_none());
+    assert!(world.get_entity(entity).is_err());
+    let mut lines = world.query::<&Line>();
+    assert_eq!(lines.iter(&world).count(), 1);
+}
+
+#[test]
+fn test_delete_event_errors() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(DeleteEvent::<Line>::new("UNKNOWN"));
+    // The generator exists but is not a line
+    world.send_event(DeleteEvent::<Line>::new("GEN"));
+    schedule.run(&mut world);
+
+    let errors = errors(&world);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].error_type, ErrorType::EntityNotFound);
+    assert_eq!(errors[1].error_type, ErrorType::ComponentNotFound);
+    assert!(world.resource::<AssetRegistry>().find("GEN").is_some());
+}
+
+#[test]
+fn test_delete_referenced_voltage_level_is_refused() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(DeleteEvent::<VoltageLevel>::new("VLHV1"));
+    schedule.run(&mut world);
+
+    let errors = errors(&world);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].error_type,
+        ErrorType::Referenced(vec![
+            "Line 'NHV1_NHV2_1'".to_string(),
+            "Line 'NHV1_NHV2_2'".to_string(),
+            "TwoWindingsTransformer 'NGEN_NHV1'".to_string(),
+        ])
+    );
+    assert!(world.resource::<AssetRegistry>().find("VLHV1").is_some());
+
+    // Once nothing points to it, the voltage level is removed with its buses
+    world.send_event(DeleteEvent::<Line>::new("NHV1_NHV2_1"));
+    world.send_event(DeleteEvent::<Line>::new("NHV1_NHV2_2"));
+    world.send_event(DeleteEvent::<TwoWindingsTransformer>::new("NGEN_NHV1"));
+    schedule.run(&mut world);
+    world.send_event(DeleteEvent::<VoltageLevel>::new("VLHV1"));
+    schedule.run(&mut world);
+
+    let registry = world.resource::<AssetRegistry>();
+    assert!(registry.find("VLHV1").is_none());
+    assert!(registry.find("NHV1").is_none());
+}
+
+#[test]
+fn test_types_share_ids() {
+    let (mut world, mut schedule) = setup();
+
+    let load = new_load("GEN");
+    world.send_event(CreateEvent {
+        id: "GEN".to_string(),
+        parent_id: Some("VLLOAD".to_string()),
+        component: load,
+    });
+    schedule.run(&mut world);
+    assert!(errors(&world).is_empty());
+
+    world.send_event(DeleteEvent::<Load>::new("GEN"));
+    schedule.run(&mut world);
+
+    let registry = world.resource::<AssetRegistry>();
+    assert!(registry.find_typed::<Load>("GEN").is_none());
+    let generator = registry.find_typed::<Generator>("GEN").unwrap();
+    assert!(world.get::<Generator>(generator).is_some());
+}
+
+#[test]
+fn test_unique_ids_refuse_shared_ids() {
+    let (mut world, mut schedule) = setup();
+    world.resource_mut::<AssetRegistry>().set_unique_ids(true);
+
+    let load = new_load("GEN");
+    world.send_event(CreateEvent {
+        id: "GEN".to_string(),
+        parent_id: Some("VLLOAD".to_string()),
+        component: load,
+    });
+    schedule.run(&mut world);
+
+    let errors = errors(&world);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, ErrorType::EntityAlreadyExists);
+    assert!(world
+        .resource::<AssetRegistry>()
+        .find_typed::<Load>("GEN")
+        .is_none());
+}
+
+#[test]
+fn test_deletions_update_topology() {
+    let (mut world, mut schedule) = setup();
+    assert_eq!(world.resource::<NetworkTopology>().island_count(), 1);
+
+    // Without the step-up transformer the generator is cut from the grid
+    world.send_event(DeleteEvent::<TwoWindingsTransformer>::new("NGEN_NHV1"));
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+    assert_eq!(world.resource::<NetworkTopology>().island_count(), 2);
+
+    world.send_event(DeleteEvent::<VoltageLevel>::new("VLGEN"));
+    schedule.run(&mut world);
+    schedule.run(&mut world);
+    let topology = world.resource::<NetworkTopology>();
+    assert!(topology.bus_view("VLGEN").is_none());
+    assert!(topology.calculated_bus(None, "NGEN").is_none());
+    assert_eq!(topology.island_count(), 1);
+}
+
+#[test]
+fn test_references_of_created_elements() {
+    let (world, _) = setup();
+
+    assert!(missing_references(&world, &new_line("NEW_LINE"), None).is_empty());
+
+    let mut line = new_line("NEW_LINE");
+    line.voltage_level_id2 = "VLLOAD".to_string();
+    line.bus2 = "NHV2".to_string();
+    assert_eq!(
+        missing_references(&world, &line, None),
+        vec![Reference::Bus {
+            voltage_level_id: Some("VLLOAD".to_string()),
+            bus: "NHV2".to_string()
+        }]
+    );
+
+    let mut generator = load_network().substations[0].voltage_levels[0]
+        .generators
+        .as_ref()
+        .unwrap()[0]
+        .clone();
+    assert!(missing_references(&world, &generator, Some("VLGEN")).is_empty());
+    // The buses of an injection are looked up in the voltage level holding it
+    assert_eq!(
+        missing_references(&world, &generator, Some("VLLOAD")),
+        vec![Reference::Bus {
+            voltage_level_id: Some("VLLOAD".to_string()),
+            bus: "NGEN".to_string()
+        }]
+    );
+    generator.bus = "UNKNOWN".to_string();
+    assert_eq!(
+        missing_references(&world, &generator, Some("VLGEN")).len(),
+        1
+    );
+}
+
+#[test]
+fn test_create_event_refuses_unknown_references() {
+    let (mut world, mut schedule) = setup();
+
+    let mut line = new_line("NEW_LINE");
+    line.voltage_level_id2 = "UNKNOWN".to_string();
+    world.send_event(CreateEvent {
+        id: "NEW_LINE".to_string(),
+        parent_id: None,
+        component: line,
+    });
+    // The buses of an injection are looked up in the voltage level holding it
+    world.send_event(CreateEvent {
+        id: "NEW_LOAD".to_string(),
+        parent_id: Some("VLGEN".to_string()),
+        component: new_load("NEW_LOAD"),
+    });
+    schedule.run(&mut world);
+
+    let events = invalid_references(&world);
+    assert_eq!(events.len(), 2);
+    let references = |id: &str| {
+        events
+            .iter()
+            .find(|event| event.id == id)
+            .unwrap()
+            .references
+            .clone()
+    };
+    assert!(references("NEW_LINE").contains(&Reference::VoltageLevel {
+        id: "UNKNOWN".to_string()
+    }));
+    assert_eq!(
+        references("NEW_LOAD"),
+        vec![Reference::Bus {
+            voltage_level_id: Some("VLGEN".to_string()),
+            bus: "NLOAD".to_string()
+        }]
+    );
+
+    let registry = world.resource::<AssetRegistry>();
+    assert!(registry.find("NEW_LINE").is_none());
+    assert!(registry.find("NEW_LOAD").is_none());
+}
+
+#[test]
+fn test_create_events_refuse_ids_created_in_the_same_run() {
+    let (mut world, mut schedule) = setup();
+
+    for r in [1.0, 2.0] {
+        let mut line = new_line("NEW_LINE");
+        line.r = r;
+        world.send_event(CreateEvent {
+            id: "NEW_LINE".to_string(),
+            parent_id: None,
+            component: line,
+        });
+    }
+    schedule.run(&mut world);
+
+    let errors = errors(&world);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, ErrorType::EntityAlreadyExists);
+
+    let entity = world.resource::<AssetRegistry>().find("NEW_LINE").unwrap();
+    assert_eq!(world.get::<Line>(entity).unwrap().r, 1.0);
+    assert_eq!(world.query::<&Line>().iter(&world).count(), 3);
+}
+
+fn dangling_line(id: &str, bus: &str) -> DanglingLine {
+    DanglingLine {
+        id: id.to_string(),
+        p0: 0.0,
+        q0: 0.0,
+        r: 1.0,
+        x: 10.0,
+        g: 0.0,
+        b: 0.0,
+        bus: bus.to_string(),
+        connectable_bus: bus.to_string(),
+    }
+}
+
+#[test]
+fn test_required_children_are_kept() {
+    let (mut world, mut schedule) = setup();
+    world.send_event(CreateEvent {
+        id: "TIE".to_string(),
+        parent_id: None,
+        component: TieLine {
+            id: "TIE".to_string(),
+            name: String::new(),
+            dangling_line1: dangling_line("TIE_1", "NHV1"),
+            dangling_line2: dangling_line("TIE_2", "NHV2"),
+            ucte_xnode_code: "XNODE".to_string(),
+        },
+    });
+    schedule.run(&mut world);
+    assert!(errors(&world).is_empty());
+
+    // The tie line only keeps blank halves, it is assembled from its children
+    let tie_line = world
+        .resource::<AssetRegistry>()
+        .find_typed::<TieLine>("TIE")
+        .unwrap();
+    assert!(world
+        .get::<TieLine>(tie_line)
+        .unwrap()
+        .dangling_line1
+        .id
+        .is_empty());
+    world.send_event(UpdateEvent::<DanglingLine> {
+        id: "TIE_1".to_string(),
+        updater: DanglingLineUpdater {
+            p0: Some(42.0),
+            ..Default::default()
+        },
+    });
+    world.send_event(DeleteEvent::<DanglingLine>::new("TIE_2"));
+    schedule.run(&mut world);
+
+    let errors = errors(&world);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].error_type,
+        ErrorType::Required("TieLine 'TIE'".to_string())
+    );
+    let registry = world.resource::<AssetRegistry>();
+    let tie_line = registry.find_typed::<TieLine>("TIE").unwrap();
+    let assembled = assemble::<TieLine>(&world, tie_line).unwrap();
+    assert_eq!(assembled.dangling_line1.p0, 42.0);
+    assert!(registry.find("TIE_2").is_some());
+
+    // Along with its parent, the child goes
+    world.send_event(DeleteEvent::<TieLine>::new("TIE"));
+    schedule.run(&mut world);
+    let registry = world.resource::<AssetRegistry>();
+    assert!(registry.find("TIE_1").is_none());
+    assert!(registry.find("TIE_2").is_none());
+}
@@ -0,0 +1,231 @@
+use bevy_ecs::{schedule::Schedule, world::World};
+use iidm::*;
+
+use super::{load_network, setup_with};
+
+fn run_load_flow(world: &mut World, schedule: &mut Schedule) -> LoadFlowResult {
+    world.send_event(LoadFlowEvent);
+    schedule.run(world);
+    world.resource::<LoadFlowResult>().clone()
+}
+
+fn bus_state(world: &World, id: &str) -> BusState {
+    world
+        .iter_entities()
+        .find_map(|entity| entity.get::<BusStates>()?.0.get(id).copied())
+        .unwrap()
+}
+
+#[test]
+fn test_load_flow_not_run_without_event() {
+    let (mut world, mut schedule) = setup_with(&load_network());
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<LoadFlowResult>().status,
+        LoadFlowStatus::NotRun
+    );
+}
+
+#[test]
+fn test_load_flow_converges() {
+    let (mut world, mut schedule) = setup_with(&load_network());
+    let result = run_load_flow(&mut world, &mut schedule);
+
+    assert_eq!(result.status, LoadFlowStatus::Converged, "{:?}", result);
+    assert!(result.max_mismatch < 1e-6);
+    assert_eq!(result.slack_bus.as_deref(), Some("NGEN"));
+    assert_eq!(result.bus_count, 4);
+
+    // Reference values computed by the powsybl open load flow on the same case
+    let ngen = bus_state(&world, "NGEN");
+    assert!((ngen.v - 24.5).abs() < 1e-6);
+    assert_eq!(ngen.angle, 0.0);
+
+    let nhv1 = bus_state(&world, "NHV1");
+    assert!((nhv1.v - 402.14).abs() < 1.0, "NHV1 at {} kV", nhv1.v);
+
+    let nload = bus_state(&world, "NLOAD");
+    assert!((nload.v - 147.58).abs() < 1.0, "NLOAD at {} kV", nload.v);
+    assert!(nload.angle < nhv1.angle);
+}
+
+#[test]
+fn test_load_flow_writes_branch_and_generator_states() {
+    let (mut world, mut schedule) = setup_with(&load_network());
+    run_load_flow(&mut world, &mut schedule);
+
+    let mut lines = world.query::<(&Line, &BranchState)>();
+    let flows: Vec<BranchState> = lines.iter(&world).map(|(_, state)| *state).collect();
+    assert_eq!(flows.len(), 2);
+    for flow in &flows {
+        // Both parallel lines carry half of the load plus their losses
+        assert!(flow.p1 > 300.0 && flow.p1 < 310.0, "p1 = {}", flow.p1);
+        assert!(flow.p2 < -295.0, "p2 = {}", flow.p2);
+        assert!(flow.p1 + flow.p2 > 0.0);
+        assert!(flow.i1 > 0.0);
+    }
+
+    let mut transformers = world.query::<(&TwoWindingsTransformer, &BranchState)>();
+    assert_eq!(transformers.iter(&world).count(), 2);
+
+    let mut generators = world.query::<(&Generator, &InjectionState)>();
+    let (_, injection) = generators.single(&world);
+    // The slack generator covers the 600 MW load and the network losses
+    assert!(
+        injection.p > 600.0 && injection.p < 620.0,
+        "p = {}",
+        injection.p
+    );
+}
+
+#[test]
+fn test_load_flow_states_on_voltage_levels() {
+    let (mut world, mut schedule) = setup_with(&load_network());
+    let counts = world.resource::<AssetRegistry>().counts();
+    run_load_flow(&mut world, &mut schedule);
+
+    // No entity is added for the buses
+    assert_eq!(world.resource::<AssetRegistry>().counts(), counts);
+    let registry = world.resource::<AssetRegistry>();
+    let entity = registry.find_typed::<VoltageLevel>("VLLOAD").unwrap();
+    let states = world.get::<BusStates>(entity).unwrap();
+    assert_eq!(states.0.keys().collect::<Vec<_>>(), ["NLOAD"]);
+}
+
+#[test]
+fn test_load_flow_clears_states_outside_island() {
+    let mut network = load_network();
+    let (mut world, mut schedule) = setup_with(&network);
+    run_load_flow(&mut world, &mut schedule);
+
+    // Disconnect the load side, the transformer no longer reaches NLOAD
+    for transformer in &mut network.substations[1].two_windings_transformers {
+        transformer.bus2 = String::new();
+    }
+    network.register(&mut world, &mut schedule);
+    let result = run_load_flow(&mut world, &mut schedule);
+    assert_eq!(result.status, LoadFlowStatus::Converged, "{:?}", result);
+
+    let registry = world.resource::<AssetRegistry>();
+    let load_level = registry.find_typed::<VoltageLevel>("VLLOAD").unwrap();
+    assert!(world.get::<BusStates>(load_level).is_none());
+    let transformer = registry
+        .find_typed::<TwoWindingsTransformer>("NHV2_NLOAD")
+        .unwrap();
+    assert!(world.get::<BranchState>(transformer).is_none());
+    let nhv2 = registry.find_typed::<VoltageLevel>("VLHV2").unwrap();
+    assert!(world.get::<BusStates>(nhv2).is_some());
+
+    // A failed run leaves no state behind
+    network.substations[0].voltage_levels[0].generators = None;
+    let mut world_without_generator = setup_with(&network);
+    let result = run_load_flow(
+        &mut world_without_generator.0,
+        &mut world_without_generator.1,
+    );
+    assert_eq!(result.status, LoadFlowStatus::Failed);
+    let world = &world_without_generator.0;
+    assert!(world
+        .iter_entities()
+        .all(|entity| !entity.contains::<BusStates>() && !entity.contains::<BranchState>()));
+}
+
+#[test]
+fn test_load_flow_dc_fallback_slack_takes_imbalance() {
+    let (mut world, mut schedule) = setup_with(&load_network());
+    world.resource_mut::<LoadFlowParameters>().max_iterations = 0;
+    let result = run_load_flow(&mut world, &mut schedule);
+    assert_eq!(result.status, LoadFlowStatus::DcFallback, "{:?}", result);
+
+    let mut generators = world.query::<(&Generator, &InjectionState)>();
+    let (generator, injection) = generators.single(&world);
+    // Lossless: the slack generator covers exactly the 600 MW load, not its 607 MW set point
+    assert_eq!(generator.target_p, 607.0);
+    assert!((injection.p - 600.0).abs() < 1e-6, "p = {}", injection.p);
+}
+
+#[test]
+fn test_load_flow_follows_updates() {
+    let mut network = load_network();
+    let (mut world, mut schedule) = setup_with(&network);
+    run_load_flow(&mut world, &mut schedule);
+    let base = bus_state(&world, "NLOAD");
+
+    network.substations[1].voltage_levels[1]
+        .loads
+        .as_mut()
+        .unwrap()[0]
+        .p0 = 900.0;
+    network.register(&mut world, &mut schedule);
+    let result = run_load_flow(&mut world, &mut schedule);
+
+    assert_eq!(result.status, LoadFlowStatus::Converged);
+    assert!(bus_state(&world, "NLOAD").v < base.v);
+}
+
+#[test]
+fn test_load_flow_without_generator_fails() {
+    let mut network = load_network();
+    network.substations[0].voltage_levels[0].generators = None;
+    let (mut world, mut schedule) = setup_with(&network);
+    let result = run_load_flow(&mut world, &mut schedule);
+
+    assert_eq!(result.status, LoadFlowStatus::Failed);
+    assert!(result.message.is_some());
+}
+
+#[test]
+fn test_load_flow_matpower_case9() {
+    let text = std::fs::read_to_string("tests/data/matpower/case9.m").unwrap();
+    let network = libs::matpower::from_str(&text).unwrap();
+    let (mut world, mut schedule) = setup_with(&network);
+    let result = run_load_flow(&mut world, &mut schedule);
+
+    assert_eq!(result.status, LoadFlowStatus::Converged, "{:?}", result);
+    assert_eq!(result.bus_count, 9);
+    // The generators hold the voltage of their bus, whichever is the slack
+    for (bus, v) in [("BUS-1", 1.04), ("BUS-2", 1.025), ("BUS-3", 1.025)] {
+        let state = bus_state(&world, bus);
+        assert!((state.v / 345.0 - v).abs() < 1e-6, "{}: {:?}", bus, state);
+    }
+    // Magnitudes of the published solution of the WSCC 9-bus system
+    let expected = [
+        ("BUS-4", 1.0258),
+        ("BUS-5", 1.0127),
+        ("BUS-6", 1.0324),
+        ("BUS-7", 1.0159),
+        ("BUS-8", 1.0258),
+        ("BUS-9", 0.9956),
+    ];
+    for (bus, v) in expected {
+        let state = bus_state(&world, bus);
+        assert!((state.v / 345.0 - v).abs() < 1e-3, "{}: {:?}", bus, state);
+    }
+}
+
+#[test]
+fn test_load_flow_psse_six_bus() {
+    let text = std::fs::read_to_string("tests/data/psse/six_bus_v33.raw").unwrap();
+    let network = libs::psse::from_str(&text).unwrap();
+    let (mut world, mut schedule) = setup_with(&network);
+    let result = run_load_flow(&mut world, &mut schedule);
+
+    // The isolated bus is left out
+    assert_eq!(result.status, LoadFlowStatus::Converged, "{:?}", result);
+    assert_eq!(result.bus_count, 5);
+    for (bus, v) in [("BUS-101", 1.02 * 18.0), ("BUS-105", 1.01 * 13.8)] {
+        let state = bus_state(&world, bus);
+        assert!((state.v - v).abs() < 1e-6, "{}: {:?}", bus, state);
+    }
+    for bus in ["BUS-102", "BUS-103", "BUS-104"] {
+        let state = bus_state(&world, bus);
+        let nominal = if bus == "BUS-104" { 115.0 } else { 230.0 };
+        assert!(
+            (state.v / nominal - 1.0).abs() < 0.1,
+            "{}: {:?}",
+            bus,
+            state
+        );
+    }
+}
@@ -1 +1,12 @@
+mod export;
+mod fixture;
+mod history;
 mod iidm;
+mod lifecycle;
+mod loadflow;
+mod relations;
+mod scenario;
+mod topology;
+mod validation;
+
+pub use fixture::*;
@@ -0,0 +1,124 @@
+use bevy_ecs::world::World;
+use iidm::*;
+
+use super::{load_network, setup};
+
+fn entity(world: &World, id: &str) -> bevy_ecs::entity::Entity {
+    world.resource::<AssetRegistry>().find(id).unwrap()
+}
+
+fn assembled_network(world: &World) -> Network {
+    assemble::<Network>(world, entity(world, "sim1")).unwrap()
+}
+
+#[test]
+fn test_parents_do_not_hold_nested_copies() {
+    let (world, _) = setup();
+
+    let network = world.get::<Network>(entity(&world, "sim1")).unwrap();
+    assert!(network.substations.is_empty());
+    assert!(network.lines.is_empty());
+
+    let voltage_level = world.get::<VoltageLevel>(entity(&world, "VLGEN")).unwrap();
+    assert_eq!(voltage_level.generators.as_deref().map(<[_]>::len), Some(0));
+
+    let relation = world.get::<ChildOf>(entity(&world, "GEN")).unwrap();
+    assert_eq!(relation.parent, entity(&world, "VLGEN"));
+    assert_eq!(relation.field, "generators");
+}
+
+#[test]
+fn test_assemble_rebuilds_uploaded_document() {
+    let (world, _) = setup();
+
+    assert_eq!(
+        serde_json::to_value(assembled_network(&world)).unwrap(),
+        serde_json::to_value(load_network()).unwrap()
+    );
+}
+
+#[test]
+fn test_assemble_sees_child_updates() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(UpdateEvent::<Line> {
+        id: "NHV1_NHV2_1".to_string(),
+        updater: LineUpdater {
+            x: Some(99.0),
+            ..Default::default()
+        },
+    });
+    schedule.run(&mut world);
+
+    let network = assembled_network(&world);
+    let line = network
+        .lines
+        .iter()
+        .find(|line| line.id == "NHV1_NHV2_1")
+        .unwrap();
+    assert_eq!(line.x, 99.0);
+}
+
+#[test]
+fn test_delete_cascades_to_nested_elements() {
+    let (mut world, mut schedule) = setup();
+    let substation = load_network().substations[0].clone();
+
+    // The lines to the substation go first, they cannot be left dangling
+    for line in load_network().lines {
+        world.send_event(DeleteEvent::<Line>::new(line.id));
+    }
+    schedule.run(&mut world);
+    world.send_event(DeleteEvent::<Substation>::new(substation.id.clone()));
+    schedule.run(&mut world);
+
+    let registry = world.resource::<AssetRegistry>();
+    assert!(registry.find(&substation.id).is_none());
+    for voltage_level in &substation.voltage_levels {
+        assert!(registry.find(&voltage_level.id).is_none());
+        for generator in voltage_level.generators.iter().flatten() {
+            assert!(registry.find(&generator.id).is_none());
+        }
+    }
+    assert_eq!(
+        assembled_network(&world).substations.len(),
+        load_network().substations.len() - 1
+    );
+}
+
+#[test]
+fn test_register_twice_keeps_single_relations() {
+    let (mut world, mut schedule) = setup();
+
+    load_network().register(&mut world, &mut schedule);
+
+    let children = world.get::<Children>(entity(&world, "sim1")).unwrap();
+    let network = load_network();
+    assert_eq!(
+        children.len(),
+        network.substations.len() + network.lines.len()
+    );
+    assert_eq!(
+        serde_json::to_value(assembled_network(&world)).unwrap(),
+        serde_json::to_value(network).unwrap()
+    );
+}
+
+#[test]
+fn test_location_of_nested_and_connected_elements() {
+    let (world, _) = setup();
+
+    let generator = location::<Generator>(&world, entity(&world, "GEN"));
+    assert_eq!(generator.voltage_levels, ["VLGEN".to_string()].into());
+    assert_eq!(generator.substations, ["P1".to_string()].into());
+
+    let line = location::<Line>(&world, entity(&world, "NHV1_NHV2_1"));
+    assert_eq!(
+        line.voltage_levels,
+        ["VLHV1".to_string(), "VLHV2".to_string()].into()
+    );
+    assert_eq!(
+        line.substations,
+        ["P1".to_string(), "P2".to_string()].into()
+    );
+}
@@ -0,0 +1,129 @@
+use bevy_ecs::{schedule::Schedule, world::World};
+use iidm::*;
+
+use super::setup;
+
+fn component<C: bevy_ecs::component::Component + Clone>(world: &World, id: &str) -> C {
+    let entity = world
+        .resource::<AssetRegistry>()
+        .find_typed::<C>(id)
+        .unwrap();
+    world.get::<C>(entity).cloned().unwrap()
+}
+
+fn peak_scenario() -> Scenario {
+    Scenario::from_json(
+        r#"{
+            "name": "Evening peak",
+            "loadFlow": false,
+            "events": [
+                {"at": 10, "type": "loadRamp", "id": "LOAD", "p0": 800, "duration": 20},
+                {"at": 40, "type": "generatorTrip", "id": "GEN"}
+            ]
+        }"#,
+    )
+    .unwrap()
+}
+
+fn step(world: &mut World, schedule: &mut Schedule, seconds: f64) -> ScenarioReport {
+    let report = step_scenario(world, seconds).unwrap();
+    schedule.run(world);
+    report
+}
+
+#[test]
+fn test_scenario_applies_events_on_time() {
+    let (mut world, mut schedule) = setup();
+    load_scenario(&mut world, peak_scenario()).unwrap();
+    let initial = component::<Load>(&world, "LOAD").p0;
+
+    step(&mut world, &mut schedule, 5.0);
+    assert_eq!(component::<Load>(&world, "LOAD").p0, initial);
+
+    // Halfway through the ramp
+    let report = step(&mut world, &mut schedule, 15.0);
+    assert_eq!(report.active_ramps, vec!["LOAD".to_string()]);
+    assert_eq!(
+        component::<Load>(&world, "LOAD").p0,
+        (initial + 800.0) / 2.0
+    );
+
+    let report = step(&mut world, &mut schedule, 15.0);
+    assert!(report.active_ramps.is_empty());
+    assert_eq!(component::<Load>(&world, "LOAD").p0, 800.0);
+    assert_eq!(component::<Generator>(&world, "GEN").bus, "NGEN");
+
+    let report = step(&mut world, &mut schedule, 10.0);
+    assert_eq!(report.status, ScenarioStatus::Finished);
+    assert_eq!(report.applied_events, 2);
+    assert_eq!(component::<Generator>(&world, "GEN").bus, "");
+    assert!(matches!(
+        step_scenario(&mut world, 1.0),
+        Err(ScenarioError::Finished)
+    ));
+}
+
+#[test]
+fn test_scenario_journals_each_event_once() {
+    let (mut world, mut schedule) = setup();
+    world.init_resource::<History>();
+    load_scenario(&mut world, peak_scenario()).unwrap();
+    let initial = component::<Load>(&world, "LOAD").p0;
+
+    for _ in 0..40 {
+        step(&mut world, &mut schedule, 1.0);
+    }
+
+    // The ramp steps are not journaled, only the ramp as a whole and the trip
+    let entries = world.resource::<History>().entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        (entries[0].id.as_str(), entries[1].id.as_str()),
+        ("LOAD", "GEN")
+    );
+    assert_eq!(entries[0].before, serde_json::json!({"p0": initial}));
+    assert_eq!(entries[0].after, serde_json::json!({"p0": 800.0}));
+
+    undo(&mut world).unwrap();
+    undo(&mut world).unwrap();
+    assert_eq!(component::<Load>(&world, "LOAD").p0, initial);
+    assert_eq!(component::<Generator>(&world, "GEN").bus, "NGEN");
+}
+
+#[test]
+fn test_scenario_clock_follows_speed_when_running() {
+    let (mut world, _) = setup();
+    load_scenario(&mut world, peak_scenario()).unwrap();
+
+    // Paused: the real time does not move the clock
+    assert_eq!(advance_scenario(&mut world, 1.0).unwrap().time, 0.0);
+
+    start_scenario(&mut world).unwrap();
+    set_scenario_speed(&mut world, 4.0).unwrap();
+    assert_eq!(advance_scenario(&mut world, 1.5).unwrap().time, 6.0);
+
+    pause_scenario(&mut world).unwrap();
+    assert_eq!(advance_scenario(&mut world, 1.0).unwrap().time, 6.0);
+    assert!(matches!(
+        set_scenario_speed(&mut world, 0.0),
+        Err(ScenarioError::InvalidSpeed(_))
+    ));
+}
+
+#[test]
+fn test_scenario_with_unknown_element_is_refused() {
+    let (mut world, _) = setup();
+    let scenario = Scenario::from_json(
+        r#"{"name": "Outage", "events": [{"at": 0, "type": "switchOpen", "id": "NOPE"}]}"#,
+    )
+    .unwrap();
+
+    assert!(matches!(
+        load_scenario(&mut world, scenario),
+        Err(ScenarioError::UnknownElement { index: 0, .. })
+    ));
+    assert!(matches!(
+        scenario_report(&world),
+        Err(ScenarioError::NotLoaded)
+    ));
+}
@@ -0,0 +1,318 @@
+use bevy_ecs::{change_detection::DetectChanges, event::Events, schedule::Schedule, world::World};
+use iidm::*;
+
+use super::{load_network, setup, setup_with, setup_world};
+
+fn switch(id: &str, bus1: &str, bus2: &str) -> Switch {
+    Switch {
+        id: id.to_string(),
+        kind: SwitchKind::Breaker,
+        open: false,
+        retained: false,
+        bus1: bus1.to_string(),
+        bus2: bus2.to_string(),
+        voltage_level_id: String::new(),
+    }
+}
+
+/// Reference network with a coupling switch in VLLOAD and a node-breaker voltage level VLNB
+/// fed from NHV1, where node 0 holds a load and node 3 the line
+fn create_switched_network() -> Network {
+    let mut network = load_network();
+
+    let vlload = &mut network.substations[1].voltage_levels[1];
+    let topology = vlload.bus_breaker_topology.as_mut().unwrap();
+    topology.buses.push(Bus {
+        id: "NLOAD2".to_string(),
+    });
+    topology.switches.push(switch("SW_LOAD", "NLOAD", "NLOAD2"));
+    let load = &mut vlload.loads.as_mut().unwrap()[0];
+    load.bus = "NLOAD2".to_string();
+    load.connectable_bus = "NLOAD2".to_string();
+
+    let mut nb_load = vlload.loads.as_ref().unwrap()[0].clone();
+    nb_load.id = "LOADNB".to_string();
+    nb_load.p0 = 10.0;
+    nb_load.q0 = 0.0;
+    nb_load.bus = "0".to_string();
+    nb_load.connectable_bus = "0".to_string();
+
+    network.substations[0].voltage_levels.push(VoltageLevel {
+        id: "VLNB".to_string(),
+        nominal_v: 380.0,
+        topology_kind: TopologyKind::NodeBreaker,
+        generators: None,
+        loads: Some(vec![nb_load]),
+        busbar_sections: None,
+        node_breaker_topology: Some(NodeBreakerTopology {
+            nodes: (0..4).map(|id| Node { id }).collect(),
+            switches: vec![switch("D1", "0", "1"), switch("BR1", "1", "2")],
+            internal_connections: vec![InternalConnection { node1: 2, node2: 3 }],
+        }),
+        bus_breaker_topology: None,
+    });
+
+    let mut line = network.lines[0].clone();
+    line.id = "NHV1_VLNB".to_string();
+    line.voltage_level_id2 = "VLNB".to_string();
+    line.bus2 = "3".to_string();
+    line.connectable_bus2 = "3".to_string();
+    network.lines.push(line);
+
+    network
+}
+
+fn setup_with_registry(network: &Network, registry: AssetRegistry) -> (World, Schedule) {
+    let (mut world, mut schedule) = setup_world();
+    world.insert_resource(registry);
+    network.register(&mut world, &mut schedule);
+    schedule.run(&mut world);
+    (world, schedule)
+}
+
+fn set_switch(world: &mut World, schedule: &mut Schedule, id: &str, open: bool) {
+    world.send_event(UpdateEvent::<Switch> {
+        id: id.to_string(),
+        updater: SwitchUpdater {
+            open: Some(open),
+            ..Default::default()
+        },
+    });
+    schedule.run(world);
+    schedule.run(world);
+}
+
+fn bus_view(world: &World, voltage_level_id: &str) -> BusView {
+    world
+        .resource::<NetworkTopology>()
+        .bus_view(voltage_level_id)
+        .cloned()
+        .unwrap()
+}
+
+#[test]
+fn test_topology_of_reference_network() {
+    let (world, _) = setup();
+    let topology = world.resource::<NetworkTopology>();
+
+    assert_eq!(topology.island_count(), 1);
+    for id in ["VLGEN", "VLHV1", "VLHV2", "VLLOAD"] {
+        let view = topology.bus_view(id).unwrap();
+        assert_eq!(view.buses.len(), 1);
+        assert_eq!(topology.islands(id), vec![0]);
+    }
+    assert_eq!(
+        topology
+            .calculated_bus(None, "NGEN")
+            .map(|bus| bus.id.as_str()),
+        Some("NGEN")
+    );
+}
+
+#[test]
+fn test_bus_view_component_on_voltage_levels() {
+    let (mut world, _) = setup_with(&create_switched_network());
+
+    let mut query = world.query::<(&VoltageLevel, &BusView)>();
+    assert_eq!(query.iter(&world).count(), 5);
+    for (voltage_level, view) in query.iter(&world) {
+        assert_eq!(voltage_level.id, view.voltage_level_id);
+    }
+}
+
+#[test]
+fn test_closed_switches_merge_buses() {
+    let (world, _) = setup_with(&create_switched_network());
+    let topology = world.resource::<NetworkTopology>();
+
+    let vlload = topology.bus_view("VLLOAD").unwrap();
+    assert_eq!(vlload.buses.len(), 1);
+    assert_eq!(vlload.buses[0].members, vec!["NLOAD", "NLOAD2"]);
+
+    let vlnb = topology.bus_view("VLNB").unwrap();
+    assert_eq!(vlnb.buses.len(), 1);
+    assert_eq!(vlnb.buses[0].id, "VLNB_0");
+    assert_eq!(
+        topology
+            .calculated_bus(Some("VLNB"), "3")
+            .map(|bus| bus.id.as_str()),
+        Some("VLNB_0")
+    );
+    // Node numbers need their voltage level
+    assert!(topology.calculated_bus(None, "3").is_none());
+    assert_eq!(topology.island_count(), 1);
+}
+
+#[test]
+fn test_switch_entities_are_created() {
+    let (world, _) = setup_with(&create_switched_network());
+    let registry = world.resource::<AssetRegistry>();
+
+    for id in ["SW_LOAD", "D1", "BR1"] {
+        let entity = registry.find(id).unwrap();
+        assert!(world.get::<Switch>(entity).is_some());
+    }
+}
+
+#[test]
+fn test_voltage_level_holds_no_switch() {
+    let (world, _) = setup_with(&create_switched_network());
+    let vlnb = world
+        .resource::<AssetRegistry>()
+        .find_typed::<VoltageLevel>("VLNB")
+        .unwrap();
+
+    let stored = world.get::<VoltageLevel>(vlnb).unwrap();
+    assert!(stored
+        .node_breaker_topology
+        .as_ref()
+        .unwrap()
+        .switches
+        .is_empty());
+    let assembled = assemble::<VoltageLevel>(&world, vlnb).unwrap();
+    let switches = &assembled.node_breaker_topology.unwrap().switches;
+    assert_eq!(
+        switches
+            .iter()
+            .map(|switch| switch.id.as_str())
+            .collect::<Vec<_>>(),
+        ["D1", "BR1"]
+    );
+}
+
+#[test]
+fn test_deleted_switch_splits_bus() {
+    let (mut world, mut schedule) = setup_with(&create_switched_network());
+    assert_eq!(bus_view(&world, "VLNB").buses.len(), 1);
+
+    world.send_event(DeleteEvent::<Switch>::new("BR1"));
+    schedule.run(&mut world);
+    // The removal is seen once the despawn is applied
+    schedule.run(&mut world);
+
+    let vlnb = bus_view(&world, "VLNB");
+    assert_eq!(vlnb.buses.len(), 2);
+    assert_eq!(vlnb.buses[0].members, vec!["0", "1"]);
+}
+
+#[test]
+fn test_switch_with_taken_id_is_reported() {
+    let mut network = create_switched_network();
+    let vlload = &mut network.substations[1].voltage_levels[1];
+    vlload.bus_breaker_topology.as_mut().unwrap().switches[0].id = "LOAD".to_string();
+    let (world, _) = setup_with_registry(&network, AssetRegistry::with_unique_ids());
+
+    let registry = world.resource::<AssetRegistry>();
+    assert!(registry.find_typed::<Switch>("LOAD").is_none());
+    assert!(registry.find_typed::<Load>("LOAD").is_some());
+    let events = world.resource::<Events<ComponentErrorEvent>>();
+    let errors: Vec<_> = events.get_cursor().read(events).cloned().collect();
+    assert!(errors.iter().any(|error| error.id == "LOAD"
+        && error.error_type == ErrorType::EntityAlreadyExists
+        && error.component_type.ends_with("Switch")));
+}
+
+#[test]
+fn test_opening_switch_splits_bus_and_island() {
+    let (mut world, mut schedule) = setup_with(&create_switched_network());
+
+    set_switch(&mut world, &mut schedule, "BR1", true);
+
+    let vlnb = bus_view(&world, "VLNB");
+    assert_eq!(vlnb.buses.len(), 2);
+    assert_eq!(vlnb.buses[0].members, vec!["0", "1"]);
+    assert_eq!(vlnb.buses[1].members, vec!["2", "3"]);
+    let topology = world.resource::<NetworkTopology>();
+    assert_eq!(topology.island_count(), 2);
+    assert_eq!(topology.islands("VLNB").len(), 2);
+    assert_eq!(
+        topology.calculated_bus(Some("VLNB"), "3").unwrap().island,
+        topology.calculated_bus(None, "NGEN").unwrap().island
+    );
+
+    set_switch(&mut world, &mut schedule, "BR1", false);
+
+    assert_eq!(bus_view(&world, "VLNB").buses.len(), 1);
+    assert_eq!(world.resource::<NetworkTopology>().island_count(), 1);
+}
+
+#[test]
+fn test_switch_update_only_touches_its_voltage_level() {
+    let (mut world, mut schedule) = setup_with(&create_switched_network());
+    let registry = world.resource::<AssetRegistry>();
+    let (vlnb, vlgen) = (
+        registry.find("VLNB").unwrap(),
+        registry.find("VLGEN").unwrap(),
+    );
+    let vlgen_tick = world
+        .entity(vlgen)
+        .get_ref::<BusView>()
+        .unwrap()
+        .last_changed();
+    let vlnb_tick = world
+        .entity(vlnb)
+        .get_ref::<BusView>()
+        .unwrap()
+        .last_changed();
+
+    // The open node-breaker bus is isolated, the islands of the other buses keep their number
+    set_switch(&mut world, &mut schedule, "D1", true);
+
+    let vlnb_ref = world.entity(vlnb).get_ref::<BusView>().unwrap();
+    assert_ne!(vlnb_ref.last_changed(), vlnb_tick);
+    assert_eq!(vlnb_ref.buses.len(), 2);
+    let vlgen_ref = world.entity(vlgen).get_ref::<BusView>().unwrap();
+    assert_eq!(vlgen_ref.last_changed(), vlgen_tick);
+}
+
+#[test]
+fn test_load_flow_follows_switches() {
+    let (mut world, mut schedule) = setup_with(&create_switched_network());
+
+    world.send_event(LoadFlowEvent);
+    schedule.run(&mut world);
+    assert_eq!(
+        world.resource::<LoadFlowResult>().status,
+        LoadFlowStatus::Converged
+    );
+    let mut generators = world.query::<(&Generator, &InjectionState)>();
+    let (_, before) = generators.single(&world);
+    assert!(before.p > 610.0, "p = {}", before.p);
+
+    // Disconnecting the 600 MW load leaves the generator with the small node-breaker load
+    set_switch(&mut world, &mut schedule, "SW_LOAD", true);
+    world.send_event(LoadFlowEvent);
+    schedule.run(&mut world);
+
+    let (_, after) = generators.single(&world);
+    assert!(after.p > 10.0 && after.p < 20.0, "p = {}", after.p);
+}
+
+#[test]
+fn test_deleted_voltage_level_takes_its_switches() {
+    let (mut world, mut schedule) = setup_with(&create_switched_network());
+    let registry = world.resource::<AssetRegistry>();
+    let vlnb = registry.find_typed::<VoltageLevel>("VLNB").unwrap();
+    let breaker = registry.find_typed::<Switch>("BR1").unwrap();
+    assert_eq!(world.get::<ChildOf>(breaker).unwrap().parent, vlnb);
+
+    // The line to the voltage level goes first, it cannot be left dangling
+    world.send_event(DeleteEvent::<Line>::new("NHV1_VLNB"));
+    schedule.run(&mut world);
+    world.send_event(DeleteEvent::<VoltageLevel>::new("VLNB"));
+    schedule.run(&mut world);
+
+    let registry = world.resource::<AssetRegistry>();
+    assert!(registry.find("D1").is_none());
+    assert!(registry.find("BR1").is_none());
+    assert!(registry.find("SW_LOAD").is_some());
+    let exported = export_network(&world).unwrap();
+    assert!(exported.network.switches.is_empty());
+    assert!(exported
+        .network
+        .substations
+        .iter()
+        .flat_map(|substation| &substation.voltage_levels)
+        .all(|voltage_level| voltage_level.id != "VLNB"));
+    assert!(exported.unplaced.is_empty());
+}
@@ -0,0 +1,328 @@
+use bevy_ecs::{event::Events, schedule::Schedule, world::World};
+use iidm::*;
+use serde_json::json;
+
+use super::{load_network, setup, setup_with};
+
+const NETWORK_XIIDM_FILE: &str = "tests/data/network.xiidm";
+const NODE_BREAKER_XIIDM_FILE: &str = "tests/data/node-breaker.xiidm";
+
+fn errors(world: &World) -> Vec<ErrorType> {
+    let events = world.resource::<Events<ComponentErrorEvent>>();
+    events
+        .get_cursor()
+        .read(events)
+        .map(|event| event.error_type.clone())
+        .collect()
+}
+
+fn generator(world: &World) -> Generator {
+    let entity = world.resource::<AssetRegistry>().find("GEN").unwrap();
+    world.get::<Generator>(entity).unwrap().clone()
+}
+
+fn set_target_p(world: &mut World, schedule: &mut Schedule, target_p: f64) {
+    world.send_event(UpdateEvent::<Generator> {
+        id: "GEN".to_string(),
+        updater: GeneratorUpdater {
+            target_p: Some(target_p),
+            ..Default::default()
+        },
+    });
+    schedule.run(world);
+}
+
+#[test]
+fn test_test_networks_are_valid() {
+    let xiidm = std::fs::read(NETWORK_XIIDM_FILE).unwrap();
+    for network in [
+        load_network(),
+        libs::xiidm::from_reader(xiidm.as_slice()).unwrap(),
+    ] {
+        assert_eq!(
+            validate_network(&network, ValidationLevel::SteadyStateHypothesis),
+            vec![]
+        );
+    }
+}
+
+#[test]
+fn test_network_violations_by_level() {
+    let mut network = load_network();
+    network.lines[0].voltage_level_id2 = "UNKNOWN".to_string();
+    let voltage_level = &mut network.substations[0].voltage_levels[0];
+    voltage_level.generators.as_mut().unwrap()[0].target_p = 20000.0;
+    voltage_level.nominal_v = 0.0;
+
+    let equipment = validate_network(&network, ValidationLevel::Equipment);
+    let paths: Vec<(&str, &str)> = equipment
+        .iter()
+        .map(|violation| (violation.id.as_str(), violation.path.as_str()))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            ("VLGEN", "/nominalV"),
+            ("NHV1_NHV2_1", ""),
+            ("NHV1_NHV2_1", "")
+        ]
+    );
+    assert_eq!(equipment[1].message, "unknown voltage level 'UNKNOWN'");
+    assert_eq!(
+        equipment[2].message,
+        "unknown bus 'NHV2' in voltage level 'UNKNOWN'"
+    );
+
+    let steady_state = validate_network(&network, ValidationLevel::SteadyStateHypothesis);
+    assert_eq!(steady_state.len(), 4);
+    assert!(steady_state
+        .iter()
+        .any(|violation| violation.id == "GEN" && violation.path == "/targetP"));
+}
+
+#[test]
+fn test_invalid_update_is_refused() {
+    let (mut world, mut schedule) = setup();
+
+    set_target_p(&mut world, &mut schedule, 20000.0);
+
+    assert_eq!(generator(&world).target_p, 607.0);
+    let errors = errors(&world);
+    assert!(matches!(
+        errors.as_slice(),
+        [ErrorType::Invalid(violations)] if violations[0].path == "/targetP"
+    ));
+}
+
+#[test]
+fn test_update_checked_at_network_level() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(UpdateEvent::<Network> {
+        id: "sim1".to_string(),
+        updater: NetworkUpdater {
+            minimum_validation_level: Some(ValidationLevel::Equipment),
+            ..Default::default()
+        },
+    });
+    schedule.run(&mut world);
+    set_target_p(&mut world, &mut schedule, 20000.0);
+
+    assert_eq!(generator(&world).target_p, 20000.0);
+    assert!(errors(&world).is_empty());
+}
+
+#[test]
+fn test_invalid_patch_and_creation_are_refused() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(PatchEvent::<Generator>::new(
+        "GEN",
+        Patch::Merge(json!({"minP": 10.0, "maxP": 0.0})),
+    ));
+    let mut created = generator(&world);
+    created.id = "GEN2".to_string();
+    created.voltage_regulator_on = true;
+    created.target_v = -1.0;
+    world.send_event(CreateEvent {
+        id: "GEN2".to_string(),
+        parent_id: Some("VLGEN".to_string()),
+        component: created,
+    });
+    schedule.run(&mut world);
+
+    assert_eq!(generator(&world).max_p, 9999.99);
+    assert!(world.resource::<AssetRegistry>().find("GEN2").is_none());
+    let errors = errors(&world);
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|error| matches!(error, ErrorType::Invalid(_))));
+}
+
+fn line(world: &World) -> Line {
+    let entity = world
+        .resource::<AssetRegistry>()
+        .find("NHV1_NHV2_1")
+        .unwrap();
+    world.get::<Line>(entity).unwrap().clone()
+}
+
+fn invalid_references(world: &World) -> Vec<Reference> {
+    let events = world.resource::<Events<InvalidReferenceEvent>>();
+    events
+        .get_cursor()
+        .read(events)
+        .flat_map(|event| event.references.clone())
+        .collect()
+}
+
+#[test]
+fn test_update_to_unknown_voltage_level_is_refused() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(UpdateEvent::<Line> {
+        id: "NHV1_NHV2_1".to_string(),
+        updater: LineUpdater {
+            voltage_level_id1: Some("NOPE".to_string()),
+            ..Default::default()
+        },
+    });
+    schedule.run(&mut world);
+
+    assert_eq!(line(&world).voltage_level_id1, "VLHV1");
+    assert!(errors(&world).is_empty());
+    assert!(
+        invalid_references(&world).contains(&Reference::VoltageLevel {
+            id: "NOPE".to_string()
+        })
+    );
+}
+
+#[test]
+fn test_patch_to_unknown_bus_is_refused() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(PatchEvent::<Line>::new(
+        "NHV1_NHV2_1",
+        Patch::Merge(json!({"bus2": "NOPE", "r": 4.0})),
+    ));
+    schedule.run(&mut world);
+
+    let line = line(&world);
+    assert_eq!(line.bus2, "NHV2");
+    assert_eq!(line.r, 3.0);
+    assert_eq!(
+        invalid_references(&world),
+        vec![Reference::Bus {
+            voltage_level_id: Some("VLHV2".to_string()),
+            bus: "NOPE".to_string()
+        }]
+    );
+}
+
+#[test]
+fn test_update_to_known_references_is_applied() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(UpdateEvent::<Line> {
+        id: "NHV1_NHV2_1".to_string(),
+        updater: LineUpdater {
+            voltage_level_id2: Some("VLHV1".to_string()),
+            bus2: Some("NHV1".to_string()),
+            connectable_bus2: Some("NHV1".to_string()),
+            r: Some(4.0),
+            ..Default::default()
+        },
+    });
+    schedule.run(&mut world);
+
+    let line = line(&world);
+    assert_eq!(line.voltage_level_id2, "VLHV1");
+    assert_eq!(line.r, 4.0);
+    assert!(invalid_references(&world).is_empty());
+}
+
+#[test]
+fn test_update_to_bus_of_another_voltage_level_is_refused() {
+    let (mut world, mut schedule) = setup();
+
+    // NHV1 is a bus of VLHV1, not of the voltage level holding the generator
+    world.send_event(UpdateEvent::<Generator> {
+        id: "GEN".to_string(),
+        updater: GeneratorUpdater {
+            bus: Some("NHV1".to_string()),
+            connectable_bus: Some("NHV1".to_string()),
+            ..Default::default()
+        },
+    });
+    schedule.run(&mut world);
+
+    assert_eq!(generator(&world).bus, "NGEN");
+    assert_eq!(
+        invalid_references(&world),
+        vec![Reference::Bus {
+            voltage_level_id: Some("VLGEN".to_string()),
+            bus: "NHV1".to_string()
+        }]
+    );
+}
+
+#[test]
+fn test_patch_to_unknown_connectable_bus_is_refused() {
+    let (mut world, mut schedule) = setup();
+
+    world.send_event(PatchEvent::<Generator>::new(
+        "GEN",
+        Patch::Merge(json!({"connectableBus": "NOPE"})),
+    ));
+    world.send_event(PatchEvent::<Line>::new(
+        "NHV1_NHV2_1",
+        Patch::Merge(json!({"connectableBus1": "NOPE"})),
+    ));
+    schedule.run(&mut world);
+
+    assert_eq!(generator(&world).connectable_bus, "NGEN");
+    assert_eq!(line(&world).connectable_bus1, "NHV1");
+    let references = invalid_references(&world);
+    assert_eq!(references.len(), 2);
+    for voltage_level_id in ["VLGEN", "VLHV1"] {
+        assert!(references.contains(&Reference::Bus {
+            voltage_level_id: Some(voltage_level_id.to_string()),
+            bus: "NOPE".to_string()
+        }));
+    }
+}
+
+fn node_breaker_network() -> Network {
+    let xiidm = std::fs::read(NODE_BREAKER_XIIDM_FILE).unwrap();
+    libs::xiidm::from_reader(xiidm.as_slice()).unwrap()
+}
+
+#[test]
+fn test_node_of_another_voltage_level_is_unknown() {
+    let mut network = node_breaker_network();
+    // Node 4 only exists in VL1, not in VL2 holding the load
+    let load = &mut network.substations[1].voltage_levels[0]
+        .loads
+        .as_mut()
+        .unwrap()[0];
+    load.bus = "4".to_string();
+    load.connectable_bus = "4".to_string();
+
+    let violations = validate_network(&network, ValidationLevel::SteadyStateHypothesis);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].id, "LOAD");
+    assert_eq!(
+        violations[0].message,
+        "unknown bus '4' in voltage level 'VL2'"
+    );
+}
+
+#[test]
+fn test_update_to_node_of_another_voltage_level_is_refused() {
+    let (mut world, mut schedule) = setup_with(&node_breaker_network());
+
+    for node in ["4", "1"] {
+        world.send_event(UpdateEvent::<Load> {
+            id: "LOAD".to_string(),
+            updater: LoadUpdater {
+                bus: Some(node.to_string()),
+                connectable_bus: Some(node.to_string()),
+                ..Default::default()
+            },
+        });
+        schedule.run(&mut world);
+    }
+
+    // Node 4 is refused, node 1 of VL2 is accepted
+    let entity = world.resource::<AssetRegistry>().find("LOAD").unwrap();
+    assert_eq!(world.get::<Load>(entity).unwrap().bus, "1");
+    assert_eq!(
+        invalid_references(&world),
+        vec![Reference::Bus {
+            voltage_level_id: Some("VL2".to_string()),
+            bus: "4".to_string()
+        }]
+    );
+}
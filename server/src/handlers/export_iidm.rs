@@ -0,0 +1,71 @@
+use axum::{
+    http::{header, HeaderName, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use iidm::{export_network, ExportError};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::states::Workspace;
+
+/// Created elements left out of the export because their parent could not be found
+const UNPLACED_HEADER: HeaderName = HeaderName::from_static("x-unplaced-elements");
+
+#[derive(Debug, Error)]
+pub enum ExportIidmError {
+    #[error("{0}")]
+    Export(#[from] ExportError),
+
+    #[error("Failed to serialize network: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+struct ExportErrorResponse {
+    status: String,
+}
+
+impl IntoResponse for ExportIidmError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ExportIidmError::Export(ExportError::NoNetwork) => StatusCode::NOT_FOUND,
+            ExportIidmError::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = Json(ExportErrorResponse {
+            status: self.to_string(),
+        });
+        (status, body).into_response()
+    }
+}
+
+/// Downloads the network rebuilt from the live components, with every update applied
+pub async fn export_iidm(Workspace(state): Workspace) -> Result<Response, ExportIidmError> {
+    let ecs = state.ecs.read().await;
+    let world = ecs.world.read().await;
+
+    let export = export_network(&world)?;
+    let body = serde_json::to_vec_pretty(&export.network)?;
+    let disposition = format!("attachment; filename=\"{}.json\"", export.network.id);
+
+    let mut response = (
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response();
+
+    if !export.unplaced.is_empty() {
+        tracing::warn!(
+            "Export left out elements without parent: {}",
+            export.unplaced.join(", ")
+        );
+        if let Ok(value) = export.unplaced.join(",").parse() {
+            response.headers_mut().insert(UNPLACED_HEADER, value);
+        }
+    }
+
+    Ok(response)
+}
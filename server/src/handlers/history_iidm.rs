@@ -0,0 +1,150 @@
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use iidm::{Checkpoint, History, HistoryEntry, HistoryError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::states::{NetworkState, Workspace};
+
+#[derive(Debug, Error)]
+pub enum HistoryIidmError {
+    #[error("{0}")]
+    History(#[from] HistoryError),
+
+    #[error("Invalid checkpoint name: {0}")]
+    InvalidName(String),
+
+    #[error("History system not initialized")]
+    NotInitialized,
+}
+
+#[derive(Debug, Serialize)]
+struct HistoryErrorResponse {
+    status: String,
+}
+
+impl IntoResponse for HistoryIidmError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            HistoryIidmError::History(HistoryError::NothingToUndo)
+            | HistoryIidmError::History(HistoryError::NothingToRedo)
+            | HistoryIidmError::History(HistoryError::Unplaced(_))
+            | HistoryIidmError::History(HistoryError::Export(_)) => StatusCode::CONFLICT,
+            HistoryIidmError::History(HistoryError::ElementNotFound(_))
+            | HistoryIidmError::History(HistoryError::UnknownCheckpoint(_)) => {
+                StatusCode::NOT_FOUND
+            }
+            HistoryIidmError::InvalidName(_) => StatusCode::BAD_REQUEST,
+            HistoryIidmError::History(HistoryError::Restore { .. })
+            | HistoryIidmError::NotInitialized => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = Json(HistoryErrorResponse {
+            status: self.to_string(),
+        });
+        (status, body).into_response()
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryResponse {
+    /// Applied changes, oldest first
+    pub entries: Vec<HistoryEntry>,
+    /// Undone changes, the next one to redo first
+    pub undone: Vec<HistoryEntry>,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckpointRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckpointPath {
+    pub name: String,
+}
+
+/// Lists the changes which can be undone or redone, and the saved checkpoints
+pub async fn get_history(
+    Workspace(state): Workspace,
+) -> Result<Json<HistoryResponse>, HistoryIidmError> {
+    let ecs = state.ecs.read().await;
+    let world = ecs.world.read().await;
+    let history = world
+        .get_resource::<History>()
+        .ok_or(HistoryIidmError::NotInitialized)?;
+
+    Ok(Json(HistoryResponse {
+        entries: history.entries().to_vec(),
+        undone: history.undone().iter().rev().cloned().collect(),
+        checkpoints: history.checkpoints().cloned().collect(),
+    }))
+}
+
+/// Reverts the last change and returns it
+pub async fn undo_iidm(
+    Workspace(state): Workspace,
+) -> Result<Json<HistoryEntry>, HistoryIidmError> {
+    apply_history(&state, iidm::undo).await
+}
+
+/// Applies again the last undone change and returns it
+pub async fn redo_iidm(
+    Workspace(state): Workspace,
+) -> Result<Json<HistoryEntry>, HistoryIidmError> {
+    apply_history(&state, iidm::redo).await
+}
+
+async fn apply_history(
+    state: &Arc<NetworkState>,
+    operation: fn(&mut bevy_ecs::world::World) -> Result<HistoryEntry, HistoryError>,
+) -> Result<Json<HistoryEntry>, HistoryIidmError> {
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+    let mut schedule = ecs.schedule.write().await;
+
+    let entry = operation(&mut world)?;
+    // Topology and other derived state follow the restored values, which are published as any
+    // other change
+    schedule.run(&mut world);
+
+    tracing::debug!("Applied history change {} on {}", entry.sequence, entry.id);
+    Ok(Json(entry))
+}
+
+/// Saves a copy of the current network under the given name
+pub async fn create_checkpoint(
+    Workspace(state): Workspace,
+    Json(payload): Json<CheckpointRequest>,
+) -> Result<impl IntoResponse, HistoryIidmError> {
+    let name = payload.name.trim();
+    if name.is_empty() || name.contains('/') {
+        return Err(HistoryIidmError::InvalidName(payload.name));
+    }
+
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+    let checkpoint = iidm::save_checkpoint(&mut world, name)?;
+
+    Ok((StatusCode::CREATED, Json(checkpoint)))
+}
+
+/// Replaces the whole network by a saved checkpoint
+pub async fn restore_checkpoint(
+    Path(CheckpointPath { name }): Path<CheckpointPath>,
+    Workspace(state): Workspace,
+) -> Result<Json<Checkpoint>, HistoryIidmError> {
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+    let mut schedule = ecs.schedule.write().await;
+
+    let checkpoint = iidm::restore_checkpoint(&mut world, &mut schedule, &name)?;
+    tracing::debug!("Restored checkpoint {}", checkpoint.name);
+    Ok(Json(checkpoint))
+}
@@ -0,0 +1,124 @@
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use iidm::Violation;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{TypePath, ViolationsResponse};
+use crate::handlers::RegisterResponse;
+use crate::states::Workspace;
+
+/// Element to create, linked to its parent: the substation of a voltage level or a two windings
+/// transformer, the voltage level of an injection or the HVDC line of a converter station. The
+/// elements of the network itself need no parent
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateRequest {
+    pub id: String,
+    #[serde(rename = "parentId", default)]
+    pub parent_id: Option<String>,
+    pub component: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteRequest {
+    pub id: String,
+}
+
+#[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
+pub enum LifecycleError {
+    #[error("Failed to parse JSON: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Invalid network data: {0}")]
+    ValidationError(String),
+
+    #[error("Unknown references: {0}")]
+    ReferenceError(String),
+
+    #[error("{0}")]
+    ParentError(String),
+
+    #[error("Component breaks {} network invariants", .0.len())]
+    InvalidComponent(Vec<Violation>),
+
+    #[error("{0}")]
+    ConflictError(String),
+
+    #[error("{0}")]
+    NotFoundError(String),
+
+    #[error("Internal server error: {0}")]
+    InternalError(String),
+}
+
+impl IntoResponse for LifecycleError {
+    fn into_response(self) -> Response {
+        let message = self.to_string();
+        let status = match self {
+            LifecycleError::SerializationError(_) | LifecycleError::ValidationError(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            LifecycleError::ReferenceError(_) | LifecycleError::ParentError(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            LifecycleError::InvalidComponent(violations) => {
+                let body = Json(ViolationsResponse {
+                    status: message,
+                    violations,
+                });
+                return (StatusCode::UNPROCESSABLE_ENTITY, body).into_response();
+            }
+            LifecycleError::ConflictError(_) => StatusCode::CONFLICT,
+            LifecycleError::NotFoundError(_) => StatusCode::NOT_FOUND,
+            LifecycleError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        let body = Json(RegisterResponse { status: message });
+
+        (status, body).into_response()
+    }
+}
+
+fn unknown_component_type(component_type: &str) -> LifecycleError {
+    LifecycleError::NotFoundError(format!(
+        "No handler registered for component type: {}",
+        component_type
+    ))
+}
+
+// Dispatcher function
+pub async fn create_iidm(
+    Path(TypePath { component_type }): Path<TypePath>,
+    Workspace(state): Workspace,
+    payload: Json<CreateRequest>,
+) -> Result<Response, LifecycleError> {
+    let ecs = state.ecs.read().await;
+    let lifecycle_registry = ecs.lifecycle_registry.read().await;
+
+    let handler = lifecycle_registry
+        .get_create_handler(&component_type)
+        .ok_or_else(|| unknown_component_type(&component_type))?;
+
+    handler(state.clone(), payload).await
+}
+
+// Dispatcher function
+pub async fn delete_iidm(
+    Path(TypePath { component_type }): Path<TypePath>,
+    Workspace(state): Workspace,
+    payload: Json<DeleteRequest>,
+) -> Result<Response, LifecycleError> {
+    let ecs = state.ecs.read().await;
+    let lifecycle_registry = ecs.lifecycle_registry.read().await;
+
+    let handler = lifecycle_registry
+        .get_delete_handler(&component_type)
+        .ok_or_else(|| unknown_component_type(&component_type))?;
+
+    handler(state.clone(), payload).await
+}
@@ -0,0 +1,101 @@
+use axum::{
+    body::Bytes,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use iidm::{LoadFlowEvent, LoadFlowParameters, LoadFlowResult};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::states::Workspace;
+
+/// Optional overrides of the load flow parameters
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadFlowRequest {
+    pub max_iterations: Option<usize>,
+    pub tolerance: Option<f64>,
+    pub dc_fallback: Option<bool>,
+}
+
+impl LoadFlowRequest {
+    fn apply(self, parameters: &mut LoadFlowParameters) {
+        if let Some(max_iterations) = self.max_iterations {
+            parameters.max_iterations = max_iterations;
+        }
+        if let Some(tolerance) = self.tolerance {
+            parameters.tolerance = tolerance;
+        }
+        if let Some(dc_fallback) = self.dc_fallback {
+            parameters.dc_fallback = dc_fallback;
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LoadFlowError {
+    #[error("Failed to parse load flow parameters: {0}")]
+    InvalidParameters(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+struct LoadFlowErrorResponse {
+    status: String,
+}
+
+impl IntoResponse for LoadFlowError {
+    fn into_response(self) -> Response {
+        let body = Json(LoadFlowErrorResponse {
+            status: self.to_string(),
+        });
+        (StatusCode::BAD_REQUEST, body).into_response()
+    }
+}
+
+/// Runs a load flow on the current network, the body may override the parameters
+pub async fn run_load_flow(
+    Workspace(state): Workspace,
+    body: Bytes,
+) -> Result<Json<LoadFlowResult>, LoadFlowError> {
+    let request = if body.iter().all(u8::is_ascii_whitespace) {
+        None
+    } else {
+        Some(serde_json::from_slice::<LoadFlowRequest>(&body)?)
+    };
+
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+    let mut schedule = ecs.schedule.write().await;
+
+    if let Some(request) = request {
+        request.apply(&mut world.resource_mut::<LoadFlowParameters>());
+    }
+
+    world.send_event(LoadFlowEvent);
+    schedule.run(&mut world);
+
+    let result = world.resource::<LoadFlowResult>().clone();
+    tracing::debug!("Load flow finished with status {:?}", result.status);
+    Ok(Json(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_overrides_parameters() {
+        let request: LoadFlowRequest =
+            serde_json::from_str(r#"{"maxIterations": 5, "dcFallback": false}"#).unwrap();
+        let mut parameters = LoadFlowParameters::default();
+        request.apply(&mut parameters);
+
+        assert_eq!(parameters.max_iterations, 5);
+        assert!(!parameters.dc_fallback);
+        assert_eq!(
+            parameters.tolerance,
+            LoadFlowParameters::default().tolerance
+        );
+    }
+}
@@ -1,9 +1,31 @@
+mod export_iidm;
+mod history_iidm;
 mod index;
+mod lifecycle_iidm;
+mod load_flow;
+mod networks;
+mod query_iidm;
+mod registry_iidm;
+mod scenario_iidm;
+mod socket_iidm;
 mod stream_iidm;
+mod topology;
 mod update_iidm;
 mod upload_iidm;
+mod validation_iidm;
 
+pub use export_iidm::*;
+pub use history_iidm::*;
 pub use index::*;
+pub use lifecycle_iidm::*;
+pub use load_flow::*;
+pub use networks::*;
+pub use query_iidm::*;
+pub use registry_iidm::*;
+pub use scenario_iidm::*;
+pub use socket_iidm::*;
 pub use stream_iidm::*;
+pub use topology::*;
 pub use update_iidm::*;
 pub use upload_iidm::*;
+pub use validation_iidm::*;
@@ -0,0 +1,61 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::states::{AppState, NetworkSummary, NetworksError};
+
+/// Path of the routes taking a component type
+#[derive(Debug, Deserialize)]
+pub struct TypePath {
+    pub component_type: String,
+}
+
+/// Path of the routes taking a component type and id
+#[derive(Debug, Deserialize)]
+pub struct ComponentPath {
+    pub component_type: String,
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NetworkPath {
+    pub network_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateNetworkRequest {
+    pub id: String,
+}
+
+/// Lists the open networks
+pub async fn list_networks(State(state): State<Arc<AppState>>) -> Json<Vec<NetworkSummary>> {
+    Json(state.networks.list().await)
+}
+
+/// Opens an empty network, routed under `/api/networks/{network_id}/iidm`
+pub async fn create_network(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CreateNetworkRequest>,
+) -> Result<impl IntoResponse, NetworksError> {
+    state.networks.create(&payload.id).await?;
+    let location = format!("/api/networks/{}/iidm", payload.id);
+    Ok((
+        StatusCode::CREATED,
+        [(axum::http::header::LOCATION, location)],
+        Json(serde_json::json!({ "id": payload.id })),
+    ))
+}
+
+/// Closes a network and drops its world
+pub async fn delete_network(
+    Path(NetworkPath { network_id }): Path<NetworkPath>,
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, NetworksError> {
+    state.networks.remove(&network_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,193 @@
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use super::{ComponentPath, TypePath};
+use crate::handlers::RegisterResponse;
+use crate::states::Workspace;
+
+/// Default and maximum number of components returned by a list request
+pub const DEFAULT_LIMIT: usize = 100;
+pub const MAX_LIMIT: usize = 1000;
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ListQuery {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+    /// Comma separated top-level fields to keep, e.g. `id,name`
+    pub fields: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct GetQuery {
+    pub fields: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListResponse {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<Value>,
+}
+
+#[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
+pub enum QueryError {
+    #[error("Failed to serialize component: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Invalid query parameters: {0}")]
+    InvalidParameters(String),
+
+    #[error("{0}")]
+    NotFoundError(String),
+}
+
+impl IntoResponse for QueryError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            QueryError::SerializationError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            QueryError::InvalidParameters(_) => StatusCode::BAD_REQUEST,
+            QueryError::NotFoundError(_) => StatusCode::NOT_FOUND,
+        };
+
+        let body = Json(RegisterResponse {
+            status: self.to_string(),
+        });
+
+        (status, body).into_response()
+    }
+}
+
+impl ListQuery {
+    /// Offset and limit to apply, the limit being capped to `MAX_LIMIT`
+    pub fn page(&self) -> Result<(usize, usize), QueryError> {
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        if limit == 0 || limit > MAX_LIMIT {
+            return Err(QueryError::InvalidParameters(format!(
+                "limit must be between 1 and {}",
+                MAX_LIMIT
+            )));
+        }
+        Ok((self.offset.unwrap_or(0), limit))
+    }
+}
+
+/// Parses a `fields` parameter, `None` keeps every field
+pub fn parse_fields(fields: Option<&str>) -> Result<Option<Vec<String>>, QueryError> {
+    let Some(fields) = fields else {
+        return Ok(None);
+    };
+
+    let fields: Vec<String> = fields
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect();
+    if fields.is_empty() {
+        return Err(QueryError::InvalidParameters(
+            "fields must name at least one field".to_string(),
+        ));
+    }
+    Ok(Some(fields))
+}
+
+/// Keeps the requested top-level fields of a serialized component
+pub fn select_fields(value: Value, fields: Option<&[String]>) -> Value {
+    match (value, fields) {
+        (Value::Object(object), Some(fields)) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(key, _)| fields.contains(key))
+                .collect(),
+        ),
+        (value, _) => value,
+    }
+}
+
+fn unknown_component_type(component_type: &str) -> QueryError {
+    QueryError::NotFoundError(format!(
+        "No handler registered for component type: {}",
+        component_type
+    ))
+}
+
+// Dispatcher function
+pub async fn get_iidm(
+    Path(ComponentPath { component_type, id }): Path<ComponentPath>,
+    Workspace(state): Workspace,
+    query: Query<GetQuery>,
+) -> Result<Response, QueryError> {
+    let ecs = state.ecs.read().await;
+    let query_registry = ecs.query_registry.read().await;
+
+    let handler = query_registry
+        .get_get_handler(&component_type)
+        .ok_or_else(|| unknown_component_type(&component_type))?;
+
+    handler(state.clone(), id, query.0).await
+}
+
+// Dispatcher function
+pub async fn list_iidm(
+    Path(TypePath { component_type }): Path<TypePath>,
+    Workspace(state): Workspace,
+    query: Query<ListQuery>,
+) -> Result<Response, QueryError> {
+    let ecs = state.ecs.read().await;
+    let query_registry = ecs.query_registry.read().await;
+
+    let handler = query_registry
+        .get_list_handler(&component_type)
+        .ok_or_else(|| unknown_component_type(&component_type))?;
+
+    handler(state.clone(), query.0).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_fields() {
+        let value = json!({"id": "L1", "r": 1.0, "x": 10.0});
+        let fields = parse_fields(Some("id, x,")).unwrap();
+
+        assert_eq!(
+            select_fields(value.clone(), fields.as_deref()),
+            json!({"id": "L1", "x": 10.0})
+        );
+        assert_eq!(select_fields(value.clone(), None), value);
+        assert!(parse_fields(Some(" , ")).is_err());
+    }
+
+    #[test]
+    fn test_page_bounds() {
+        assert_eq!(ListQuery::default().page().unwrap(), (0, DEFAULT_LIMIT));
+
+        let query = ListQuery {
+            offset: Some(20),
+            limit: Some(5),
+            fields: None,
+        };
+        assert_eq!(query.page().unwrap(), (20, 5));
+
+        let query = ListQuery {
+            limit: Some(MAX_LIMIT + 1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            query.page(),
+            Err(QueryError::InvalidParameters(_))
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::Query, Json};
+use bevy_ecs::entity::Entity;
+use iidm::AssetRegistry;
+use serde::{Deserialize, Serialize};
+
+use super::{ListQuery, QueryError};
+use crate::states::Workspace;
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct IdsQuery {
+    /// Component type to keep, e.g. `Line`
+    #[serde(rename = "type")]
+    pub component_type: Option<String>,
+    /// Start of the IDs to keep
+    pub prefix: Option<String>,
+    /// IDs to keep, `*` standing for any sequence of characters and `?` for one character
+    pub pattern: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct RegisteredId {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub component_type: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IdsResponse {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<RegisteredId>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CountsResponse {
+    pub total: usize,
+    pub types: BTreeMap<String, usize>,
+}
+
+/// Lists the registered IDs sorted, with the type each one is registered for
+pub async fn list_ids(
+    Workspace(state): Workspace,
+    Query(query): Query<IdsQuery>,
+) -> Result<Json<IdsResponse>, QueryError> {
+    let (offset, limit) = ListQuery {
+        offset: query.offset,
+        limit: query.limit,
+        fields: None,
+    }
+    .page()?;
+    if query.prefix.is_some() && query.pattern.is_some() {
+        return Err(QueryError::InvalidParameters(
+            "prefix and pattern cannot be used together".to_string(),
+        ));
+    }
+
+    let ecs = state.ecs.read().await;
+    let world = ecs.world.read().await;
+    let Some(registry) = world.get_resource::<AssetRegistry>() else {
+        return Ok(Json(IdsResponse {
+            total: 0,
+            offset,
+            limit,
+            items: Vec::new(),
+        }));
+    };
+
+    let ids = registered_ids(registry, &query);
+    Ok(Json(IdsResponse {
+        total: ids.len(),
+        offset,
+        limit,
+        items: ids.into_iter().skip(offset).take(limit).collect(),
+    }))
+}
+
+/// Counts the registered elements by type
+pub async fn count_ids(Workspace(state): Workspace) -> Json<CountsResponse> {
+    let ecs = state.ecs.read().await;
+    let world = ecs.world.read().await;
+
+    Json(match world.get_resource::<AssetRegistry>() {
+        Some(registry) => CountsResponse {
+            total: registry.len(),
+            types: registry
+                .counts()
+                .into_iter()
+                .map(|(name, count)| (name.to_string(), count))
+                .collect(),
+        },
+        None => CountsResponse {
+            total: 0,
+            types: BTreeMap::new(),
+        },
+    })
+}
+
+fn registered_ids(registry: &AssetRegistry, query: &IdsQuery) -> Vec<RegisteredId> {
+    let entities: Box<dyn Iterator<Item = (&str, Entity)>> =
+        match (query.prefix.as_deref(), query.pattern.as_deref()) {
+            (_, Some(pattern)) => Box::new(registry.matching(pattern)),
+            (Some(prefix), None) => Box::new(registry.with_prefix(prefix)),
+            (None, None) => Box::new(registry.iter()),
+        };
+
+    entities
+        .filter_map(|(id, entity)| {
+            let component_type = registry.type_name_of(entity)?;
+            Some(RegisteredId {
+                id: id.to_string(),
+                component_type: component_type.to_string(),
+            })
+        })
+        .filter(|registered| {
+            query
+                .component_type
+                .as_ref()
+                .is_none_or(|component_type| &registered.component_type == component_type)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::{system::Commands, world::CommandQueue, world::World};
+    use iidm::{Generator, Load};
+
+    #[test]
+    fn test_registered_ids_filters() {
+        let world = World::new();
+        let mut registry = AssetRegistry::default();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        for id in ["GEN_1", "GEN_2", "LOAD_1"] {
+            registry.register::<Generator>(&mut commands, id).unwrap();
+        }
+        registry.register::<Load>(&mut commands, "GEN_1").unwrap();
+
+        let ids = |query: IdsQuery| -> Vec<String> {
+            registered_ids(&registry, &query)
+                .into_iter()
+                .map(|registered| format!("{}:{}", registered.component_type, registered.id))
+                .collect()
+        };
+
+        assert_eq!(
+            ids(IdsQuery {
+                prefix: Some("GEN".to_string()),
+                ..Default::default()
+            }),
+            vec!["Generator:GEN_1", "Load:GEN_1", "Generator:GEN_2"]
+        );
+        assert_eq!(
+            ids(IdsQuery {
+                pattern: Some("*_1".to_string()),
+                component_type: Some("Generator".to_string()),
+                ..Default::default()
+            }),
+            vec!["Generator:GEN_1", "Generator:LOAD_1"]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use iidm::{Scenario, ScenarioError, ScenarioReport};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::states::Workspace;
+
+#[derive(Debug, Error)]
+pub enum ScenarioIidmError {
+    #[error("{0}")]
+    Scenario(#[from] ScenarioError),
+}
+
+#[derive(Debug, Serialize)]
+struct ScenarioErrorResponse {
+    status: String,
+}
+
+impl IntoResponse for ScenarioIidmError {
+    fn into_response(self) -> Response {
+        let ScenarioIidmError::Scenario(error) = &self;
+        let status = match error {
+            ScenarioError::Parse(_)
+            | ScenarioError::InvalidEvent { .. }
+            | ScenarioError::InvalidSpeed(_)
+            | ScenarioError::InvalidStep(_) => StatusCode::BAD_REQUEST,
+            ScenarioError::UnknownElement { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ScenarioError::NotLoaded => StatusCode::NOT_FOUND,
+            ScenarioError::Finished => StatusCode::CONFLICT,
+        };
+        let body = Json(ScenarioErrorResponse {
+            status: self.to_string(),
+        });
+        (status, body).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpeedRequest {
+    /// Simulated seconds per real second
+    pub speed: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StepRequest {
+    /// Simulated seconds to advance by
+    pub seconds: f64,
+}
+
+/// Loads a scenario, paused at its start, in place of the current one
+pub async fn load_scenario_iidm(
+    Workspace(state): Workspace,
+    Json(scenario): Json<Scenario>,
+) -> Result<(StatusCode, Json<ScenarioReport>), ScenarioIidmError> {
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+
+    let report = iidm::load_scenario(&mut world, scenario)?;
+    state.scenario.stop();
+    tracing::debug!("Loaded scenario {}", report.name);
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+/// Clock and progress of the loaded scenario
+pub async fn get_scenario(
+    Workspace(state): Workspace,
+) -> Result<Json<ScenarioReport>, ScenarioIidmError> {
+    let ecs = state.ecs.read().await;
+    let world = ecs.world.read().await;
+
+    Ok(Json(iidm::scenario_report(&world)?))
+}
+
+/// Lets the scenario advance with the real time, at its speed
+pub async fn start_scenario_iidm(
+    Workspace(state): Workspace,
+) -> Result<Json<ScenarioReport>, ScenarioIidmError> {
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+
+    let report = iidm::start_scenario(&mut world)?;
+    state.scenario.start(&state);
+    Ok(Json(report))
+}
+
+pub async fn pause_scenario_iidm(
+    Workspace(state): Workspace,
+) -> Result<Json<ScenarioReport>, ScenarioIidmError> {
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+
+    let report = iidm::pause_scenario(&mut world)?;
+    state.scenario.stop();
+    Ok(Json(report))
+}
+
+/// Accelerates or slows down the scenario clock
+pub async fn set_scenario_speed_iidm(
+    Workspace(state): Workspace,
+    Json(request): Json<SpeedRequest>,
+) -> Result<Json<ScenarioReport>, ScenarioIidmError> {
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+
+    Ok(Json(iidm::set_scenario_speed(&mut world, request.speed)?))
+}
+
+/// Advances the scenario by a number of simulated seconds, whether it runs or not
+pub async fn step_scenario_iidm(
+    Workspace(state): Workspace,
+    Json(request): Json<StepRequest>,
+) -> Result<Json<ScenarioReport>, ScenarioIidmError> {
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+    let mut schedule = ecs.schedule.write().await;
+
+    let report = iidm::step_scenario(&mut world, request.seconds)?;
+    schedule.run(&mut world);
+    Ok(Json(report))
+}
@@ -0,0 +1,326 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::IntoResponse,
+};
+use iidm::Violation;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use super::{BatchItem, BatchRequest, UpdateError};
+use crate::states::{Change, ChangeFilter, ChangeSet, NetworkState, Workspace};
+
+/// Message sent by a client, answered with the same `requestId` when one is given
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketRequest {
+    pub request_id: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub command: SocketCommand,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "camelCase")]
+pub enum SocketCommand {
+    /// Same fields as an update of a batch
+    Update(BatchItem),
+    Batch(BatchRequest),
+    /// Replaces the filter of the changes pushed on the connection
+    Subscribe(Subscription),
+    Unsubscribe,
+}
+
+/// Changes to push, with the filters of the network stream given as lists
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Subscription {
+    pub types: Vec<String>,
+    pub ids: Vec<String>,
+    pub voltage_levels: Vec<String>,
+    pub substations: Vec<String>,
+}
+
+impl From<Subscription> for ChangeFilter {
+    fn from(subscription: Subscription) -> Self {
+        ChangeFilter {
+            types: subscription.types.into_iter().collect(),
+            ids: subscription.ids.into_iter().collect(),
+            voltage_levels: subscription.voltage_levels.into_iter().collect(),
+            substations: subscription.substations.into_iter().collect(),
+        }
+    }
+}
+
+/// Message pushed to a client
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum SocketEvent {
+    #[serde(rename_all = "camelCase")]
+    Updated {
+        request_id: Option<serde_json::Value>,
+        updated: usize,
+    },
+    /// Components matching a new subscription, as they are when it starts
+    #[serde(rename_all = "camelCase")]
+    Snapshot {
+        request_id: Option<serde_json::Value>,
+        components: Vec<Change>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Unsubscribed {
+        request_id: Option<serde_json::Value>,
+    },
+    Changes(ChangeSet),
+    /// Change sets missed by a slow client
+    Lagged {
+        missed: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Error {
+        request_id: Option<serde_json::Value>,
+        status: u16,
+        message: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        violations: Vec<Violation>,
+    },
+}
+
+impl SocketEvent {
+    fn error(request_id: Option<serde_json::Value>, error: UpdateError) -> Self {
+        SocketEvent::Error {
+            request_id,
+            status: error.status().as_u16(),
+            violations: error.violations().to_vec(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Commands and pushed changes of one connection
+pub struct SocketSession {
+    state: Arc<NetworkState>,
+    /// Nothing is pushed before the first subscription
+    filter: Option<ChangeFilter>,
+}
+
+impl SocketSession {
+    pub fn new(state: Arc<NetworkState>) -> Self {
+        Self {
+            state,
+            filter: None,
+        }
+    }
+
+    /// Runs a command received as text, an unreadable one being answered with an error
+    pub async fn handle(&mut self, text: &str) -> SocketEvent {
+        match serde_json::from_str::<SocketRequest>(text) {
+            Ok(SocketRequest {
+                request_id,
+                command,
+            }) => self.run(request_id, command).await,
+            Err(error) => SocketEvent::error(None, error.into()),
+        }
+    }
+
+    async fn run(
+        &mut self,
+        request_id: Option<serde_json::Value>,
+        command: SocketCommand,
+    ) -> SocketEvent {
+        let result = match command {
+            SocketCommand::Update(item) => self.update(item).await.map(|_| 1),
+            SocketCommand::Batch(request) => self.update_batch(request).await,
+            SocketCommand::Subscribe(subscription) => {
+                let filter = ChangeFilter::from(subscription);
+                let components = {
+                    let ecs = self.state.ecs.read().await;
+                    let world = ecs.world.read().await;
+                    let update_registry = ecs.update_registry.read().await;
+                    update_registry.snapshot(&world, &filter)
+                };
+                self.filter = Some(filter);
+                return SocketEvent::Snapshot {
+                    request_id,
+                    components,
+                };
+            }
+            SocketCommand::Unsubscribe => {
+                self.filter = None;
+                return SocketEvent::Unsubscribed { request_id };
+            }
+        };
+        match result {
+            Ok(updated) => SocketEvent::Updated {
+                request_id,
+                updated,
+            },
+            Err(error) => SocketEvent::error(request_id, error),
+        }
+    }
+
+    async fn update(&self, item: BatchItem) -> Result<(), UpdateError> {
+        let component_type = item.component_type.clone();
+        let id = item.id.clone();
+        let body = item.into_update()?.body;
+
+        let ecs = self.state.ecs.read().await;
+        let update_registry = ecs.update_registry.read().await;
+        let handler = update_registry
+            .get_handler(&component_type)
+            .ok_or_else(|| {
+                UpdateError::NotFoundError(format!(
+                    "No handler registered for component type: {}",
+                    component_type
+                ))
+            })?;
+        handler(self.state.clone(), id, body).await.map(|_| ())
+    }
+
+    async fn update_batch(&self, request: BatchRequest) -> Result<usize, UpdateError> {
+        let updates = request.into_updates()?;
+        let ecs = self.state.ecs.read().await;
+        let update_registry = ecs.update_registry.read().await;
+        update_registry.update_batch(&self.state, updates).await
+    }
+
+    /// Part of a change set to push, none when not subscribed or when nothing matches
+    pub fn changes(&self, change_set: &ChangeSet) -> Option<SocketEvent> {
+        self.filter
+            .as_ref()?
+            .apply(change_set)
+            .map(SocketEvent::Changes)
+    }
+}
+
+/// Accepts update, batch and subscribe commands as JSON messages, and pushes back their results
+/// and the changes subscribed to on the same connection
+pub async fn socket_iidm(ws: WebSocketUpgrade, Workspace(state): Workspace) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| serve_socket(socket, state))
+}
+
+async fn serve_socket(mut socket: WebSocket, state: Arc<NetworkState>) {
+    let mut changes = state
+        .ecs
+        .read()
+        .await
+        .sse_registry
+        .read()
+        .await
+        .subscribe_changes();
+    let mut session = SocketSession::new(state);
+
+    loop {
+        let event = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => session.handle(text.as_str()).await,
+                Some(Ok(Message::Binary(_))) => SocketEvent::error(
+                    None,
+                    UpdateError::ValidationError("commands are sent as text messages".to_string()),
+                ),
+                // Pings are answered by axum
+                Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+            },
+            change_set = changes.recv() => match change_set {
+                Ok(change_set) => match session.changes(&change_set) {
+                    Some(event) => event,
+                    None => continue,
+                },
+                Err(RecvError::Lagged(missed)) => SocketEvent::Lagged { missed },
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        let Ok(text) = serde_json::to_string(&event) else {
+            continue;
+        };
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            break;
+        }
+    }
+    tracing::debug!("WebSocket connection closed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn loaded_session() -> SocketSession {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../iidm/tests/data/network.json");
+        let network: iidm::Network = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        let state = Arc::new(NetworkState::default());
+        {
+            let ecs = state.ecs.read().await;
+            let mut world = ecs.world.write().await;
+            let mut schedule = ecs.schedule.write().await;
+            iidm::Identifiable::register(&network, &mut world, &mut schedule);
+        }
+        SocketSession::new(state)
+    }
+
+    async fn handle(session: &mut SocketSession, command: serde_json::Value) -> serde_json::Value {
+        serde_json::to_value(session.handle(&command.to_string()).await).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_commands_answered() {
+        let mut session = loaded_session().await;
+
+        let reply = handle(
+            &mut session,
+            json!({"command": "update", "requestId": 1, "type": "Line", "id": "NHV1_NHV2_1", "component": {"r": 4.0}}),
+        )
+        .await;
+        assert_eq!(
+            reply,
+            json!({"event": "updated", "requestId": 1, "updated": 1})
+        );
+
+        let reply = handle(
+            &mut session,
+            json!({"command": "batch", "updates": [
+                {"type": "Generator", "id": "GEN", "component": {"targetP": 20000.0}}
+            ]}),
+        )
+        .await;
+        assert_eq!(reply["event"], "error");
+        assert_eq!(reply["status"], 422);
+        assert!(!reply["violations"].as_array().unwrap().is_empty());
+
+        let reply = handle(&mut session, json!({"command": "restart"})).await;
+        assert_eq!(reply["status"], 400);
+    }
+
+    #[tokio::test]
+    async fn test_subscription_filters_changes() {
+        let mut session = loaded_session().await;
+        let change = |component_type: &str, id: &str| Change {
+            component_type: component_type.to_string(),
+            id: id.to_string(),
+            component: json!({}),
+            location: Default::default(),
+        };
+        let change_set = ChangeSet {
+            changes: vec![change("Line", "NHV1_NHV2_1"), change("Generator", "GEN")],
+            ..Default::default()
+        };
+        assert!(session.changes(&change_set).is_none());
+
+        let reply = handle(
+            &mut session,
+            json!({"command": "subscribe", "requestId": "s", "types": ["Generator"]}),
+        )
+        .await;
+        assert_eq!(reply["event"], "snapshot");
+        assert_eq!(reply["components"][0]["id"], "GEN");
+
+        let Some(SocketEvent::Changes(pushed)) = session.changes(&change_set) else {
+            panic!("changes of the subscribed type are pushed");
+        };
+        assert_eq!(pushed.changes.len(), 1);
+        assert_eq!(pushed.changes[0].id, "GEN");
+    }
+}
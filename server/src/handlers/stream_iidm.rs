@@ -1,20 +1,23 @@
-use std::{convert::Infallible, sync::Arc};
+use std::collections::BTreeSet;
+use std::convert::Infallible;
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query},
     response::{sse::Event, IntoResponse, Sse},
 };
 use futures::StreamExt;
+use serde::{Deserialize, Serialize};
 use tokio_stream::wrappers::BroadcastStream;
 
-use crate::states::AppState;
+use super::ComponentPath;
+use crate::states::{Change, ChangeFilter, Workspace};
 
 pub async fn stream_iidm(
-    Path((component_type, id)): Path<(String, String)>,
-    State(state): State<Arc<AppState>>,
+    Path(ComponentPath { component_type, id }): Path<ComponentPath>,
+    Workspace(state): Workspace,
 ) -> impl IntoResponse {
     let ecs = state.ecs.read().await;
-    let mut sse_registry = ecs.sse_registry.write().await;
+    let sse_registry = ecs.sse_registry.read().await;
 
     // Obtenir un canal d'abonnement pour ce composant
     let tx = { sse_registry.get_or_create_channel(&component_type, &id) };
@@ -44,3 +47,103 @@ pub async fn stream_iidm(
             .text("keep-alive"),
     )
 }
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamQuery {
+    /// Component types to keep, separated by commas, e.g. `Line,Generator`
+    pub types: Option<String>,
+    /// IDs of the components to keep, separated by commas
+    pub ids: Option<String>,
+    /// Voltage levels the components are in or connected to, separated by commas
+    pub voltage_levels: Option<String>,
+    /// Substations the components are in or connected to, separated by commas
+    pub substations: Option<String>,
+}
+
+impl StreamQuery {
+    pub fn filter(&self) -> ChangeFilter {
+        let list = |values: &Option<String>| -> BTreeSet<String> {
+            values
+                .iter()
+                .flat_map(|values| values.split(','))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        ChangeFilter {
+            types: list(&self.types),
+            ids: list(&self.ids),
+            voltage_levels: list(&self.voltage_levels),
+            substations: list(&self.substations),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Snapshot<'a> {
+    components: &'a [Change],
+}
+
+/// Streams the changes of the whole network matching the query, a change set being sent as a
+/// single event. The components matching when the client connects are sent first.
+pub async fn stream_network_iidm(
+    Workspace(state): Workspace,
+    Query(query): Query<StreamQuery>,
+) -> impl IntoResponse {
+    let filter = query.filter();
+    let ecs = state.ecs.read().await;
+    // Subscribed before the snapshot is read, a change may be sent twice but none is missed
+    let rx = ecs.sse_registry.read().await.subscribe_changes();
+    let snapshot = {
+        let world = ecs.world.read().await;
+        ecs.update_registry.read().await.snapshot(&world, &filter)
+    };
+    let snapshot = serde_json::to_string(&Snapshot {
+        components: &snapshot,
+    })
+    .unwrap_or_default();
+
+    let initial = futures::stream::once(async move {
+        Ok::<_, Infallible>(Event::default().event("snapshot").data(snapshot))
+    });
+    let changes = BroadcastStream::new(rx).filter_map(move |msg| {
+        let event = match msg {
+            Ok(change_set) => filter
+                .apply(&change_set)
+                .and_then(|changes| serde_json::to_string(&changes).ok())
+                .map(|data| Event::default().event("changes").data(data)),
+            // Change sets missed by a slow client
+            Err(_) => Some(Event::default().comment("lagged")),
+        };
+        futures::future::ready(event.map(Ok::<_, Infallible>))
+    });
+
+    Sse::new(initial.chain(changes)).keep_alive(
+        axum::response::sse::KeepAlive::new()
+            .interval(std::time::Duration::from_secs(15))
+            .text("keep-alive"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_query_filter() {
+        let filter = StreamQuery {
+            types: Some("Line, Generator".to_string()),
+            voltage_levels: Some("VLHV1,".to_string()),
+            ..Default::default()
+        }
+        .filter();
+        assert_eq!(
+            filter.types,
+            ["Generator".to_string(), "Line".to_string()].into()
+        );
+        assert_eq!(filter.voltage_levels, ["VLHV1".to_string()].into());
+        assert!(filter.ids.is_empty() && filter.substations.is_empty());
+    }
+}
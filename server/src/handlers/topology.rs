@@ -0,0 +1,52 @@
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use iidm::{BusView, NetworkTopology};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::states::Workspace;
+
+#[derive(Debug, Error)]
+pub enum TopologyError {
+    #[error("No bus view for voltage level: {0}")]
+    NotFound(String),
+}
+
+#[derive(Debug, Serialize)]
+struct TopologyErrorResponse {
+    status: String,
+}
+
+impl IntoResponse for TopologyError {
+    fn into_response(self) -> Response {
+        let body = Json(TopologyErrorResponse {
+            status: self.to_string(),
+        });
+        (StatusCode::NOT_FOUND, body).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VoltageLevelPath {
+    pub voltage_level_id: String,
+}
+
+/// Returns the calculated buses of a voltage level and the islands they belong to
+pub async fn get_bus_view(
+    Path(VoltageLevelPath { voltage_level_id }): Path<VoltageLevelPath>,
+    Workspace(state): Workspace,
+) -> Result<Json<BusView>, TopologyError> {
+    let ecs = state.ecs.read().await;
+    let world = ecs.world.read().await;
+
+    world
+        .get_resource::<NetworkTopology>()
+        .and_then(|topology| topology.bus_view(&voltage_level_id))
+        .cloned()
+        .map(Json)
+        .ok_or(TopologyError::NotFound(voltage_level_id))
+}
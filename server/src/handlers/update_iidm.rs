@@ -1,15 +1,15 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::Path,
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use iidm::{Patch, Violation};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
 use thiserror::Error;
-use tracing::error;
 
-use crate::states::AppState;
+use super::{TypePath, ViolationsResponse};
+use crate::states::Workspace;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RegisterRequest {
@@ -23,6 +23,7 @@ pub struct RegisterResponse {
 }
 
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum UpdateError {
     #[error("Failed to parse JSON: {0}")]
     SerializationError(#[from] serde_json::Error),
@@ -33,33 +34,190 @@ pub enum UpdateError {
     #[error("{0}")]
     NotFoundError(String),
 
+    #[error("Patch cannot be applied: {0}")]
+    PatchError(String),
+
+    #[error("Update breaks {} network invariants", .0.len())]
+    InvalidComponent(Vec<Violation>),
+
+    #[error("Unknown references: {0}")]
+    ReferenceError(String),
+
+    #[error("Unsupported content type: {0}")]
+    UnsupportedMediaType(String),
+
+    #[error("Update {index} of the batch is refused, nothing was applied: {source}")]
+    BatchItem {
+        index: usize,
+        source: Box<UpdateError>,
+    },
+
     #[error("Internal server error: {0}")]
     InternalError(String),
 }
 
-impl IntoResponse for UpdateError {
-    fn into_response(self) -> Response {
-        let status = match self {
+/// Content of the `component` field of an update request, chosen by its content type
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateBody {
+    /// Generated updater, whose fields replace the component ones (`application/json`)
+    Updater(serde_json::Value),
+    /// `application/merge-patch+json` or `application/json-patch+json`
+    Patch(Patch),
+}
+
+impl UpdateBody {
+    pub fn from_request(
+        headers: &HeaderMap,
+        component: serde_json::Value,
+    ) -> Result<Self, UpdateError> {
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/json");
+        Self::from_content_type(content_type, component)
+    }
+
+    pub fn from_content_type(
+        content_type: &str,
+        component: serde_json::Value,
+    ) -> Result<Self, UpdateError> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        match mime.as_str() {
+            "application/json" => Ok(UpdateBody::Updater(component)),
+            "application/merge-patch+json" => Ok(UpdateBody::Patch(Patch::Merge(component))),
+            "application/json-patch+json" => Ok(UpdateBody::Patch(Patch::Json(
+                serde_json::from_value(component)?,
+            ))),
+            _ => Err(UpdateError::UnsupportedMediaType(content_type.to_string())),
+        }
+    }
+}
+
+/// One update of a batch, whose `component` is read as with the content type given, the
+/// generated updater by default
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItem {
+    #[serde(rename = "type")]
+    pub component_type: String,
+    pub id: String,
+    pub component: serde_json::Value,
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    pub updates: Vec<BatchItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchResponse {
+    pub status: String,
+    pub updated: usize,
+}
+
+impl BatchRequest {
+    /// Reads the body of each update, the first one unreadable refusing the batch
+    pub fn into_updates(self) -> Result<Vec<BatchUpdate>, UpdateError> {
+        self.updates
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                item.into_update().map_err(|source| UpdateError::BatchItem {
+                    index,
+                    source: Box::new(source),
+                })
+            })
+            .collect()
+    }
+}
+
+impl BatchItem {
+    pub fn into_update(self) -> Result<BatchUpdate, UpdateError> {
+        let content_type = self.content_type.as_deref().unwrap_or("application/json");
+        Ok(BatchUpdate {
+            body: UpdateBody::from_content_type(content_type, self.component)?,
+            component_type: self.component_type,
+            id: self.id,
+        })
+    }
+}
+
+/// Update of a batch, ready for the handlers of its type
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchUpdate {
+    pub component_type: String,
+    pub id: String,
+    pub body: UpdateBody,
+}
+
+impl UpdateError {
+    /// Error giving the status, the one of the refused update for a batch
+    fn cause(&self) -> &UpdateError {
+        match self {
+            UpdateError::BatchItem { source, .. } => source.cause(),
+            error => error,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self.cause() {
             UpdateError::SerializationError(_) | UpdateError::ValidationError(_) => {
                 StatusCode::BAD_REQUEST
             }
             UpdateError::NotFoundError(_) => StatusCode::NOT_FOUND,
-            UpdateError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+            UpdateError::PatchError(_)
+            | UpdateError::ReferenceError(_)
+            | UpdateError::InvalidComponent(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            UpdateError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            UpdateError::InternalError(_) | UpdateError::BatchItem { .. } => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
 
-        let body = Json(RegisterResponse {
-            status: self.to_string(),
-        });
+    /// Network invariants broken by the refused update
+    pub fn violations(&self) -> &[Violation] {
+        match self.cause() {
+            UpdateError::InvalidComponent(violations) => violations,
+            _ => &[],
+        }
+    }
+}
+
+impl IntoResponse for UpdateError {
+    fn into_response(self) -> Response {
+        // The update refused in a batch gives the status, the message names it
+        let status = self.status();
+        let message = self.to_string();
+        if let UpdateError::InvalidComponent(violations) = self.cause() {
+            let body = Json(ViolationsResponse {
+                status: message,
+                violations: violations.clone(),
+            });
+            return (status, body).into_response();
+        }
+
+        let body = Json(RegisterResponse { status: message });
 
         (status, body).into_response()
     }
 }
 // Dispatcher function
 pub async fn update_iidm(
-    Path(component_type): Path<String>,
-    state: State<Arc<AppState>>,
-    payload: Json<RegisterRequest>,
+    Path(TypePath { component_type }): Path<TypePath>,
+    Workspace(state): Workspace,
+    headers: HeaderMap,
+    Json(payload): Json<RegisterRequest>,
 ) -> Result<Response, UpdateError> {
+    let body = UpdateBody::from_request(&headers, payload.component)?;
+
     let ecs = state.ecs.read().await;
     let update_registry = ecs.update_registry.read().await;
 
@@ -74,5 +232,68 @@ pub async fn update_iidm(
         })?;
 
     // Call the handler with the original state and payload
-    handler(state.clone(), payload).await
+    handler(state.clone(), payload.id, body).await
+}
+
+/// Applies several updates, of any types, as a whole or not at all
+pub async fn update_batch_iidm(
+    Workspace(state): Workspace,
+    Json(request): Json<BatchRequest>,
+) -> Result<Json<BatchResponse>, UpdateError> {
+    let updates = request.into_updates()?;
+
+    let ecs = state.ecs.read().await;
+    let update_registry = ecs.update_registry.read().await;
+    let updated = update_registry.update_batch(&state, updates).await?;
+
+    Ok(Json(BatchResponse {
+        status: "Components updated successfully".to_string(),
+        updated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn headers(content_type: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_body_from_content_type() {
+        let component = json!({"r": 1.0});
+
+        assert_eq!(
+            UpdateBody::from_request(&HeaderMap::new(), component.clone()).unwrap(),
+            UpdateBody::Updater(component.clone())
+        );
+        assert_eq!(
+            UpdateBody::from_request(&headers("application/merge-patch+json"), component.clone())
+                .unwrap(),
+            UpdateBody::Patch(Patch::Merge(component.clone()))
+        );
+        assert!(matches!(
+            UpdateBody::from_request(
+                &headers("application/json-patch+json; charset=utf-8"),
+                json!([{"op": "remove", "path": "/currentLimits1"}])
+            ),
+            Ok(UpdateBody::Patch(Patch::Json(operations))) if operations.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_invalid_body() {
+        assert!(matches!(
+            UpdateBody::from_request(&headers("application/json-patch+json"), json!({"r": 1.0})),
+            Err(UpdateError::SerializationError(_))
+        ));
+        assert!(matches!(
+            UpdateBody::from_request(&headers("text/plain"), json!({})),
+            Err(UpdateError::UnsupportedMediaType(_))
+        ));
+    }
 }
@@ -1,13 +1,33 @@
-use crate::states::AppState;
+use crate::states::{NetworkState, StagedWorld, UploadReport, Workspace};
 use askama::Template;
 use axum::{
-    extract::{Multipart, State},
-    http::StatusCode,
-    response::{Html, IntoResponse, Response},
+    body::Bytes,
+    extract::{multipart::Field, Multipart},
+    http::{header::CONTENT_LENGTH, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive},
+        Html, IntoResponse, Response, Sse,
+    },
 };
+use bevy_ecs::{
+    event::{EventCursor, Events},
+    world::World,
+};
+use futures::StreamExt;
+use iidm::libs::json_stream::{read_network, Registration};
 use iidm::*;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::io::{self, Read};
+use std::rc::Rc;
 use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::BroadcastStream;
+
+/// Chunks of a JSON upload waiting to be registered
+const PENDING_CHUNKS: usize = 16;
 
 #[derive(Error, Debug)]
 pub enum UploadError {
@@ -15,10 +35,37 @@ pub enum UploadError {
     MultipartError(#[from] axum::extract::multipart::MultipartError),
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] NetworkError),
+    #[error("XIIDM parsing error: {0}")]
+    XmlError(#[from] libs::xiidm::XiidmError),
+    #[error("CGMES parsing error: {0}")]
+    CgmesError(#[from] libs::cgmes::CgmesError),
+    #[error("UCTE parsing error: {0}")]
+    UcteError(#[from] libs::ucte::UcteError),
+    #[error("MATPOWER parsing error: {0}")]
+    MatpowerError(#[from] libs::matpower::MatpowerError),
+    #[error("PSS/E parsing error: {0}")]
+    PsseError(#[from] libs::psse::PsseError),
     #[error("Template rendering error: {0}")]
     TemplateError(#[from] askama::Error),
+    #[error("JSON registration failed: {0}")]
+    RegistrationError(#[from] tokio::task::JoinError),
     #[error("No IIDM file provided")]
     NoFile,
+    #[error("IDs already taken by another type: {}", .0.join(", "))]
+    Conflict(Vec<String>),
+    #[error("Network is not valid at level {level}: {}", join(violations))]
+    Invalid {
+        level: ValidationLevel,
+        violations: Vec<Violation>,
+    },
+}
+
+fn join(violations: &[Violation]) -> String {
+    violations
+        .iter()
+        .map(Violation::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 // Implement IntoResponse for our error type
@@ -34,56 +81,454 @@ impl IntoResponse for UploadError {
 struct IIdmTableTemplate {
     message: String,
     network: Option<Network>,
+    /// Elements registered by type, for a network registered as it was read
+    counts: BTreeMap<&'static str, usize>,
 }
 
 impl IIdmTableTemplate {
     fn new(message: String, network: Option<Network>) -> Self {
-        Self { message, network }
+        Self {
+            message,
+            network,
+            counts: BTreeMap::new(),
+        }
     }
 }
 
+/// Network registered from an upload
+struct Uploaded {
+    network: Network,
+    /// Elements registered by type, empty when the whole network was parsed at once
+    counts: BTreeMap<&'static str, usize>,
+    bytes_read: u64,
+}
+
 pub async fn upload_iidm(
-    State(state): State<Arc<AppState>>,
+    Workspace(state): Workspace,
+    headers: HeaderMap,
     mut multipart: Multipart,
 ) -> Result<impl IntoResponse, UploadError> {
-    let result = process_upload(&mut multipart).await;
-
-    match result {
-        Ok(network) => {
-            update_ecs_state(&state, &network).await;
-            let template = IIdmTableTemplate::new("".to_string(), Some(network));
-            let html = template.render().map_err(UploadError::TemplateError)?;
-            Ok(Html(html))
+    let total_bytes = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse().ok());
+    let template = match process_upload(&state, total_bytes, &mut multipart).await {
+        Ok(Uploaded {
+            network, counts, ..
+        }) => IIdmTableTemplate {
+            counts,
+            ..IIdmTableTemplate::new("".to_string(), Some(network))
+        },
+        Err(err) => IIdmTableTemplate::new(err.to_string(), None),
+    };
+    let html = template.render().map_err(UploadError::TemplateError)?;
+    Ok(Html(html))
+}
+
+/// Progress of the uploads into the network, as `progress` events
+pub async fn upload_progress(Workspace(state): Workspace) -> impl IntoResponse {
+    let stream =
+        BroadcastStream::new(state.uploads.subscribe()).filter_map(|progress| async move {
+            // A lagging subscriber only misses intermediate reports
+            let progress = progress.ok()?;
+            let event = Event::default()
+                .event("progress")
+                .json_data(&*progress)
+                .ok()?;
+            Some(Ok::<_, Infallible>(event))
+        });
+
+    Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(std::time::Duration::from_secs(15))
+            .text("keep-alive"),
+    )
+}
+
+/// Serialization formats accepted by the upload endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UploadFormat {
+    Json,
+    Xiidm,
+    /// RDF/XML profile, or zip archive of profiles
+    Cgmes,
+    /// UCTE-DEF text, `.uct` files
+    Ucte,
+    /// MATPOWER case, `.m` files
+    Matpower,
+    /// PSS/E RAW case, `.raw` files
+    Psse,
+}
+
+impl UploadFormat {
+    /// Picks the format from the file extension, falling back to content sniffing
+    fn detect(file_name: Option<&str>, bytes: &[u8]) -> Self {
+        let extension = file_name
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("json") => Self::Json,
+            Some("xiidm") => Self::Xiidm,
+            Some("xml") => Self::sniff_xml(bytes),
+            Some("zip") => Self::Cgmes,
+            Some("uct" | "ucte") => Self::Ucte,
+            Some("m") => Self::Matpower,
+            Some("raw") => Self::Psse,
+            _ => Self::sniff(bytes),
+        }
+    }
+
+    fn sniff(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"PK\x03\x04") {
+            return Self::Cgmes;
+        }
+        let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+        let start = bytes.iter().position(|byte| !byte.is_ascii_whitespace());
+        let content = &bytes[start.unwrap_or(bytes.len())..];
+        // UCTE-DEF documents open with comments or the node block
+        if content.starts_with(b"##C") || content.starts_with(b"##N") {
+            return Self::Ucte;
+        }
+        // MATPOWER cases are MATLAB functions, possibly after a comment
+        if content.starts_with(b"function") || content.starts_with(b"%") {
+            return Self::Matpower;
+        }
+        match content.first() {
+            Some(b'<') => Self::sniff_xml(bytes),
+            _ => Self::Json,
+        }
+    }
+
+    /// CGMES profiles are RDF documents, `<rdf:RDF ...>`
+    fn sniff_xml(bytes: &[u8]) -> Self {
+        let mut tags = bytes.split(|byte| *byte == b'<').skip(1);
+        let root = tags.find(|tag| tag.first().is_some_and(u8::is_ascii_alphabetic));
+        let name = root
+            .map(|tag| tag.split(|byte| byte.is_ascii_whitespace() || b"/>".contains(byte)))
+            .and_then(|mut parts| parts.next())
+            .unwrap_or_default();
+        let local_name = name.rsplit(|byte| *byte == b':').next().unwrap_or_default();
+        if local_name == b"RDF" {
+            Self::Cgmes
+        } else {
+            Self::Xiidm
         }
-        Err(err) => {
-            let error_message = err.to_string();
-            let template = IIdmTableTemplate::new(error_message, None);
-            let html = template.render().map_err(UploadError::TemplateError)?;
-            Ok(Html(html))
+    }
+
+    fn parse(self, bytes: &[u8]) -> Result<Network, UploadError> {
+        match self {
+            Self::Json => serde_json::from_slice(bytes)
+                .map_err(NetworkError::Deserialization)
+                .map_err(UploadError::JsonError),
+            Self::Xiidm => libs::xiidm::from_reader(bytes).map_err(UploadError::XmlError),
+            Self::Cgmes => {
+                let import = if bytes.starts_with(b"PK\x03\x04") {
+                    libs::cgmes::from_zip(std::io::Cursor::new(bytes))?
+                } else {
+                    libs::cgmes::from_profiles([bytes])?
+                };
+                tracing::info!(
+                    "CGMES import left out {:?}, skipped {:?}",
+                    import.report.unsupported_classes,
+                    import.report.skipped
+                );
+                Ok(import.network)
+            }
+            Self::Ucte => Ok(libs::ucte::from_reader(bytes)?),
+            Self::Matpower => Ok(libs::matpower::from_reader(bytes)?),
+            Self::Psse => Ok(libs::psse::from_reader(bytes)?),
         }
     }
 }
 
-async fn process_upload(multipart: &mut Multipart) -> Result<Network, UploadError> {
-    while let Some(field) = multipart
+async fn process_upload(
+    state: &Arc<NetworkState>,
+    total_bytes: Option<u64>,
+    multipart: &mut Multipart,
+) -> Result<Uploaded, UploadError> {
+    while let Some(mut field) = multipart
         .next_field()
         .await
         .map_err(UploadError::MultipartError)?
     {
         if field.name() == Some("iidm_file") {
-            let bytes = field.bytes().await.map_err(UploadError::MultipartError)?;
-            return serde_json::from_slice(&bytes)
-                .map_err(|e| NetworkError::Deserialization(e))
-                .map_err(UploadError::JsonError);
+            let mut report = state.uploads.start(total_bytes);
+            let file_name = field.file_name().map(str::to_string);
+            let first = field.chunk().await?.unwrap_or_default();
+            let format = UploadFormat::detect(file_name.as_deref(), &first);
+            tracing::debug!("Parsing uploaded IIDM file as {:?}", format);
+            if format == UploadFormat::Json {
+                return stream_json(state, first, field, report).await;
+            }
+
+            let mut bytes = first.to_vec();
+            while let Some(chunk) = field.chunk().await? {
+                bytes.extend_from_slice(&chunk);
+                report.update(bytes.len() as u64, &BTreeMap::new());
+            }
+            let network = load_network(state, format.parse(&bytes)?).await?;
+            report.registered(bytes.len() as u64, &BTreeMap::new());
+            return Ok(Uploaded {
+                network,
+                counts: BTreeMap::new(),
+                bytes_read: bytes.len() as u64,
+            });
         }
     }
     Err(UploadError::NoFile)
 }
 
-async fn update_ecs_state(state: &Arc<AppState>, network: &Network) {
+/// Hands the chunks of a JSON file over to a blocking thread registering the network as it is
+/// read, so that neither the file nor the whole network is held in memory. The network is read
+/// into a staged world, which replaces the live one once the whole file is accepted.
+async fn stream_json(
+    state: &Arc<NetworkState>,
+    first: Bytes,
+    mut field: Field<'_>,
+    report: UploadReport,
+) -> Result<Uploaded, UploadError> {
+    let (sender, chunks) = mpsc::channel(PENDING_CHUNKS);
+    let staged = state.ecs.read().await.stage().await;
+    let registration = tokio::task::spawn_blocking(move || register_json(staged, chunks, report));
+
+    let mut chunk = Some(first);
+    let mut multipart_error = None;
+    while let Some(bytes) = chunk.take() {
+        // The registration stops early on a malformed or invalid file
+        if sender.send(Ok(bytes)).await.is_err() {
+            break;
+        }
+        match field.chunk().await {
+            Ok(next) => chunk = next,
+            Err(error) => {
+                let _ = sender.send(Err(io::Error::other(error.to_string()))).await;
+                multipart_error = Some(error);
+            }
+        }
+    }
+    drop(sender);
+
+    // The staged world is dropped along with a failed registration, the live one is untouched
+    let (staged, uploaded) = registration.await?;
+    if let Some(error) = multipart_error {
+        return Err(UploadError::MultipartError(error));
+    }
+    let (uploaded, report) = uploaded?;
+    state.ecs.read().await.replace(staged).await;
+    report.registered(uploaded.bytes_read, &uploaded.counts);
+    Ok(uploaded)
+}
+
+/// Registers a JSON network element by element into a staged world as its chunks arrive. A file
+/// which turns out to be malformed or invalid leaves the live world untouched.
+fn register_json(
+    mut staged: StagedWorld,
+    chunks: mpsc::Receiver<io::Result<Bytes>>,
+    mut report: UploadReport,
+) -> (StagedWorld, Result<(Uploaded, UploadReport), UploadError>) {
+    let bytes_read = Rc::new(Cell::new(0));
+    let reader = ChunkReader {
+        chunks,
+        current: Bytes::new(),
+        bytes_read: bytes_read.clone(),
+    };
+    let StagedWorld { world, schedule } = &mut staged;
+    let mut registration = Registration::default();
+    let mut validator = NetworkValidator::default();
+    let mut errors = error_cursor(world);
+
+    let result = read_network(reader, |element| {
+        validator.element(&element);
+        registration.register(world, schedule, &element);
+        report.update(bytes_read.get(), registration.counts());
+    })
+    .map_err(|error| UploadError::JsonError(NetworkError::Deserialization(error)))
+    .and_then(|network| {
+        let taken = taken_ids(world, &mut errors);
+        if !taken.is_empty() {
+            return Err(UploadError::Conflict(taken));
+        }
+        validator.network(&network);
+        let level = network.minimum_validation_level;
+        let violations = validator.finish(level);
+        if violations.is_empty() {
+            Ok(network)
+        } else {
+            Err(UploadError::Invalid { level, violations })
+        }
+    })
+    .map(|network| {
+        let counts = registration.counts().clone();
+        registration.finish(world, schedule, &network);
+        let uploaded = Uploaded {
+            network,
+            counts,
+            bytes_read: bytes_read.get(),
+        };
+        (uploaded, report)
+    });
+    (staged, result)
+}
+
+/// Reads the chunks of an upload from a blocking thread, waiting for the next ones to arrive
+struct ChunkReader {
+    chunks: mpsc::Receiver<io::Result<Bytes>>,
+    current: Bytes,
+    bytes_read: Rc<Cell<u64>>,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.current.is_empty() {
+            match self.chunks.blocking_recv() {
+                Some(chunk) => self.current = chunk?,
+                None => return Ok(0),
+            }
+        }
+        let read = self.current.split_to(buf.len().min(self.current.len()));
+        buf[..read.len()].copy_from_slice(&read);
+        self.bytes_read
+            .set(self.bytes_read.get() + read.len() as u64);
+        Ok(read.len())
+    }
+}
+
+/// Refuses a network which does not reach the validation level it declares
+fn check_network(network: Network) -> Result<Network, UploadError> {
+    let level = network.minimum_validation_level;
+    let violations = validate_network(&network, level);
+    if violations.is_empty() {
+        Ok(network)
+    } else {
+        Err(UploadError::Invalid { level, violations })
+    }
+}
+
+/// Loads a parsed network in place of the one a state holds, once it is valid and its IDs are
+/// free. The live world is untouched otherwise.
+pub async fn load_network(
+    state: &Arc<NetworkState>,
+    network: Network,
+) -> Result<Network, UploadError> {
+    let network = check_network(network)?;
+    update_ecs_state(state, &network).await?;
+    Ok(network)
+}
+
+/// Registers the network into a staged world, which replaces the live one and the network it
+/// holds once the network is accepted
+async fn update_ecs_state(state: &Arc<NetworkState>, network: &Network) -> Result<(), UploadError> {
     let ecs = state.ecs.read().await;
-    let mut world = ecs.world.write().await;
-    let mut schedule = ecs.schedule.write().await;
+    let mut staged = ecs.stage().await;
+
+    let mut errors = error_cursor(&staged.world);
+    network.register(&mut staged.world, &mut staged.schedule);
+    let taken = taken_ids(&staged.world, &mut errors);
+    if !taken.is_empty() {
+        return Err(UploadError::Conflict(taken));
+    }
+    ecs.replace(staged).await;
+    Ok(())
+}
+
+fn error_cursor(world: &World) -> EventCursor<ComponentErrorEvent> {
+    world
+        .get_resource::<Events<ComponentErrorEvent>>()
+        .map(Events::get_cursor_current)
+        .unwrap_or_default()
+}
 
-    network.register(&mut world, &mut schedule);
+/// IDs refused since the cursor because another type registered them first, which only
+/// happens with unique IDs
+fn taken_ids(world: &World, cursor: &mut EventCursor<ComponentErrorEvent>) -> Vec<String> {
+    let Some(events) = world.get_resource::<Events<ComponentErrorEvent>>() else {
+        return Vec::new();
+    };
+    cursor
+        .read(events)
+        .filter(|error| error.error_type == ErrorType::EntityAlreadyExists)
+        .map(|error| error.id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_by_extension() {
+        assert_eq!(
+            UploadFormat::detect(Some("network.json"), b"<"),
+            UploadFormat::Json
+        );
+        assert_eq!(
+            UploadFormat::detect(Some("network.XIIDM"), b"{"),
+            UploadFormat::Xiidm
+        );
+        assert_eq!(
+            UploadFormat::detect(Some("network.xml"), b"{"),
+            UploadFormat::Xiidm
+        );
+        assert_eq!(
+            UploadFormat::detect(Some("grid.zip"), b""),
+            UploadFormat::Cgmes
+        );
+        assert_eq!(
+            UploadFormat::detect(Some("20260115_1030_FO4_UX0.uct"), b""),
+            UploadFormat::Ucte
+        );
+        assert_eq!(
+            UploadFormat::detect(Some("case9.m"), b""),
+            UploadFormat::Matpower
+        );
+        assert_eq!(
+            UploadFormat::detect(Some("savnw.RAW"), b""),
+            UploadFormat::Psse
+        );
+    }
+
+    #[test]
+    fn test_detect_by_content() {
+        assert_eq!(
+            UploadFormat::detect(None, b"  \n<?xml version=\"1.0\"?>"),
+            UploadFormat::Xiidm
+        );
+        assert_eq!(
+            UploadFormat::detect(Some("network"), b"\xEF\xBB\xBF<iidm:network/>"),
+            UploadFormat::Xiidm
+        );
+        assert_eq!(
+            UploadFormat::detect(Some("network.txt"), b"{\"id\": \"sim1\"}"),
+            UploadFormat::Json
+        );
+        assert_eq!(
+            UploadFormat::detect(None, b"PK\x03\x04\x14\x00"),
+            UploadFormat::Cgmes
+        );
+        assert_eq!(
+            UploadFormat::detect(Some("network"), b"##C 2026.01.15\n##N\n"),
+            UploadFormat::Ucte
+        );
+        assert_eq!(
+            UploadFormat::detect(None, b"function mpc = case9\n"),
+            UploadFormat::Matpower
+        );
+    }
+
+    #[test]
+    fn test_detect_cgmes_profile() {
+        let profile = br#"<?xml version="1.0" encoding="UTF-8"?>
+            <!-- Equipment -->
+            <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">"#;
+        assert_eq!(
+            UploadFormat::detect(Some("grid_EQ.xml"), profile),
+            UploadFormat::Cgmes
+        );
+        assert_eq!(UploadFormat::detect(None, profile), UploadFormat::Cgmes);
+        assert_eq!(
+            UploadFormat::detect(Some("network.xml"), b"<iidm:network id=\"sim1\">"),
+            UploadFormat::Xiidm
+        );
+    }
 }
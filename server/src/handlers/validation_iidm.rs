@@ -0,0 +1,48 @@
+use axum::{extract::Query, Json};
+use iidm::{export_network, validate_network, ValidationLevel, Violation};
+use serde::{Deserialize, Serialize};
+
+use super::ExportIidmError;
+use crate::states::Workspace;
+
+/// Body of the responses refusing invalid data
+#[derive(Debug, Serialize)]
+pub struct ViolationsResponse {
+    pub status: String,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidationQuery {
+    /// Defaults to the level declared by the network
+    pub level: Option<ValidationLevel>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    pub level: ValidationLevel,
+    pub valid: bool,
+    pub violations: Vec<Violation>,
+}
+
+/// Checks the whole network, as it would be exported, at the requested level
+pub async fn validate_iidm(
+    Workspace(state): Workspace,
+    Query(query): Query<ValidationQuery>,
+) -> Result<Json<ValidationReport>, ExportIidmError> {
+    let ecs = state.ecs.read().await;
+    let world = ecs.world.read().await;
+
+    let export = export_network(&world)?;
+    let level = query
+        .level
+        .unwrap_or(export.network.minimum_validation_level);
+    let violations = validate_network(&export.network, level);
+
+    Ok(Json(ValidationReport {
+        level,
+        valid: violations.is_empty(),
+        violations,
+    }))
+}
@@ -4,15 +4,89 @@ mod handlers;
 mod states;
 
 use axum::{
-    routing::{get, get_service, post},
+    extract::DefaultBodyLimit,
+    routing::{delete, get, get_service, post},
     Router,
 };
-use handlers::{index, stream_iidm, update_iidm, upload_iidm};
-use states::AppState;
+use handlers::{
+    count_ids, create_checkpoint, create_iidm, create_network, delete_iidm, delete_network,
+    export_iidm, get_bus_view, get_history, get_iidm, get_scenario, index, list_ids, list_iidm,
+    list_networks, load_scenario_iidm, pause_scenario_iidm, redo_iidm, restore_checkpoint,
+    run_load_flow, set_scenario_speed_iidm, socket_iidm, start_scenario_iidm, step_scenario_iidm,
+    stream_iidm, stream_network_iidm, undo_iidm, update_batch_iidm, update_iidm, upload_iidm,
+    upload_progress, validate_iidm,
+};
+use states::{
+    evict_idle_networks, max_upload_bytes, save_networks_periodically, AppState, Persistence,
+};
 use std::{path::PathBuf, sync::Arc};
 use tower_http::{limit::RequestBodyLimitLayer, services::ServeDir, trace::TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+/// Size limit of the request bodies, uploads aside
+const MAX_REQUEST_BYTES: usize = 200 * 1024 * 1024;
+
+/// Routes acting on one network, served for the default network and for each named one
+fn iidm_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/upload/progress", get(upload_progress))
+        .route("/update/{component_type}", post(update_iidm))
+        .route("/batch", post(update_batch_iidm))
+        .route("/stream", get(stream_network_iidm))
+        .route("/stream/{component_type}/{id}", get(stream_iidm))
+        .route("/ws", get(socket_iidm))
+        .route("/loadflow", post(run_load_flow))
+        .route("/export", get(export_iidm))
+        .route("/topology/{voltage_level_id}", get(get_bus_view))
+        .route("/validation", get(validate_iidm))
+        .route("/ids", get(list_ids))
+        .route("/ids/counts", get(count_ids))
+        .route("/history", get(get_history))
+        .route("/history/undo", post(undo_iidm))
+        .route("/history/redo", post(redo_iidm))
+        .route("/history/checkpoints", post(create_checkpoint))
+        .route(
+            "/history/checkpoints/{name}/restore",
+            post(restore_checkpoint),
+        )
+        .route("/scenario", get(get_scenario).post(load_scenario_iidm))
+        .route("/scenario/start", post(start_scenario_iidm))
+        .route("/scenario/pause", post(pause_scenario_iidm))
+        .route("/scenario/speed", post(set_scenario_speed_iidm))
+        .route("/scenario/step", post(step_scenario_iidm))
+        .route(
+            "/{component_type}",
+            get(list_iidm).post(create_iidm).delete(delete_iidm),
+        )
+        .route("/{component_type}/{id}", get(get_iidm))
+}
+
+/// Upload of a network file, under its own size limit
+fn upload_routes(max_upload_bytes: usize) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/upload", post(upload_iidm))
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(max_upload_bytes))
+}
+
+fn app(state: Arc<AppState>, static_path: PathBuf, max_upload_bytes: usize) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/api/networks", get(list_networks).post(create_network))
+        .route("/api/networks/{network_id}", delete(delete_network))
+        .nest("/api/iidm", iidm_routes())
+        .nest("/api/networks/{network_id}/iidm", iidm_routes())
+        .nest_service("/static", get_service(ServeDir::new(static_path)))
+        .layer(RequestBodyLimitLayer::new(MAX_REQUEST_BYTES))
+        .nest("/api/iidm", upload_routes(max_upload_bytes))
+        .nest(
+            "/api/networks/{network_id}/iidm",
+            upload_routes(max_upload_bytes),
+        )
+        .layer(TraceLayer::new_for_http())
+        .with_state(state)
+}
+
 #[tokio::main]
 async fn main() {
     // Init log
@@ -26,16 +100,29 @@ async fn main() {
     // Path for js dependencies
     let static_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("static");
 
+    // Close the networks left idle
+    let state = Arc::new(AppState::default());
+    tokio::spawn(evict_idle_networks(state.clone()));
+
+    // Reload the networks saved before the last shutdown
+    let persistence = Persistence::from_env().map(Arc::new);
+    if let Some(persistence) = &persistence {
+        match persistence.restore(&state.networks).await {
+            Ok(restored) => tracing::info!(
+                "Restored {} networks from {}",
+                restored.len(),
+                persistence.data_dir().display()
+            ),
+            Err(error) => tracing::error!("Failed to restore the networks: {}", error),
+        }
+        tokio::spawn(save_networks_periodically(
+            state.clone(),
+            persistence.clone(),
+        ));
+    }
+
     // Build routes
-    let app = Router::new()
-        .route("/", get(index))
-        .route("/api/iidm/upload", post(upload_iidm))
-        .route("/api/iidm/update/{component_type}", post(update_iidm))
-        .route("/api/iidm/stream/{component_type}/{id}", get(stream_iidm))
-        .nest_service("/static", get_service(ServeDir::new(static_path)))
-        .layer(TraceLayer::new_for_http())
-        .layer(RequestBodyLimitLayer::new(200 * 1024 * 1024))
-        .with_state(Arc::new(AppState::default()));
+    let app = app(state.clone(), static_path, max_upload_bytes());
 
     // Start server
     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
@@ -43,5 +130,237 @@ async fn main() {
         .unwrap();
     tracing::info!("Server started http://127.0.0.1:3000");
 
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+
+    if let Some(persistence) = persistence {
+        match persistence.save(&state.networks).await {
+            Ok(count) => tracing::info!("Saved {} network snapshots", count),
+            Err(error) => tracing::error!("Failed to save the networks: {}", error),
+        }
+    }
+}
+
+/// Completes on Ctrl+C, or on SIGTERM on Unix
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("Shutting down");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header::CONTENT_TYPE, Request, StatusCode},
+    };
+    use iidm::AssetRegistry;
+    use states::DEFAULT_NETWORK;
+    use tower::ServiceExt;
+
+    const BOUNDARY: &str = "mentor-test-boundary";
+
+    fn data(file: &str) -> String {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../iidm/tests/data")
+            .join(file);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    /// Application whose default network refuses an ID shared by two types
+    async fn app_with_unique_ids() -> Router {
+        let state = Arc::new(AppState::default());
+        let network = state.networks.get(DEFAULT_NETWORK).await.unwrap();
+        network
+            .ecs
+            .read()
+            .await
+            .world
+            .write()
+            .await
+            .resource_mut::<AssetRegistry>()
+            .set_unique_ids(true);
+        app(state, PathBuf::from("static"), usize::MAX)
+    }
+
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, String) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn upload(app: &Router, file_name: &str, content: &str) -> String {
+        let body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"iidm_file\"; filename=\"{file_name}\"\r\n\r\n{content}\r\n--{BOUNDARY}--\r\n"
+        );
+        let request = Request::post("/api/iidm/upload")
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        let (status, page) = send(app, request).await;
+        assert_eq!(status, StatusCode::OK);
+        page
+    }
+
+    async fn get(app: &Router, uri: &str) -> StatusCode {
+        send(app, Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .0
+    }
+
+    #[tokio::test]
+    async fn test_create_refuses_taken_id() {
+        let app = app_with_unique_ids().await;
+        upload(&app, "network.json", &data("network.json")).await;
+
+        let load = serde_json::json!({
+            "id": "GEN",
+            "parentId": "VLGEN",
+            "component": {
+                "id": "GEN", "loadType": "UNDEFINED", "p0": 10.0, "q0": 0.0,
+                "bus": "NGEN", "connectableBus": "NGEN"
+            }
+        });
+        let request = Request::post("/api/iidm/Load")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(load.to_string()))
+            .unwrap();
+        let (status, body) = send(&app, request).await;
+
+        assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+        assert!(body.contains("GEN"), "{}", body);
+        assert_eq!(get(&app, "/api/iidm/Load/GEN").await, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_upload_refuses_taken_id() {
+        let app = app_with_unique_ids().await;
+
+        // Read as it is streamed
+        let json = data("network.json").replace("\"id\" : \"LOAD\"", "\"id\" : \"GEN\"");
+        let page = upload(&app, "network.json", &json).await;
+        assert!(
+            page.contains("IDs already taken by another type: GEN"),
+            "{}",
+            page
+        );
+        assert_eq!(
+            get(&app, "/api/iidm/Substation/P1").await,
+            StatusCode::NOT_FOUND
+        );
+
+        // Parsed as a whole
+        let xiidm = data("network.xiidm").replace("id=\"LOAD\"", "id=\"GEN\"");
+        let page = upload(&app, "network.xiidm", &xiidm).await;
+        assert!(
+            page.contains("IDs already taken by another type: GEN"),
+            "{}",
+            page
+        );
+        assert_eq!(
+            get(&app, "/api/iidm/Substation/P1").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            get(&app, "/api/iidm/Network/sim1").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_upload_keeps_network() {
+        let app = app(
+            Arc::new(AppState::default()),
+            PathBuf::from("static"),
+            usize::MAX,
+        );
+        let json = data("network.json");
+        upload(&app, "network.json", &json).await;
+
+        let page = upload(&app, "network.json", &format!("{}x", json)).await;
+        assert!(page.contains("trailing characters"), "{}", page);
+        assert_eq!(get(&app, "/api/iidm/Substation/P1").await, StatusCode::OK);
+        assert_eq!(get(&app, "/api/iidm/Substation/P2").await, StatusCode::OK);
+        assert_eq!(
+            get(&app, "/api/iidm/Line/NHV1_NHV2_1").await,
+            StatusCode::OK
+        );
+        assert_eq!(get(&app, "/api/iidm/export").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_upload_replaces_network() {
+        let app = app(
+            Arc::new(AppState::default()),
+            PathBuf::from("static"),
+            usize::MAX,
+        );
+        let export = |app: Router| async move {
+            let (status, body) = send(
+                &app,
+                Request::get("/api/iidm/export")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            serde_json::from_str::<iidm::Network>(&body).unwrap()
+        };
+        upload(&app, "network.json", &data("network.json")).await;
+
+        let json = data("network.json")
+            .replace("\"id\" : \"sim1\"", "\"id\" : \"sim2\"")
+            .replace("\"id\" : \"LOAD\"", "\"id\" : \"LOAD2\"");
+        upload(&app, "network.json", &json).await;
+        let network = export(app.clone()).await;
+        assert_eq!(network.id, "sim2");
+        assert_eq!(
+            get(&app, "/api/iidm/Network/sim1").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            get(&app, "/api/iidm/Load/LOAD").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(get(&app, "/api/iidm/Load/LOAD2").await, StatusCode::OK);
+
+        // Parsed as a whole
+        upload(&app, "network.xiidm", &data("network.xiidm")).await;
+        let network = export(app.clone()).await;
+        assert_eq!(network.id, "sim1");
+        assert_eq!(
+            get(&app, "/api/iidm/Network/sim2").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            get(&app, "/api/iidm/Load/LOAD2").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(get(&app, "/api/iidm/Load/LOAD").await, StatusCode::OK);
+    }
 }
@@ -1,12 +1,35 @@
+mod change_feed;
+mod lifecycle_registry;
+mod query_registry;
 mod sse_registry;
 mod update_registry;
 
-use bevy_ecs::{event::Events, schedule::Schedule, world::World};
+use bevy_ecs::{
+    event::Events,
+    schedule::{IntoSystemConfigs, Schedule},
+    world::World,
+};
+use change_feed::{
+    detect_changes, detect_removals, init_change_feed, publish_reload, ChangeFeed, DetectChanges,
+};
 use iidm::*;
+use lifecycle_registry::LifecycleRegistry;
+use query_registry::QueryRegistry;
 use sse_registry::SseRegistry;
+pub use sse_registry::{Change, ChangeFilter, ChangeSet};
 use tokio::sync::RwLock;
 use update_registry::UpdateRegistry;
 
+/// Environment variable making the IDs unique over all the component types, as IIDM requires,
+/// read when each network is opened
+pub const UNIQUE_IDS_VAR: &str = "MENTOR_UNIQUE_IDS";
+
+/// Whether `MENTOR_UNIQUE_IDS` is set to `true` or `1`
+fn unique_ids() -> bool {
+    std::env::var(UNIQUE_IDS_VAR)
+        .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+}
+
 trait ComponentInit: 'static + Send + Sync {}
 impl<T: 'static + Send + Sync> ComponentInit for T {}
 
@@ -16,7 +39,7 @@ fn assert_updatable<T: Updatable>() {}
 
 macro_rules! init_identifiable_components {
     ($($component:ty),* $(,)?) => {
-        fn init_identifiable_component(world: &mut World, schedule: &mut Schedule) {
+        fn init_identifiable_component(world: &mut World, schedule: &mut Schedule, lifecycle: &mut LifecycleRegistry, query: &mut QueryRegistry) {
             $(
                 // Static verification that the type implements Identifiable
                 assert_identifiable::<$component>();
@@ -26,6 +49,17 @@ macro_rules! init_identifiable_components {
 
                 world.init_resource::<Events<RegisterEvent<$component>>>();
                 schedule.add_systems(iidm::handle_register_events::<$component>);
+
+                // Runtime creation and deletion
+                world.init_resource::<Events<CreateEvent<$component>>>();
+                world.init_resource::<Events<DeleteEvent<$component>>>();
+                // Applied before the detection, so the changes are published by the same run
+                schedule.add_systems(iidm::handle_create_events::<$component>.before(DetectChanges));
+                schedule.add_systems(iidm::handle_delete_events::<$component>.before(DetectChanges));
+                lifecycle.register::<$component>(stringify!($component));
+
+                // Read access
+                query.register::<$component>(stringify!($component));
             )*
         }
     };
@@ -41,10 +75,18 @@ macro_rules! init_updatable_components {
                 assert_component::<$component>();
                 world.init_resource::<Events<UpdateEvent<$component>>>();
                 schedule.add_systems(iidm::handle_update_events::<$component>);
+                world.init_resource::<Events<PatchEvent<$component>>>();
+                schedule.add_systems(iidm::handle_patch_events::<$component>);
+                schedule.add_systems(
+                    detect_changes::<$component>
+                        .in_set(DetectChanges)
+                        .after(iidm::handle_update_events::<$component>)
+                        .after(iidm::handle_patch_events::<$component>),
+                );
+                world.add_observer(detect_removals::<$component>);
 
-                // Register component type with update registry in PascalCase
-                // Register component type with its corresponding updater and error types
-                updater.register::<$component, paste::paste! {[<$component Updater>]}, paste::paste! {[<$component Error>]}>(&stringify!($component));
+                // Register component type with its corresponding updater in PascalCase
+                updater.register::<$component, paste::paste! {[<$component Updater>]}>(stringify!($component));
             )*
         }
     };
@@ -110,32 +152,108 @@ pub struct EcsState {
     pub world: RwLock<World>,
     pub schedule: RwLock<Schedule>,
     pub update_registry: RwLock<UpdateRegistry>,
+    pub lifecycle_registry: RwLock<LifecycleRegistry>,
+    pub query_registry: RwLock<QueryRegistry>,
     pub sse_registry: RwLock<SseRegistry>,
 }
 
+/// World and schedule of a network being loaded, set up as the live ones and swapped in once the
+/// network is accepted
+pub struct StagedWorld {
+    pub world: World,
+    pub schedule: Schedule,
+}
+
+/// World and schedule with the resources and systems of every component type, filling the
+/// registries dispatching the requests to them
+fn init_world(
+    sse_registry: SseRegistry,
+    update_registry: &mut UpdateRegistry,
+    lifecycle_registry: &mut LifecycleRegistry,
+    query_registry: &mut QueryRegistry,
+) -> (World, Schedule) {
+    let mut world = World::default();
+    let mut schedule = Schedule::default();
+
+    let mut registry = AssetRegistry::default();
+    registry.set_unique_ids(unique_ids());
+    world.insert_resource(registry);
+
+    // Init Resources and Systems
+    init_identifiable_component(
+        &mut world,
+        &mut schedule,
+        lifecycle_registry,
+        query_registry,
+    );
+    init_updatable_components(&mut world, &mut schedule, update_registry);
+    init_topology(&mut world, &mut schedule);
+    init_load_flow(&mut world, &mut schedule);
+    init_history(&mut world);
+    init_change_feed(&mut world, &mut schedule, sse_registry);
+
+    // Init Errors handler
+    world.insert_resource(Events::<ComponentErrorEvent>::default());
+    world.insert_resource(Events::<InvalidReferenceEvent>::default());
+
+    (world, schedule)
+}
+
+impl EcsState {
+    /// Empty world to load a network into while the live one is still served, with the ID
+    /// namespaces of the live one. Its changes are not published while it is loaded.
+    pub async fn stage(&self) -> StagedWorld {
+        let sse_registry = self.sse_registry.read().await.clone();
+        let unique_ids = self
+            .world
+            .read()
+            .await
+            .get_resource::<AssetRegistry>()
+            .is_some_and(AssetRegistry::unique_ids);
+        let (mut world, schedule) = init_world(
+            sse_registry,
+            &mut UpdateRegistry::default(),
+            &mut LifecycleRegistry::default(),
+            &mut QueryRegistry::default(),
+        );
+        world
+            .resource_mut::<AssetRegistry>()
+            .set_unique_ids(unique_ids);
+        world.resource_mut::<ChangeFeed>().start_loading();
+        StagedWorld { world, schedule }
+    }
+
+    /// Replaces the live world by a staged one, dropping every element of the previous network
+    /// along with its edit journal and checkpoints. The subscribers are told to read it again.
+    pub async fn replace(&self, staged: StagedWorld) {
+        let mut world = self.world.write().await;
+        let mut schedule = self.schedule.write().await;
+        *world = staged.world;
+        *schedule = staged.schedule;
+        publish_reload(&mut world);
+    }
+}
+
 impl Default for EcsState {
     fn default() -> Self {
-        // Init world
-        let mut world = World::default();
-        let mut schedule = Schedule::default();
-
-        // Init registry
         let mut update_registry = UpdateRegistry::default();
-        world.init_resource::<AssetRegistry>();
-
+        let mut lifecycle_registry = LifecycleRegistry::default();
+        let mut query_registry = QueryRegistry::default();
         let sse_registry = SseRegistry::default();
 
-        // Init Resources and Systems
-        init_identifiable_component(&mut world, &mut schedule);
-        init_updatable_components(&mut world, &mut schedule, &mut update_registry);
-
-        // Init Errors handler
-        world.insert_resource(Events::<EntityNotFoundEvent>::default());
+        let (world, schedule) = init_world(
+            sse_registry.clone(),
+            &mut update_registry,
+            &mut lifecycle_registry,
+            &mut query_registry,
+        );
 
         Self {
             world: RwLock::new(world),
             schedule: RwLock::new(schedule),
             update_registry: RwLock::new(update_registry),
+            lifecycle_registry: RwLock::new(lifecycle_registry),
+            query_registry: RwLock::new(query_registry),
             sse_registry: RwLock::new(sse_registry),
         }
     }
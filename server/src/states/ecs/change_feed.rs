@@ -0,0 +1,290 @@
+use bevy_ecs::{prelude::*, world::DeferredWorld};
+use iidm::{
+    handle_load_flow_events, locate, short_type_name, update_topology, AssetRegistry, Id, Location,
+    Reference, References, Updatable,
+};
+
+use super::sse_registry::{Change, ChangeSet, SseRegistry};
+
+/// Systems collecting the components changed during a schedule run, whatever changed them
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DetectChanges;
+
+/// Component changed since the previous run, waiting to be published
+struct PendingChange {
+    component_type: &'static str,
+    id: String,
+    entity: Entity,
+    /// Null when the component was removed
+    component: serde_json::Value,
+    references: Vec<Reference>,
+    /// Location of a removed component, read before it left the world
+    location: Option<Location>,
+}
+
+/// Changes detected in the world, published to the SSE and WebSocket subscribers at the end of
+/// each schedule run
+#[derive(Resource)]
+pub struct ChangeFeed {
+    registry: SseRegistry,
+    pending: Vec<PendingChange>,
+    /// Changes are kept back while the ones made by a batch may still be rolled back
+    held: bool,
+    /// Nothing is collected while a whole network is loaded, see [`publish_reload`]
+    loading: bool,
+}
+
+impl ChangeFeed {
+    pub fn new(registry: SseRegistry) -> Self {
+        Self {
+            registry,
+            pending: Vec::new(),
+            held: false,
+            loading: false,
+        }
+    }
+
+    /// Stops collecting the changes until the network being loaded is published as a whole
+    pub fn start_loading(&mut self) {
+        self.pending.clear();
+        self.loading = true;
+    }
+
+    /// Keeps the changes detected from now on until released or discarded
+    pub fn hold(&mut self) {
+        self.held = true;
+    }
+
+    /// Drops the changes kept back and publishes the next ones again
+    pub fn discard(&mut self) {
+        self.pending.clear();
+        self.held = false;
+    }
+}
+
+/// Collects the components of type `C` modified or added since the previous run. The components
+/// are read under the ID and type they are registered with, the others are not published.
+pub fn detect_changes<C>(
+    changed: Query<(Entity, &C), Changed<C>>,
+    registry: Res<AssetRegistry>,
+    mut feed: ResMut<ChangeFeed>,
+) where
+    C: Component + Updatable + References,
+{
+    if feed.loading {
+        return;
+    }
+    let component_type = short_type_name::<C>();
+    for (entity, component) in changed.iter() {
+        if registry.type_name_of(entity) != Some(component_type) {
+            continue;
+        }
+        let (Some(id), Ok(value)) = (registry.id_of(entity), serde_json::to_value(component))
+        else {
+            continue;
+        };
+        feed.pending.push(PendingChange {
+            component_type,
+            id: id.to_string(),
+            entity,
+            component: value,
+            references: component.references(),
+            location: None,
+        });
+    }
+}
+
+/// Collects a component of type `C` being removed, published with a null component. The
+/// registry has already forgotten the entity, which still holds its ID and relations.
+pub fn detect_removals<C>(trigger: Trigger<OnRemove, C>, mut world: DeferredWorld)
+where
+    C: Component + References,
+{
+    let entity = trigger.entity();
+    if world
+        .get_resource::<ChangeFeed>()
+        .is_none_or(|feed| feed.loading)
+    {
+        return;
+    }
+    let (Some(id), Some(component)) = (world.get::<Id>(entity), world.get::<C>(entity)) else {
+        return;
+    };
+    let id = id.value().to_string();
+    let references = component.references();
+    let location = locate(&world, entity, &references);
+
+    if let Some(mut feed) = world.get_resource_mut::<ChangeFeed>() {
+        feed.pending.push(PendingChange {
+            component_type: short_type_name::<C>(),
+            id,
+            entity,
+            component: serde_json::Value::Null,
+            references,
+            location: Some(location),
+        });
+    }
+}
+
+/// Publishes the changes collected during the run as a single change set, unless held
+pub fn publish_changes(world: &mut World) {
+    let Some(mut feed) = world.get_resource_mut::<ChangeFeed>() else {
+        return;
+    };
+    if feed.held || feed.loading || feed.pending.is_empty() {
+        return;
+    }
+    let pending = std::mem::take(&mut feed.pending);
+    let registry = feed.registry.clone();
+
+    let changes = pending
+        .into_iter()
+        .map(|change| Change {
+            location: change
+                .location
+                .unwrap_or_else(|| locate(world, change.entity, &change.references)),
+            component_type: change.component_type.to_string(),
+            id: change.id,
+            component: change.component,
+        })
+        .collect();
+    registry.publish_changes(ChangeSet {
+        changes,
+        ..Default::default()
+    });
+}
+
+/// Tells the subscribers that the network was replaced as a whole, in a single change set,
+/// and collects the changes again
+pub fn publish_reload(world: &mut World) {
+    let Some(mut feed) = world.get_resource_mut::<ChangeFeed>() else {
+        return;
+    };
+    feed.pending.clear();
+    feed.held = false;
+    feed.loading = false;
+    feed.registry.publish_changes(ChangeSet {
+        reload: true,
+        ..Default::default()
+    });
+}
+
+/// Publishes the changes kept back by [`ChangeFeed::hold`]
+pub fn release_changes(world: &mut World) {
+    if let Some(mut feed) = world.get_resource_mut::<ChangeFeed>() {
+        feed.held = false;
+    }
+    publish_changes(world);
+}
+
+/// Registers the feed, whose systems run once the topology and the load flow results are up to
+/// date. The detection of each type is added with its update handlers.
+pub fn init_change_feed(world: &mut World, schedule: &mut Schedule, registry: SseRegistry) {
+    world.insert_resource(ChangeFeed::new(registry));
+    schedule.configure_sets(
+        DetectChanges
+            .after(update_topology)
+            .after(handle_load_flow_events),
+    );
+    schedule.add_systems(publish_changes.after(DetectChanges));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::states::{ChangeFilter, NetworkState};
+
+    #[tokio::test]
+    async fn test_changes_published_whatever_their_source() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../iidm/tests/data/network.json");
+        let network: iidm::Network = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        let state = NetworkState::default();
+        let ecs = state.ecs.read().await;
+        let mut world = ecs.world.write().await;
+        let mut schedule = ecs.schedule.write().await;
+        let mut changes = ecs.sse_registry.read().await.subscribe_changes();
+
+        iidm::Identifiable::register(&network, &mut world, &mut schedule);
+        schedule.run(&mut world);
+        // The loaded components are published as added
+        let mut loaded = Vec::new();
+        while let Ok(change_set) = changes.try_recv() {
+            loaded.extend(change_set.changes.iter().map(|change| change.id.clone()));
+        }
+        assert!(loaded.contains(&"NHV1_NHV2_1".to_string()));
+
+        // Modified outside of the update handlers, as a simulation step would
+        let entity = world
+            .resource::<AssetRegistry>()
+            .find_typed::<iidm::Line>("NHV1_NHV2_1")
+            .unwrap();
+        world.get_mut::<iidm::Line>(entity).unwrap().r = 4.0;
+        schedule.run(&mut world);
+
+        let change_set = changes.try_recv().unwrap();
+        assert_eq!(change_set.changes.len(), 1);
+        let change = &change_set.changes[0];
+        assert_eq!(
+            (change.component_type.as_str(), change.id.as_str()),
+            ("Line", "NHV1_NHV2_1")
+        );
+        assert_eq!(change.component["r"], 4.0);
+        assert!(change.location.voltage_levels.contains("VLHV1"));
+
+        // Nothing changed, nothing published
+        schedule.run(&mut world);
+        assert!(changes.try_recv().is_err());
+
+        // Removed components are published without their content
+        world.send_event(iidm::DeleteEvent::<iidm::Load>::new("LOAD"));
+        schedule.run(&mut world);
+        let change_set = changes.try_recv().unwrap();
+        let change = &change_set.changes[0];
+        assert_eq!(
+            (change.component_type.as_str(), change.id.as_str()),
+            ("Load", "LOAD")
+        );
+        assert!(change.component.is_null());
+        assert!(change.location.voltage_levels.contains("VLLOAD"));
+    }
+
+    #[tokio::test]
+    async fn test_staged_network_published_as_reload() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../iidm/tests/data/network.json");
+        let network: iidm::Network = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        let state = NetworkState::default();
+        let ecs = state.ecs.read().await;
+        let mut changes = ecs.sse_registry.read().await.subscribe_changes();
+
+        let mut staged = ecs.stage().await;
+        iidm::Identifiable::register(&network, &mut staged.world, &mut staged.schedule);
+        staged.schedule.run(&mut staged.world);
+        // Nothing is published while the network is loaded
+        assert!(changes.try_recv().is_err());
+
+        ecs.replace(staged).await;
+        let change_set = changes.try_recv().unwrap();
+        assert!(change_set.reload);
+        assert!(change_set.changes.is_empty());
+        assert!(changes.try_recv().is_err());
+        // Whatever their filter, the subscribers read the network again
+        let filter = ChangeFilter {
+            types: ["Line".to_string()].into(),
+            ..Default::default()
+        };
+        assert!(filter.apply(&change_set).is_some_and(|set| set.reload));
+
+        // The changes of the live network are published again
+        let mut world = ecs.world.write().await;
+        let mut schedule = ecs.schedule.write().await;
+        let entity = world
+            .resource::<AssetRegistry>()
+            .find_typed::<iidm::Line>("NHV1_NHV2_1")
+            .unwrap();
+        world.get_mut::<iidm::Line>(entity).unwrap().r = 4.0;
+        schedule.run(&mut world);
+        assert_eq!(changes.try_recv().unwrap().changes.len(), 1);
+    }
+}
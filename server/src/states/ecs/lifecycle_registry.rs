@@ -0,0 +1,237 @@
+use crate::{
+    handlers::{CreateRequest, DeleteRequest, LifecycleError, RegisterResponse},
+    states::NetworkState,
+};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bevy_ecs::{component::Component, event::Events, schedule::Schedule, world::World};
+use iidm::{
+    AssetRegistry, ComponentErrorEvent, CreateEvent, DeleteEvent, ErrorType, Hierarchy,
+    Identifiable, InvalidReferenceEvent, Placement, Reference, References, Validate,
+};
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::sync::Arc;
+use std::{collections::HashMap, pin::Pin};
+
+type LifecycleFuture = Pin<Box<dyn Future<Output = Result<Response, LifecycleError>> + Send>>;
+
+// Type-erased functions to dispatch to create/delete with correct types
+type CreateHandlerFn =
+    Box<dyn Fn(Arc<NetworkState>, Json<CreateRequest>) -> LifecycleFuture + Send + Sync>;
+type DeleteHandlerFn =
+    Box<dyn Fn(Arc<NetworkState>, Json<DeleteRequest>) -> LifecycleFuture + Send + Sync>;
+
+// Registry to store creation and deletion handlers by component name
+#[derive(Default)]
+pub struct LifecycleRegistry {
+    create_handlers: HashMap<String, CreateHandlerFn>,
+    delete_handlers: HashMap<String, DeleteHandlerFn>,
+}
+
+impl LifecycleRegistry {
+    pub fn register<C>(&mut self, type_name: &str)
+    where
+        C: Component
+            + Identifiable
+            + Hierarchy
+            + Placement
+            + References
+            + Validate
+            + Clone
+            + DeserializeOwned
+            + 'static,
+    {
+        let create = Box::new(
+            move |state: Arc<NetworkState>, Json(payload): Json<CreateRequest>| {
+                Box::pin(async move {
+                    create_component::<C>(&state, payload).await?;
+                    Ok((
+                        StatusCode::CREATED,
+                        Json(RegisterResponse {
+                            status: "Component created successfully".to_string(),
+                        }),
+                    )
+                        .into_response())
+                }) as LifecycleFuture
+            },
+        );
+        let delete = Box::new(
+            move |state: Arc<NetworkState>, Json(payload): Json<DeleteRequest>| {
+                Box::pin(async move {
+                    delete_component::<C>(&state, payload).await?;
+                    Ok((
+                        StatusCode::OK,
+                        Json(RegisterResponse {
+                            status: "Component deleted successfully".to_string(),
+                        }),
+                    )
+                        .into_response())
+                }) as LifecycleFuture
+            },
+        );
+
+        self.create_handlers.insert(type_name.to_string(), create);
+        self.delete_handlers.insert(type_name.to_string(), delete);
+        tracing::debug!("Registered lifecycle handlers for {}", type_name);
+    }
+
+    pub fn get_create_handler(&self, component_type: &str) -> Option<&CreateHandlerFn> {
+        self.create_handlers.get(component_type)
+    }
+
+    pub fn get_delete_handler(&self, component_type: &str) -> Option<&DeleteHandlerFn> {
+        self.delete_handlers.get(component_type)
+    }
+}
+
+async fn create_component<C>(
+    state: &Arc<NetworkState>,
+    payload: CreateRequest,
+) -> Result<(), LifecycleError>
+where
+    C: Component
+        + Identifiable
+        + Hierarchy
+        + Placement
+        + References
+        + Validate
+        + Clone
+        + DeserializeOwned
+        + 'static,
+{
+    tracing::debug!("Received create request for component ID: {}", payload.id);
+
+    let component: C = serde_json::from_value(payload.component)?;
+    if component.id() != payload.id {
+        return Err(LifecycleError::ValidationError(format!(
+            "Component ID '{}' does not match request ID '{}'",
+            component.id(),
+            payload.id
+        )));
+    }
+
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+    let mut schedule = ecs.schedule.write().await;
+
+    if !world.contains_resource::<Events<CreateEvent<C>>>() {
+        return Err(LifecycleError::InternalError(format!(
+            "Event system for {} not initialized",
+            std::any::type_name::<C>()
+        )));
+    }
+    if let Err(error) = component.check_available(world.resource::<AssetRegistry>()) {
+        return Err(LifecycleError::ConflictError(error.to_string()));
+    }
+    world.send_event(CreateEvent {
+        id: payload.id.clone(),
+        parent_id: payload.parent_id,
+        component,
+    });
+    run_and_check(&mut world, &mut schedule, &payload.id)
+}
+
+async fn delete_component<C>(
+    state: &Arc<NetworkState>,
+    payload: DeleteRequest,
+) -> Result<(), LifecycleError>
+where
+    C: Component + 'static,
+{
+    tracing::debug!("Received delete request for component ID: {}", payload.id);
+
+    let ecs = state.ecs.read().await;
+    let mut world = ecs.world.write().await;
+    let mut schedule = ecs.schedule.write().await;
+
+    if !world.contains_resource::<Events<DeleteEvent<C>>>() {
+        return Err(LifecycleError::InternalError(format!(
+            "Event system for {} not initialized",
+            std::any::type_name::<C>()
+        )));
+    }
+
+    world.send_event(DeleteEvent::<C>::new(payload.id.clone()));
+    run_and_check(&mut world, &mut schedule, &payload.id)
+}
+
+fn already_exists(id: &str) -> LifecycleError {
+    LifecycleError::ConflictError(format!("Entity with ID '{}' already exists", id))
+}
+
+// Helper function to run the schedule and report the errors raised for this ID
+fn run_and_check(
+    world: &mut World,
+    schedule: &mut Schedule,
+    id: &str,
+) -> Result<(), LifecycleError> {
+    let mut error_reader = world
+        .get_resource::<Events<ComponentErrorEvent>>()
+        .ok_or_else(|| {
+            LifecycleError::InternalError("Error errors system not initialized".to_string())
+        })?
+        .get_cursor_current();
+    let mut reference_reader = world
+        .get_resource::<Events<InvalidReferenceEvent>>()
+        .ok_or_else(|| {
+            LifecycleError::InternalError("Reference errors system not initialized".to_string())
+        })?
+        .get_cursor_current();
+
+    schedule.run(world);
+
+    let reference_events = world.resource::<Events<InvalidReferenceEvent>>();
+    if let Some(event) = reference_reader
+        .read(reference_events)
+        .find(|event| event.id == id)
+    {
+        return Err(LifecycleError::ReferenceError(
+            event
+                .references
+                .iter()
+                .map(Reference::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ));
+    }
+
+    let error_events = world.resource::<Events<ComponentErrorEvent>>();
+    for error in error_reader.read(error_events) {
+        if error.id == id {
+            return Err(match error.error_type {
+                ErrorType::EntityNotFound => {
+                    LifecycleError::NotFoundError(format!("Entity with ID '{}' not found", id))
+                }
+                ErrorType::ComponentNotFound => LifecycleError::NotFoundError(format!(
+                    "Component of type '{}' not found on entity with ID '{}'",
+                    error.component_type, id
+                )),
+                ErrorType::EntityAlreadyExists => already_exists(id),
+                ErrorType::InvalidPatch(ref message) => {
+                    LifecycleError::ValidationError(message.clone())
+                }
+                ErrorType::Invalid(ref violations) => {
+                    LifecycleError::InvalidComponent(violations.clone())
+                }
+                ErrorType::InvalidParent(ref message) => {
+                    LifecycleError::ParentError(message.clone())
+                }
+                ErrorType::Required(ref parent) => LifecycleError::ConflictError(format!(
+                    "Entity with ID '{}' is required by {}",
+                    id, parent
+                )),
+                ErrorType::Referenced(ref referrers) => LifecycleError::ConflictError(format!(
+                    "Entity with ID '{}' is referenced by {}",
+                    id,
+                    referrers.join(", ")
+                )),
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use crate::{
+    handlers::{parse_fields, select_fields, GetQuery, ListQuery, ListResponse, QueryError},
+    states::NetworkState,
+};
+use axum::{
+    response::{IntoResponse, Response},
+    Json,
+};
+use bevy_ecs::{component::Component, world::World};
+use iidm::{assemble, AssetRegistry, Hierarchy, Identifiable};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::{collections::HashMap, pin::Pin};
+
+type QueryFuture = Pin<Box<dyn Future<Output = Result<Response, QueryError>> + Send>>;
+
+// Type-erased functions to dispatch to get/list with correct types
+type GetHandlerFn = Box<dyn Fn(Arc<NetworkState>, String, GetQuery) -> QueryFuture + Send + Sync>;
+type ListHandlerFn = Box<dyn Fn(Arc<NetworkState>, ListQuery) -> QueryFuture + Send + Sync>;
+
+// Registry to store read handlers by component name
+#[derive(Default)]
+pub struct QueryRegistry {
+    get_handlers: HashMap<String, GetHandlerFn>,
+    list_handlers: HashMap<String, ListHandlerFn>,
+}
+
+impl QueryRegistry {
+    pub fn register<C>(&mut self, type_name: &str)
+    where
+        C: Component + Identifiable + Hierarchy + Clone + Serialize + 'static,
+    {
+        let get = Box::new(
+            move |state: Arc<NetworkState>, id: String, query: GetQuery| {
+                Box::pin(async move {
+                    let fields = parse_fields(query.fields.as_deref())?;
+                    let ecs = state.ecs.read().await;
+                    let world = ecs.world.read().await;
+
+                    let value = get_component::<C>(&world, &id)?;
+                    Ok(Json(select_fields(value, fields.as_deref())).into_response())
+                }) as QueryFuture
+            },
+        );
+        let list = Box::new(move |state: Arc<NetworkState>, query: ListQuery| {
+            Box::pin(async move {
+                let fields = parse_fields(query.fields.as_deref())?;
+                let (offset, limit) = query.page()?;
+                let ecs = state.ecs.read().await;
+                let world = ecs.world.read().await;
+
+                let response = list_components::<C>(&world, offset, limit, fields.as_deref())?;
+                Ok(Json(response).into_response())
+            }) as QueryFuture
+        });
+
+        self.get_handlers.insert(type_name.to_string(), get);
+        self.list_handlers.insert(type_name.to_string(), list);
+        tracing::debug!("Registered query handlers for {}", type_name);
+    }
+
+    pub fn get_get_handler(&self, component_type: &str) -> Option<&GetHandlerFn> {
+        self.get_handlers.get(component_type)
+    }
+
+    pub fn get_list_handler(&self, component_type: &str) -> Option<&ListHandlerFn> {
+        self.list_handlers.get(component_type)
+    }
+}
+
+// Nested identifiables are put back in place from the entity relations
+fn get_component<C>(world: &World, id: &str) -> Result<serde_json::Value, QueryError>
+where
+    C: Component + Hierarchy + Clone + Serialize,
+{
+    let component = world
+        .get_resource::<AssetRegistry>()
+        .and_then(|registry| registry.find_typed::<C>(id))
+        .and_then(|entity| assemble::<C>(world, entity))
+        .ok_or_else(|| {
+            QueryError::NotFoundError(format!(
+                "Component of type '{}' not found with ID '{}'",
+                std::any::type_name::<C>(),
+                id
+            ))
+        })?;
+
+    Ok(serde_json::to_value(component)?)
+}
+
+// Components are sorted by ID so that pages are stable between requests
+fn list_components<C>(
+    world: &World,
+    offset: usize,
+    limit: usize,
+    fields: Option<&[String]>,
+) -> Result<ListResponse, QueryError>
+where
+    C: Component + Identifiable + Hierarchy + Clone + Serialize,
+{
+    let Some(registry) = world.get_resource::<AssetRegistry>() else {
+        return Ok(ListResponse {
+            total: 0,
+            offset,
+            limit,
+            items: Vec::new(),
+        });
+    };
+
+    // The registry iterates by ID, only the requested page is assembled
+    let items = registry
+        .iter_typed::<C>()
+        .skip(offset)
+        .take(limit)
+        .filter_map(|(_, entity)| assemble::<C>(world, entity))
+        .map(|component| Ok(select_fields(serde_json::to_value(component)?, fields)))
+        .collect::<Result<Vec<_>, QueryError>>()?;
+
+    Ok(ListResponse {
+        total: registry.count::<C>(),
+        offset,
+        limit,
+        items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::{system::Commands, world::CommandQueue};
+    use iidm::Substation;
+
+    fn world_with_substations(ids: &[&str]) -> World {
+        let mut world = World::new();
+        let mut registry = AssetRegistry::default();
+
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        for id in ids {
+            let substation: Substation = serde_json::from_value(serde_json::json!({
+                "id": id, "country": "FR", "tso": "RTE", "geographicalTags": [],
+                "voltageLevels": [], "twoWindingsTransformers": []
+            }))
+            .unwrap();
+            registry
+                .add_component(&mut commands, *id, substation)
+                .unwrap();
+        }
+        queue.apply(&mut world);
+        world.insert_resource(registry);
+        world
+    }
+
+    #[test]
+    fn test_list_components_pages_sorted_by_id() {
+        let world = world_with_substations(&["S3", "S1", "S2"]);
+        let fields = vec!["id".to_string()];
+
+        let response = list_components::<Substation>(&world, 1, 1, Some(&fields)).unwrap();
+
+        assert_eq!(response.total, 3);
+        assert_eq!(response.items, vec![serde_json::json!({"id": "S2"})]);
+    }
+
+    #[test]
+    fn test_get_component_not_found() {
+        let world = world_with_substations(&["S1"]);
+
+        assert!(get_component::<Substation>(&world, "S1").is_ok());
+        assert!(matches!(
+            get_component::<Substation>(&world, "S2"),
+            Err(QueryError::NotFoundError(_))
+        ));
+    }
+}
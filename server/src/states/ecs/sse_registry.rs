@@ -1,25 +1,110 @@
-use std::collections::HashMap;
+use iidm::Location;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 
-// Structure pour gérer les streams SSE par type de composant et ID
-#[derive(Default)]
+/// Component modified by an update, as broadcast to the subscribers
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    #[serde(rename = "type")]
+    pub component_type: String,
+    pub id: String,
+    pub component: serde_json::Value,
+    /// Voltage levels and substations of the component, used to filter the changes
+    #[serde(skip)]
+    pub location: Location,
+}
+
+/// Components modified together by one request, a removed one having a null component
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChangeSet {
+    /// The network was replaced as a whole, by an upload: it is to be read again
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub reload: bool,
+    pub changes: Vec<Change>,
+}
+
+/// Changes a subscriber asks for. Each filter given must match, an empty one matching any
+/// change; a change matches a list when it matches one of its values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeFilter {
+    pub types: BTreeSet<String>,
+    pub ids: BTreeSet<String>,
+    pub voltage_levels: BTreeSet<String>,
+    pub substations: BTreeSet<String>,
+}
+
+impl ChangeFilter {
+    pub fn matches(&self, change: &Change) -> bool {
+        let any = |filter: &BTreeSet<String>, values: &BTreeSet<String>| {
+            filter.is_empty() || !filter.is_disjoint(values)
+        };
+        (self.types.is_empty() || self.types.contains(&change.component_type))
+            && (self.ids.is_empty() || self.ids.contains(&change.id))
+            && any(&self.voltage_levels, &change.location.voltage_levels)
+            && any(&self.substations, &change.location.substations)
+    }
+
+    /// Changes of the set which match, none when nothing matches. A reload concerns every
+    /// subscriber.
+    pub fn apply(&self, change_set: &ChangeSet) -> Option<ChangeSet> {
+        if change_set.reload {
+            return Some(change_set.clone());
+        }
+        let changes: Vec<Change> = change_set
+            .changes
+            .iter()
+            .filter(|change| self.matches(change))
+            .cloned()
+            .collect();
+        (!changes.is_empty()).then_some(ChangeSet {
+            changes,
+            ..Default::default()
+        })
+    }
+}
+
+type Channels = HashMap<(String, String), broadcast::Sender<String>>;
+
+// Structure pour gérer les streams SSE par type de composant et ID. Les clones partagent les
+// mêmes canaux, la détection des changements du monde publiant par l'un d'eux.
+#[derive(Clone)]
 pub struct SseRegistry {
     // Map de (component_type, id) -> channel broadcast
-    channels: HashMap<(String, String), broadcast::Sender<String>>,
+    channels: Arc<Mutex<Channels>>,
+    /// Every change set, whatever the components it holds
+    change_sets: broadcast::Sender<Arc<ChangeSet>>,
+}
+
+impl Default for SseRegistry {
+    fn default() -> Self {
+        Self {
+            channels: Default::default(),
+            change_sets: broadcast::channel(100).0,
+        }
+    }
+}
+
+// Clé d'un composant, le type étant donné sans le chemin de son module
+fn channel_key(component_type: &str, id: &str) -> (String, String) {
+    (
+        format!("iidm::entities::{}", component_type),
+        id.to_string(),
+    )
 }
 
 impl SseRegistry {
     // Obtenir ou créer un canal pour un composant spécifique
     pub fn get_or_create_channel(
-        &mut self,
+        &self,
         component_type: &str,
         id: &str,
     ) -> broadcast::Sender<String> {
-        let c = format!("iidm::entities::{}", component_type);
-        let key = (c, id.to_string());
-
         self.channels
-            .entry(key)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(channel_key(component_type, id))
             .or_insert_with(|| {
                 let (tx, _) = broadcast::channel(100);
                 tx
@@ -27,16 +112,43 @@ impl SseRegistry {
             .clone()
     }
 
-    // Publier une mise à jour pour un composant
-    pub fn publish_update(&self, component_type: &str, id: &str, data: &str) {
-        let key = (component_type.to_string(), id.to_string());
+    /// Whether a stream or a socket listens to the components or to the change sets
+    pub fn has_subscribers(&self) -> bool {
+        self.change_sets.receiver_count() > 0
+            || self
+                .channels
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .values()
+                .any(|tx| tx.receiver_count() > 0)
+    }
+
+    /// Receives the change sets published from now on
+    pub fn subscribe_changes(&self) -> broadcast::Receiver<Arc<ChangeSet>> {
+        self.change_sets.subscribe()
+    }
 
-        match self.channels.get(&key) {
-            Some(tx) => {
-                let _ = tx.send(data.to_string());
-                tracing::debug!("Published SSE update for {}/{}", component_type, id);
+    /// Sends each component to its own subscribers, then the whole set to the change set ones
+    pub fn publish_changes(&self, change_set: ChangeSet) {
+        let channels = self
+            .channels
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for change in &change_set.changes {
+            let Some(tx) = channels.get(&channel_key(&change.component_type, &change.id)) else {
+                continue;
+            };
+            if let Ok(data) = serde_json::to_string(&change.component) {
+                let _ = tx.send(data);
+                tracing::debug!(
+                    "Published SSE update for {}/{}",
+                    change.component_type,
+                    change.id
+                );
             }
-            None => tracing::error!("Key not found: {}/{}", key.0, key.1,),
         }
+
+        // Nobody may be listening, which is not an error. Each subscriber filters the set.
+        let _ = self.change_sets.send(Arc::new(change_set));
     }
 }
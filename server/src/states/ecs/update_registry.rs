@@ -1,25 +1,38 @@
+use super::change_feed::{release_changes, ChangeFeed};
+use super::sse_registry::{Change, ChangeFilter};
 use crate::{
-    handlers::{RegisterRequest, RegisterResponse, UpdateError},
-    states::AppState,
+    handlers::{BatchUpdate, RegisterResponse, UpdateBody, UpdateError},
+    states::NetworkState,
 };
 use axum::{
-    extract::State,
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
-use bevy_ecs::{component::Component, event::Events};
-use iidm::{AssetRegistry, EntityNotFoundEvent, ErrorType, JsonSchema, Updatable, UpdateEvent};
+use bevy_ecs::{
+    component::Component,
+    event::{EventCursor, Events},
+    schedule::Schedule,
+    world::World,
+};
+use iidm::{
+    location, short_type_name, AssetRegistry, ComponentErrorEvent, ErrorType, History,
+    InvalidReferenceEvent, JsonSchema, Patch, PatchEvent, Patchable, Reference, References,
+    Updatable, UpdateEvent, Validate,
+};
+use std::collections::HashSet;
 use std::fmt::Display;
 use std::future::Future;
+use std::marker::PhantomData;
 use std::sync::Arc;
 use std::{collections::HashMap, pin::Pin};
 
 // Type-erased function to dispatch to update_iidm with correct types
 type UpdateHandlerFn = Box<
     dyn Fn(
-            State<Arc<AppState>>,
-            Json<RegisterRequest>,
+            Arc<NetworkState>,
+            String,
+            UpdateBody,
         ) -> Pin<Box<dyn Future<Output = Result<Response, UpdateError>> + Send>>
         + Send
         + Sync,
@@ -29,20 +42,21 @@ type UpdateHandlerFn = Box<
 #[derive(Default)]
 pub struct UpdateRegistry {
     handlers: HashMap<String, UpdateHandlerFn>,
+    batch_handlers: HashMap<String, Box<dyn BatchHandler>>,
 }
 
 impl UpdateRegistry {
-    pub fn register<C, U, E>(&mut self, type_name: &str)
+    pub fn register<C, U>(&mut self, type_name: &str)
     where
-        C: Updatable<Updater = U> + Component + 'static,
+        C: Updatable<Updater = U> + Patchable + Validate + References + Component + Clone + 'static,
         U: JsonSchema + Send + Sync + 'static,
         U::Err: Display,
     {
         let handler = Box::new(
-            move |state: State<Arc<AppState>>, payload: Json<RegisterRequest>| {
+            move |state: Arc<NetworkState>, id: String, body: UpdateBody| {
                 Box::pin(async move {
                     // Call update_iidm and convert the result to Response
-                    match update_iidm::<C, U, E>(state, payload).await {
+                    match update_iidm::<C, U>(state, id, body).await {
                         Ok(response) => Ok(response.into_response()),
                         Err(err) => Err(err),
                     }
@@ -52,26 +66,299 @@ impl UpdateRegistry {
         );
 
         self.handlers.insert(type_name.to_string(), handler);
+        self.batch_handlers.insert(
+            type_name.to_string(),
+            Box::new(TypedBatchHandler::<C>(PhantomData)),
+        );
         tracing::debug!("Registered update handler for {}", type_name);
     }
 
     pub fn get_handler(&self, component_type: &str) -> Option<&UpdateHandlerFn> {
         self.handlers.get(component_type)
     }
+
+    /// Applies every update of a batch in a single schedule run, or none of them. The updates
+    /// are all checked before, and the ones applied are rolled back when another one is
+    /// refused. Returns the number of updated components.
+    pub async fn update_batch(
+        &self,
+        state: &Arc<NetworkState>,
+        updates: Vec<BatchUpdate>,
+    ) -> Result<usize, UpdateError> {
+        let ecs = state.ecs.read().await;
+        let mut world = ecs.world.write().await;
+        let mut schedule = ecs.schedule.write().await;
+
+        self.apply_batch(&mut world, &mut schedule, updates)
+    }
+
+    /// Current state of a component, as sent to the subscribers
+    pub fn change(&self, world: &World, component_type: &str, id: &str) -> Option<Change> {
+        self.batch_handlers.get(component_type)?.change(world, id)
+    }
+
+    /// Current state of the registered components matching the filter, sorted by ID
+    pub fn snapshot(&self, world: &World, filter: &ChangeFilter) -> Vec<Change> {
+        let Some(registry) = world.get_resource::<AssetRegistry>() else {
+            return Vec::new();
+        };
+        registry
+            .iter()
+            .filter_map(|(id, entity)| self.change(world, registry.type_name_of(entity)?, id))
+            .filter(|change| filter.matches(change))
+            .collect()
+    }
+
+    fn apply_batch(
+        &self,
+        world: &mut World,
+        schedule: &mut Schedule,
+        updates: Vec<BatchUpdate>,
+    ) -> Result<usize, UpdateError> {
+        let mut staged = Vec::with_capacity(updates.len());
+        let mut updated = HashSet::new();
+        for (index, update) in updates.into_iter().enumerate() {
+            let at = |source| UpdateError::BatchItem {
+                index,
+                source: Box::new(source),
+            };
+            let handler = self
+                .batch_handlers
+                .get(&update.component_type)
+                .ok_or_else(|| at(unknown_component_type(&update.component_type)))?;
+            // Errors are reported by ID, an element updated twice could not be told apart
+            if !updated.insert((update.component_type.clone(), update.id.clone())) {
+                return Err(at(UpdateError::ValidationError(format!(
+                    "{} '{}' is updated twice",
+                    update.component_type, update.id
+                ))));
+            }
+            let (previous, send) = handler
+                .prepare(world, &update.id, update.body)
+                .map_err(at)?;
+            staged.push(StagedUpdate {
+                index,
+                handler: handler.as_ref(),
+                id: update.id,
+                previous,
+                send: Some(send),
+            });
+        }
+
+        let errors = world
+            .resource::<Events<ComponentErrorEvent>>()
+            .get_cursor_current();
+        let references = world
+            .resource::<Events<InvalidReferenceEvent>>()
+            .get_cursor_current();
+        let mark = world.get_resource::<History>().map(History::mark);
+        // The changes are only published once the whole batch is accepted
+        if let Some(mut feed) = world.get_resource_mut::<ChangeFeed>() {
+            feed.hold();
+        }
+
+        for send in staged.iter_mut().filter_map(|update| update.send.take()) {
+            send(world);
+        }
+        schedule.run(world);
+
+        let failure = staged.iter().find_map(|update| {
+            update
+                .handler
+                .check(world, errors.clone(), references.clone(), &update.id)
+                .err()
+                .map(|source| UpdateError::BatchItem {
+                    index: update.index,
+                    source: Box::new(source),
+                })
+        });
+        if let Some(error) = failure {
+            for update in &staged {
+                update.handler.restore(world, &update.id, &update.previous);
+            }
+            if let (Some(mark), Some(mut history)) = (mark, world.get_resource_mut::<History>()) {
+                history.rollback(mark);
+            }
+            // The state derived from the components, such as the bus views, follows them back
+            schedule.run(world);
+            if let Some(mut feed) = world.get_resource_mut::<ChangeFeed>() {
+                feed.discard();
+            }
+            return Err(error);
+        }
+
+        release_changes(world);
+        Ok(staged.len())
+    }
 }
 
-async fn update_iidm<C, U, E>(
-    State(state): State<Arc<AppState>>,
-    Json(payload): Json<RegisterRequest>,
+/// Sends the event of an update checked beforehand
+type SendFn = Box<dyn FnOnce(&mut World)>;
+
+/// Update of a batch once checked, waiting for the others
+struct StagedUpdate<'a> {
+    index: usize,
+    handler: &'a dyn BatchHandler,
+    id: String,
+    /// Serialized component to roll back to
+    previous: serde_json::Value,
+    /// Taken once the whole batch is checked
+    send: Option<SendFn>,
+}
+
+// Type-erased steps of a batch update, for one component type
+trait BatchHandler: Send + Sync {
+    /// Checks an update without applying it, returns the current component and the sending of
+    /// the update event
+    fn prepare(
+        &self,
+        world: &World,
+        id: &str,
+        body: UpdateBody,
+    ) -> Result<(serde_json::Value, SendFn), UpdateError>;
+
+    /// Refusal of the update by the event handlers, raised after the cursors
+    fn check(
+        &self,
+        world: &World,
+        errors: EventCursor<ComponentErrorEvent>,
+        references: EventCursor<InvalidReferenceEvent>,
+        id: &str,
+    ) -> Result<(), UpdateError>;
+
+    /// Serialized component
+    fn read(&self, world: &World, id: &str) -> Option<serde_json::Value>;
+
+    /// Serialized component with its location
+    fn change(&self, world: &World, id: &str) -> Option<Change>;
+
+    /// Puts a serialized component back when it changed
+    fn restore(&self, world: &mut World, id: &str, previous: &serde_json::Value);
+}
+
+struct TypedBatchHandler<C>(PhantomData<fn() -> C>);
+
+impl<C> BatchHandler for TypedBatchHandler<C>
+where
+    C: Updatable + Patchable + Validate + References + Component + Clone + 'static,
+    C::Updater: JsonSchema + 'static,
+    <C::Updater as JsonSchema>::Err: Display,
+{
+    fn prepare(
+        &self,
+        world: &World,
+        id: &str,
+        body: UpdateBody,
+    ) -> Result<(serde_json::Value, SendFn), UpdateError> {
+        verify_resources::<C>(world)?;
+        let previous = self.read(world, id).ok_or_else(|| {
+            UpdateError::NotFoundError(format!(
+                "{} with ID '{}' not found",
+                short_type_name::<C>(),
+                id
+            ))
+        })?;
+
+        let id = id.to_string();
+        let send: SendFn = match body {
+            UpdateBody::Updater(component) => {
+                let json_str = serde_json::to_string(&component)?;
+                let updater = parse_and_validate_json::<C::Updater>(&json_str)?;
+                Box::new(move |world: &mut World| {
+                    world.send_event(UpdateEvent::<C> { id, updater });
+                })
+            }
+            UpdateBody::Patch(patch) => {
+                // A patch which does not apply is refused before anything is sent
+                let current = serde_json::from_value::<C>(previous.clone())?;
+                patch
+                    .apply(&current)
+                    .map_err(|error| UpdateError::PatchError(error.to_string()))?;
+                Box::new(move |world: &mut World| {
+                    world.send_event(PatchEvent::<C>::new(id, patch));
+                })
+            }
+        };
+        Ok((previous, send))
+    }
+
+    fn check(
+        &self,
+        world: &World,
+        mut errors: EventCursor<ComponentErrorEvent>,
+        mut references: EventCursor<InvalidReferenceEvent>,
+        id: &str,
+    ) -> Result<(), UpdateError> {
+        check_for_errors::<C>(world, &mut errors, id)?;
+        check_for_invalid_references::<C>(world, &mut references, id)
+    }
+
+    fn read(&self, world: &World, id: &str) -> Option<serde_json::Value> {
+        read_component::<C>(world, id)
+    }
+
+    fn change(&self, world: &World, id: &str) -> Option<Change> {
+        component_change::<C>(world, id)
+    }
+
+    fn restore(&self, world: &mut World, id: &str, previous: &serde_json::Value) {
+        if self.read(world, id).as_ref() == Some(previous) {
+            return;
+        }
+        let (Some(entity), Ok(restored)) = (
+            world
+                .get_resource::<AssetRegistry>()
+                .and_then(|registry| registry.find_typed::<C>(id)),
+            serde_json::from_value::<C>(previous.clone()),
+        ) else {
+            return;
+        };
+        if let Some(mut component) = world.get_mut::<C>(entity) {
+            *component = restored;
+        }
+    }
+}
+
+// Helper function to serialize the component registered under an ID
+fn read_component<C: Component + Updatable>(world: &World, id: &str) -> Option<serde_json::Value> {
+    let entity = world.get_resource::<AssetRegistry>()?.find_typed::<C>(id)?;
+    serde_json::to_value(world.get::<C>(entity)?).ok()
+}
+
+// Helper function to describe a component for the subscribers, under its short type name
+fn component_change<C: Component + Updatable + References>(
+    world: &World,
+    id: &str,
+) -> Option<Change> {
+    let entity = world.get_resource::<AssetRegistry>()?.find_typed::<C>(id)?;
+    Some(Change {
+        component_type: short_type_name::<C>().to_string(),
+        id: id.to_string(),
+        component: serde_json::to_value(world.get::<C>(entity)?).ok()?,
+        location: location::<C>(world, entity),
+    })
+}
+
+fn unknown_component_type(component_type: &str) -> UpdateError {
+    UpdateError::NotFoundError(format!(
+        "No handler registered for component type: {}",
+        component_type
+    ))
+}
+
+async fn update_iidm<C, U>(
+    state: Arc<NetworkState>,
+    id: String,
+    body: UpdateBody,
 ) -> Result<impl IntoResponse, UpdateError>
 where
-    C: Updatable<Updater = U> + Component + 'static,
+    C: Updatable<Updater = U> + Patchable + Validate + References + Component + Clone + 'static,
     U: JsonSchema + Send + Sync + 'static,
     U::Err: Display,
 {
-    tracing::debug!("Received update request for component ID: {}", payload.id);
+    tracing::debug!("Received update request for component ID: {}", id);
 
-    update_component::<C, U, E>(&state, &payload).await?;
+    update_component::<C, U>(&state, id, body).await?;
 
     Ok((
         StatusCode::OK,
@@ -81,12 +368,13 @@ where
     ))
 }
 
-async fn update_component<C, U, E>(
-    state: &Arc<AppState>,
-    payload: &RegisterRequest,
+async fn update_component<C, U>(
+    state: &Arc<NetworkState>,
+    id: String,
+    body: UpdateBody,
 ) -> Result<(), UpdateError>
 where
-    C: Updatable<Updater = U> + Component + 'static,
+    C: Updatable<Updater = U> + Patchable + Validate + References + Component + Clone + 'static,
     U: JsonSchema + Send + Sync + 'static,
     U::Err: Display,
 {
@@ -94,34 +382,20 @@ where
     let ecs = state.ecs.read().await;
     let mut world = ecs.world.write().await;
     let mut schedule = ecs.schedule.write().await;
-    let id = payload.id.clone();
 
     // Verify required resources exist
     verify_resources::<C>(&world)?;
 
-    // Parse and validate the JSON
-    let json_str = serde_json::to_string(&payload.component)?;
-    let update = parse_and_validate_json::<U>(&json_str)?;
-
-    // Process the update
-    process_update::<C, U, E>(&mut world, &mut schedule, &id, update)?;
+    match body {
+        UpdateBody::Updater(component) => {
+            // Parse and validate the JSON
+            let json_str = serde_json::to_string(&component)?;
+            let update = parse_and_validate_json::<U>(&json_str)?;
 
-    // TODO: Update with SSE events, very dirty code
-    let sse_registry = ecs.sse_registry.read().await;
-    let asset_registry = world.resource::<AssetRegistry>();
-    let component_type = std::any::type_name::<C>(); // Ou extraire le nom du type d'une autre façon
-
-    {
-        if let Some(entity) = asset_registry.find(&id) {
-            // Si l'entité existe, récupérer le composant
-            if let Some(component) = world.entity(entity).get::<C>() {
-                // Sérialiser le composant complet
-                if let Ok(component_json) = serde_json::to_string(component) {
-                    // Envoyer le composant complet via SSE
-                    sse_registry.publish_update(component_type, &id, &component_json);
-                }
-            }
+            // Process the update
+            process_update::<C, U>(&mut world, &mut schedule, &id, update)?;
         }
+        UpdateBody::Patch(patch) => process_patch::<C>(&mut world, &mut schedule, &id, patch)?,
     }
 
     tracing::debug!("Successfully updated component: {}", id);
@@ -131,7 +405,7 @@ where
 // Helper function to verify resources
 fn verify_resources<C>(world: &bevy_ecs::world::World) -> Result<(), UpdateError>
 where
-    C: Updatable + 'static,
+    C: Updatable + Component + 'static,
 {
     // Check for UpdateEvent resource
     if !world.contains_resource::<Events<UpdateEvent<C>>>() {
@@ -145,14 +419,29 @@ where
         )));
     }
 
-    // Check for EntityNotFoundEvent resource
-    if !world.contains_resource::<Events<EntityNotFoundEvent>>() {
-        tracing::error!("Events<EntityNotFoundEvent> not initialized");
+    // Check for PatchEvent resource
+    if !world.contains_resource::<Events<PatchEvent<C>>>() {
+        return Err(UpdateError::InternalError(format!(
+            "Patch system for {} not initialized",
+            std::any::type_name::<C>()
+        )));
+    }
+
+    // Check for ComponentErrorEvent resource
+    if !world.contains_resource::<Events<ComponentErrorEvent>>() {
+        tracing::error!("Events<ComponentErrorEvent> not initialized");
         return Err(UpdateError::InternalError(
             "Error errors system not initialized".to_string(),
         ));
     }
 
+    // Check for InvalidReferenceEvent resource
+    if !world.contains_resource::<Events<InvalidReferenceEvent>>() {
+        return Err(UpdateError::InternalError(
+            "Reference errors system not initialized".to_string(),
+        ));
+    }
+
     Ok(())
 }
 
@@ -166,7 +455,7 @@ where
 }
 
 // Helper function to process the update
-fn process_update<C, U, E>(
+fn process_update<C, U>(
     world: &mut bevy_ecs::world::World,
     schedule: &mut bevy_ecs::schedule::Schedule,
     id: &str,
@@ -186,21 +475,59 @@ where
         id: id.to_string(),
         updater: update,
     });
+    let mut error_reader = world
+        .resource::<Events<ComponentErrorEvent>>()
+        .get_cursor_current();
+    let mut reference_reader = world
+        .resource::<Events<InvalidReferenceEvent>>()
+        .get_cursor_current();
 
     // Run the schedule to process the event
     schedule.run(world);
 
     // Check for errors
-    check_for_errors(world, id)
+    check_for_errors::<C>(world, &mut error_reader, id)?;
+    check_for_invalid_references::<C>(world, &mut reference_reader, id)
 }
 
-// Helper function to check for errors after update
-fn check_for_errors(world: &bevy_ecs::world::World, id: &str) -> Result<(), UpdateError> {
-    let error_events = world.resource::<Events<EntityNotFoundEvent>>();
-    let mut error_reader = error_events.get_cursor();
+// Helper function to process a merge patch or a JSON Patch
+fn process_patch<C>(
+    world: &mut bevy_ecs::world::World,
+    schedule: &mut bevy_ecs::schedule::Schedule,
+    id: &str,
+    patch: Patch,
+) -> Result<(), UpdateError>
+where
+    C: Patchable + Updatable + Validate + References + Component + Clone + 'static,
+{
+    world
+        .get_resource_mut::<Events<PatchEvent<C>>>()
+        .ok_or_else(|| UpdateError::InternalError("Event system not initialized".to_string()))?
+        .send(PatchEvent::new(id, patch));
+    let mut error_reader = world
+        .resource::<Events<ComponentErrorEvent>>()
+        .get_cursor_current();
+    let mut reference_reader = world
+        .resource::<Events<InvalidReferenceEvent>>()
+        .get_cursor_current();
+
+    schedule.run(world);
+
+    check_for_errors::<C>(world, &mut error_reader, id)?;
+    check_for_invalid_references::<C>(world, &mut reference_reader, id)
+}
+
+// Helper function to check for the errors raised for a component since the cursor was taken
+fn check_for_errors<C: 'static>(
+    world: &bevy_ecs::world::World,
+    error_reader: &mut EventCursor<ComponentErrorEvent>,
+    id: &str,
+) -> Result<(), UpdateError> {
+    let error_events = world.resource::<Events<ComponentErrorEvent>>();
+    let component_type = std::any::type_name::<C>();
 
     for error in error_reader.read(error_events) {
-        if error.id == id {
+        if error.id == id && error.component_type == component_type {
             match error.error_type {
                 ErrorType::EntityNotFound => {
                     return Err(UpdateError::NotFoundError(format!(
@@ -214,6 +541,28 @@ fn check_for_errors(world: &bevy_ecs::world::World, id: &str) -> Result<(), Upda
                         error.component_type, id
                     )));
                 }
+                ErrorType::EntityAlreadyExists => {
+                    return Err(UpdateError::ValidationError(format!(
+                        "Entity with ID '{}' already exists",
+                        id
+                    )));
+                }
+                ErrorType::InvalidPatch(ref message) => {
+                    return Err(UpdateError::PatchError(message.clone()));
+                }
+                ErrorType::Invalid(ref violations) => {
+                    return Err(UpdateError::InvalidComponent(violations.clone()));
+                }
+                ErrorType::InvalidParent(ref message) | ErrorType::Required(ref message) => {
+                    return Err(UpdateError::ValidationError(message.clone()));
+                }
+                ErrorType::Referenced(ref referrers) => {
+                    return Err(UpdateError::ValidationError(format!(
+                        "Entity with ID '{}' is referenced by {}",
+                        id,
+                        referrers.join(", ")
+                    )));
+                }
             }
         }
     }
@@ -221,6 +570,31 @@ fn check_for_errors(world: &bevy_ecs::world::World, id: &str) -> Result<(), Upda
     Ok(())
 }
 
+// Helper function to report the references the change pointed to and which do not exist
+fn check_for_invalid_references<C: 'static>(
+    world: &bevy_ecs::world::World,
+    reference_reader: &mut EventCursor<InvalidReferenceEvent>,
+    id: &str,
+) -> Result<(), UpdateError> {
+    let reference_events = world.resource::<Events<InvalidReferenceEvent>>();
+    let component_type = std::any::type_name::<C>();
+
+    match reference_reader
+        .read(reference_events)
+        .find(|event| event.id == id && event.component_type == component_type)
+    {
+        Some(event) => Err(UpdateError::ReferenceError(
+            event
+                .references
+                .iter()
+                .map(Reference::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        )),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -228,26 +602,18 @@ mod tests {
     use serde::{Deserialize, Serialize};
 
     // Mock types for testing
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize, Component)]
     struct MockComponent;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     struct MockUpdater;
 
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    struct MockError;
-
-    impl Display for MockError {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "Mock error")
-        }
-    }
-
     impl Updatable for MockComponent {
         type Updater = MockUpdater;
 
-        fn update(&mut self, _updates: Self::Updater) {
+        fn update(&mut self, _updates: Self::Updater) -> Result<(), iidm::PatchError> {
             // Mock implementation
+            Ok(())
         }
     }
 
@@ -277,7 +643,9 @@ mod tests {
 
         // Add required resources
         world.insert_resource(Events::<UpdateEvent<MockComponent>>::default());
-        world.insert_resource(Events::<EntityNotFoundEvent>::default());
+        world.insert_resource(Events::<PatchEvent<MockComponent>>::default());
+        world.insert_resource(Events::<ComponentErrorEvent>::default());
+        world.insert_resource(Events::<InvalidReferenceEvent>::default());
 
         // Test with all resources present
         let result = verify_resources::<MockComponent>(&world);
@@ -287,22 +655,159 @@ mod tests {
     #[test]
     fn test_check_for_errors() {
         let mut world = World::new();
-        world.insert_resource(Events::<EntityNotFoundEvent>::default());
+        world.insert_resource(Events::<ComponentErrorEvent>::default());
 
         // Test with no errors
-        let result = check_for_errors(&world, "test_id");
+        let mut error_reader = world.resource::<Events<ComponentErrorEvent>>().get_cursor();
+        let result = check_for_errors::<MockComponent>(&world, &mut error_reader, "test_id");
         assert!(result.is_ok());
 
         // Add an error event
-        let mut error_events = world.resource_mut::<Events<EntityNotFoundEvent>>();
-        error_events.send(EntityNotFoundEvent {
+        let mut error_events = world.resource_mut::<Events<ComponentErrorEvent>>();
+        error_events.send(ComponentErrorEvent {
             id: "test_id".to_string(),
             error_type: ErrorType::EntityNotFound,
-            component_type: "MockComponent".to_string(),
+            component_type: std::any::type_name::<MockComponent>().to_string(),
         });
 
         // Test with an error
-        let result = check_for_errors(&world, "test_id");
+        let result = check_for_errors::<MockComponent>(&world, &mut error_reader, "test_id");
         assert!(result.is_err());
+
+        // Errors already read are not reported again
+        let result = check_for_errors::<MockComponent>(&world, &mut error_reader, "test_id");
+        assert!(result.is_ok());
+    }
+
+    async fn loaded_state() -> Arc<NetworkState> {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../iidm/tests/data/network.json");
+        let network: iidm::Network = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        let state = Arc::new(NetworkState::default());
+        {
+            let ecs = state.ecs.read().await;
+            let mut world = ecs.world.write().await;
+            let mut schedule = ecs.schedule.write().await;
+            iidm::Identifiable::register(&network, &mut world, &mut schedule);
+        }
+        state
+    }
+
+    fn batch_update(component_type: &str, id: &str, component: serde_json::Value) -> BatchUpdate {
+        BatchUpdate {
+            component_type: component_type.to_string(),
+            id: id.to_string(),
+            body: UpdateBody::Updater(component),
+        }
+    }
+
+    async fn read(state: &Arc<NetworkState>) -> (f64, f64, usize) {
+        let ecs = state.ecs.read().await;
+        let world = ecs.world.read().await;
+        let registry = world.resource::<AssetRegistry>();
+        let line = registry.find_typed::<iidm::Line>("NHV1_NHV2_1").unwrap();
+        let generator = registry.find_typed::<iidm::Generator>("GEN").unwrap();
+        (
+            world.get::<iidm::Line>(line).unwrap().r,
+            world.get::<iidm::Generator>(generator).unwrap().target_p,
+            world.resource::<History>().entries().len(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_batch_applied_as_a_whole() {
+        let state = loaded_state().await;
+        let ecs = state.ecs.read().await;
+        let registry = ecs.update_registry.read().await;
+
+        let updated = registry
+            .update_batch(
+                &state,
+                vec![
+                    batch_update("Line", "NHV1_NHV2_1", serde_json::json!({"r": 4.0})),
+                    BatchUpdate {
+                        component_type: "Generator".to_string(),
+                        id: "GEN".to_string(),
+                        body: UpdateBody::Patch(Patch::Merge(
+                            serde_json::json!({"targetP": 500.0}),
+                        )),
+                    },
+                ],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(updated, 2);
+        assert_eq!(read(&state).await, (4.0, 500.0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_batch_rolled_back() {
+        let state = loaded_state().await;
+        let ecs = state.ecs.read().await;
+        let registry = ecs.update_registry.read().await;
+
+        // Refused by the network validation once the line is already modified
+        let result = registry
+            .update_batch(
+                &state,
+                vec![
+                    batch_update("Line", "NHV1_NHV2_1", serde_json::json!({"r": 4.0})),
+                    batch_update("Generator", "GEN", serde_json::json!({"targetP": 20000.0})),
+                ],
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(UpdateError::BatchItem { index: 1, ref source })
+                if matches!(**source, UpdateError::InvalidComponent(_))
+        ));
+        assert_eq!(read(&state).await, (3.0, 607.0, 0));
+
+        // Refused before anything is sent
+        let result = registry
+            .update_batch(
+                &state,
+                vec![
+                    batch_update("Line", "NHV1_NHV2_1", serde_json::json!({"r": 4.0})),
+                    batch_update("Line", "NHV1_NHV2_1", serde_json::json!({"x": 4.0})),
+                ],
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(UpdateError::BatchItem { index: 1, .. })
+        ));
+        let result = registry
+            .update_batch(
+                &state,
+                vec![batch_update("Unknown", "X", serde_json::json!({}))],
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(UpdateError::BatchItem { index: 0, .. })
+        ));
+        assert_eq!(read(&state).await, (3.0, 607.0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_filtered_by_location() {
+        let state = loaded_state().await;
+        let ecs = state.ecs.read().await;
+        let world = ecs.world.read().await;
+        let registry = ecs.update_registry.read().await;
+
+        let filter = ChangeFilter {
+            types: ["Line".to_string(), "Generator".to_string()].into(),
+            voltage_levels: ["VLHV2".to_string()].into(),
+            ..Default::default()
+        };
+        let ids: Vec<String> = registry
+            .snapshot(&world, &filter)
+            .into_iter()
+            .map(|change| format!("{}:{}", change.component_type, change.id))
+            .collect();
+        assert_eq!(ids, vec!["Line:NHV1_NHV2_1", "Line:NHV1_NHV2_2"]);
     }
 }
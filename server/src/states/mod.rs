@@ -1,9 +1,41 @@
 mod ecs;
+mod networks;
+mod persistence;
+mod scenario;
+mod upload;
 
 use ecs::EcsState;
+pub use ecs::{Change, ChangeFilter, ChangeSet, StagedWorld};
+pub use networks::*;
+pub use persistence::*;
+pub use scenario::*;
 use tokio::sync::RwLock;
+pub use upload::*;
 
+/// World of one network and the registries dispatching requests to it
 #[derive(Default)]
-pub struct AppState {
+pub struct NetworkState {
     pub ecs: RwLock<EcsState>,
+    pub scenario: ScenarioTask,
+    pub uploads: UploadFeed,
+}
+
+impl NetworkState {
+    /// Whether the network is used without requests, by its subscribers or its scenario
+    pub async fn is_live(&self) -> bool {
+        self.scenario.is_running()
+            || self
+                .ecs
+                .read()
+                .await
+                .sse_registry
+                .read()
+                .await
+                .has_subscribers()
+    }
+}
+
+#[derive(Default)]
+pub struct AppState {
+    pub networks: Networks,
 }
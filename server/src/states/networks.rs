@@ -0,0 +1,365 @@
+use axum::{
+    extract::{FromRequestParts, RawPathParams},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+use super::{AppState, NetworkState};
+
+/// Network used by the routes without a network id, it is never evicted
+pub const DEFAULT_NETWORK: &str = "default";
+
+/// Path parameter naming the network of the nested routes
+const NETWORK_PARAM: &str = "network_id";
+
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_MAX_NETWORKS: usize = 16;
+const MAX_ID_LENGTH: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum NetworksError {
+    #[error("Network '{0}' not found")]
+    NotFound(String),
+
+    #[error("Network '{0}' already exists")]
+    AlreadyExists(String),
+
+    #[error("Invalid network id '{0}': use up to 64 letters, digits, '-' or '_'")]
+    InvalidId(String),
+
+    #[error("Network '{0}' cannot be deleted")]
+    Reserved(String),
+
+    #[error("Too many networks, at most {0} can be open")]
+    TooMany(usize),
+}
+
+#[derive(Debug, Serialize)]
+struct NetworksErrorResponse {
+    status: String,
+}
+
+impl IntoResponse for NetworksError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            NetworksError::NotFound(_) => StatusCode::NOT_FOUND,
+            NetworksError::AlreadyExists(_) => StatusCode::CONFLICT,
+            NetworksError::InvalidId(_) | NetworksError::Reserved(_) => StatusCode::BAD_REQUEST,
+            NetworksError::TooMany(_) => StatusCode::SERVICE_UNAVAILABLE,
+        };
+        let body = Json(NetworksErrorResponse {
+            status: self.to_string(),
+        });
+        (status, body).into_response()
+    }
+}
+
+struct Session {
+    state: Arc<NetworkState>,
+    /// Milliseconds between the creation of the registry and the last request
+    last_used: AtomicU64,
+}
+
+/// Networks opened on the server, each in its own world
+pub struct Networks {
+    sessions: RwLock<HashMap<String, Session>>,
+    started: Instant,
+    idle_timeout: Duration,
+    max_networks: usize,
+}
+
+impl Default for Networks {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDLE_TIMEOUT, DEFAULT_MAX_NETWORKS)
+    }
+}
+
+/// Summary of an open network
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkSummary {
+    pub id: String,
+    /// Id of the loaded IIDM network, if any
+    pub network: Option<String>,
+    pub idle_seconds: u64,
+}
+
+impl Networks {
+    pub fn new(idle_timeout: Duration, max_networks: usize) -> Self {
+        let sessions = HashMap::from([(DEFAULT_NETWORK.to_string(), Self::session(0))]);
+        Self {
+            sessions: RwLock::new(sessions),
+            started: Instant::now(),
+            idle_timeout,
+            max_networks,
+        }
+    }
+
+    fn session(now: u64) -> Session {
+        Session {
+            state: Arc::new(NetworkState::default()),
+            last_used: AtomicU64::new(now),
+        }
+    }
+
+    fn now(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// State of a network, which is marked as used
+    pub async fn get(&self, id: &str) -> Result<Arc<NetworkState>, NetworksError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(id)
+            .ok_or_else(|| NetworksError::NotFound(id.to_string()))?;
+        session.last_used.store(self.now(), Ordering::Relaxed);
+        Ok(session.state.clone())
+    }
+
+    /// Opens an empty network, to be filled by an upload
+    pub async fn create(&self, id: &str) -> Result<Arc<NetworkState>, NetworksError> {
+        let valid = !id.is_empty()
+            && id.len() <= MAX_ID_LENGTH
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(NetworksError::InvalidId(id.to_string()));
+        }
+
+        let mut sessions = self.sessions.write().await;
+        if sessions.contains_key(id) {
+            return Err(NetworksError::AlreadyExists(id.to_string()));
+        }
+        if sessions.len() >= self.max_networks {
+            return Err(NetworksError::TooMany(self.max_networks));
+        }
+
+        let session = Self::session(self.now());
+        let state = session.state.clone();
+        sessions.insert(id.to_string(), session);
+        tracing::info!("Opened network {}", id);
+        Ok(state)
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<(), NetworksError> {
+        if id == DEFAULT_NETWORK {
+            return Err(NetworksError::Reserved(id.to_string()));
+        }
+        self.sessions
+            .write()
+            .await
+            .remove(id)
+            .map(|_| tracing::info!("Closed network {}", id))
+            .ok_or_else(|| NetworksError::NotFound(id.to_string()))
+    }
+
+    /// States of the open networks, without marking them as used
+    pub async fn states(&self) -> Vec<(String, Arc<NetworkState>)> {
+        let sessions = self.sessions.read().await;
+        sessions
+            .iter()
+            .map(|(id, session)| (id.clone(), session.state.clone()))
+            .collect()
+    }
+
+    /// Open networks sorted by id
+    pub async fn list(&self) -> Vec<NetworkSummary> {
+        let sessions = self.sessions.read().await;
+        let now = self.now();
+
+        let mut summaries = Vec::with_capacity(sessions.len());
+        for (id, session) in sessions.iter() {
+            let ecs = session.state.ecs.read().await;
+            let world = ecs.world.read().await;
+            let network = world
+                .iter_entities()
+                .find_map(|entity| entity.get::<iidm::Network>())
+                .map(|network| network.id.clone());
+            let idle = now.saturating_sub(session.last_used.load(Ordering::Relaxed));
+            summaries.push(NetworkSummary {
+                id: id.clone(),
+                network,
+                idle_seconds: idle / 1000,
+            });
+        }
+        summaries.sort_by(|a, b| a.id.cmp(&b.id));
+        summaries
+    }
+
+    /// Closes the networks unused for longer than the idle timeout, returns their ids. The
+    /// networks with subscribers or a running scenario are in use and marked as such.
+    pub async fn evict_idle(&self) -> Vec<String> {
+        let timeout = self.idle_timeout.as_millis() as u64;
+        let is_stale = |session: &Session, now: u64| {
+            now.saturating_sub(session.last_used.load(Ordering::Relaxed)) > timeout
+        };
+
+        // Requests keep reaching the networks while the candidates are checked
+        let now = self.now();
+        let candidates: Vec<(String, Arc<NetworkState>)> = self
+            .sessions
+            .read()
+            .await
+            .iter()
+            .filter(|(id, session)| *id != DEFAULT_NETWORK && is_stale(session, now))
+            .map(|(id, session)| (id.clone(), session.state.clone()))
+            .collect();
+
+        let mut unused = Vec::new();
+        for (id, state) in candidates {
+            if state.is_live().await {
+                self.touch(&id, &state).await;
+            } else {
+                unused.push((id, state));
+            }
+        }
+
+        // A network used or replaced meanwhile is kept
+        let now = self.now();
+        let mut sessions = self.sessions.write().await;
+        let mut idle = Vec::new();
+        for (id, state) in unused {
+            let evict = sessions.get(&id).is_some_and(|session| {
+                Arc::ptr_eq(&session.state, &state) && is_stale(session, now)
+            });
+            if evict {
+                sessions.remove(&id);
+                tracing::info!("Evicted idle network {}", id);
+                idle.push(id);
+            }
+        }
+        idle
+    }
+
+    /// Marks a network in use without a request
+    async fn touch(&self, id: &str, state: &Arc<NetworkState>) {
+        if let Some(session) = self.sessions.read().await.get(id) {
+            if Arc::ptr_eq(&session.state, state) {
+                session.last_used.store(self.now(), Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Closes the idle networks periodically
+pub async fn evict_idle_networks(state: Arc<AppState>) {
+    let period = (state.networks.idle_timeout() / 4).max(Duration::from_secs(1));
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        state.networks.evict_idle().await;
+    }
+}
+
+/// Network targeted by a request: the one named by the `network_id` path parameter, or the
+/// default one for the routes outside `/api/networks/{network_id}`
+pub struct Workspace(pub Arc<NetworkState>);
+
+impl FromRequestParts<Arc<AppState>> for Workspace {
+    type Rejection = NetworksError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let id = RawPathParams::from_request_parts(parts, state)
+            .await
+            .ok()
+            .and_then(|params| {
+                params
+                    .iter()
+                    .find(|(key, _)| *key == NETWORK_PARAM)
+                    .map(|(_, value)| value.to_string())
+            })
+            .unwrap_or_else(|| DEFAULT_NETWORK.to_string());
+
+        state.networks.get(&id).await.map(Workspace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_create_and_remove() {
+        let networks = Networks::new(DEFAULT_IDLE_TIMEOUT, 2);
+
+        assert!(networks.get(DEFAULT_NETWORK).await.is_ok());
+        assert!(networks.create("trainee-1").await.is_ok());
+        assert!(matches!(
+            networks.create("trainee-1").await,
+            Err(NetworksError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            networks.create("trainee-2").await,
+            Err(NetworksError::TooMany(2))
+        ));
+        assert!(matches!(
+            networks.create("../etc").await,
+            Err(NetworksError::InvalidId(_))
+        ));
+
+        let ids: Vec<String> = networks.list().await.into_iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec!["default", "trainee-1"]);
+
+        assert!(networks.remove("trainee-1").await.is_ok());
+        assert!(matches!(
+            networks.remove(DEFAULT_NETWORK).await,
+            Err(NetworksError::Reserved(_))
+        ));
+        assert!(matches!(
+            networks.get("trainee-1").await,
+            Err(NetworksError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_evict_idle_keeps_default() {
+        let networks = Networks::new(Duration::ZERO, 4);
+        networks.create("trainee-1").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        assert_eq!(networks.evict_idle().await, vec!["trainee-1".to_string()]);
+        assert!(networks.get(DEFAULT_NETWORK).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_evict_idle_keeps_live_networks() {
+        let networks = Networks::new(Duration::ZERO, 4);
+        let subscribed = networks.create("trainee-1").await.unwrap();
+        let running = networks.create("trainee-2").await.unwrap();
+        let subscriber = {
+            let ecs = subscribed.ecs.read().await;
+            let sse_registry = ecs.sse_registry.read().await;
+            sse_registry.subscribe_changes()
+        };
+        // Without a scenario, the task stops on its first tick
+        running.scenario.start(&running);
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        assert!(networks.evict_idle().await.is_empty());
+        assert!(networks.get("trainee-1").await.is_ok());
+        assert!(networks.get("trainee-2").await.is_ok());
+
+        drop(subscriber);
+        running.scenario.stop();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let mut evicted = networks.evict_idle().await;
+        evicted.sort();
+        assert_eq!(evicted, vec!["trainee-1", "trainee-2"]);
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use iidm::{export_network, ExportError, Network};
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use crate::handlers::{load_network, UploadError};
+
+use super::{AppState, NetworkState, Networks, NetworksError, DEFAULT_NETWORK};
+
+/// Environment variable naming the directory of the snapshots, persistence is off without it
+pub const DATA_DIR_VAR: &str = "MENTOR_DATA_DIR";
+
+/// Environment variable giving the number of seconds between two snapshots
+pub const SNAPSHOT_INTERVAL_VAR: &str = "MENTOR_SNAPSHOT_INTERVAL";
+
+const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+const SNAPSHOT_EXTENSION: &str = "json";
+
+#[derive(Debug, Error)]
+pub enum PersistenceError {
+    #[error("Failed to access '{}': {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid snapshot '{}': {source}", path.display())]
+    Snapshot {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error("Snapshot '{}' refused: {source}", path.display())]
+    Refused { path: PathBuf, source: UploadError },
+
+    #[error("Network '{id}' has elements which cannot be placed in a snapshot: {}", elements.join(", "))]
+    Unplaced { id: String, elements: Vec<String> },
+
+    #[error("Failed to serialize network '{id}': {source}")]
+    Serialization {
+        id: String,
+        source: serde_json::Error,
+    },
+
+    #[error("{0}")]
+    Network(#[from] NetworksError),
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> PersistenceError + '_ {
+    move |source| PersistenceError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// Snapshots of the open networks, one IIDM JSON file per network named after its id.
+///
+/// The files hold the exported network, so they can also be uploaded as is. The edit journal
+/// and the checkpoints are not kept. A network with elements left out of the export is not
+/// saved, its previous snapshot is kept.
+pub struct Persistence {
+    data_dir: PathBuf,
+    interval: Duration,
+    /// Hash of the last snapshot written for each network
+    written: Mutex<HashMap<String, u64>>,
+}
+
+impl Persistence {
+    pub fn new(data_dir: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self {
+            data_dir: data_dir.into(),
+            interval,
+            written: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Configuration read from `MENTOR_DATA_DIR` and `MENTOR_SNAPSHOT_INTERVAL`
+    pub fn from_env() -> Option<Self> {
+        let data_dir = std::env::var_os(DATA_DIR_VAR).filter(|dir| !dir.is_empty())?;
+        let interval = std::env::var(SNAPSHOT_INTERVAL_VAR)
+            .ok()
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .filter(|seconds| *seconds > 0)
+            .map_or(DEFAULT_SNAPSHOT_INTERVAL, Duration::from_secs);
+        Some(Self::new(data_dir, interval))
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn snapshot_path(&self, id: &str) -> PathBuf {
+        self.data_dir.join(format!("{}.{}", id, SNAPSHOT_EXTENSION))
+    }
+
+    /// Writes the snapshot of every network changed since the last call, and removes the ones
+    /// of the networks closed since. Returns the number of snapshots written, or the first
+    /// network which could not be saved once the others are.
+    pub async fn save(&self, networks: &Networks) -> Result<usize, PersistenceError> {
+        tokio::fs::create_dir_all(&self.data_dir)
+            .await
+            .map_err(io_error(&self.data_dir))?;
+
+        let mut written = self.written.lock().await;
+        let open = networks.states().await;
+        let mut count = 0;
+        let mut unsaved = None;
+
+        for (id, state) in &open {
+            let bytes = match snapshot(id, state).await {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => continue,
+                Err(error @ PersistenceError::Unplaced { .. }) => {
+                    unsaved.get_or_insert(error);
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            let hash = hash(&bytes);
+            if written.get(id) == Some(&hash) {
+                continue;
+            }
+
+            // Written aside then renamed, a crash never leaves a truncated snapshot
+            let path = self.snapshot_path(id);
+            let temporary = path.with_extension("tmp");
+            tokio::fs::write(&temporary, &bytes)
+                .await
+                .map_err(io_error(&temporary))?;
+            tokio::fs::rename(&temporary, &path)
+                .await
+                .map_err(io_error(&path))?;
+
+            written.insert(id.clone(), hash);
+            count += 1;
+        }
+
+        let closed: Vec<String> = written
+            .keys()
+            .filter(|id| !open.iter().any(|(open_id, _)| open_id == *id))
+            .cloned()
+            .collect();
+        for id in closed {
+            let path = self.snapshot_path(&id);
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => return Err(io_error(&path)(error)),
+            }
+            written.remove(&id);
+            tracing::debug!("Removed snapshot of closed network {}", id);
+        }
+
+        match unsaved {
+            Some(error) => Err(error),
+            None => Ok(count),
+        }
+    }
+
+    /// Reopens the networks found in the data directory and loads their snapshot. An unreadable
+    /// or refused snapshot is skipped and left in place. Returns the ids of the restored networks.
+    pub async fn restore(&self, networks: &Networks) -> Result<Vec<String>, PersistenceError> {
+        let mut entries = match tokio::fs::read_dir(&self.data_dir).await {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(io_error(&self.data_dir)(error)),
+        };
+
+        let mut restored = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(io_error(&self.data_dir))?
+        {
+            let path = entry.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(SNAPSHOT_EXTENSION)
+            {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            match self.restore_network(networks, id, &path).await {
+                Ok(hash) => {
+                    self.written.lock().await.insert(id.to_string(), hash);
+                    restored.push(id.to_string());
+                }
+                Err(error) => tracing::error!("Skipped snapshot of network {}: {}", id, error),
+            }
+        }
+
+        restored.sort();
+        Ok(restored)
+    }
+
+    async fn restore_network(
+        &self,
+        networks: &Networks,
+        id: &str,
+        path: &Path,
+    ) -> Result<u64, PersistenceError> {
+        let bytes = tokio::fs::read(path).await.map_err(io_error(path))?;
+        let network: Network =
+            serde_json::from_slice(&bytes).map_err(|source| PersistenceError::Snapshot {
+                path: path.to_path_buf(),
+                source,
+            })?;
+
+        let state = if id == DEFAULT_NETWORK {
+            networks.get(id).await?
+        } else {
+            networks.create(id).await?
+        };
+        // Checked and staged as an upload, a fresh world comes without any edit journal
+        if let Err(source) = load_network(&state, network).await {
+            if id != DEFAULT_NETWORK {
+                networks.remove(id).await?;
+            }
+            return Err(PersistenceError::Refused {
+                path: path.to_path_buf(),
+                source,
+            });
+        }
+
+        tracing::info!("Restored network {} from {}", id, path.display());
+        Ok(hash(&bytes))
+    }
+}
+
+/// Serialized export of a network, none when nothing has been loaded in it
+async fn snapshot(
+    id: &str,
+    state: &Arc<NetworkState>,
+) -> Result<Option<Vec<u8>>, PersistenceError> {
+    let ecs = state.ecs.read().await;
+    let world = ecs.world.read().await;
+    let export = match export_network(&world) {
+        Ok(export) => export,
+        Err(ExportError::NoNetwork) => return Ok(None),
+    };
+    if !export.unplaced.is_empty() {
+        return Err(PersistenceError::Unplaced {
+            id: id.to_string(),
+            elements: export.unplaced,
+        });
+    }
+
+    serde_json::to_vec(&export.network)
+        .map(Some)
+        .map_err(|source| PersistenceError::Serialization {
+            id: id.to_string(),
+            source,
+        })
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Writes the snapshots periodically
+pub async fn save_networks_periodically(state: Arc<AppState>, persistence: Arc<Persistence>) {
+    let mut interval = tokio::time::interval(persistence.interval());
+    // The first tick completes immediately, right after the restore
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        match persistence.save(&state.networks).await {
+            Ok(0) => {}
+            Ok(count) => tracing::debug!("Saved {} network snapshots", count),
+            Err(error) => tracing::error!("Failed to save the networks: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iidm::{Generator, Identifiable, RegisterEvent};
+
+    fn data_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mentor-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    async fn load(state: &Arc<NetworkState>) {
+        let path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../iidm/tests/data/network.json");
+        let network: Network = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        let ecs = state.ecs.read().await;
+        let mut world = ecs.world.write().await;
+        let mut schedule = ecs.schedule.write().await;
+        network.register(&mut world, &mut schedule);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_restore() {
+        let dir = data_dir("restore");
+        let persistence = Persistence::new(&dir, DEFAULT_SNAPSHOT_INTERVAL);
+        let networks = Networks::default();
+        load(&networks.get(DEFAULT_NETWORK).await.unwrap()).await;
+        load(&networks.create("trainee-1").await.unwrap()).await;
+        networks.create("empty").await.unwrap();
+
+        assert_eq!(persistence.save(&networks).await.unwrap(), 2);
+        assert!(dir.join("default.json").exists());
+        assert!(!dir.join("empty.json").exists());
+        // Unchanged networks are not written again
+        assert_eq!(persistence.save(&networks).await.unwrap(), 0);
+
+        let restarted = Networks::default();
+        let restored = Persistence::new(&dir, DEFAULT_SNAPSHOT_INTERVAL)
+            .restore(&restarted)
+            .await
+            .unwrap();
+        assert_eq!(restored, vec!["default", "trainee-1"]);
+
+        let summaries = restarted.list().await;
+        assert_eq!(summaries.len(), 2);
+        assert!(summaries
+            .iter()
+            .all(|summary| summary.network.as_deref() == Some("sim1")));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_snapshot_skipped() {
+        let dir = data_dir("invalid");
+        let path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../iidm/tests/data/network.json");
+        let mut network: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        network["substations"][0]["voltageLevels"][0]["generators"][0]["minP"] =
+            serde_json::json!(1e6);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("trainee-1.json"),
+            serde_json::to_vec(&network).unwrap(),
+        )
+        .unwrap();
+
+        let networks = Networks::default();
+        let restored = Persistence::new(&dir, DEFAULT_SNAPSHOT_INTERVAL)
+            .restore(&networks)
+            .await
+            .unwrap();
+        assert!(restored.is_empty());
+        // The network is not opened, and the snapshot is left in place
+        assert!(networks.get("trainee-1").await.is_err());
+        assert!(dir.join("trainee-1.json").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_unplaced_elements_fail_the_save() {
+        let dir = data_dir("unplaced");
+        let persistence = Persistence::new(&dir, DEFAULT_SNAPSHOT_INTERVAL);
+        let networks = Networks::default();
+        let state = networks.create("trainee-1").await.unwrap();
+        load(&state).await;
+        load(&networks.create("trainee-2").await.unwrap()).await;
+        persistence.save(&networks).await.unwrap();
+        let saved = std::fs::read(dir.join("trainee-1.json")).unwrap();
+
+        {
+            let ecs = state.ecs.read().await;
+            let mut world = ecs.world.write().await;
+            let mut schedule = ecs.schedule.write().await;
+            let generator: Generator = serde_json::from_value(serde_json::json!({
+                "id": "LOST", "energySource": "OTHER", "minP": 0.0, "maxP": 100.0,
+                "voltageRegulatorOn": false, "targetP": 50.0, "targetV": 20.0, "targetQ": 0.0,
+                "bus": "NOWHERE", "connectableBus": "NOWHERE"
+            }))
+            .unwrap();
+            world.send_event(RegisterEvent {
+                id: "LOST".to_string(),
+                component: generator,
+            });
+            schedule.run(&mut world);
+        }
+
+        let result = persistence.save(&networks).await;
+        assert!(matches!(
+            result,
+            Err(PersistenceError::Unplaced { id, elements }) if id == "trainee-1" && elements == ["LOST"]
+        ));
+        // The previous snapshot is kept, and the other networks are still saved
+        assert_eq!(std::fs::read(dir.join("trainee-1.json")).unwrap(), saved);
+        assert!(dir.join("trainee-2.json").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_closed_network_snapshot_removed() {
+        let dir = data_dir("closed");
+        let persistence = Persistence::new(&dir, DEFAULT_SNAPSHOT_INTERVAL);
+        let networks = Networks::default();
+        load(&networks.create("trainee-1").await.unwrap()).await;
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("broken.json"), b"{").unwrap();
+
+        persistence.save(&networks).await.unwrap();
+        assert!(dir.join("trainee-1.json").exists());
+
+        networks.remove("trainee-1").await.unwrap();
+        persistence.save(&networks).await.unwrap();
+        assert!(!dir.join("trainee-1.json").exists());
+        // Files not written by the server are left alone
+        assert!(dir.join("broken.json").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
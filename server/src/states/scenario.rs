@@ -0,0 +1,79 @@
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use iidm::ScenarioStatus;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use super::NetworkState;
+
+/// Real time between two steps of a running scenario
+pub const SCENARIO_TICK: Duration = Duration::from_millis(500);
+
+/// Task advancing the scenario of a network while it runs
+#[derive(Default)]
+pub struct ScenarioTask {
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ScenarioTask {
+    /// Starts ticking the scenario of the network, in place of the previous task
+    pub fn start(&self, state: &Arc<NetworkState>) {
+        let task = tokio::spawn(run_scenario(Arc::downgrade(state)));
+        if let Some(previous) = self.lock().replace(task) {
+            previous.abort();
+        }
+    }
+
+    pub fn stop(&self) {
+        if let Some(task) = self.lock().take() {
+            task.abort();
+        }
+    }
+
+    /// Whether the scenario is still ticking
+    pub fn is_running(&self) -> bool {
+        self.lock().as_ref().is_some_and(|task| !task.is_finished())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<JoinHandle<()>>> {
+        self.handle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Advances the clock by the real time elapsed and runs the schedule on each tick, until the
+/// scenario is paused, finished or replaced, or the network is closed
+async fn run_scenario(state: Weak<NetworkState>) {
+    let mut interval = tokio::time::interval(SCENARIO_TICK);
+    interval.tick().await;
+    let mut last = Instant::now();
+    loop {
+        interval.tick().await;
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let ecs = state.ecs.read().await;
+        let mut world = ecs.world.write().await;
+        let mut schedule = ecs.schedule.write().await;
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(last).as_secs_f64();
+        last = now;
+        let report = match iidm::advance_scenario(&mut world, elapsed) {
+            Ok(report) if report.status != ScenarioStatus::Paused => report,
+            Ok(_) => return,
+            Err(error) => {
+                tracing::debug!("Scenario stopped: {}", error);
+                return;
+            }
+        };
+        schedule.run(&mut world);
+
+        if report.status == ScenarioStatus::Finished {
+            tracing::debug!("Scenario {} finished at {}s", report.name, report.time);
+            return;
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Environment variable giving the size limit of the uploaded files, in megabytes
+pub const MAX_UPLOAD_VAR: &str = "MENTOR_MAX_UPLOAD_MB";
+
+const DEFAULT_MAX_UPLOAD_MB: usize = 2048;
+
+/// Minimum time between two progress reports of an upload
+pub const UPLOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Size limit of the uploaded files, read from `MENTOR_MAX_UPLOAD_MB`
+pub fn max_upload_bytes() -> usize {
+    std::env::var(MAX_UPLOAD_VAR)
+        .ok()
+        .and_then(|megabytes| megabytes.parse::<usize>().ok())
+        .filter(|megabytes| *megabytes > 0)
+        .unwrap_or(DEFAULT_MAX_UPLOAD_MB)
+        .saturating_mul(1024 * 1024)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UploadStatus {
+    Reading,
+    Registered,
+    Failed,
+}
+
+/// Progress of an upload, as broadcast to the subscribers
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadProgress {
+    pub status: UploadStatus,
+    pub bytes_read: u64,
+    /// Size of the request, when announced by the client
+    pub total_bytes: Option<u64>,
+    /// Elements registered so far by type, when the file is registered as it is read
+    pub elements: BTreeMap<&'static str, usize>,
+}
+
+/// Channel of the upload progress of one network
+pub struct UploadFeed {
+    sender: broadcast::Sender<Arc<UploadProgress>>,
+}
+
+impl Default for UploadFeed {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(16).0,
+        }
+    }
+}
+
+impl UploadFeed {
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<UploadProgress>> {
+        self.sender.subscribe()
+    }
+
+    /// Starts reporting a new upload
+    pub fn start(&self, total_bytes: Option<u64>) -> UploadReport {
+        let mut report = UploadReport {
+            sender: self.sender.clone(),
+            progress: UploadProgress {
+                status: UploadStatus::Reading,
+                bytes_read: 0,
+                total_bytes,
+                elements: BTreeMap::new(),
+            },
+            published: None,
+        };
+        report.publish();
+        report
+    }
+}
+
+/// Reports the progress of one upload, and its failure when dropped before it is registered
+pub struct UploadReport {
+    sender: broadcast::Sender<Arc<UploadProgress>>,
+    progress: UploadProgress,
+    published: Option<Instant>,
+}
+
+impl UploadReport {
+    /// Records the bytes read and the elements registered so far, published at most once per
+    /// `UPLOAD_PROGRESS_INTERVAL`
+    pub fn update(&mut self, bytes_read: u64, elements: &BTreeMap<&'static str, usize>) {
+        self.progress.bytes_read = bytes_read;
+        if self
+            .published
+            .is_some_and(|published| published.elapsed() < UPLOAD_PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        self.progress.elements.clone_from(elements);
+        self.publish();
+    }
+
+    pub fn registered(mut self, bytes_read: u64, elements: &BTreeMap<&'static str, usize>) {
+        self.progress.status = UploadStatus::Registered;
+        self.progress.bytes_read = bytes_read;
+        self.progress.elements.clone_from(elements);
+        self.publish();
+    }
+
+    fn publish(&mut self) {
+        self.published = Some(Instant::now());
+        // Nobody may be listening
+        let _ = self.sender.send(Arc::new(self.progress.clone()));
+    }
+}
+
+impl Drop for UploadReport {
+    fn drop(&mut self) {
+        if self.progress.status == UploadStatus::Reading {
+            self.progress.status = UploadStatus::Failed;
+            self.publish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_throttled_then_closed() {
+        let feed = UploadFeed::default();
+        let mut receiver = feed.subscribe();
+        let elements = BTreeMap::from([("Substation", 2)]);
+
+        let mut report = feed.start(Some(100));
+        report.update(10, &elements);
+        report.update(20, &elements);
+        drop(report);
+        let statuses: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|progress| (progress.status, progress.bytes_read))
+            .collect();
+        assert_eq!(
+            statuses,
+            [(UploadStatus::Reading, 0), (UploadStatus::Failed, 20)]
+        );
+
+        let report = feed.start(None);
+        report.registered(100, &elements);
+        let progress: Vec<_> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[1].status, UploadStatus::Registered);
+        assert_eq!(progress[1].elements, elements);
+    }
+}